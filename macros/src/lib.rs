@@ -0,0 +1,61 @@
+#![forbid(unsafe_code)]
+#![warn(missing_docs)]
+
+//! Procedural macros for the [`token-list`](https://docs.rs/token-list)
+//! crate, which re-exports them with its `macros` feature.
+
+use std::path::PathBuf;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, LitStr};
+
+mod validate;
+
+/// Embeds the token list in a JSON file at compile time, expanding to a
+/// `Lazy<TokenList>` that parses it on first use.
+///
+/// The path is relative to the manifest directory of the crate being built.
+/// The build fails if the file can't be read, isn't a token list, or breaks
+/// the constraints of the token list schema that `TokenList::validate`
+/// reports as errors.
+///
+/// ```ignore
+/// use token_list::{include_token_list, Lazy, TokenList};
+///
+/// static DEFAULT_LIST: Lazy<TokenList> = include_token_list!("lists/default.json");
+/// ```
+#[proc_macro]
+pub fn include_token_list(input: TokenStream) -> TokenStream {
+    let lit = parse_macro_input!(input as LitStr);
+    match expand(&lit) {
+        Ok(tokens) => tokens.into(),
+        Err(message) => syn::Error::new(lit.span(), message)
+            .to_compile_error()
+            .into(),
+    }
+}
+
+fn expand(lit: &LitStr) -> Result<proc_macro2::TokenStream, String> {
+    let mut path = PathBuf::from(
+        std::env::var("CARGO_MANIFEST_DIR").map_err(|_| "CARGO_MANIFEST_DIR is not set")?,
+    );
+    path.push(lit.value());
+    let json = std::fs::read_to_string(&path)
+        .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+
+    let errors = validate::validate(&json)?;
+    if !errors.is_empty() {
+        return Err(format!("invalid token list:\n{}", errors.join("\n")));
+    }
+
+    // `include_str!` rather than the contents read here, so that the crate is
+    // rebuilt when the file changes
+    let path = path.to_string_lossy();
+    Ok(quote! {
+        ::token_list::__private::Lazy::new(|| {
+            ::token_list::__private::serde_json::from_str(include_str!(#path))
+                .expect("embedded token list was validated at compile time")
+        })
+    })
+}
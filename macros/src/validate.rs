@@ -0,0 +1,273 @@
+//! Checks of an embedded list against the token list schema.
+//!
+//! These mirror the checks `TokenList::validate` reports as errors, which
+//! can't be called from here without a dependency cycle.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenList {
+    name: String,
+    timestamp: String,
+    version: Version,
+    #[serde(rename = "logoURI")]
+    logo_uri: Option<String>,
+    #[serde(default)]
+    keywords: Vec<String>,
+    #[serde(default)]
+    tags: HashMap<String, Tag>,
+    #[serde(default)]
+    tokens: Vec<Token>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Version {
+    Object {
+        #[allow(dead_code)]
+        major: u64,
+        #[allow(dead_code)]
+        minor: u64,
+        #[allow(dead_code)]
+        patch: u64,
+    },
+    String(String),
+}
+
+#[derive(Deserialize)]
+struct Tag {
+    name: String,
+    description: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Token {
+    name: String,
+    symbol: String,
+    address: String,
+    chain_id: u32,
+    decimals: u16,
+    #[serde(rename = "logoURI")]
+    logo_uri: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    extensions: HashMap<String, Value>,
+}
+
+#[derive(Default)]
+struct Validator {
+    errors: Vec<String>,
+}
+
+impl Validator {
+    fn report(&mut self, path: String, message: &str) {
+        self.errors.push(format!("{}: {}", path, message));
+    }
+
+    fn string(
+        &mut self,
+        path: String,
+        value: &str,
+        min: usize,
+        max: usize,
+        valid: fn(char) -> bool,
+    ) {
+        let len = value.chars().count();
+        if len < min {
+            self.report(path, &format!("shorter than the minimum of {}", min));
+        } else if len > max {
+            self.report(path, &format!("longer than the maximum of {}", max));
+        } else if !value.chars().all(valid) {
+            self.report(path, "contains invalid characters");
+        }
+    }
+
+    fn count(&mut self, path: String, len: usize, min: usize, max: usize) {
+        if len < min {
+            self.report(path, &format!("shorter than the minimum of {}", min));
+        } else if len > max {
+            self.report(path, &format!("longer than the maximum of {}", max));
+        }
+    }
+
+    fn uri(&mut self, path: String, uri: Option<&str>) {
+        if let Some(uri) = uri {
+            if url::Url::parse(uri).is_err() {
+                self.report(path, "not a valid URI");
+            }
+        }
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn is_word_or_space(c: char) -> bool {
+    is_word(c) || c == ' '
+}
+
+fn is_description(c: char) -> bool {
+    is_word_or_space(c) || matches!(c, '.' | ',' | ':')
+}
+
+fn is_token_name(c: char) -> bool {
+    is_word_or_space(c)
+        || ".'+-%/:&[]()".contains(c)
+        || matches!(c, 'À'..='Ö' | 'Ø'..='ö' | 'ø'..='ÿ')
+}
+
+fn is_symbol(c: char) -> bool {
+    !c.is_whitespace()
+}
+
+fn is_address(address: &str) -> bool {
+    address
+        .strip_prefix("0x")
+        .is_some_and(|hex| hex.len() == 40 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Returns the schema violations in the list `json`, each as
+/// `<JSON pointer>: <problem>`, or an error if it isn't a token list at all.
+pub(crate) fn validate(json: &str) -> Result<Vec<String>, String> {
+    let list: TokenList =
+        serde_json::from_str(json).map_err(|err| format!("failed to parse token list: {}", err))?;
+    let mut v = Validator::default();
+
+    v.string("/name".to_owned(), &list.name, 1, 30, is_word_or_space);
+    if chrono::DateTime::parse_from_rfc3339(&list.timestamp).is_err() {
+        v.report("/timestamp".to_owned(), "not an RFC 3339 timestamp");
+    }
+    if let Version::String(version) = &list.version {
+        if semver::Version::parse(version).is_err() {
+            v.report("/version".to_owned(), "not a semantic version");
+        }
+    }
+    v.uri("/logoURI".to_owned(), list.logo_uri.as_deref());
+
+    v.count("/keywords".to_owned(), list.keywords.len(), 0, 20);
+    let mut keywords = HashSet::new();
+    for (i, keyword) in list.keywords.iter().enumerate() {
+        let path = format!("/keywords/{}", i);
+        v.string(path.clone(), keyword, 1, 20, is_word_or_space);
+        if !keywords.insert(keyword) {
+            v.report(path, "duplicate keyword");
+        }
+    }
+
+    v.count("/tags".to_owned(), list.tags.len(), 0, 20);
+    for (id, tag) in &list.tags {
+        let path = format!("/tags/{}", escape(id));
+        v.string(path.clone(), id, 1, 10, is_word);
+        v.string(format!("{}/name", path), &tag.name, 1, 20, is_word_or_space);
+        v.string(
+            format!("{}/description", path),
+            &tag.description,
+            1,
+            200,
+            is_description,
+        );
+    }
+
+    v.count("/tokens".to_owned(), list.tokens.len(), 1, 10_000);
+    let mut addresses = HashSet::new();
+    for (i, token) in list.tokens.iter().enumerate() {
+        let path = format!("/tokens/{}", i);
+
+        v.string(format!("{}/name", path), &token.name, 1, 40, is_token_name);
+        v.string(format!("{}/symbol", path), &token.symbol, 1, 20, is_symbol);
+        if !is_address(&token.address) {
+            v.report(format!("{}/address", path), "not a hex address");
+        }
+        if token.chain_id == 0 {
+            v.report(format!("{}/chainId", path), "chain ID must be at least 1");
+        }
+        if token.decimals > 255 {
+            v.report(format!("{}/decimals", path), "decimals must be at most 255");
+        }
+        v.uri(format!("{}/logoURI", path), token.logo_uri.as_deref());
+
+        v.count(format!("{}/tags", path), token.tags.len(), 0, 10);
+        for (j, tag) in token.tags.iter().enumerate() {
+            let tag_path = format!("{}/tags/{}", path, j);
+            v.string(tag_path.clone(), tag, 1, 10, is_word);
+            if !list.tags.contains_key(tag) {
+                v.report(tag_path, "tag is not defined by the list");
+            }
+        }
+
+        v.count(
+            format!("{}/extensions", path),
+            token.extensions.len(),
+            0,
+            10,
+        );
+        for (key, value) in &token.extensions {
+            let key_path = format!("{}/extensions/{}", path, escape(key));
+            v.string(key_path.clone(), key, 1, 40, is_word);
+            match value {
+                Value::String(value) if value.chars().count() > 42 => {
+                    v.report(key_path, "longer than the maximum of 42")
+                }
+                Value::Array(_) | Value::Object(_) => {
+                    v.report(key_path, "not a string, number, boolean, or null")
+                }
+                _ => {}
+            }
+        }
+
+        if !addresses.insert((token.chain_id, token.address.to_lowercase())) {
+            v.report(path, "same chain and address as an earlier token");
+        }
+    }
+
+    Ok(v.errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL: &str = include_str!("../../fixtures/full.json");
+
+    #[test]
+    fn accepts_valid_lists() {
+        assert_eq!(validate(FULL).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_schema_violations() {
+        let mut list: Value = serde_json::from_str(FULL).unwrap();
+        list["timestamp"] = "yesterday".into();
+        list["tokens"][0]["address"] = "0x1234".into();
+        list["tokens"][0]["tags"][0] = "missing".into();
+        list["tokens"][1]["chainId"] = 0.into();
+
+        let errors = validate(&list.to_string()).unwrap();
+        assert_eq!(
+            errors,
+            [
+                "/timestamp: not an RFC 3339 timestamp",
+                "/tokens/0/address: not a hex address",
+                "/tokens/0/tags/0: tag is not defined by the list",
+                "/tokens/1/chainId: chain ID must be at least 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_lists_that_do_not_parse() {
+        let err = validate(r#"{"name": "No tokens"}"#).unwrap_err();
+        assert!(err.starts_with("failed to parse token list"), "{}", err);
+    }
+}
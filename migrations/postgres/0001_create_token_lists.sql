@@ -0,0 +1,51 @@
+-- Token lists, as stored by `token_list::PostgresStore`.
+--
+-- Tokens are shared between the versions of a list they are unchanged in, so
+-- storing a new version only inserts the tokens it adds or changes. Versions
+-- are stored in full, alongside a key whose byte order is their precedence.
+
+CREATE TABLE lists (
+    id BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    version_key TEXT COLLATE "C" NOT NULL,
+    timestamp TIMESTAMPTZ NOT NULL,
+    logo_uri TEXT,
+    keywords TEXT[] NOT NULL,
+    UNIQUE (name, version)
+);
+
+CREATE INDEX lists_by_version ON lists (name, version_key);
+
+CREATE TABLE tags (
+    list_id BIGINT NOT NULL REFERENCES lists (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    PRIMARY KEY (list_id, position)
+);
+
+CREATE TABLE tokens (
+    id BIGSERIAL PRIMARY KEY,
+    list_name TEXT NOT NULL,
+    chain_id BIGINT NOT NULL,
+    address TEXT NOT NULL,
+    name TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    decimals INTEGER NOT NULL,
+    logo_uri TEXT,
+    tags TEXT[] NOT NULL,
+    extensions JSONB NOT NULL,
+    content_hash BYTEA NOT NULL,
+    UNIQUE (list_name, chain_id, address, content_hash)
+);
+
+CREATE INDEX tokens_by_address ON tokens (chain_id, lower(address));
+
+CREATE TABLE list_tokens (
+    list_id BIGINT NOT NULL REFERENCES lists (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    token_id BIGINT NOT NULL REFERENCES tokens (id),
+    PRIMARY KEY (list_id, position)
+);
@@ -0,0 +1,196 @@
+//! [`Arbitrary`](::arbitrary::Arbitrary) implementations generating lists that
+//! conform to the token list schema.
+
+use std::collections::HashSet;
+
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+use indexmap::{IndexMap, IndexSet};
+use semver::Version;
+use url::Url;
+
+use crate::{ExtensionValue, Number, Tag, Timestamp, Token, TokenList};
+
+const WORD: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789_";
+const TEXT: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789_ ";
+const HEX: &[u8] = b"0123456789abcdefABCDEF";
+
+impl<'a> Arbitrary<'a> for TokenList {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut tags = IndexMap::new();
+        for _ in 0..u.int_in_range(0..=4)? {
+            tags.insert(string(u, WORD, 1, 10)?, Tag::arbitrary(u)?);
+        }
+        let tag_ids: Vec<&String> = tags.keys().collect();
+
+        let tokens = (0..u.int_in_range(1..=8)?)
+            .map(|_| token(u, &tag_ids))
+            .collect::<Result<_>>()?;
+        let keywords = (0..u.int_in_range(0..=4)?)
+            .map(|_| string(u, TEXT, 1, 20))
+            .collect::<Result<_>>()?;
+
+        let mut token_list = TokenList {
+            name: string(u, TEXT, 1, 30)?,
+            timestamp: timestamp(u)?,
+            version: Version::new(
+                u.int_in_range(0..=99)?,
+                u.int_in_range(0..=99)?,
+                u.int_in_range(0..=99)?,
+            ),
+            logo_uri: logo_uri(u)?,
+            keywords,
+            tags,
+            tokens,
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: Default::default(),
+        };
+        dedupe(&mut token_list);
+        Ok(token_list)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Token {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        token(u, &[])
+    }
+}
+
+impl<'a> Arbitrary<'a> for Tag {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Tag {
+            name: string(u, TEXT, 1, 20)?,
+            description: string(u, TEXT, 1, 200)?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for ExtensionValue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=3)? {
+            0 => ExtensionValue::String(string(u, TEXT, 1, 42)?),
+            1 => ExtensionValue::Number(Number::arbitrary(u)?),
+            2 => ExtensionValue::Boolean(u.arbitrary()?),
+            _ => ExtensionValue::Null,
+        })
+    }
+}
+
+/// Generates numbers in their canonical variant, so they read back the same.
+impl<'a> Arbitrary<'a> for Number {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=2)? {
+            0 => Number::Integer(u.arbitrary()?),
+            1 => Number::BigInteger(u.int_in_range(i64::MAX as u128 + 1..=u64::MAX as u128)?),
+            _ => {
+                let value: f64 = u.arbitrary()?;
+                Number::Float(match value.is_finite() {
+                    true => value,
+                    false => 0.0,
+                })
+            }
+        })
+    }
+}
+
+/// Removes repeated keywords, and tokens repeating the chain and address of
+/// an earlier token, which the schema doesn't allow.
+pub(crate) fn dedupe(token_list: &mut TokenList) {
+    let keywords: IndexSet<String> = token_list.keywords.drain(..).collect();
+    token_list.keywords = keywords.into_iter().collect();
+
+    let mut addresses = HashSet::new();
+    token_list
+        .tokens
+        .retain(|token| addresses.insert((token.chain_id, token.address.to_lowercase())));
+}
+
+/// Generates a token, tagged with some of `tag_ids`.
+fn token(u: &mut Unstructured, tag_ids: &[&String]) -> Result<Token> {
+    let mut tags = Vec::new();
+    for id in tag_ids {
+        if u.arbitrary()? {
+            tags.push(id.to_string());
+        }
+    }
+
+    let mut extensions = IndexMap::new();
+    for _ in 0..u.int_in_range(0..=4)? {
+        extensions.insert(string(u, WORD, 1, 40)?, u.arbitrary()?);
+    }
+
+    Ok(Token {
+        name: string(u, TEXT, 1, 40)?,
+        symbol: string(u, WORD, 1, 20)?,
+        address: format!("0x{}", string(u, HEX, 40, 40)?),
+        chain_id: u.int_in_range(1..=u32::MAX)?,
+        decimals: u.int_in_range(0..=255)?,
+        logo_uri: logo_uri(u)?,
+        tags,
+        extensions,
+        #[cfg(feature = "unknown-fields")]
+        unknown_fields: Default::default(),
+    })
+}
+
+/// Generates a string of `min` to `max` characters from `charset`.
+fn string(u: &mut Unstructured, charset: &[u8], min: usize, max: usize) -> Result<String> {
+    (0..u.int_in_range(min..=max)?)
+        .map(|_| u.choose(charset).map(|&c| char::from(c)))
+        .collect()
+}
+
+fn timestamp(u: &mut Unstructured) -> Result<Timestamp> {
+    // RFC 3339 offsets have minute precision
+    let offset = u.int_in_range(-1439..=1439)? * 60;
+    // up to 9999-12-30T23:59:59Z, so the local date has a four-digit year
+    let seconds = u.int_in_range(0..=253_402_214_399)?;
+
+    Ok(crate::timestamp::from_unix(seconds, 0, offset).expect("timestamp is in range"))
+}
+
+fn logo_uri(u: &mut Unstructured) -> Result<Option<Url>> {
+    if !u.arbitrary()? {
+        return Ok(None);
+    }
+    let uri = format!("https://example.com/{}.png", string(u, WORD, 1, 20)?);
+    Ok(Some(Url::parse(&uri).expect("URI is valid")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_generated_lists() {
+        let bytes: Vec<u8> = (0..4096u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        let mut u = Unstructured::new(&bytes);
+
+        while !u.is_empty() {
+            let token_list = TokenList::arbitrary(&mut u).unwrap();
+            let json = serde_json::to_string(&token_list).unwrap();
+
+            assert_eq!(
+                serde_json::from_str::<TokenList>(&json).unwrap(),
+                token_list
+            );
+        }
+    }
+
+    #[cfg(feature = "validate")]
+    #[test]
+    fn generates_valid_lists() {
+        let bytes: Vec<u8> = (0..4096u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        let mut u = Unstructured::new(&bytes);
+
+        while !u.is_empty() {
+            let token_list = TokenList::arbitrary(&mut u).unwrap();
+            let report = token_list.validate();
+
+            assert!(!report.has_errors(), "{:?}", report);
+        }
+    }
+}
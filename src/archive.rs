@@ -0,0 +1,412 @@
+//! An archive of every version of a token list that has been fetched.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "storage")]
+use std::sync::Arc;
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::hash::{canonical_json, to_hex};
+#[cfg(feature = "storage")]
+use crate::Storage;
+use crate::{Error, Fetcher, Timestamp, TokenList, TokenListDiff};
+
+/// An archive of the versions of a token list, kept in a directory or a
+/// [`Storage`](crate::Storage) backend so that what the list said at any
+/// point can be looked up later.
+///
+/// In a directory, each archived list is written as canonical JSON to
+/// `<fetched at>-<hash>.json`, whose SHA-256 digest is its hash, next to a
+/// `<fetched at>-<hash>.meta.json` file holding its [`ArchiveEntry`]. Files
+/// are read and written synchronously.
+///
+/// A list is only archived if its content differs from the last list
+/// archived, so polling an unchanged list doesn't grow the archive.
+#[derive(Clone)]
+pub struct ListArchive {
+    backend: Backend,
+}
+
+#[derive(Clone)]
+enum Backend {
+    Dir(PathBuf),
+    #[cfg(feature = "storage")]
+    Storage {
+        storage: Arc<dyn Storage>,
+        name: String,
+    },
+}
+
+/// The metadata of a list in a [`ListArchive`].
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntry {
+    /// When the list was fetched
+    #[serde(with = "crate::timestamp")]
+    pub fetched_at: Timestamp,
+
+    /// The URI the list was fetched from, if known
+    pub uri: Option<String>,
+
+    /// The version of the list
+    #[serde(with = "crate::version")]
+    pub version: Version,
+
+    /// The timestamp of the list
+    #[serde(with = "crate::timestamp")]
+    pub timestamp: Timestamp,
+
+    /// The SHA-256 digest of the canonical JSON of the list, as lowercase
+    /// hexadecimal
+    pub sha256: String,
+
+    /// The number of tokens in the list
+    pub tokens: usize,
+}
+
+impl ArchiveEntry {
+    fn new(token_list: &TokenList, json: &[u8], uri: Option<&str>, fetched_at: Timestamp) -> Self {
+        ArchiveEntry {
+            fetched_at,
+            uri: uri.map(str::to_owned),
+            version: token_list.version.clone(),
+            timestamp: token_list.timestamp,
+            sha256: to_hex(&Sha256::digest(json)),
+            tokens: token_list.tokens.len(),
+        }
+    }
+
+    /// Returns the file name of the entry's list, without its extension.
+    fn stem(&self) -> String {
+        format!(
+            "{}-{}",
+            crate::timestamp::format_utc(&self.fetched_at, "%Y%m%dT%H%M%S%.6fZ"),
+            &self.sha256[..16]
+        )
+    }
+}
+
+impl ListArchive {
+    /// Opens the archive in `dir`, which is created when the first list is
+    /// archived.
+    pub fn open<P: Into<PathBuf>>(dir: P) -> Self {
+        ListArchive {
+            backend: Backend::Dir(dir.into()),
+        }
+    }
+
+    /// Opens the archive of the list named `name` in `storage`.
+    ///
+    /// Storage keeps a list per name and version, without fetch metadata, so
+    /// lists are stored under their own name and only those named `name`
+    /// are archived, a version archived again with different content
+    /// replaces the earlier one, and entries give each list's timestamp as
+    /// when it was fetched, without a URI.
+    #[cfg(feature = "storage")]
+    pub fn with_storage<S: Into<String>>(storage: Arc<dyn Storage>, name: S) -> Self {
+        ListArchive {
+            backend: Backend::Storage {
+                storage,
+                name: name.into(),
+            },
+        }
+    }
+
+    /// Returns the directory the archive is kept in, or `None` if it's kept
+    /// in a [`Storage`](crate::Storage) backend.
+    pub fn dir(&self) -> Option<&Path> {
+        match &self.backend {
+            Backend::Dir(dir) => Some(dir),
+            #[cfg(feature = "storage")]
+            Backend::Storage { .. } => None,
+        }
+    }
+
+    /// Archives `token_list`, fetched from `uri` at `fetched_at`, returning
+    /// its entry, or `None` if it's the same as the last list archived.
+    pub async fn add(
+        &self,
+        token_list: &TokenList,
+        uri: Option<&str>,
+        fetched_at: Timestamp,
+    ) -> Result<Option<ArchiveEntry>, Error> {
+        let json = canonical_json(token_list)?;
+        let entry = ArchiveEntry::new(token_list, &json, uri, fetched_at);
+        let unchanged = self
+            .entries()
+            .await?
+            .last()
+            .is_some_and(|last| last.sha256 == entry.sha256);
+        if unchanged {
+            return Ok(None);
+        }
+
+        match &self.backend {
+            Backend::Dir(dir) => {
+                fs::create_dir_all(dir)?;
+                let stem = entry.stem();
+                write_atomic(&dir.join(format!("{}.json", stem)), &json)?;
+                write_atomic(
+                    &dir.join(format!("{}.meta.json", stem)),
+                    &serde_json::to_vec_pretty(&entry)?,
+                )?;
+            }
+            #[cfg(feature = "storage")]
+            Backend::Storage { storage, .. } => storage.put_list(token_list).await?,
+        }
+        Ok(Some(entry))
+    }
+
+    /// Fetches the list at `uri` using the provided [`Fetcher`] and archives
+    /// it, returning the list and its entry if it was archived.
+    pub async fn fetch<F: Fetcher + ?Sized>(
+        &self,
+        fetcher: &F,
+        uri: &str,
+    ) -> Result<(TokenList, Option<ArchiveEntry>), Error> {
+        let token_list = TokenList::from_fetcher(fetcher, uri).await?;
+        let entry = self
+            .add(&token_list, Some(uri), crate::timestamp::now())
+            .await?;
+        Ok((token_list, entry))
+    }
+
+    /// Returns the entries of the archive, in the order they were fetched.
+    pub async fn entries(&self) -> Result<Vec<ArchiveEntry>, Error> {
+        let mut entries = Vec::new();
+        match &self.backend {
+            Backend::Dir(dir) => {
+                let dir = match fs::read_dir(dir) {
+                    Ok(dir) => dir,
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+                    Err(err) => return Err(err.into()),
+                };
+                for file in dir {
+                    let path = file?.path();
+                    let is_meta = path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.ends_with(".meta.json"));
+                    if is_meta {
+                        let entry: ArchiveEntry = serde_json::from_slice(&fs::read(path)?)?;
+                        entries.push(entry);
+                    }
+                }
+            }
+            #[cfg(feature = "storage")]
+            Backend::Storage { storage, name } => {
+                for version in storage.list_versions(name).await? {
+                    if let Some(token_list) = storage.get_list(name, Some(&version)).await? {
+                        let json = canonical_json(&token_list)?;
+                        entries.push(ArchiveEntry::new(
+                            &token_list,
+                            &json,
+                            None,
+                            token_list.timestamp,
+                        ));
+                    }
+                }
+            }
+        }
+        entries.sort_by_key(|entry| entry.fetched_at);
+        Ok(entries)
+    }
+
+    /// Returns the versions of the list in the archive, oldest first.
+    pub async fn versions(&self) -> Result<Vec<Version>, Error> {
+        let mut versions: Vec<_> = self
+            .entries()
+            .await?
+            .into_iter()
+            .map(|entry| entry.version)
+            .collect();
+        versions.sort();
+        versions.dedup();
+        Ok(versions)
+    }
+
+    /// Returns the list of `entry`.
+    pub async fn load(&self, entry: &ArchiveEntry) -> Result<TokenList, Error> {
+        match &self.backend {
+            Backend::Dir(dir) => {
+                let path = dir.join(format!("{}.json", entry.stem()));
+                Ok(serde_json::from_slice(&fs::read(path)?)?)
+            }
+            #[cfg(feature = "storage")]
+            Backend::Storage { storage, name } => storage
+                .get_list(name, Some(&entry.version))
+                .await?
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound).into()),
+        }
+    }
+
+    /// Returns `version` of the list, or `None` if it isn't archived.
+    ///
+    /// If the version was archived with different content more than once,
+    /// the last fetched is returned.
+    pub async fn get(&self, version: &Version) -> Result<Option<TokenList>, Error> {
+        let entries = self.entries().await?;
+        match entries.iter().rev().find(|entry| entry.version == *version) {
+            Some(entry) => self.load(entry).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the list as last fetched at or before `time`, or `None` if
+    /// none had been fetched by then.
+    pub async fn at(&self, time: Timestamp) -> Result<Option<TokenList>, Error> {
+        let entries = self.entries().await?;
+        match entries.iter().rev().find(|entry| entry.fetched_at <= time) {
+            Some(entry) => self.load(entry).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Compares the tokens of versions `old` and `new` of the list, or
+    /// returns `None` if either isn't archived.
+    pub async fn diff(&self, old: &Version, new: &Version) -> Result<Option<TokenListDiff>, Error> {
+        match (self.get(old).await?, self.get(new).await?) {
+            (Some(old), Some(new)) => Ok(Some(old.diff(&new))),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl fmt::Debug for ListArchive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("ListArchive");
+        match &self.backend {
+            Backend::Dir(dir) => debug.field("dir", dir),
+            #[cfg(feature = "storage")]
+            Backend::Storage { name, .. } => debug.field("name", name),
+        };
+        debug.finish_non_exhaustive()
+    }
+}
+
+/// Writes `bytes` to `path` through a temporary file, so that readers never
+/// see a partial file.
+fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    fn versions() -> (TokenList, TokenList) {
+        let old = fixtures::full();
+        let mut new = old.clone();
+        new.version = Version::new(1, 0, 0);
+        new.tokens.remove(0);
+        (old, new)
+    }
+
+    fn day(day: u32) -> Timestamp {
+        crate::timestamp::parse_rfc3339(&format!("2024-01-{:02}T00:00:00Z", day)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn archives_changed_lists() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = ListArchive::open(dir.path().join("archive"));
+        let (old, new) = versions();
+
+        assert!(archive
+            .add(&old, Some("memory://list.json"), day(1))
+            .await
+            .unwrap()
+            .is_some());
+        assert!(archive.add(&old, None, day(2)).await.unwrap().is_none());
+        let entry = archive.add(&new, None, day(3)).await.unwrap().unwrap();
+        assert_eq!(entry.tokens, new.tokens.len());
+
+        let entries = archive.entries().await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].uri.as_deref(), Some("memory://list.json"));
+        assert_eq!(
+            archive.versions().await.unwrap(),
+            [old.version.clone(), new.version.clone()]
+        );
+
+        let json = fs::read(
+            dir.path()
+                .join("archive")
+                .join(format!("{}.json", entry.stem())),
+        );
+        assert_eq!(to_hex(&Sha256::digest(json.unwrap())), entry.sha256);
+    }
+
+    #[tokio::test]
+    async fn looks_up_versions_and_dates() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = ListArchive::open(dir.path());
+        let (old, new) = versions();
+        archive.add(&old, None, day(1)).await.unwrap();
+        archive.add(&new, None, day(3)).await.unwrap();
+
+        assert_eq!(
+            archive.get(&old.version).await.unwrap().as_ref(),
+            Some(&old)
+        );
+        assert_eq!(archive.get(&Version::new(9, 0, 0)).await.unwrap(), None);
+        assert_eq!(archive.at(day(2)).await.unwrap().as_ref(), Some(&old));
+        assert_eq!(archive.at(day(3)).await.unwrap().as_ref(), Some(&new));
+        let before = crate::timestamp::parse_rfc3339("2023-12-31T00:00:00Z").unwrap();
+        assert_eq!(archive.at(before).await.unwrap(), None);
+
+        let diff = archive
+            .diff(&old.version, &new.version)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(diff.removed, vec![old.tokens[0].clone()]);
+        assert_eq!(
+            archive
+                .diff(&old.version, &Version::new(9, 0, 0))
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[cfg(feature = "storage")]
+    #[tokio::test]
+    async fn archives_in_storage() {
+        let storage = Arc::new(crate::MemoryStorage::new());
+        let (old, mut new) = versions();
+        new.timestamp = crate::timestamp::parse_rfc3339("2021-07-07T20:25:22Z").unwrap();
+        let archive = ListArchive::with_storage(storage.clone(), old.name.clone());
+        assert_eq!(archive.dir(), None);
+
+        assert!(archive.add(&old, None, day(1)).await.unwrap().is_some());
+        assert!(archive.add(&old, None, day(2)).await.unwrap().is_none());
+        assert!(archive.add(&new, None, day(3)).await.unwrap().is_some());
+
+        assert_eq!(
+            storage.list_versions(&old.name).await.unwrap(),
+            [old.version.clone(), new.version.clone()]
+        );
+        let entries = archive.entries().await.unwrap();
+        assert_eq!(entries[1].fetched_at, new.timestamp);
+        assert_eq!(archive.load(&entries[0]).await.unwrap(), old);
+        assert_eq!(
+            archive.at(old.timestamp).await.unwrap().as_ref(),
+            Some(&old)
+        );
+        let diff = archive
+            .diff(&old.version, &new.version)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(diff.removed, vec![old.tokens[0].clone()]);
+    }
+}
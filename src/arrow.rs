@@ -0,0 +1,167 @@
+//! Arrow and Parquet export of the tokens in a list.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::Arc;
+
+use arrow_array::builder::{ListBuilder, StringBuilder};
+use arrow_array::{ArrayRef, RecordBatch, StringArray, UInt32Array, UInt8Array};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use parquet::arrow::ArrowWriter;
+
+use crate::{Error, TokenList};
+
+impl TokenList {
+    /// Returns the schema of the record batches produced by
+    /// [`tokens_to_record_batch`](Self::tokens_to_record_batch).
+    ///
+    /// Each token is a row with `name`, `symbol`, `address`, `chain_id`,
+    /// `decimals`, `logo_uri`, and `tags` columns. Extensions are stored in
+    /// the `extensions` column as a JSON object with sorted keys, or null for
+    /// tokens without any.
+    pub fn tokens_arrow_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new("symbol", DataType::Utf8, false),
+            Field::new("address", DataType::Utf8, false),
+            Field::new("chain_id", DataType::UInt32, false),
+            Field::new("decimals", DataType::UInt8, false),
+            Field::new("logo_uri", DataType::Utf8, true),
+            Field::new(
+                "tags",
+                DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+                false,
+            ),
+            Field::new("extensions", DataType::Utf8, true),
+        ]))
+    }
+
+    /// Converts the tokens in the list into an Arrow record batch, one row per
+    /// token.
+    pub fn tokens_to_record_batch(&self) -> Result<RecordBatch, Error> {
+        let tokens = &self.tokens;
+
+        let mut tags = ListBuilder::new(StringBuilder::new());
+        for token in tokens {
+            for tag in &token.tags {
+                tags.values().append_value(tag);
+            }
+            tags.append(true);
+        }
+
+        let extensions = tokens
+            .iter()
+            .map(|token| match token.extensions.is_empty() {
+                true => Ok(None),
+                // sorted, so equal extensions produce identical cells
+                false => {
+                    let extensions: BTreeMap<_, _> = token.extensions.iter().collect();
+                    serde_json::to_string(&extensions).map(Some)
+                }
+            })
+            .collect::<Result<StringArray, _>>()?;
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from_iter_values(
+                tokens.iter().map(|token| &token.name),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                tokens.iter().map(|token| &token.symbol),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                tokens.iter().map(|token| &token.address),
+            )),
+            Arc::new(UInt32Array::from_iter_values(
+                tokens.iter().map(|token| token.chain_id),
+            )),
+            Arc::new(UInt8Array::from_iter_values(
+                tokens.iter().map(|token| token.decimals),
+            )),
+            Arc::new(
+                tokens
+                    .iter()
+                    .map(|token| token.logo_uri.as_ref().map(|uri| uri.as_str()))
+                    .collect::<StringArray>(),
+            ),
+            Arc::new(tags.finish()),
+            Arc::new(extensions),
+        ];
+
+        Ok(RecordBatch::try_new(Self::tokens_arrow_schema(), columns)?)
+    }
+
+    /// Writes the tokens in the list as a Parquet file with the schema of
+    /// [`tokens_arrow_schema`](Self::tokens_arrow_schema).
+    pub fn tokens_to_parquet<W: io::Write + Send>(&self, writer: W) -> Result<(), Error> {
+        let mut writer = ArrowWriter::try_new(writer, Self::tokens_arrow_schema(), None)?;
+        writer.write(&self.tokens_to_record_batch()?)?;
+        writer.close()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{Array, ListArray};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    fn full() -> TokenList {
+        fixtures::full()
+    }
+
+    #[test]
+    fn converts_tokens_to_rows() {
+        let batch = full().tokens_to_record_batch().unwrap();
+
+        assert_eq!(batch.num_rows(), 2);
+
+        let chain_ids = batch
+            .column_by_name("chain_id")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<UInt32Array>()
+            .unwrap();
+        assert_eq!(chain_ids.values(), &[1, 137]);
+
+        let tags = batch
+            .column_by_name("tags")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap();
+        assert_eq!(tags.value(0).len(), 1);
+
+        let extensions = batch
+            .column_by_name("extensions")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(
+            extensions.value(0),
+            r#"{"polygonAddress":"0xdF7837DE1F2Fa4631D716CF2502f8b230F1dcc32"}"#
+        );
+        assert!(extensions.is_null(1));
+    }
+
+    #[test]
+    fn writes_readable_parquet() {
+        let token_list = full();
+        let file = tempfile::tempfile().unwrap();
+
+        token_list
+            .tokens_to_parquet(file.try_clone().unwrap())
+            .unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(batches, vec![token_list.tokens_to_record_batch().unwrap()]);
+    }
+}
@@ -0,0 +1,294 @@
+//! Serving token lists from [axum](https://docs.rs/axum) applications.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ::axum::http::header::{
+    CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use ::axum::http::{HeaderMap, HeaderValue, StatusCode};
+use ::axum::response::{IntoResponse, Response};
+use ::axum::routing::get;
+use ::axum::Router;
+use chrono::DateTime;
+use sha2::{Digest, Sha256};
+
+use crate::hash::{canonical_json, to_hex};
+#[cfg(feature = "cache")]
+use crate::TokenListCache;
+#[cfg(feature = "service")]
+use crate::TokenListService;
+use crate::{Timestamp, TokenList};
+
+/// The `HTTP-date` format of `Last-Modified` and `If-Modified-Since`.
+const HTTP_DATE: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// Serves a token list as JSON, with an `ETag` of its content hash, a
+/// `Last-Modified` of its timestamp, and a `Cache-Control` max age, answering
+/// conditional requests for an unchanged list with `304 Not Modified`.
+///
+/// The list is served as canonical JSON, so its `ETag` is the SHA-256 digest
+/// of the body. The list served may change, as with
+/// [`from_cache`](Self::from_cache); it's serialized once per change.
+#[derive(Clone)]
+pub struct TokenListHandler {
+    inner: Arc<Inner>,
+    max_age: Duration,
+}
+
+struct Inner {
+    current: Box<dyn Fn() -> Option<Arc<TokenList>> + Send + Sync>,
+    rendered: Mutex<Option<(Arc<TokenList>, Arc<Rendered>)>>,
+}
+
+/// A list serialized for serving.
+struct Rendered {
+    body: Vec<u8>,
+    etag: String,
+    last_modified: String,
+}
+
+impl TokenListHandler {
+    /// The `Cache-Control` max age of handlers that don't set one.
+    pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(300);
+
+    /// Creates a handler serving `token_list`.
+    pub fn new(token_list: TokenList) -> Self {
+        let token_list = Arc::new(token_list);
+        Self::from_fn(move || Some(token_list.clone()))
+    }
+
+    /// Creates a handler serving the list `current` returns on each request,
+    /// or `503 Service Unavailable` while it returns `None`.
+    pub fn from_fn<F>(current: F) -> Self
+    where
+        F: Fn() -> Option<Arc<TokenList>> + Send + Sync + 'static,
+    {
+        TokenListHandler {
+            inner: Arc::new(Inner {
+                current: Box::new(current),
+                rendered: Mutex::new(None),
+            }),
+            max_age: Self::DEFAULT_MAX_AGE,
+        }
+    }
+
+    /// Creates a handler serving the list `cache` holds for `uri`, which it
+    /// refreshes in the background as it goes stale.
+    ///
+    /// **Note**: This must be used from within a running tokio >1.0.0 runtime.
+    #[cfg(feature = "cache")]
+    pub fn from_cache(cache: TokenListCache, uri: &str) -> Self {
+        let uri = uri.to_owned();
+        Self::from_fn(move || cache.get(&uri))
+    }
+
+    /// Creates a handler serving the list `service` keeps up to date.
+    #[cfg(feature = "service")]
+    pub fn from_service(service: TokenListService) -> Self {
+        Self::from_fn(move || service.current())
+    }
+
+    /// Sets the `Cache-Control` max age of responses, five minutes by
+    /// default.
+    pub fn with_max_age(self, max_age: Duration) -> Self {
+        TokenListHandler { max_age, ..self }
+    }
+
+    /// Returns a router serving the list at `path` for `GET` and `HEAD`
+    /// requests.
+    pub fn router<S>(self, path: &str) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        Router::new().route(
+            path,
+            get(move |headers: HeaderMap| async move { self.respond(&headers) }),
+        )
+    }
+
+    /// Responds to a request for the list with `headers`.
+    pub fn respond(&self, headers: &HeaderMap) -> Response {
+        let rendered = match self.rendered() {
+            Some(Ok(rendered)) => rendered,
+            Some(Err(err)) => {
+                return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+            }
+            None => return StatusCode::SERVICE_UNAVAILABLE.into_response(),
+        };
+
+        let mut response = match rendered.is_fresh(headers) {
+            true => StatusCode::NOT_MODIFIED.into_response(),
+            false => {
+                let mut response = rendered.body.clone().into_response();
+                response
+                    .headers_mut()
+                    .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+                response
+            }
+        };
+        let response_headers = response.headers_mut();
+        let cache_control = format!("public, max-age={}", self.max_age.as_secs());
+        for (name, value) in [
+            (ETAG, &rendered.etag),
+            (LAST_MODIFIED, &rendered.last_modified),
+            (CACHE_CONTROL, &cache_control),
+        ] {
+            response_headers.insert(name, HeaderValue::from_str(value).expect("valid header"));
+        }
+        response
+    }
+
+    /// Returns the current list serialized, reusing the last serialization if
+    /// the list hasn't changed, or `None` if there is no list.
+    fn rendered(&self) -> Option<serde_json::Result<Arc<Rendered>>> {
+        let token_list = (self.inner.current)()?;
+
+        let mut rendered = self.inner.rendered.lock().unwrap();
+        if let Some((last, last_rendered)) = &*rendered {
+            if Arc::ptr_eq(last, &token_list) {
+                return Some(Ok(last_rendered.clone()));
+            }
+        }
+        let body = match canonical_json(&token_list) {
+            Ok(body) => body,
+            Err(err) => return Some(Err(err)),
+        };
+        let new = Arc::new(Rendered {
+            etag: format!("\"{}\"", to_hex(&Sha256::digest(&body))),
+            last_modified: http_date(&token_list.timestamp),
+            body,
+        });
+        *rendered = Some((token_list, new.clone()));
+        Some(Ok(new))
+    }
+}
+
+impl Rendered {
+    /// Returns `true` if a client sending `headers` already has this list.
+    ///
+    /// As in RFC 9110, `If-Modified-Since` is ignored when `If-None-Match`
+    /// is present.
+    fn is_fresh(&self, headers: &HeaderMap) -> bool {
+        if let Some(if_none_match) = headers.get(IF_NONE_MATCH) {
+            let if_none_match = if_none_match.to_str().unwrap_or_default();
+            return if_none_match
+                .split(',')
+                .map(str::trim)
+                .any(|etag| etag == "*" || etag.strip_prefix("W/").unwrap_or(etag) == self.etag);
+        }
+
+        let since = headers
+            .get(IF_MODIFIED_SINCE)
+            .and_then(|since| since.to_str().ok())
+            .and_then(|since| DateTime::parse_from_rfc2822(since).ok());
+        let last_modified = DateTime::parse_from_rfc2822(&self.last_modified).ok();
+        match (since, last_modified) {
+            (Some(since), Some(last_modified)) => last_modified <= since,
+            _ => false,
+        }
+    }
+}
+
+/// Formats `timestamp` as an HTTP date, in GMT.
+fn http_date(timestamp: &Timestamp) -> String {
+    crate::timestamp::format_utc(timestamp, HTTP_DATE)
+}
+
+impl fmt::Debug for TokenListHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenListHandler")
+            .field("max_age", &self.max_age)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::axum::body::Body;
+    use ::axum::http::Request;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    fn request(name: &str, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            ::axum::http::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+            HeaderValue::from_str(value).unwrap(),
+        );
+        headers
+    }
+
+    #[tokio::test]
+    async fn serves_lists_with_caching_headers() {
+        let token_list = fixtures::full();
+        let router: Router = TokenListHandler::new(token_list.clone())
+            .with_max_age(Duration::from_secs(60))
+            .router("/tokens.json");
+
+        let response = router
+            .oneshot(Request::get("/tokens.json").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(headers[CONTENT_TYPE], "application/json");
+        assert_eq!(headers[CACHE_CONTROL], "public, max-age=60");
+        assert_eq!(headers[LAST_MODIFIED], http_date(&token_list.timestamp));
+        let body = ::axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<TokenList>(&body).unwrap(),
+            token_list
+        );
+    }
+
+    #[test]
+    fn answers_conditional_requests() {
+        let handler = TokenListHandler::new(fixtures::full());
+        let response = handler.respond(&HeaderMap::new());
+        let etag = response.headers()[ETAG].to_str().unwrap().to_owned();
+        let last_modified = response.headers()[LAST_MODIFIED]
+            .to_str()
+            .unwrap()
+            .to_owned();
+
+        let not_modified = |headers| handler.respond(&headers).status() == StatusCode::NOT_MODIFIED;
+        assert!(not_modified(request("if-none-match", &etag)));
+        assert!(not_modified(request(
+            "if-none-match",
+            &format!("\"x\", W/{}", etag)
+        )));
+        assert!(not_modified(request("if-modified-since", &last_modified)));
+        assert!(!not_modified(request("if-none-match", "\"x\"")));
+        assert!(!not_modified(request(
+            "if-modified-since",
+            "Sat, 01 Jan 2000 00:00:00 GMT"
+        )));
+    }
+
+    #[test]
+    fn serves_the_current_list() {
+        let current = Arc::new(Mutex::new(None));
+        let handler = TokenListHandler::from_fn({
+            let current = current.clone();
+            move || current.lock().unwrap().clone()
+        });
+        assert_eq!(
+            handler.respond(&HeaderMap::new()).status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        let mut token_list = fixtures::full();
+        *current.lock().unwrap() = Some(Arc::new(token_list.clone()));
+        let first = handler.respond(&HeaderMap::new()).headers()[ETAG].clone();
+        token_list.tokens.pop();
+        *current.lock().unwrap() = Some(Arc::new(token_list));
+        assert_ne!(handler.respond(&HeaderMap::new()).headers()[ETAG], first);
+    }
+}
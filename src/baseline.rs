@@ -0,0 +1,161 @@
+//! Detection of tokens impersonating those of a trusted list.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::{Token, TokenList};
+
+/// The tokens of a list that impersonate tokens of a trusted baseline, found
+/// with [`TokenList::compare_against_baseline`].
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct BaselineReport {
+    /// The tokens whose address differs from that of the baseline token with
+    /// their symbol or name, in list order
+    pub address_changes: Vec<AddressChange>,
+}
+
+impl BaselineReport {
+    /// Returns `true` if no token impersonates a baseline token.
+    pub fn is_clean(&self) -> bool {
+        self.address_changes.is_empty()
+    }
+}
+
+/// A token with the symbol or name of a baseline token on the same chain, at
+/// an address the baseline doesn't list there.
+#[derive(PartialEq, Clone, Debug)]
+pub struct AddressChange {
+    /// The token in the compared list
+    pub token: Token,
+
+    /// The baseline token it matches
+    pub baseline: Token,
+
+    /// Which of the baseline token's fields it matches
+    pub matched: MatchedOn,
+}
+
+impl fmt::Display for AddressChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} on chain {} moved from {} to {} (same {})",
+            self.baseline.symbol,
+            self.token.chain_id,
+            self.baseline.address,
+            self.token.address,
+            self.matched
+        )
+    }
+}
+
+/// The fields a token shares with the baseline token it impersonates.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum MatchedOn {
+    /// Only the symbol
+    Symbol,
+
+    /// Only the name
+    Name,
+
+    /// Both the symbol and the name
+    SymbolAndName,
+}
+
+impl fmt::Display for MatchedOn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MatchedOn::Symbol => "symbol",
+            MatchedOn::Name => "name",
+            MatchedOn::SymbolAndName => "symbol and name",
+        })
+    }
+}
+
+impl TokenList {
+    /// Finds tokens impersonating those of `trusted`: tokens with the symbol
+    /// or name of a trusted token on the same chain, but at an address
+    /// `trusted` doesn't list on that chain.
+    ///
+    /// Swapping a token's address while keeping its symbol and name is how
+    /// hijacked lists steer users to malicious contracts, so unlike a general
+    /// diff, this ignores added, removed, and renamed tokens. Symbols, names,
+    /// and addresses are compared case-insensitively.
+    pub fn compare_against_baseline(&self, trusted: &TokenList) -> BaselineReport {
+        let mut addresses = HashSet::new();
+        let mut symbols = HashMap::new();
+        let mut names = HashMap::new();
+        for token in &trusted.tokens {
+            addresses.insert((token.chain_id, token.address.to_lowercase()));
+            symbols
+                .entry((token.chain_id, token.symbol.to_lowercase()))
+                .or_insert(token);
+            names
+                .entry((token.chain_id, token.name.to_lowercase()))
+                .or_insert(token);
+        }
+
+        let mut report = BaselineReport::default();
+        for token in &self.tokens {
+            if addresses.contains(&(token.chain_id, token.address.to_lowercase())) {
+                continue;
+            }
+            let by_symbol = symbols.get(&(token.chain_id, token.symbol.to_lowercase()));
+            let by_name = names.get(&(token.chain_id, token.name.to_lowercase()));
+            let (baseline, matched) = match (by_symbol, by_name) {
+                (Some(symbol), Some(name)) if symbol == name => (symbol, MatchedOn::SymbolAndName),
+                (Some(symbol), _) => (symbol, MatchedOn::Symbol),
+                (None, Some(name)) => (name, MatchedOn::Name),
+                (None, None) => continue,
+            };
+            report.address_changes.push(AddressChange {
+                token: token.clone(),
+                baseline: (*baseline).clone(),
+                matched,
+            });
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    #[test]
+    fn flags_changed_addresses() {
+        let trusted = fixtures::full();
+        let mut token_list = trusted.clone();
+        let original = trusted.tokens[0].clone();
+        token_list.tokens[0].address = "0x000000000000000000000000000000000000dEaD".to_owned();
+        token_list.tokens.push(Token {
+            name: "Impostor".to_owned(),
+            symbol: original.symbol.to_lowercase(),
+            address: "0x000000000000000000000000000000000000bEEF".to_owned(),
+            ..original.clone()
+        });
+
+        let report = token_list.compare_against_baseline(&trusted);
+
+        assert_eq!(report.address_changes.len(), 2);
+        let change = &report.address_changes[0];
+        assert_eq!(change.baseline, original);
+        assert_eq!(change.matched, MatchedOn::SymbolAndName);
+        assert_eq!(report.address_changes[1].matched, MatchedOn::Symbol);
+    }
+
+    #[test]
+    fn ignores_other_changes() {
+        let trusted = fixtures::full();
+        let mut token_list = trusted.clone();
+        // a renamed token
+        token_list.tokens[0].symbol = "NEW".to_owned();
+        token_list.tokens[0].address = token_list.tokens[0].address.to_lowercase();
+        // the same symbol on another chain
+        token_list.tokens[1].chain_id = 100;
+        token_list.tokens[1].address = "0x000000000000000000000000000000000000dEaD".to_owned();
+
+        assert!(token_list.compare_against_baseline(&trusted).is_clean());
+    }
+}
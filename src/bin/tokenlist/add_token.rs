@@ -0,0 +1,132 @@
+//! `tokenlist add-token`: adds a token to a list file.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use token_list::{timestamp, RpcClient, Timestamp, Token, TokenList};
+use url::Url;
+
+use crate::{output, Result};
+
+/// Adds a token to a list, bumping the list's version and timestamp, and
+/// writes it back if it still validates.
+///
+/// The token's name, symbol, and decimals are read from its contract with
+/// `--rpc-url`, or must be given; any that are given override the contract's.
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The list to add the token to, as a file
+    list: PathBuf,
+
+    /// The chain the token is deployed on
+    #[arg(long)]
+    chain_id: u32,
+
+    /// The address of the token's contract
+    #[arg(long)]
+    address: String,
+
+    /// The JSON-RPC endpoint of the token's chain, to read its metadata from
+    #[arg(long, env = "TOKENLIST_RPC_URL")]
+    rpc_url: Option<String>,
+
+    /// The token's name
+    #[arg(long, required_unless_present = "rpc_url")]
+    name: Option<String>,
+
+    /// The token's symbol
+    #[arg(long, required_unless_present = "rpc_url")]
+    symbol: Option<String>,
+
+    /// The token's decimals
+    #[arg(long, required_unless_present = "rpc_url")]
+    decimals: Option<u8>,
+
+    /// The token's logo URI
+    #[arg(long)]
+    logo_uri: Option<Url>,
+
+    /// A tag of the token, which the list must define; repeat for several
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+
+    /// Write the list to this file rather than back to the list file
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// The timestamp to give the list, rather than now, in RFC 3339 form
+    #[arg(long, value_parser = crate::parse_timestamp)]
+    timestamp: Option<Timestamp>,
+}
+
+pub async fn run(args: Args) -> Result<ExitCode> {
+    let bytes =
+        std::fs::read(&args.list).map_err(|err| format!("{}: {}", args.list.display(), err))?;
+    let previous = TokenList::from_slice(&bytes)?;
+    let exists = previous.tokens.iter().any(|token| {
+        token.chain_id == args.chain_id && token.address.eq_ignore_ascii_case(&args.address)
+    });
+    if exists {
+        return Err(format!(
+            "{} on chain {} is already listed",
+            args.address, args.chain_id
+        )
+        .into());
+    }
+
+    let mut token = match &args.rpc_url {
+        Some(rpc_url) => {
+            Token::from_contract(
+                &RpcClient::new(rpc_url.as_str())?,
+                args.chain_id,
+                &args.address,
+            )
+            .await?
+        }
+        None => Token {
+            name: String::new(),
+            symbol: String::new(),
+            address: args.address.clone(),
+            chain_id: args.chain_id,
+            decimals: 0,
+            logo_uri: None,
+            tags: Vec::new(),
+            extensions: Default::default(),
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: Default::default(),
+        },
+    };
+    if let Some(name) = args.name {
+        token.name = name;
+    }
+    if let Some(symbol) = args.symbol {
+        token.symbol = symbol;
+    }
+    if let Some(decimals) = args.decimals {
+        token.decimals = decimals;
+    }
+    token.logo_uri = args.logo_uri;
+    token.tags = args.tags;
+
+    let mut token_list = previous.clone();
+    token_list.tokens.push(token);
+    let timestamp = args.timestamp.unwrap_or_else(timestamp::now);
+    let bump = token_list.bump_from(&previous, timestamp);
+    eprintln!(
+        "{} -> {} ({} bump)",
+        previous.version, token_list.version, bump
+    );
+
+    let validation = token_list.validate();
+    for diagnostic in &validation.diagnostics {
+        eprintln!("{}", diagnostic);
+    }
+    if validation.has_errors() {
+        eprintln!("error: the list would be invalid; not writing it");
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let path = args.output.as_deref().unwrap_or(&args.list);
+    output::write(Some(path), &output::to_json(&token_list)?)?;
+    Ok(ExitCode::SUCCESS)
+}
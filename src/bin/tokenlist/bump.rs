@@ -0,0 +1,60 @@
+//! `tokenlist bump`: sets the version and timestamp of a new release.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use token_list::{timestamp, Timestamp, TokenList};
+
+use crate::{input, output, Result};
+
+/// Sets the version of a new release of a list to that of the previous
+/// release, bumped as its token changes call for, and its timestamp to now.
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The previous release: a file, a URI, or `-` for standard input
+    previous: String,
+
+    /// The list to release, as a file
+    list: PathBuf,
+
+    /// Write the bumped list to this file rather than standard output
+    #[arg(short, long, conflicts_with = "in_place")]
+    output: Option<PathBuf>,
+
+    /// Overwrite the list to release with the bumped list
+    #[arg(short, long)]
+    in_place: bool,
+
+    /// Only print the new version, without writing the list
+    #[arg(long, conflicts_with_all = ["output", "in_place"])]
+    dry_run: bool,
+
+    /// The timestamp to give the release, rather than now, in RFC 3339 form
+    #[arg(long, value_parser = crate::parse_timestamp)]
+    timestamp: Option<Timestamp>,
+}
+
+pub async fn run(args: Args) -> Result<ExitCode> {
+    let previous = TokenList::from_slice(&input::read(&args.previous).await?)?;
+    let bytes =
+        std::fs::read(&args.list).map_err(|err| format!("{}: {}", args.list.display(), err))?;
+    let mut token_list = TokenList::from_slice(&bytes)?;
+
+    let timestamp = args.timestamp.unwrap_or_else(timestamp::now);
+    let bump = token_list.bump_from(&previous, timestamp);
+    eprintln!(
+        "{} -> {} ({} bump)",
+        previous.version, token_list.version, bump
+    );
+
+    if args.dry_run {
+        println!("{}", token_list.version);
+        return Ok(ExitCode::SUCCESS);
+    }
+    let path = match args.in_place {
+        true => Some(args.list.as_path()),
+        false => args.output.as_deref(),
+    };
+    output::write(path, &output::to_json(&token_list)?)?;
+    Ok(ExitCode::SUCCESS)
+}
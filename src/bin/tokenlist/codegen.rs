@@ -0,0 +1,59 @@
+//! `tokenlist codegen`: generates Rust constants for the tokens of a list.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use token_list::{Codegen, TokenList};
+
+use crate::{input, output, Result};
+
+/// Generates a Rust module with a constant for each token in a list, such as
+/// `mainnet::TEL`, grouped by chain.
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The list: a file, a URI, or `-` for standard input
+    list: String,
+
+    /// Write the module to this file rather than standard output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Names the module of a chain's tokens, as `<chain ID>=<name>`; may be
+    /// repeated
+    #[arg(long = "chain-name", value_name = "CHAIN_ID=NAME", value_parser = parse_chain_name)]
+    chain_names: Vec<(u32, String)>,
+}
+
+fn parse_chain_name(value: &str) -> std::result::Result<(u32, String), String> {
+    let (chain_id, name) = value.split_once('=').ok_or("expected <chain ID>=<name>")?;
+    let chain_id = chain_id
+        .parse()
+        .map_err(|err| format!("invalid chain ID: {}", err))?;
+    Ok((chain_id, name.to_owned()))
+}
+
+pub async fn run(args: Args) -> Result<ExitCode> {
+    let token_list = TokenList::from_slice(&input::read(&args.list).await?)?;
+
+    let mut codegen = Codegen::new(&token_list);
+    for (chain_id, name) in &args.chain_names {
+        codegen = codegen.chain_name(*chain_id, name);
+    }
+    output::write(args.output.as_deref(), codegen.generate().as_bytes())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_chain_names() {
+        assert_eq!(
+            parse_chain_name("1=ethereum"),
+            Ok((1, "ethereum".to_owned()))
+        );
+        assert!(parse_chain_name("ethereum").is_err());
+        assert!(parse_chain_name("one=ethereum").is_err());
+    }
+}
@@ -0,0 +1,107 @@
+//! `tokenlist diff`: compares the tokens of two lists.
+
+use std::fmt::Write;
+use std::process::ExitCode;
+
+use clap::ValueEnum;
+use serde_json::json;
+use token_list::{Token, TokenList, TokenListDiff};
+
+use crate::{input, Result};
+
+/// Compares the tokens of two versions of a list, printing what was added,
+/// removed, and changed, and the version bump that calls for.
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The old list: a file, a URI, or `-` for standard input
+    old: String,
+
+    /// The new list: a file, a URI, or `-` for standard input
+    new: String,
+
+    /// How to print the differences
+    #[arg(long, value_enum, default_value_t = Format::Markdown)]
+    format: Format,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+pub enum Format {
+    /// A Markdown summary, e.g. for release notes
+    Markdown,
+    /// A single JSON object
+    Json,
+}
+
+pub async fn run(args: Args) -> Result<ExitCode> {
+    let old = TokenList::from_slice(&input::read(&args.old).await?)?;
+    let new = TokenList::from_slice(&input::read(&args.new).await?)?;
+    let diff = old.diff(&new);
+
+    match args.format {
+        Format::Markdown => print!("{}", markdown(&diff)),
+        Format::Json => println!(
+            "{}",
+            json!({
+                "versionBump": diff.version_bump(),
+                "added": diff.added,
+                "removed": diff.removed,
+                "changed": diff.changed,
+            })
+        ),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Formats `diff` as a Markdown summary.
+fn markdown(diff: &TokenListDiff) -> String {
+    let mut output = format!("## Changes ({} version bump)\n", diff.version_bump());
+    if diff.is_empty() {
+        output.push_str("\nNo tokens changed.\n");
+    }
+
+    let mut section = |title: &str, lines: Vec<String>| {
+        if !lines.is_empty() {
+            let _ = write!(output, "\n### {}\n\n", title);
+            for line in lines {
+                let _ = writeln!(output, "- {}", line);
+            }
+        }
+    };
+    section("Added", diff.added.iter().map(describe).collect());
+    section("Removed", diff.removed.iter().map(describe).collect());
+    section(
+        "Changed",
+        diff.changed
+            .iter()
+            .map(|change| format!("{}: {}", describe(&change.after), change.fields.join(", ")))
+            .collect(),
+    );
+    output
+}
+
+fn describe(token: &Token) -> String {
+    format!(
+        "`{}` {} (`{}` on chain {})",
+        token.symbol, token.name, token.address, token.chain_id
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_diffs_in_markdown() {
+        let old: TokenList =
+            serde_json::from_str(include_str!("../../../fixtures/full.json")).unwrap();
+        let mut new = old.clone();
+        let removed = new.tokens.remove(0);
+
+        let output = markdown(&old.diff(&new));
+
+        assert!(output.starts_with("## Changes (major version bump)\n\n### Removed\n\n"));
+        assert!(output.contains(&format!("- {}\n", describe(&removed))));
+        assert!(!output.contains("### Added"));
+        assert!(markdown(&old.diff(&old)).contains("No tokens changed."));
+    }
+}
@@ -0,0 +1,106 @@
+//! `tokenlist fetch`: downloads a list, verifies it, and saves it in
+//! canonical form.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use token_list::{ipfs_gateway_uri, ListRegistry, RawTokenList, RpcClient};
+
+use crate::{input, output, Result};
+
+/// Downloads a list from a URI, an `ipfs://` or `ipns://` URI, or an ENS name,
+/// checks it against expected hashes, and prints its hashes and IPFS CID.
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The list to fetch: a URI, a file, `-` for standard input, or an ENS
+    /// name such as `ens://tokens.example.eth` whose contenthash points to it
+    source: String,
+
+    /// Write the list as canonical JSON to this file
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Fail unless the SHA-256 digest of the published bytes is this
+    #[arg(long, value_name = "HEX")]
+    expect_sha256: Option<String>,
+
+    /// Fail unless the IPFS CID of the canonical JSON is this
+    #[arg(long, value_name = "CID")]
+    expect_cid: Option<String>,
+
+    /// The Ethereum JSON-RPC endpoint to resolve ENS names through
+    #[arg(long, env = "TOKENLIST_RPC_URL")]
+    rpc_url: Option<String>,
+
+    /// The HTTP gateway to fetch `ipfs://` and `ipns://` URIs through
+    #[arg(long, default_value = "https://ipfs.io")]
+    ipfs_gateway: String,
+}
+
+pub async fn run(args: Args) -> Result<ExitCode> {
+    let uri = match ens_name(&args.source) {
+        Some(name) => {
+            let rpc_url = args
+                .rpc_url
+                .as_deref()
+                .ok_or("--rpc-url is required to resolve ENS names")?;
+            let uri = ListRegistry::new(RpcClient::new(rpc_url)?)
+                .resolve(name)
+                .await?;
+            eprintln!("{} resolves to {}", name, uri);
+            uri
+        }
+        None => args.source.clone(),
+    };
+    let bytes = input::read(&ipfs_gateway_uri(&uri, &args.ipfs_gateway)).await?;
+    let raw = RawTokenList::from_bytes(bytes)?;
+    let release = raw.token_list.ipfs_release()?;
+
+    println!("sha256: {}", raw.sha256_hex());
+    println!("content-hash: {}", release.sha256_hex());
+    println!("cid: {}", release.cid);
+
+    let mut verified = true;
+    if let Some(expected) = &args.expect_sha256 {
+        if !expected.eq_ignore_ascii_case(&raw.sha256_hex()) {
+            eprintln!("error: expected SHA-256 {}", expected);
+            verified = false;
+        }
+    }
+    if let Some(expected) = &args.expect_cid {
+        if *expected != release.cid {
+            eprintln!("error: expected CID {}", expected);
+            verified = false;
+        }
+    }
+    if !verified {
+        return Ok(ExitCode::FAILURE);
+    }
+
+    if let Some(path) = &args.output {
+        output::write(Some(path), &release.bytes)?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Returns the ENS name `source` refers to, if it's `ens://` followed by a
+/// name.
+fn ens_name(source: &str) -> Option<&str> {
+    source.strip_prefix("ens://")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_ens_names() {
+        assert_eq!(
+            ens_name("ens://tokens.example.eth"),
+            Some("tokens.example.eth")
+        );
+        assert_eq!(ens_name("tokens.example.eth"), None);
+        assert_eq!(ens_name("https://example.eth"), None);
+        assert_eq!(ens_name("list.json"), None);
+    }
+}
@@ -0,0 +1,47 @@
+//! Reading lists from files, standard input, and URIs.
+
+use std::io::Read;
+
+use token_list::{Fetcher, ReqwestFetcher};
+
+use crate::Result;
+
+/// Reads the contents of `source`: `-` for standard input, a URI with a
+/// scheme, or otherwise a file path.
+pub async fn read(source: &str) -> Result<Vec<u8>> {
+    if source == "-" {
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes)?;
+        return Ok(bytes);
+    }
+    if is_uri(source) {
+        return Ok(ReqwestFetcher::default().fetch(source).await?);
+    }
+    std::fs::read(source).map_err(|err| format!("{}: {}", source, err).into())
+}
+
+/// Returns `true` if `source` is a URI rather than a file path.
+fn is_uri(source: &str) -> bool {
+    source.contains("://") || source.starts_with("data:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_uris() {
+        assert!(is_uri("https://example.com/list.json"));
+        assert!(is_uri("data:application/json,{}"));
+        assert!(!is_uri("lists/list.json"));
+        assert!(!is_uri("C:\\lists\\list.json"));
+    }
+
+    #[tokio::test]
+    async fn reads_files() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/full.json");
+
+        assert_eq!(read(path).await.unwrap(), std::fs::read(path).unwrap());
+        assert!(read("missing.json").await.is_err());
+    }
+}
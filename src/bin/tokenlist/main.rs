@@ -0,0 +1,71 @@
+//! `tokenlist`, a command-line tool for maintaining token lists.
+
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use token_list::{timestamp, Timestamp};
+
+mod add_token;
+mod bump;
+mod codegen;
+mod convert;
+mod diff;
+mod fetch;
+mod input;
+mod merge;
+mod output;
+mod validate;
+mod verify_onchain;
+
+/// The errors the tool reports before exiting with status 2.
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Parses a `--timestamp` argument in RFC 3339 form.
+fn parse_timestamp(value: &str) -> std::result::Result<Timestamp, String> {
+    timestamp::parse_rfc3339(value).ok_or_else(|| "expected an RFC 3339 timestamp".to_owned())
+}
+
+/// Tools for maintaining token lists.
+#[derive(Parser, Debug)]
+#[command(name = "tokenlist", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    Validate(validate::Args),
+    Diff(diff::Args),
+    Bump(bump::Args),
+    Fetch(fetch::Args),
+    Convert(convert::Args),
+    Merge(merge::Args),
+    VerifyOnchain(verify_onchain::Args),
+    AddToken(add_token::Args),
+    Codegen(codegen::Args),
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Validate(args) => validate::run(args).await,
+        Command::Diff(args) => diff::run(args).await,
+        Command::Bump(args) => bump::run(args).await,
+        Command::Fetch(args) => fetch::run(args).await,
+        Command::Convert(args) => convert::run(args).await,
+        Command::Merge(args) => merge::run(args).await,
+        Command::VerifyOnchain(args) => verify_onchain::run(args).await,
+        Command::AddToken(args) => add_token::run(args).await,
+        Command::Codegen(args) => codegen::run(args).await,
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::from(2)
+        }
+    }
+}
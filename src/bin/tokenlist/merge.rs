@@ -0,0 +1,217 @@
+//! `tokenlist merge`: combines several lists into one.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use semver::Version;
+use token_list::{timestamp, MergeReport, MergeStrategy, Timestamp, TokenList};
+use url::Url;
+
+use crate::{input, output, Result};
+
+/// Merges the tokens of several lists into one, resolving lists that disagree
+/// about a token with a strategy, and validates the result.
+///
+/// The merged list takes its metadata from the first list unless given by the
+/// options below, and is written only if it has no validation errors.
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The lists to merge: files, URIs, or `-` for standard input
+    #[arg(required = true, num_args = 2..)]
+    inputs: Vec<String>,
+
+    /// How to pick between lists that disagree about a token:
+    /// `prefer-first`, `prefer-newest`, or `quorum=N` to keep only tokens in at
+    /// least N lists, with the metadata most of them agree on
+    #[arg(long, default_value_t = MergeStrategy::PreferFirst)]
+    strategy: MergeStrategy,
+
+    /// Write the merged list to this file rather than standard output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// The name of the merged list
+    #[arg(long)]
+    name: Option<String>,
+
+    /// The version of the merged list
+    #[arg(long)]
+    version: Option<Version>,
+
+    /// The timestamp of the merged list, rather than now, in RFC 3339 form
+    #[arg(long, value_parser = crate::parse_timestamp)]
+    timestamp: Option<Timestamp>,
+
+    /// The logo URI of the merged list
+    #[arg(long)]
+    logo_uri: Option<Url>,
+
+    /// A keyword of the merged list, replacing those of the first list;
+    /// repeat for several
+    #[arg(long = "keyword")]
+    keywords: Vec<String>,
+}
+
+pub async fn run(args: Args) -> Result<ExitCode> {
+    let mut lists = Vec::with_capacity(args.inputs.len());
+    for source in &args.inputs {
+        let token_list = TokenList::from_slice(&input::read(source).await?)
+            .map_err(|err| format!("{}: {}", source, err))?;
+        lists.push(token_list);
+    }
+
+    let (merged, report) = merge(&lists, &args)?;
+    for conflict in &report.conflicts {
+        eprintln!(
+            "note: lists {} disagree about {} on chain {}; took list {}",
+            sources(&args.inputs, &conflict.sources),
+            conflict.address,
+            conflict.chain_id,
+            conflict.chosen.map_or("", |i| &args.inputs[i]),
+        );
+    }
+    for dropped in &report.below_quorum {
+        eprintln!(
+            "note: dropped {} on chain {}, in only {}",
+            dropped.address,
+            dropped.chain_id,
+            sources(&args.inputs, &dropped.sources),
+        );
+    }
+
+    let validation = merged.validate();
+    for diagnostic in &validation.diagnostics {
+        eprintln!("{}", diagnostic);
+    }
+    if validation.has_errors() {
+        eprintln!("error: the merged list is invalid; not writing it");
+        return Ok(ExitCode::FAILURE);
+    }
+
+    output::write(args.output.as_deref(), &output::to_json(&merged)?)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Merges `lists` with the strategy in `args`, and gives the merged list the
+/// metadata in `args`.
+fn merge(lists: &[TokenList], args: &Args) -> Result<(TokenList, MergeReport)> {
+    let (mut merged, report) = TokenList::merge(lists, args.strategy).ok_or("no lists to merge")?;
+
+    if let Some(name) = &args.name {
+        merged.name = name.clone();
+    }
+    if let Some(version) = &args.version {
+        merged.version = version.clone();
+    }
+    merged.timestamp = args.timestamp.unwrap_or_else(timestamp::now);
+    if args.logo_uri.is_some() {
+        merged.logo_uri = args.logo_uri.clone();
+    }
+    if !args.keywords.is_empty() {
+        merged.keywords = args.keywords.clone();
+    }
+    Ok((merged, report))
+}
+
+/// Lists the inputs at `indices`, for notes.
+fn sources(inputs: &[String], indices: &[usize]) -> String {
+    indices
+        .iter()
+        .map(|&i| inputs[i].as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: Args,
+    }
+
+    fn args(flags: &[&str]) -> Args {
+        let argv = ["merge", "first.json", "second.json"];
+        Cli::try_parse_from(argv.iter().chain(flags)).unwrap().args
+    }
+
+    /// The fixture, and a newer copy with the first token renamed and the
+    /// second removed.
+    fn lists() -> Vec<TokenList> {
+        let first: TokenList =
+            serde_json::from_str(include_str!("../../../fixtures/full.json")).unwrap();
+        let mut second = first.clone();
+        second.name = "Second".to_owned();
+        second.timestamp = timestamp::parse_rfc3339("2030-01-01T00:00:00Z").unwrap();
+        second.tokens[0].name = "Renamed".to_owned();
+        second.tokens.truncate(1);
+        vec![first, second]
+    }
+
+    #[test]
+    fn resolves_conflicts_with_the_strategy() {
+        let lists = lists();
+
+        let (merged, report) = merge(&lists, &args(&[])).unwrap();
+        assert_eq!(merged.tokens, lists[0].tokens);
+        assert_eq!(report.conflicts.len(), 1);
+
+        let (merged, _) = merge(&lists, &args(&["--strategy", "prefer-newest"])).unwrap();
+        assert_eq!(merged.tokens[0].name, "Renamed");
+        assert_eq!(merged.tokens.len(), 2);
+
+        let (merged, report) = merge(&lists, &args(&["--strategy", "quorum=2"])).unwrap();
+        assert_eq!(merged.tokens.len(), 1);
+        assert_eq!(report.below_quorum[0].address, lists[0].tokens[1].address);
+
+        assert!(
+            Cli::try_parse_from(["merge", "a.json", "b.json", "--strategy", "quorum=0"]).is_err()
+        );
+    }
+
+    #[test]
+    fn takes_metadata_from_flags_or_the_first_list() {
+        let lists = lists();
+
+        let (merged, _) = merge(&lists, &args(&[])).unwrap();
+        assert_eq!(merged.name, lists[0].name);
+        assert_eq!(merged.version, lists[0].version);
+        assert_eq!(merged.logo_uri, lists[0].logo_uri);
+        assert_eq!(merged.keywords, lists[0].keywords);
+        assert!(merged.timestamp > lists[0].timestamp);
+
+        let (merged, _) = merge(
+            &lists,
+            &args(&[
+                "--name",
+                "Merged",
+                "--version",
+                "2.0.0",
+                "--timestamp",
+                "2031-01-01T00:00:00Z",
+                "--logo-uri",
+                "https://example.com/logo.png",
+                "--keyword",
+                "merged",
+                "--keyword",
+                "tokens",
+            ]),
+        )
+        .unwrap();
+        assert_eq!(merged.name, "Merged");
+        assert_eq!(merged.version, Version::new(2, 0, 0));
+        assert_eq!(
+            merged.timestamp,
+            timestamp::parse_rfc3339("2031-01-01T00:00:00Z").unwrap()
+        );
+        assert_eq!(
+            merged.logo_uri.unwrap().as_str(),
+            "https://example.com/logo.png"
+        );
+        assert_eq!(merged.keywords, ["merged", "tokens"]);
+    }
+}
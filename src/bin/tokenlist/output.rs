@@ -0,0 +1,27 @@
+//! Writing lists to files and standard output.
+
+use std::io::Write;
+use std::path::Path;
+
+use token_list::TokenList;
+
+use crate::Result;
+
+/// Writes `bytes` to the file at `path`, or to standard output if there is
+/// none.
+pub fn write(path: Option<&Path>, bytes: &[u8]) -> Result<()> {
+    match path {
+        Some(path) => {
+            std::fs::write(path, bytes).map_err(|err| format!("{}: {}", path.display(), err))?
+        }
+        None => std::io::stdout().write_all(bytes)?,
+    }
+    Ok(())
+}
+
+/// Serializes `token_list` as pretty-printed JSON with a trailing newline.
+pub fn to_json(token_list: &TokenList) -> Result<Vec<u8>> {
+    let mut json = serde_json::to_vec_pretty(token_list)?;
+    json.push(b'\n');
+    Ok(json)
+}
@@ -0,0 +1,142 @@
+//! `tokenlist validate`: checks a list against the schema and lints it.
+
+use std::process::ExitCode;
+
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use token_list::{Diagnostic, TokenList, ValidationReport};
+
+use crate::{input, Result};
+
+/// Checks a list against the token list schema and lints it for likely
+/// mistakes, exiting with status 1 if it has errors.
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The list to validate: a file, a URI, or `-` for standard input
+    input: String,
+
+    /// How to print diagnostics
+    #[arg(long, value_enum, default_value_t = Format::Human)]
+    format: Format,
+
+    /// Exit with status 1 on warnings too
+    #[arg(long)]
+    strict: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+pub enum Format {
+    /// One line per diagnostic, then a summary
+    Human,
+    /// A single JSON object
+    Json,
+}
+
+pub async fn run(args: Args) -> Result<ExitCode> {
+    let bytes = input::read(&args.input).await?;
+    let outcome = TokenList::from_slice(&bytes)
+        .map(|token_list| token_list.validate())
+        .map_err(|err| err.to_string());
+
+    let failed = match &outcome {
+        Ok(report) => report.has_errors() || (args.strict && !report.is_clean()),
+        Err(_) => true,
+    };
+    match args.format {
+        Format::Human => print!("{}", human(&args.input, &outcome)),
+        Format::Json => println!("{}", self::json(&outcome)),
+    }
+
+    Ok(match failed {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    })
+}
+
+/// Formats the outcome of validating `input` for people.
+fn human(input: &str, outcome: &std::result::Result<ValidationReport, String>) -> String {
+    let report = match outcome {
+        Ok(report) => report,
+        Err(err) => return format!("error: {} is not a valid token list: {}\n", input, err),
+    };
+
+    let mut output = String::new();
+    for diagnostic in &report.diagnostics {
+        output.push_str(&format!("{}\n", diagnostic));
+    }
+    output.push_str(&format!(
+        "{}: {} errors, {} warnings\n",
+        input,
+        report.errors().count(),
+        report.warnings().count()
+    ));
+    output
+}
+
+/// Formats the outcome of validation as JSON, for other tools.
+fn json(outcome: &std::result::Result<ValidationReport, String>) -> Value {
+    match outcome {
+        Ok(report) => json!({
+            "valid": !report.has_errors(),
+            "diagnostics": report.diagnostics.iter().map(diagnostic).collect::<Vec<_>>(),
+        }),
+        Err(err) => json!({
+            "valid": false,
+            "diagnostics": [{
+                "path": "",
+                "severity": "error",
+                "code": "parse",
+                "message": err,
+            }],
+        }),
+    }
+}
+
+fn diagnostic(diagnostic: &Diagnostic) -> Value {
+    json!({
+        "path": diagnostic.path,
+        "severity": diagnostic.severity.to_string(),
+        "code": diagnostic.kind.code(),
+        "message": diagnostic.kind.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> ValidationReport {
+        let mut token_list: TokenList =
+            serde_json::from_str(include_str!("../../../fixtures/full.json")).unwrap();
+        token_list.tokens[0].chain_id = 0;
+        token_list.validate()
+    }
+
+    #[test]
+    fn prints_diagnostics_for_people() {
+        let output = human("list.json", &Ok(report()));
+
+        assert!(output.contains("error: /tokens/0/chainId: chain ID must be at least 1\n"));
+        assert!(output.ends_with("list.json: 1 errors, 0 warnings\n"));
+    }
+
+    #[test]
+    fn prints_diagnostics_as_json() {
+        let output = json(&Ok(report()));
+
+        assert_eq!(output["valid"], false);
+        assert_eq!(
+            output["diagnostics"][0],
+            json!({
+                "path": "/tokens/0/chainId",
+                "severity": "error",
+                "code": "invalid-chain-id",
+                "message": "chain ID must be at least 1",
+            })
+        );
+        assert_eq!(
+            json(&Err("EOF".to_owned()))["diagnostics"][0]["code"],
+            "parse"
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! `tokenlist verify-onchain`: audits a list against its token contracts.
+
+use std::collections::HashMap;
+use std::process::ExitCode;
+
+use token_list::TokenList;
+
+use crate::{input, Result};
+
+/// Checks the name, symbol, and decimals of each token against its ERC-20
+/// contract, printing any differences and exiting with status 1 if there are
+/// any or some tokens couldn't be checked.
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The list to verify: a file, a URI, or `-` for standard input
+    input: String,
+
+    /// The JSON-RPC endpoint of a chain, as `CHAIN_ID=URL`; repeat for each
+    /// chain in the list
+    #[arg(long = "rpc-url", value_name = "CHAIN_ID=URL", value_parser = parse_endpoint, required = true)]
+    rpc_urls: Vec<(u32, String)>,
+}
+
+pub async fn run(args: Args) -> Result<ExitCode> {
+    let token_list = TokenList::from_slice(&input::read(&args.input).await?)?;
+    let rpc_urls: HashMap<_, _> = args.rpc_urls.into_iter().collect();
+    let report = token_list.verify_onchain(&rpc_urls).await;
+
+    for mismatch in &report.mismatches {
+        println!("mismatch: {}", mismatch);
+    }
+    for token in &report.missing_contracts {
+        println!("missing contract: {}", token);
+    }
+    for failed in &report.failed_calls {
+        println!("failed call: {}", failed);
+    }
+    for chain in &report.unreachable_chains {
+        println!("unreachable: {}", chain);
+    }
+    println!(
+        "{}: {} mismatches, {} missing contracts, {} failed calls, {} unreachable chains",
+        args.input,
+        report.mismatches.len(),
+        report.missing_contracts.len(),
+        report.failed_calls.len(),
+        report.unreachable_chains.len()
+    );
+
+    Ok(match report.is_clean() {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    })
+}
+
+/// Parses an endpoint given as `CHAIN_ID=URL`.
+fn parse_endpoint(s: &str) -> std::result::Result<(u32, String), String> {
+    let (chain_id, url) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected CHAIN_ID=URL, got `{}`", s))?;
+    let chain_id = chain_id
+        .parse()
+        .map_err(|_| format!("invalid chain ID `{}`", chain_id))?;
+    Ok((chain_id, url.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_endpoints() {
+        assert_eq!(
+            parse_endpoint("1=https://rpc.example/?key=a"),
+            Ok((1, "https://rpc.example/?key=a".to_owned()))
+        );
+        assert!(parse_endpoint("https://rpc.example").is_err());
+        assert!(parse_endpoint("mainnet=https://rpc.example").is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! A token list model that borrows its strings from the parsed input.
+
+use std::borrow::Cow;
+
+use indexmap::IndexMap;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{ExtensionValue, Tag, Timestamp, Token, TokenList};
+
+/// A [`TokenList`] whose name and token names, symbols, and addresses borrow
+/// from the input they were deserialized from where possible.
+///
+/// Parsing a list this way avoids allocating those strings for every token,
+/// which adds up for services that parse large lists only to inspect them.
+/// Strings containing escape sequences can't be borrowed and are allocated as
+/// usual. Use [`to_owned`](Self::to_owned) to convert to a [`TokenList`].
+///
+/// ```
+/// # use token_list::TokenListRef;
+/// let json = r#"{
+///     "name": "TELcoins",
+///     "timestamp": "2021-07-05T20:25:22+00:00",
+///     "version": { "major": 0, "minor": 1, "patch": 0 },
+///     "tokens": [{
+///         "name": "Telcoin",
+///         "symbol": "TEL",
+///         "address": "0x467Bccd9d29f223BcE8043b84E8C8B282827790F",
+///         "chainId": 1,
+///         "decimals": 2
+///     }]
+/// }"#;
+/// let token_list: TokenListRef = serde_json::from_str(json)?;
+///
+/// assert_eq!(token_list.tokens[0].symbol, "TEL");
+/// let token_list = token_list.to_owned();
+/// # Ok::<_, serde_json::Error>(())
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct TokenListRef<'a> {
+    /// The name of the token list
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+
+    /// The timestamp of this list version; i.e. when this immutable version of
+    /// the list was created
+    #[serde(with = "crate::timestamp")]
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(schema_with = "crate::timestamp::json_schema")
+    )]
+    pub timestamp: Timestamp,
+
+    /// The version of the list, used in change detection
+    #[serde(with = "crate::version")]
+    #[cfg_attr(feature = "schemars", schemars(with = "crate::version::VersionSchema"))]
+    pub version: Version,
+
+    /// A URI for the logo of the token list; prefer SVG or PNG of size 256x256
+    #[serde(rename = "logoURI", skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<Url>,
+
+    /// Keywords associated with the contents of the list; may be used in list
+    /// discoverability
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+
+    /// A mapping of tag identifiers to their name and description
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub tags: IndexMap<String, Tag>,
+
+    /// The list of tokens included in the list
+    #[serde(borrow, default, skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<TokenRef<'a>>,
+
+    /// Fields not defined by the token list schema
+    #[cfg(feature = "unknown-fields")]
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl TokenListRef<'_> {
+    /// Converts to an owned [`TokenList`], allocating any borrowed strings.
+    pub fn to_owned(&self) -> TokenList {
+        TokenList {
+            name: self.name.clone().into_owned(),
+            timestamp: self.timestamp,
+            version: self.version.clone(),
+            logo_uri: self.logo_uri.clone(),
+            keywords: self.keywords.clone(),
+            tags: self.tags.clone(),
+            tokens: self.tokens.iter().map(TokenRef::to_owned).collect(),
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: self.unknown_fields.clone(),
+        }
+    }
+}
+
+impl From<TokenListRef<'_>> for TokenList {
+    fn from(token_list: TokenListRef<'_>) -> Self {
+        TokenList {
+            name: token_list.name.into_owned(),
+            timestamp: token_list.timestamp,
+            version: token_list.version,
+            logo_uri: token_list.logo_uri,
+            keywords: token_list.keywords,
+            tags: token_list.tags,
+            tokens: token_list.tokens.into_iter().map(Token::from).collect(),
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: token_list.unknown_fields,
+        }
+    }
+}
+
+/// A [`Token`] whose name, symbol, and address borrow from the input they were
+/// deserialized from where possible.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct TokenRef<'a> {
+    /// The name of the token
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+
+    /// The symbol for the token; must be alphanumeric.
+    #[serde(borrow)]
+    pub symbol: Cow<'a, str>,
+
+    /// The checksummed address of the token on the specified chain ID
+    #[serde(borrow)]
+    pub address: Cow<'a, str>,
+
+    /// The chain ID of the Ethereum network where this token is deployed
+    pub chain_id: u32,
+
+    /// The number of decimals for the token balance
+    pub decimals: u8,
+
+    /// A URI to the token logo asset; if not set, interface will attempt to
+    /// find a logo based on the token address; suggest SVG or PNG of size 64x64
+    #[serde(rename = "logoURI", skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<Url>,
+
+    /// An array of tag identifiers associated with the token; tags are defined
+    /// at the list level
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// An object containing any arbitrary or vendor-specific token metadata
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub extensions: IndexMap<String, ExtensionValue>,
+
+    /// Fields not defined by the token list schema
+    #[cfg(feature = "unknown-fields")]
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl TokenRef<'_> {
+    /// Converts to an owned [`Token`], allocating any borrowed strings.
+    pub fn to_owned(&self) -> Token {
+        Token::from(self.clone())
+    }
+}
+
+impl From<TokenRef<'_>> for Token {
+    fn from(token: TokenRef<'_>) -> Self {
+        Token {
+            name: token.name.into_owned(),
+            symbol: token.symbol.into_owned(),
+            address: token.address.into_owned(),
+            chain_id: token.chain_id,
+            decimals: token.decimals,
+            logo_uri: token.logo_uri,
+            tags: token.tags,
+            extensions: token.extensions,
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: token.unknown_fields,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures::FULL;
+
+    #[test]
+    fn converts_to_owned() {
+        let token_list: TokenListRef = serde_json::from_str(FULL).unwrap();
+        let expected: TokenList = serde_json::from_str(FULL).unwrap();
+
+        assert_eq!(token_list.to_owned(), expected);
+        assert_eq!(TokenList::from(token_list), expected);
+    }
+
+    #[test]
+    fn borrows_unescaped_strings() {
+        let token_list: TokenListRef = serde_json::from_str(FULL).unwrap();
+
+        assert!(matches!(token_list.name, Cow::Borrowed("TELcoins")));
+        for token in &token_list.tokens {
+            assert!(matches!(token.name, Cow::Borrowed(_)));
+            assert!(matches!(token.symbol, Cow::Borrowed(_)));
+            assert!(matches!(token.address, Cow::Borrowed(_)));
+        }
+    }
+
+    #[test]
+    fn allocates_escaped_strings() {
+        let json = FULL.replace(r#""name": "Telcoin","#, r#""name": "Tel\u0063oin","#);
+        let token_list: TokenListRef = serde_json::from_str(&json).unwrap();
+
+        assert!(matches!(&token_list.tokens[0].name, Cow::Owned(name) if name == "Telcoin"));
+    }
+
+    #[test]
+    fn serializes_like_token_list() {
+        let token_list: TokenListRef = serde_json::from_str(FULL).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&token_list).unwrap(),
+            serde_json::to_value(token_list.to_owned()).unwrap()
+        );
+    }
+}
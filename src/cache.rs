@@ -0,0 +1,290 @@
+//! An in-memory cache of fetched token lists.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "storage")]
+use crate::Storage;
+use crate::{Error, FetchOptions, Fetcher, ReqwestFetcher, TokenList};
+
+/// An in-memory cache of token lists keyed by URI, refreshed in the
+/// background once entries are older than a time-to-live.
+///
+/// The cache always serves the last list that was fetched successfully; a
+/// failed refresh leaves the cached list in place until the next attempt.
+///
+/// **Note**: This must be used from within a running tokio >1.0.0 runtime.
+#[derive(Clone)]
+pub struct TokenListCache {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    ttl: Duration,
+    fetcher: Arc<dyn Fetcher>,
+    #[cfg(feature = "storage")]
+    storage: Option<Arc<dyn Storage>>,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+#[derive(Debug, Default)]
+struct Entry {
+    token_list: Option<Arc<TokenList>>,
+    refreshed_at: Option<Instant>,
+    refreshing: bool,
+}
+
+impl Entry {
+    fn is_stale(&self, ttl: Duration) -> bool {
+        match self.refreshed_at {
+            Some(refreshed_at) => refreshed_at.elapsed() >= ttl,
+            None => true,
+        }
+    }
+}
+
+impl TokenListCache {
+    /// Creates an empty cache whose entries are refreshed once older than
+    /// `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self::with_options(ttl, FetchOptions::default())
+    }
+
+    /// Creates an empty cache that fetches lists using the provided
+    /// [`FetchOptions`].
+    pub fn with_options(ttl: Duration, options: FetchOptions) -> Self {
+        Self::with_fetcher(ttl, Arc::new(ReqwestFetcher::new(options)))
+    }
+
+    /// Creates an empty cache that fetches lists using the provided
+    /// [`Fetcher`].
+    pub fn with_fetcher(ttl: Duration, fetcher: Arc<dyn Fetcher>) -> Self {
+        TokenListCache {
+            inner: Arc::new(Inner {
+                ttl,
+                fetcher,
+                #[cfg(feature = "storage")]
+                storage: None,
+                entries: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Creates an empty cache that fetches lists using the provided
+    /// [`Fetcher`] and puts each list it fetches in `storage`.
+    ///
+    /// A refresh fails if the list can't be stored, leaving the cached list
+    /// in place.
+    #[cfg(feature = "storage")]
+    pub fn with_storage(
+        ttl: Duration,
+        fetcher: Arc<dyn Fetcher>,
+        storage: Arc<dyn Storage>,
+    ) -> Self {
+        TokenListCache {
+            inner: Arc::new(Inner {
+                ttl,
+                fetcher,
+                storage: Some(storage),
+                entries: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Returns the cached list for `uri` without waiting.
+    ///
+    /// If the entry is missing or stale, a refresh is started in the
+    /// background; the list it fetches is served by subsequent calls.
+    pub fn get(&self, uri: &str) -> Option<Arc<TokenList>> {
+        let (token_list, refresh) = self.lookup(uri);
+        if refresh {
+            self.spawn_refresh(uri);
+        }
+        token_list
+    }
+
+    /// Returns the cached list for `uri`, fetching it first if the cache holds
+    /// no list for it yet.
+    ///
+    /// A stale list is returned immediately and refreshed in the background,
+    /// as with [`get`](Self::get).
+    pub async fn get_or_fetch(&self, uri: &str) -> Result<Arc<TokenList>, Error> {
+        match self.lookup(uri) {
+            (Some(token_list), refresh) => {
+                if refresh {
+                    self.spawn_refresh(uri);
+                }
+                Ok(token_list)
+            }
+            (None, _) => self.refresh(uri).await,
+        }
+    }
+
+    /// Fetches `uri` now, storing the list in the cache if successful.
+    ///
+    /// A failed refresh leaves the entry stale, so the next lookup retries it.
+    pub async fn refresh(&self, uri: &str) -> Result<Arc<TokenList>, Error> {
+        let result = self.fetch(uri).await;
+
+        let mut entries = self.inner.entries.lock().unwrap();
+        let entry = entries.entry(uri.to_owned()).or_default();
+        entry.refreshing = false;
+
+        let token_list = Arc::new(result?);
+        entry.token_list = Some(token_list.clone());
+        entry.refreshed_at = Some(Instant::now());
+        Ok(token_list)
+    }
+
+    /// Fetches `uri`, putting the list in the cache's storage, if any.
+    async fn fetch(&self, uri: &str) -> Result<TokenList, Error> {
+        let token_list = TokenList::from_fetcher(&*self.inner.fetcher, uri).await?;
+        #[cfg(feature = "storage")]
+        if let Some(storage) = &self.inner.storage {
+            storage.put_list(&token_list).await?;
+        }
+        Ok(token_list)
+    }
+
+    /// Returns the cached list for `uri`, and whether the caller should
+    /// refresh it.
+    fn lookup(&self, uri: &str) -> (Option<Arc<TokenList>>, bool) {
+        let mut entries = self.inner.entries.lock().unwrap();
+        let entry = entries.entry(uri.to_owned()).or_default();
+
+        let refresh = entry.is_stale(self.inner.ttl) && !entry.refreshing;
+        if refresh {
+            entry.refreshing = true;
+        }
+
+        #[cfg(feature = "metrics")]
+        crate::telemetry::cache_request(
+            "memory",
+            match (&entry.token_list, entry.is_stale(self.inner.ttl)) {
+                (None, _) => "miss",
+                (Some(_), true) => "stale",
+                (Some(_), false) => "hit",
+            },
+        );
+
+        (entry.token_list.clone(), refresh)
+    }
+
+    fn spawn_refresh(&self, uri: &str) {
+        let cache = self.clone();
+        let uri = uri.to_owned();
+        tokio::spawn(async move {
+            let _ = cache.refresh(&uri).await;
+        });
+    }
+
+    /// Inserts a list into the cache for `uri`, as if it was just fetched.
+    pub fn insert(&self, uri: &str, token_list: TokenList) {
+        let mut entries = self.inner.entries.lock().unwrap();
+        let entry = entries.entry(uri.to_owned()).or_default();
+        entry.token_list = Some(Arc::new(token_list));
+        entry.refreshed_at = Some(Instant::now());
+    }
+
+    /// Removes the entry for `uri`, returning its list if one was cached.
+    pub fn remove(&self, uri: &str) -> Option<Arc<TokenList>> {
+        let mut entries = self.inner.entries.lock().unwrap();
+        entries.remove(uri).and_then(|entry| entry.token_list)
+    }
+}
+
+impl fmt::Debug for TokenListCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenListCache")
+            .field("ttl", &self.inner.ttl)
+            .field("entries", &self.inner.entries)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{fixtures, MockFetcher, MockResponse};
+
+    const URI: &str = "https://example.com/list.json";
+
+    fn cache(ttl: Duration, fetcher: &Arc<MockFetcher>) -> TokenListCache {
+        TokenListCache::with_fetcher(ttl, fetcher.clone())
+    }
+
+    #[tokio::test]
+    async fn fetches_on_first_use_then_serves_from_cache() {
+        let fetcher = Arc::new(MockFetcher::new());
+        fetcher.respond_once(URI, MockResponse::body(fixtures::FULL));
+        let cache = cache(Duration::from_secs(60), &fetcher);
+
+        let token_list = cache.get_or_fetch(URI).await.unwrap();
+        assert_eq!(token_list.name, "TELcoins");
+
+        // the fetcher only answers once, so this must come from the cache
+        assert_eq!(cache.get_or_fetch(URI).await.unwrap(), token_list);
+        assert_eq!(fetcher.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn refreshes_stale_entries_in_background() {
+        let mut updated = fixtures::full();
+        updated.name = "Updated".to_owned();
+        let fetcher = Arc::new(MockFetcher::new());
+        fetcher
+            .respond_once(URI, MockResponse::body(fixtures::FULL))
+            .respond(URI, MockResponse::json(&updated));
+        let cache = cache(Duration::from_millis(0), &fetcher);
+
+        cache.get_or_fetch(URI).await.unwrap();
+        assert_eq!(cache.get(URI).unwrap().name, "TELcoins");
+
+        for _ in 0..100 {
+            if cache.get(URI).unwrap().name == "Updated" {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("cache was not refreshed");
+    }
+
+    #[tokio::test]
+    async fn keeps_last_good_list_on_failure() {
+        let fetcher = Arc::new(MockFetcher::new());
+        fetcher.respond(URI, MockResponse::error("not found"));
+        let cache = cache(Duration::from_millis(0), &fetcher);
+        cache.insert(URI, fixtures::full());
+
+        assert!(cache.refresh(URI).await.is_err());
+        assert_eq!(cache.get(URI).unwrap().name, "TELcoins");
+    }
+
+    #[tokio::test]
+    async fn retries_after_failed_refresh() {
+        let fetcher = Arc::new(MockFetcher::new());
+        fetcher.respond(URI, MockResponse::error("not found"));
+        let cache = cache(Duration::from_secs(60), &fetcher);
+
+        assert!(cache.refresh(URI).await.is_err());
+        assert_eq!(cache.lookup(URI), (None, true));
+    }
+
+    #[cfg(feature = "storage")]
+    #[tokio::test]
+    async fn puts_fetched_lists_in_storage() {
+        let fetcher = Arc::new(MockFetcher::new());
+        fetcher.respond(URI, MockResponse::body(fixtures::FULL));
+        let storage = Arc::new(crate::MemoryStorage::new());
+        let cache = TokenListCache::with_storage(Duration::from_secs(60), fetcher, storage.clone());
+
+        let token_list = cache.get_or_fetch(URI).await.unwrap();
+
+        assert_eq!(
+            storage.get_list(&token_list.name, None).await.unwrap(),
+            Some((*token_list).clone())
+        );
+    }
+}
@@ -0,0 +1,61 @@
+//! CBOR serialization of token lists.
+
+use crate::{Error, TokenList};
+
+impl TokenList {
+    /// Parses a [`TokenList`] from CBOR.
+    pub fn from_cbor_slice(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(ciborium::de::from_reader(bytes)?)
+    }
+
+    /// Serializes the list as CBOR.
+    pub fn to_cbor_vec(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(self, &mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures::FULL;
+
+    #[test]
+    fn round_trips_through_cbor() {
+        let token_list: TokenList = serde_json::from_str(FULL).unwrap();
+
+        let cbor = token_list.to_cbor_vec().unwrap();
+
+        assert_eq!(TokenList::from_cbor_slice(&cbor).unwrap(), token_list);
+        assert!(cbor.len() < serde_json::to_vec(&token_list).unwrap().len());
+    }
+
+    #[test]
+    fn round_trips_extension_values() {
+        let mut token_list: TokenList = serde_json::from_str(FULL).unwrap();
+        let extensions = &mut token_list.tokens[0].extensions;
+        extensions.insert("bridged".to_owned(), crate::ExtensionValue::Boolean(true));
+        extensions.insert(
+            "rank".to_owned(),
+            crate::ExtensionValue::Number(crate::Number::Integer(3)),
+        );
+        extensions.insert(
+            "weight".to_owned(),
+            crate::ExtensionValue::Number(crate::Number::Float(0.5)),
+        );
+        extensions.insert("note".to_owned(), crate::ExtensionValue::Null);
+
+        let cbor = token_list.to_cbor_vec().unwrap();
+
+        assert_eq!(TokenList::from_cbor_slice(&cbor).unwrap(), token_list);
+    }
+
+    #[test]
+    fn reports_invalid_cbor() {
+        match TokenList::from_cbor_slice(FULL.as_bytes()) {
+            Err(Error::Cbor(_)) => {}
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Tokens keyed by chain and address, as many frontends consume them.
+
+use std::collections::BTreeMap;
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{Token, TokenList};
+
+/// A list's tokens keyed by chain ID, then by address, serialized as
+/// `{ [chainId]: { [address]: token } }` like the token maps of the Uniswap
+/// interface.
+///
+/// ```
+/// # use token_list::TokenList;
+/// let token_list = TokenList::from_slice(br#"{
+///     "name": "TELcoins",
+///     "timestamp": "2021-07-05T20:25:22+00:00",
+///     "version": { "major": 0, "minor": 1, "patch": 0 },
+///     "tokens": [{
+///         "name": "Telcoin",
+///         "symbol": "TEL",
+///         "address": "0x467Bccd9d29f223BcE8043b84E8C8B282827790F",
+///         "chainId": 1,
+///         "decimals": 2
+///     }]
+/// }"#)?;
+///
+/// let chain_map = token_list.to_chain_map();
+/// let token = chain_map.get(1, "0x467bccd9d29f223bce8043b84e8c8b282827790f").unwrap();
+/// assert_eq!(token.symbol, "TEL");
+///
+/// let json = serde_json::to_value(&chain_map)?;
+/// assert_eq!(json["1"]["0x467Bccd9d29f223BcE8043b84E8C8B282827790F"]["symbol"], "TEL");
+/// # Ok::<_, token_list::Error>(())
+/// ```
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(transparent)]
+pub struct ChainMap(pub BTreeMap<u32, IndexMap<String, Token>>);
+
+impl ChainMap {
+    /// Returns the token at `address` on `chain_id`, comparing addresses
+    /// case-insensitively.
+    pub fn get(&self, chain_id: u32, address: &str) -> Option<&Token> {
+        let tokens = self.0.get(&chain_id)?;
+        tokens.get(address).or_else(|| {
+            tokens
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(address))
+                .map(|(_, token)| token)
+        })
+    }
+
+    /// Returns the tokens, in order of chain, then in the order of the map.
+    pub fn tokens(&self) -> impl Iterator<Item = &Token> {
+        self.0.values().flat_map(IndexMap::values)
+    }
+}
+
+impl TokenList {
+    /// Returns the list's tokens keyed by chain ID and address.
+    ///
+    /// Where a chain has several tokens at the same address, the first is
+    /// kept.
+    pub fn to_chain_map(&self) -> ChainMap {
+        let mut chain_map = ChainMap::default();
+        for token in &self.tokens {
+            chain_map
+                .0
+                .entry(token.chain_id)
+                .or_default()
+                .entry(token.address.clone())
+                .or_insert_with(|| token.clone());
+        }
+        chain_map
+    }
+
+    /// Creates a list named `name` of the tokens in `chain_map`, in order of
+    /// chain.
+    pub fn from_chain_map(name: &str, chain_map: ChainMap) -> Self {
+        TokenList {
+            name: name.to_owned(),
+            tokens: chain_map
+                .0
+                .into_values()
+                .flat_map(IndexMap::into_values)
+                .collect(),
+            ..TokenList::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures::FULL;
+
+    #[test]
+    fn round_trips_tokens() {
+        let mut token_list = TokenList::from_slice(FULL.as_bytes()).unwrap();
+        token_list.tokens.sort_by_key(|token| token.chain_id);
+        let mut duplicate = token_list.tokens[0].clone();
+        duplicate.name = "Duplicate".to_owned();
+        token_list.tokens.push(duplicate);
+
+        let chain_map = token_list.to_chain_map();
+        let json = serde_json::to_string(&chain_map).unwrap();
+        let chain_map: ChainMap = serde_json::from_str(&json).unwrap();
+
+        token_list.tokens.pop();
+        let tokens: Vec<_> = chain_map.tokens().cloned().collect();
+        assert_eq!(tokens, token_list.tokens);
+        assert_eq!(
+            TokenList::from_chain_map("TELcoins", chain_map).tokens,
+            token_list.tokens
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! Metadata about the chains tokens live on.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::iter::FromIterator;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "fetcher")]
+use crate::Fetcher;
+use crate::{Error, Token};
+
+/// Where the chainlist dataset is published, in the format read by
+/// [`ChainRegistry::from_chainlist_slice`].
+pub const CHAINLIST_URI: &str = "https://chainid.network/chains.json";
+
+/// A native currency's name, symbol, and decimals.
+type Currency = (&'static str, &'static str, u8);
+
+/// The chains known without fetching the chainlist dataset: chain ID, name,
+/// explorer, and native currency.
+const BUNDLED: &[(u32, &str, &str, Currency)] = &[
+    (
+        1,
+        "Ethereum Mainnet",
+        "https://etherscan.io",
+        ("Ether", "ETH", 18),
+    ),
+    (
+        10,
+        "OP Mainnet",
+        "https://optimistic.etherscan.io",
+        ("Ether", "ETH", 18),
+    ),
+    (
+        56,
+        "BNB Smart Chain Mainnet",
+        "https://bscscan.com",
+        ("BNB Chain Native Token", "BNB", 18),
+    ),
+    (100, "Gnosis", "https://gnosisscan.io", ("xDAI", "XDAI", 18)),
+    (
+        137,
+        "Polygon Mainnet",
+        "https://polygonscan.com",
+        ("POL", "POL", 18),
+    ),
+    (8453, "Base", "https://basescan.org", ("Ether", "ETH", 18)),
+    (
+        42161,
+        "Arbitrum One",
+        "https://arbiscan.io",
+        ("Ether", "ETH", 18),
+    ),
+    (
+        43114,
+        "Avalanche C-Chain",
+        "https://snowtrace.io",
+        ("Avalanche", "AVAX", 18),
+    ),
+    (
+        11155111,
+        "Sepolia",
+        "https://sepolia.etherscan.io",
+        ("Sepolia Ether", "ETH", 18),
+    ),
+];
+
+/// A chain, as identified by its EIP-155 chain ID.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Chain {
+    /// The chain's ID
+    pub chain_id: u32,
+
+    /// The chain's human-readable name, e.g. `Polygon Mainnet`
+    pub name: String,
+
+    /// The currency gas is paid in
+    pub native_currency: NativeCurrency,
+
+    /// The base URL of an EIP-3091 block explorer, without a trailing slash
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explorer_url: Option<String>,
+}
+
+impl Chain {
+    /// Returns the explorer page of the contract or account at `address`.
+    pub fn address_url(&self, address: &str) -> Option<String> {
+        self.explorer_page("address", address)
+    }
+
+    /// Returns the explorer page of the token at `address`.
+    pub fn token_url(&self, address: &str) -> Option<String> {
+        self.explorer_page("token", address)
+    }
+
+    /// Returns the explorer page of the transaction with hash `hash`.
+    pub fn transaction_url(&self, hash: &str) -> Option<String> {
+        self.explorer_page("tx", hash)
+    }
+
+    fn explorer_page(&self, kind: &str, id: &str) -> Option<String> {
+        let base = self.explorer_url.as_deref()?;
+        Some(format!("{}/{}/{}", base.trim_end_matches('/'), kind, id))
+    }
+}
+
+/// The currency gas is paid in on a chain.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct NativeCurrency {
+    /// The currency's name, e.g. `Ether`
+    pub name: String,
+
+    /// The currency's symbol, e.g. `ETH`
+    pub symbol: String,
+
+    /// The number of decimals of the currency's amounts
+    pub decimals: u8,
+}
+
+/// Chains by ID.
+///
+/// [`ChainRegistry::bundled`] knows a handful of popular chains; the full
+/// chainlist dataset can be read from [`CHAINLIST_URI`] to cover the rest.
+///
+/// ```
+/// use token_list::ChainRegistry;
+///
+/// let chains = ChainRegistry::bundled();
+/// let polygon = chains.get(137).unwrap();
+///
+/// assert_eq!(polygon.name, "Polygon Mainnet");
+/// assert_eq!(
+///     polygon.token_url("0xdF7837DE1F2Fa4631D716CF2502f8b230F1dcc32").unwrap(),
+///     "https://polygonscan.com/token/0xdF7837DE1F2Fa4631D716CF2502f8b230F1dcc32"
+/// );
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ChainRegistry {
+    chains: BTreeMap<u32, Chain>,
+}
+
+impl ChainRegistry {
+    /// Returns the chains bundled with the crate.
+    pub fn bundled() -> Self {
+        BUNDLED
+            .iter()
+            .map(
+                |&(chain_id, name, explorer_url, (currency, symbol, decimals))| Chain {
+                    chain_id,
+                    name: name.to_owned(),
+                    native_currency: NativeCurrency {
+                        name: currency.to_owned(),
+                        symbol: symbol.to_owned(),
+                        decimals,
+                    },
+                    explorer_url: Some(explorer_url.to_owned()),
+                },
+            )
+            .collect()
+    }
+
+    /// Reads chains from the JSON of the chainlist dataset, as published at
+    /// [`CHAINLIST_URI`].
+    ///
+    /// Chains whose ID doesn't fit in a `u32` are skipped. The first explorer
+    /// that follows EIP-3091 is used, if any.
+    pub fn from_chainlist_slice(bytes: &[u8]) -> Result<Self, Error> {
+        let entries: Vec<ChainlistEntry> = crate::json::from_slice(bytes)?;
+
+        Ok(entries
+            .into_iter()
+            .filter_map(|entry| {
+                let explorer_url = entry
+                    .explorers
+                    .into_iter()
+                    .find(|explorer| explorer.standard.as_deref() == Some("EIP3091"))
+                    .map(|explorer| explorer.url.trim_end_matches('/').to_owned());
+                Some(Chain {
+                    chain_id: u32::try_from(entry.chain_id).ok()?,
+                    name: entry.name,
+                    native_currency: entry.native_currency,
+                    explorer_url,
+                })
+            })
+            .collect())
+    }
+
+    /// Returns the bundled chains, updated from the chainlist dataset at
+    /// [`CHAINLIST_URI`].
+    #[cfg(feature = "fetcher")]
+    pub async fn refreshed<F: Fetcher + ?Sized>(fetcher: &F) -> Result<Self, Error> {
+        let mut chains = ChainRegistry::bundled();
+        chains.extend(ChainRegistry::from_chainlist_slice(
+            &fetcher.fetch(CHAINLIST_URI).await?,
+        )?);
+        Ok(chains)
+    }
+
+    /// Returns the chain with ID `chain_id`, if known.
+    pub fn get(&self, chain_id: u32) -> Option<&Chain> {
+        self.chains.get(&chain_id)
+    }
+
+    /// Adds `chain`, replacing any chain with the same ID.
+    pub fn insert(&mut self, chain: Chain) {
+        self.chains.insert(chain.chain_id, chain);
+    }
+
+    /// Returns the known chains, in order of ID.
+    pub fn iter(&self) -> impl Iterator<Item = &Chain> {
+        self.chains.values()
+    }
+
+    /// Returns the explorer page of `token`'s contract, if its chain and an
+    /// explorer for it are known.
+    pub fn explorer_url(&self, token: &Token) -> Option<String> {
+        self.get(token.chain_id)?.token_url(&token.address)
+    }
+}
+
+impl FromIterator<Chain> for ChainRegistry {
+    fn from_iter<I: IntoIterator<Item = Chain>>(iter: I) -> Self {
+        let mut chains = ChainRegistry::default();
+        chains.extend(iter);
+        chains
+    }
+}
+
+impl Extend<Chain> for ChainRegistry {
+    fn extend<I: IntoIterator<Item = Chain>>(&mut self, iter: I) {
+        for chain in iter {
+            self.insert(chain);
+        }
+    }
+}
+
+impl IntoIterator for ChainRegistry {
+    type Item = Chain;
+    type IntoIter = std::collections::btree_map::IntoValues<u32, Chain>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chains.into_values()
+    }
+}
+
+impl Token {
+    /// Returns the page of the token's contract on a block explorer for its
+    /// chain, if the chain is among the [bundled](ChainRegistry::bundled)
+    /// ones.
+    ///
+    /// ```
+    /// # use token_list::Token;
+    /// let token: Token = serde_json::from_str(r#"{
+    ///     "name": "Telcoin",
+    ///     "symbol": "TEL",
+    ///     "address": "0x467Bccd9d29f223BcE8043b84E8C8B282827790F",
+    ///     "chainId": 1,
+    ///     "decimals": 2
+    /// }"#)?;
+    ///
+    /// assert_eq!(
+    ///     token.explorer_url().unwrap(),
+    ///     "https://etherscan.io/token/0x467Bccd9d29f223BcE8043b84E8C8B282827790F"
+    /// );
+    /// # Ok::<_, serde_json::Error>(())
+    /// ```
+    pub fn explorer_url(&self) -> Option<String> {
+        ChainRegistry::bundled().explorer_url(self)
+    }
+}
+
+/// An entry of the chainlist dataset; chain IDs can exceed a `u32` there.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChainlistEntry {
+    name: String,
+    chain_id: u64,
+    native_currency: NativeCurrency,
+    #[serde(default)]
+    explorers: Vec<ChainlistExplorer>,
+}
+
+#[derive(Deserialize)]
+struct ChainlistExplorer {
+    url: String,
+    standard: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_to_explorers() {
+        let chains = ChainRegistry::bundled();
+        let mainnet = chains.get(1).unwrap();
+
+        assert_eq!(mainnet.native_currency.symbol, "ETH");
+        assert_eq!(
+            mainnet.address_url("0x0").unwrap(),
+            "https://etherscan.io/address/0x0"
+        );
+        assert_eq!(
+            mainnet.transaction_url("0x1").unwrap(),
+            "https://etherscan.io/tx/0x1"
+        );
+        assert!(chains.get(2017).is_none());
+    }
+
+    #[test]
+    fn reads_chainlist() {
+        let json = br#"[
+            {
+                "name": "Telcoin Network",
+                "chainId": 2017,
+                "nativeCurrency": { "name": "Telcoin", "symbol": "TEL", "decimals": 18 },
+                "explorers": [
+                    { "name": "blockscout", "url": "https://example.com/", "standard": "none" },
+                    { "name": "telscan", "url": "https://telscan.example/", "standard": "EIP3091" }
+                ]
+            },
+            {
+                "name": "No Explorer",
+                "chainId": 7,
+                "nativeCurrency": { "name": "Seven", "symbol": "SVN", "decimals": 8 }
+            },
+            {
+                "name": "Too Big",
+                "chainId": 99999999999,
+                "nativeCurrency": { "name": "Big", "symbol": "BIG", "decimals": 18 }
+            }
+        ]"#;
+
+        let mut chains = ChainRegistry::bundled();
+        chains.extend(ChainRegistry::from_chainlist_slice(json).unwrap());
+
+        let telcoin = chains.get(2017).unwrap();
+        assert_eq!(telcoin.native_currency.symbol, "TEL");
+        assert_eq!(
+            telcoin.token_url("0x0").unwrap(),
+            "https://telscan.example/token/0x0"
+        );
+        assert_eq!(chains.get(7).unwrap().token_url("0x0"), None);
+        assert_eq!(
+            chains.iter().count(),
+            ChainRegistry::bundled().iter().count() + 2
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Recognition of wrapped native tokens and stablecoins.
+
+use crate::Token;
+
+/// The tag marking a token as its chain's wrapped native currency, for
+/// lists that include wrapped tokens this crate doesn't know.
+pub const WRAPPED_NATIVE_TAG: &str = "wrapped-native";
+
+/// The tag marking a token as a stablecoin, for lists that include
+/// stablecoins this crate doesn't know.
+pub const STABLECOIN_TAG: &str = "stablecoin";
+
+/// The canonical wrapped native token of each bundled chain.
+const WRAPPED_NATIVE: &[(u32, &str)] = &[
+    (1, "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+    (10, "0x4200000000000000000000000000000000000006"),
+    (56, "0xbb4CdB9CBd36B01bD1cBaEBF2De08d9173bc095c"),
+    (100, "0xe91D153E0b41518A2Ce8Dd3D7944Fa863463a97d"),
+    (137, "0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270"),
+    (8453, "0x4200000000000000000000000000000000000006"),
+    (42161, "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1"),
+    (43114, "0xB31f66AA3C1e785363F0875A1B74E27b85FD66c7"),
+    (11155111, "0xfFf9976782d46CC05630D1f6eBAb18b2324d6B14"),
+];
+
+/// Widely used USD stablecoins on the bundled chains.
+const STABLECOINS: &[(u32, &str)] = &[
+    // USDC, USDT, DAI
+    (1, "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+    (1, "0xdAC17F958D2ee523a2206206994597C13D831ec7"),
+    (1, "0x6B175474E89094C44Da98b954EedeAC495271d0F"),
+    // USDC, USDT, DAI
+    (10, "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85"),
+    (10, "0x94b008aA00579c1307B0EF2c499aD98a8ce58e58"),
+    (10, "0xDA10009cBd5D07dd0CeCc66161FC93D7c9000da1"),
+    // USDC, USDT
+    (56, "0x8AC76a51cc950d9822D68b83fE1Ad97B32Cd580d"),
+    (56, "0x55d398326f99059fF775485246999027B3197955"),
+    // USDC, USDC.e, USDT, DAI
+    (137, "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359"),
+    (137, "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174"),
+    (137, "0xc2132D05D31c914a87C6611C10748AEb04B58e8F"),
+    (137, "0x8f3Cf7ad23Cd3CaDbD9735AFf958023239c6A063"),
+    // USDC
+    (8453, "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"),
+    // USDC, USDT
+    (42161, "0xaf88d065e77c8cC2239327C5EDb3A432268e5831"),
+    (42161, "0xFd086bC7CD5C481DCC9C85ebE478A1C0b69FCbb9"),
+    // USDC, USDt
+    (43114, "0xB97EF9Ef8734C71904D8002F8b6Bc66Dd9c48a6E"),
+    (43114, "0x9702230A8Ea53601f5cD2dc00fDBc13d4dF4A8c7"),
+];
+
+impl Token {
+    /// Returns `true` if the token is its chain's canonical wrapped native
+    /// currency, such as WETH on Ethereum or WPOL on Polygon, or is tagged
+    /// [`WRAPPED_NATIVE_TAG`].
+    pub fn is_wrapped_native(&self) -> bool {
+        self.is_listed_in(WRAPPED_NATIVE) || self.has_tag(WRAPPED_NATIVE_TAG)
+    }
+
+    /// Returns `true` if the token is a well-known stablecoin, or is tagged
+    /// [`STABLECOIN_TAG`].
+    ///
+    /// This is a hint for presentation and routing, such as to suggest
+    /// stablecoins as swap targets, rather than a guarantee that the token
+    /// holds its peg.
+    pub fn is_stablecoin_hint(&self) -> bool {
+        self.is_listed_in(STABLECOINS) || self.has_tag(STABLECOIN_TAG)
+    }
+
+    fn is_listed_in(&self, registry: &[(u32, &str)]) -> bool {
+        registry.iter().any(|&(chain_id, address)| {
+            chain_id == self.chain_id && address.eq_ignore_ascii_case(&self.address)
+        })
+    }
+
+    fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|id| id.eq_ignore_ascii_case(tag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(chain_id: u32, address: &str) -> Token {
+        Token {
+            name: "Token".to_owned(),
+            symbol: "TKN".to_owned(),
+            address: address.to_owned(),
+            chain_id,
+            decimals: 18,
+            logo_uri: None,
+            tags: Vec::new(),
+            extensions: Default::default(),
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: Default::default(),
+        }
+    }
+
+    #[test]
+    fn recognizes_bundled_tokens() {
+        let weth = token(1, "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+        let usdc = token(137, "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359");
+
+        assert!(weth.is_wrapped_native() && !weth.is_stablecoin_hint());
+        assert!(usdc.is_stablecoin_hint() && !usdc.is_wrapped_native());
+        // the same address on another chain isn't the same token
+        assert!(!token(137, &weth.address).is_wrapped_native());
+    }
+
+    #[test]
+    fn recognizes_tagged_tokens() {
+        let mut token = token(2017, "0x0000000000000000000000000000000000000001");
+        assert!(!token.is_wrapped_native() && !token.is_stablecoin_hint());
+
+        token.tags = vec!["Stablecoin".to_owned()];
+        assert!(token.is_stablecoin_hint());
+
+        token.tags = vec![WRAPPED_NATIVE_TAG.to_owned()];
+        assert!(token.is_wrapped_native());
+    }
+}
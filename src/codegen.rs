@@ -0,0 +1,303 @@
+//! Generation of Rust constants for the tokens of a list.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::{Token, TokenList};
+
+/// The module names given to well-known chains.
+const CHAIN_NAMES: &[(u32, &str)] = &[
+    (1, "mainnet"),
+    (10, "optimism"),
+    (56, "bsc"),
+    (100, "gnosis"),
+    (137, "polygon"),
+    (8453, "base"),
+    (42161, "arbitrum"),
+    (43114, "avalanche"),
+    (11155111, "sepolia"),
+];
+
+/// Generates a Rust module with a constant for each token in a list, so that
+/// application code can refer to known tokens as, say, `tokens::mainnet::TEL`
+/// without looking them up at runtime.
+///
+/// The module defines its own `Token` type, so it doesn't depend on this
+/// crate. Tokens are grouped into a module per chain, named after the chain
+/// where it's well known and `chain_<id>` otherwise, and each holds an `ALL`
+/// slice of its tokens. Constants are named after the token's symbol,
+/// upper-cased, with characters that can't appear in an identifier replaced
+/// by `_`; tokens sharing a symbol on a chain are told apart by the start of
+/// their address.
+///
+/// ```
+/// # use token_list::{Codegen, TokenList};
+/// # let token_list: TokenList = serde_json::from_str(include_str!("../fixtures/full.json"))?;
+/// let source = Codegen::new(&token_list).chain_name(137, "pos").generate();
+///
+/// assert!(source.contains("pub mod pos {"));
+/// assert!(source.contains("pub const TEL: Token = Token {"));
+/// # Ok::<_, serde_json::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct Codegen<'a> {
+    token_list: &'a TokenList,
+    chain_names: HashMap<u32, String>,
+}
+
+impl<'a> Codegen<'a> {
+    /// Creates a generator for the tokens of `token_list`.
+    pub fn new(token_list: &'a TokenList) -> Self {
+        Codegen {
+            token_list,
+            chain_names: CHAIN_NAMES
+                .iter()
+                .map(|&(chain_id, name)| (chain_id, name.to_owned()))
+                .collect(),
+        }
+    }
+
+    /// Names the module of tokens on `chain_id`.
+    pub fn chain_name(mut self, chain_id: u32, name: &str) -> Self {
+        self.chain_names.insert(chain_id, name.to_owned());
+        self
+    }
+
+    /// Returns the source of the module.
+    pub fn generate(&self) -> String {
+        let mut chains: BTreeMap<u32, Vec<&Token>> = BTreeMap::new();
+        for token in &self.token_list.tokens {
+            chains.entry(token.chain_id).or_default().push(token);
+        }
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "// @generated from the {} token list, version {}. Do not edit.",
+            one_line(&self.token_list.name),
+            self.token_list.version
+        );
+        out.push_str(
+            "
+/// A token in the list.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Token {
+    /// The name of the token
+    pub name: &'static str,
+    /// The symbol of the token
+    pub symbol: &'static str,
+    /// The address of the token
+    pub address: &'static str,
+    /// The chain ID of the network the token is deployed on
+    pub chain_id: u32,
+    /// The number of decimals of the token
+    pub decimals: u8,
+}
+",
+        );
+
+        for (chain_id, tokens) in &chains {
+            let module = match self.chain_names.get(chain_id) {
+                Some(name) => identifier(name, false),
+                None => format!("chain_{}", chain_id),
+            };
+            let _ = write!(
+                out,
+                "\n/// Tokens on chain {}.\npub mod {} {{\n    use super::Token;\n",
+                chain_id, module
+            );
+
+            let mut names = Vec::with_capacity(tokens.len());
+            let mut taken = HashSet::new();
+            taken.insert("ALL".to_owned());
+            for token in tokens {
+                let name = constant_name(token, &mut taken);
+                let _ = write!(
+                    out,
+                    "
+    /// {}
+    pub const {}: Token = Token {{
+        name: {:?},
+        symbol: {:?},
+        address: {:?},
+        chain_id: {},
+        decimals: {},
+    }};
+",
+                    one_line(&token.name),
+                    name,
+                    token.name,
+                    token.symbol,
+                    token.address,
+                    token.chain_id,
+                    token.decimals
+                );
+                names.push(name);
+            }
+
+            let _ = writeln!(
+                out,
+                "\n    /// Every token on the chain.\n    pub const ALL: &[Token] = &[{}];\n}}",
+                names.join(", ")
+            );
+        }
+        out
+    }
+}
+
+impl TokenList {
+    /// Generates a Rust module with a constant for each token in the list.
+    ///
+    /// See [`Codegen`] for the form of the module and for naming chains.
+    pub fn to_rust_module(&self) -> String {
+        Codegen::new(self).generate()
+    }
+}
+
+/// Returns a name for the constant of `token` that isn't in `taken`, and
+/// adds it.
+fn constant_name(token: &Token, taken: &mut HashSet<String>) -> String {
+    let base = identifier(&token.symbol, true);
+    let mut name = base.clone();
+    if taken.contains(&name) {
+        let address: String = token
+            .address
+            .trim_start_matches("0x")
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .take(6)
+            .collect();
+        name = format!("{}_{}", base, address.to_ascii_uppercase());
+    }
+    let mut n = 2;
+    let unique = name.clone();
+    while taken.contains(&name) {
+        name = format!("{}_{}", unique, n);
+        n += 1;
+    }
+    taken.insert(name.clone());
+    name
+}
+
+/// Turns `value` into a Rust identifier, in upper or lower case.
+fn identifier(value: &str, upper: bool) -> String {
+    let mut ident: String = value
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => c,
+            _ => '_',
+        })
+        .collect();
+    ident = match upper {
+        true => ident.to_ascii_uppercase(),
+        false => ident.to_ascii_lowercase(),
+    };
+    if ident.chars().all(|c| c == '_') {
+        ident = match upper {
+            true => "TOKEN".to_owned(),
+            false => "chain".to_owned(),
+        };
+    } else if ident.starts_with(|c: char| c.is_ascii_digit()) || is_keyword(&ident) {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+/// Returns `true` for the keywords that could be produced in lower case.
+fn is_keyword(ident: &str) -> bool {
+    matches!(
+        ident,
+        "as" | "async"
+            | "await"
+            | "break"
+            | "const"
+            | "continue"
+            | "crate"
+            | "dyn"
+            | "else"
+            | "enum"
+            | "extern"
+            | "false"
+            | "fn"
+            | "for"
+            | "if"
+            | "impl"
+            | "in"
+            | "let"
+            | "loop"
+            | "match"
+            | "mod"
+            | "move"
+            | "mut"
+            | "pub"
+            | "ref"
+            | "return"
+            | "self"
+            | "static"
+            | "struct"
+            | "super"
+            | "trait"
+            | "true"
+            | "type"
+            | "unsafe"
+            | "use"
+            | "where"
+            | "while"
+    )
+}
+
+/// Collapses `value` onto one line, for use in a comment.
+fn one_line(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    #[test]
+    fn generates_a_module_per_chain() {
+        let source = fixtures::full().to_rust_module();
+
+        assert!(source.starts_with("// @generated from the TELcoins token list, version 0.1.0."));
+        assert!(source.contains("pub mod mainnet {"));
+        assert!(source.contains("pub mod polygon {"));
+        assert!(source.contains(
+            "    pub const TEL: Token = Token {
+        name: \"Telcoin\",
+        symbol: \"TEL\",
+        address: \"0x467Bccd9d29f223BcE8043b84E8C8B282827790F\",
+        chain_id: 1,
+        decimals: 2,
+    };"
+        ));
+        assert!(source.contains("    pub const ALL: &[Token] = &[TEL];"));
+    }
+
+    #[test]
+    fn names_constants_uniquely() {
+        let mut token_list = fixtures::full();
+        let mut tokens = vec![token_list.tokens[0].clone(); 4];
+        tokens[1].address = "0x1111111111111111111111111111111111111111".to_owned();
+        tokens[2].symbol = "1INCH".to_owned();
+        tokens[3].symbol = "all".to_owned();
+        tokens[3].address = "0x2222222222222222222222222222222222222222".to_owned();
+        token_list.tokens = tokens;
+
+        let source = Codegen::new(&token_list)
+            .chain_name(1, "Ethereum Main")
+            .generate();
+
+        assert!(source.contains("pub mod ethereum_main {"));
+        assert!(source.contains("&[TEL, TEL_111111, _1INCH, ALL_222222];"));
+    }
+
+    #[test]
+    fn makes_identifiers() {
+        assert_eq!(identifier("USDC.e", true), "USDC_E");
+        assert_eq!(identifier("₿", true), "TOKEN");
+        assert_eq!(identifier("Type", false), "_type");
+        assert_eq!(identifier("42", false), "_42");
+    }
+}
@@ -0,0 +1,292 @@
+//! Import of tokens from CoinGecko's API responses.
+
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+use url::Url;
+
+use crate::{Error, ExtensionValue, Token, TokenList};
+
+/// The extension holding the CoinGecko ID of imported tokens.
+pub const COINGECKO_ID_EXTENSION: &str = "coingeckoId";
+
+/// CoinGecko's asset platforms for EVM chains, and their chain IDs.
+const PLATFORMS: &[(&str, u32)] = &[
+    ("ethereum", 1),
+    ("optimistic-ethereum", 10),
+    ("binance-smart-chain", 56),
+    ("xdai", 100),
+    ("polygon-pos", 137),
+    ("fantom", 250),
+    ("base", 8453),
+    ("arbitrum-one", 42161),
+    ("celo", 42220),
+    ("avalanche", 43114),
+];
+
+/// Assembles a draft [`TokenList`] from CoinGecko's API responses.
+///
+/// The response of `/coins/list?include_platform=true` gives the addresses
+/// of every coin on each platform, but not their decimals or logos, which
+/// are only in the response of `/coins/{id}` for each coin. Tokens are only
+/// included in the list once their decimals are known, so read the coin list
+/// first, then the details of the coins still [`pending`](Self::pending).
+///
+/// ```no_run
+/// # fn get(path: &str) -> Vec<u8> { unimplemented!() }
+/// use token_list::CoinGeckoImport;
+///
+/// let mut import = CoinGeckoImport::new();
+/// import.add_coins_list(&get("/coins/list?include_platform=true"))?;
+/// for id in import.pending() {
+///     import.add_coin(&get(&format!("/coins/{}", id)))?;
+/// }
+/// let token_list = import.finish("CoinGecko");
+/// # Ok::<_, token_list::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct CoinGeckoImport {
+    platforms: HashMap<String, u32>,
+    tokens: IndexMap<(u32, String), Draft>,
+}
+
+/// A token whose decimals may not be known yet.
+#[derive(Clone, Debug)]
+struct Draft {
+    id: String,
+    token: Token,
+    has_decimals: bool,
+}
+
+impl CoinGeckoImport {
+    /// Creates an import of tokens on the EVM chains CoinGecko lists.
+    pub fn new() -> Self {
+        CoinGeckoImport {
+            platforms: PLATFORMS
+                .iter()
+                .map(|&(platform, chain_id)| (platform.to_owned(), chain_id))
+                .collect(),
+            tokens: IndexMap::new(),
+        }
+    }
+
+    /// Imports tokens on CoinGecko's asset platform `platform` as tokens on
+    /// `chain_id`.
+    pub fn platform(mut self, platform: &str, chain_id: u32) -> Self {
+        self.platforms.insert(platform.to_owned(), chain_id);
+        self
+    }
+
+    /// Reads the response of `/coins/list?include_platform=true`.
+    pub fn add_coins_list(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let coins: Vec<Coin> = crate::json::from_slice(bytes)?;
+        for coin in coins {
+            for (platform, address) in &coin.platforms {
+                if let Some(address) = address {
+                    self.add(&coin, platform, address, None, None);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the response of `/coins/{id}`, which gives decimals and a logo.
+    pub fn add_coin(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let detail: CoinDetail = crate::json::from_slice(bytes)?;
+        let logo_uri = detail
+            .image
+            .as_ref()
+            .and_then(|image| image.large.as_deref())
+            .and_then(|uri| Url::parse(uri).ok());
+        for (platform, contract) in &detail.detail_platforms {
+            if let Some(address) = &contract.contract_address {
+                let logo_uri = logo_uri.clone();
+                self.add(
+                    &detail.coin,
+                    platform,
+                    address,
+                    contract.decimal_place,
+                    logo_uri,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the IDs of coins with tokens whose decimals aren't known, and
+    /// so aren't yet included in the list.
+    pub fn pending(&self) -> Vec<String> {
+        let mut ids = Vec::new();
+        for draft in self.tokens.values().filter(|draft| !draft.has_decimals) {
+            if !ids.contains(&draft.id) {
+                ids.push(draft.id.clone());
+            }
+        }
+        ids
+    }
+
+    /// Returns a list named `name` of the tokens whose decimals are known, in
+    /// the order they were read.
+    pub fn finish(self, name: &str) -> TokenList {
+        TokenList {
+            name: name.to_owned(),
+            tokens: self
+                .tokens
+                .into_values()
+                .filter(|draft| draft.has_decimals)
+                .map(|draft| draft.token)
+                .collect(),
+            ..TokenList::default()
+        }
+    }
+
+    fn add(
+        &mut self,
+        coin: &Coin,
+        platform: &str,
+        address: &str,
+        decimals: Option<u8>,
+        logo_uri: Option<Url>,
+    ) {
+        let chain_id = match self.platforms.get(platform) {
+            Some(&chain_id) if !address.is_empty() => chain_id,
+            _ => return,
+        };
+        let draft = self
+            .tokens
+            .entry((chain_id, address.to_ascii_lowercase()))
+            .or_insert_with(|| Draft {
+                id: coin.id.clone(),
+                token: Token {
+                    name: coin.name.clone(),
+                    symbol: coin.symbol.to_ascii_uppercase(),
+                    address: address.to_owned(),
+                    chain_id,
+                    decimals: 0,
+                    logo_uri: None,
+                    tags: Vec::new(),
+                    extensions: std::iter::once((
+                        COINGECKO_ID_EXTENSION.to_owned(),
+                        ExtensionValue::String(coin.id.clone()),
+                    ))
+                    .collect(),
+                    #[cfg(feature = "unknown-fields")]
+                    unknown_fields: Default::default(),
+                },
+                has_decimals: false,
+            });
+        if let Some(decimals) = decimals {
+            draft.token.decimals = decimals;
+            draft.has_decimals = true;
+        }
+        if logo_uri.is_some() {
+            draft.token.logo_uri = logo_uri;
+        }
+    }
+}
+
+impl Default for CoinGeckoImport {
+    fn default() -> Self {
+        CoinGeckoImport::new()
+    }
+}
+
+/// An entry of `/coins/list`.
+#[derive(Deserialize)]
+struct Coin {
+    id: String,
+    symbol: String,
+    name: String,
+    #[serde(default)]
+    platforms: IndexMap<String, Option<String>>,
+}
+
+/// The response of `/coins/{id}`.
+#[derive(Deserialize)]
+struct CoinDetail {
+    #[serde(flatten)]
+    coin: Coin,
+    #[serde(default)]
+    detail_platforms: IndexMap<String, DetailPlatform>,
+    image: Option<Image>,
+}
+
+#[derive(Deserialize)]
+struct DetailPlatform {
+    decimal_place: Option<u8>,
+    contract_address: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Image {
+    large: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COINS_LIST: &str = r#"[
+        {
+            "id": "telcoin",
+            "symbol": "tel",
+            "name": "Telcoin",
+            "platforms": {
+                "ethereum": "0x467bccd9d29f223bce8043b84e8c8b282827790f",
+                "polygon-pos": "0xdf7837de1f2fa4631d716cf2502f8b230f1dcc32",
+                "some-other-chain": "tel.token"
+            }
+        },
+        { "id": "bitcoin", "symbol": "btc", "name": "Bitcoin", "platforms": {} },
+        { "id": "empty", "symbol": "mt", "name": "Empty", "platforms": { "": "" } }
+    ]"#;
+
+    const COIN: &str = r#"{
+        "id": "telcoin",
+        "symbol": "tel",
+        "name": "Telcoin",
+        "platforms": {
+            "ethereum": "0x467bccd9d29f223bce8043b84e8c8b282827790f"
+        },
+        "detail_platforms": {
+            "ethereum": {
+                "decimal_place": 2,
+                "contract_address": "0x467bccd9d29f223bce8043b84e8c8b282827790f"
+            },
+            "polygon-pos": {
+                "decimal_place": 2,
+                "contract_address": "0xdf7837de1f2fa4631d716cf2502f8b230f1dcc32"
+            }
+        },
+        "image": { "large": "https://assets.coingecko.com/coins/images/1899/large/tel.png" }
+    }"#;
+
+    #[test]
+    fn imports_tokens_once_decimals_are_known() {
+        let mut import = CoinGeckoImport::new();
+        import.add_coins_list(COINS_LIST.as_bytes()).unwrap();
+
+        assert_eq!(import.pending(), ["telcoin"]);
+        assert!(import.clone().finish("CoinGecko").tokens.is_empty());
+
+        import.add_coin(COIN.as_bytes()).unwrap();
+        assert!(import.pending().is_empty());
+
+        let token_list = import.finish("CoinGecko");
+        assert_eq!(token_list.name, "CoinGecko");
+        assert_eq!(token_list.tokens.len(), 2);
+        let token = &token_list.tokens[1];
+        assert_eq!(token.chain_id, 137);
+        assert_eq!(token.symbol, "TEL");
+        assert_eq!(token.decimals, 2);
+        assert_eq!(
+            token.logo_uri.as_ref().unwrap().as_str(),
+            "https://assets.coingecko.com/coins/images/1899/large/tel.png"
+        );
+        assert_eq!(
+            token.extensions[COINGECKO_ID_EXTENSION],
+            ExtensionValue::String("telcoin".to_owned())
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Comparison of which tokens several lists include, and what they say about
+//! them.
+
+use std::collections::HashMap;
+
+use crate::TokenList;
+
+/// Compares the tokens of `lists`, giving which lists include each token,
+/// where they disagree about its symbol or decimals, and how many tokens
+/// each list alone includes.
+///
+/// Tokens are identified by chain and address, compared case-insensitively,
+/// and kept in order of first appearance. Where a list repeats a token, its
+/// first is compared.
+///
+/// ```
+/// # use token_list::{compare_lists, TokenList};
+/// let first = TokenList::from_slice(include_bytes!("../fixtures/full.json"))?;
+/// let mut second = first.clone();
+/// second.tokens[0].decimals = 18;
+/// second.tokens.pop();
+///
+/// let comparison = compare_lists(&[first, second]);
+/// assert!(comparison.tokens[0].decimals_disagree());
+/// assert_eq!(comparison.unique, [1, 0]);
+/// # Ok::<_, token_list::Error>(())
+/// ```
+pub fn compare_lists(lists: &[TokenList]) -> ListComparison {
+    let mut comparison = ListComparison {
+        tokens: Vec::new(),
+        unique: vec![0; lists.len()],
+    };
+    let mut indices: HashMap<(u32, String), usize> = HashMap::new();
+    for (i, token_list) in lists.iter().enumerate() {
+        for token in &token_list.tokens {
+            let key = (token.chain_id, token.address.to_lowercase());
+            let index = *indices.entry(key).or_insert_with(|| {
+                comparison.tokens.push(TokenPresence {
+                    chain_id: token.chain_id,
+                    address: token.address.clone(),
+                    symbols: vec![None; lists.len()],
+                    decimals: vec![None; lists.len()],
+                });
+                comparison.tokens.len() - 1
+            });
+            let presence = &mut comparison.tokens[index];
+            if presence.symbols[i].is_none() {
+                presence.symbols[i] = Some(token.symbol.clone());
+                presence.decimals[i] = Some(token.decimals);
+            }
+        }
+    }
+
+    for presence in &comparison.tokens {
+        if let [list] = presence.lists()[..] {
+            comparison.unique[list] += 1;
+        }
+    }
+    comparison
+}
+
+/// The outcome of [`compare_lists`], a matrix of tokens by list.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ListComparison {
+    /// Every token of any list, in order of first appearance
+    pub tokens: Vec<TokenPresence>,
+
+    /// The number of tokens of each list that no other list includes
+    pub unique: Vec<usize>,
+}
+
+impl ListComparison {
+    /// Returns the tokens the lists disagree about the symbol or decimals of.
+    pub fn disagreements(&self) -> impl Iterator<Item = &TokenPresence> {
+        self.tokens
+            .iter()
+            .filter(|presence| presence.symbols_disagree() || presence.decimals_disagree())
+    }
+
+    /// Returns the number of tokens included by every list.
+    pub fn in_every_list(&self) -> usize {
+        self.tokens
+            .iter()
+            .filter(|presence| presence.symbols.iter().all(Option::is_some))
+            .count()
+    }
+}
+
+/// A token of the lists given to [`compare_lists`], and what each list says
+/// about it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TokenPresence {
+    /// The token's chain ID
+    pub chain_id: u32,
+
+    /// The token's address, as in the first list that has it
+    pub address: String,
+
+    /// The token's symbol in each list, or `None` for lists without it
+    pub symbols: Vec<Option<String>>,
+
+    /// The token's decimals in each list, or `None` for lists without it
+    pub decimals: Vec<Option<u8>>,
+}
+
+impl TokenPresence {
+    /// Returns the indices of the lists that include the token.
+    pub fn lists(&self) -> Vec<usize> {
+        self.symbols
+            .iter()
+            .enumerate()
+            .filter(|(_, symbol)| symbol.is_some())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Returns whether the lists including the token give different symbols.
+    pub fn symbols_disagree(&self) -> bool {
+        disagree(&self.symbols)
+    }
+
+    /// Returns whether the lists including the token give different decimals.
+    pub fn decimals_disagree(&self) -> bool {
+        disagree(&self.decimals)
+    }
+}
+
+fn disagree<T: PartialEq>(values: &[Option<T>]) -> bool {
+    let mut values = values.iter().flatten();
+    match values.next() {
+        Some(first) => values.any(|value| value != first),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    #[test]
+    fn compares_lists() {
+        let first = fixtures::full();
+        let mut second = first.clone();
+        second.tokens[0].symbol = "tel".to_owned();
+        second.tokens[1].address = second.tokens[1].address.to_lowercase();
+        let mut extra = second.tokens[0].clone();
+        extra.chain_id = 100;
+        second.tokens.push(extra);
+        let third = TokenList::default();
+
+        let comparison = compare_lists(&[first.clone(), second, third]);
+
+        assert_eq!(comparison.tokens.len(), first.tokens.len() + 1);
+        assert_eq!(comparison.tokens[1].address, first.tokens[1].address);
+        assert_eq!(comparison.tokens[0].lists(), [0, 1]);
+        assert_eq!(
+            comparison.tokens[0].symbols,
+            [Some("TEL".to_owned()), Some("tel".to_owned()), None]
+        );
+        assert!(!comparison.tokens[0].decimals_disagree());
+        assert_eq!(comparison.disagreements().count(), 1);
+        assert_eq!(comparison.unique, [0, 1, 0]);
+        assert_eq!(comparison.in_every_list(), 0);
+    }
+}
@@ -0,0 +1,123 @@
+//! Gzip and zstd compression of token lists.
+
+use std::borrow::Cow;
+
+use crate::{Error, TokenList};
+
+#[cfg(feature = "gzip")]
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+#[cfg(feature = "zstd")]
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+impl TokenList {
+    /// Parses a [`TokenList`] from gzip-compressed JSON, such as the contents
+    /// of a `.json.gz` file.
+    #[cfg(feature = "gzip")]
+    pub fn from_gzip_slice(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_slice(&gunzip(bytes)?)
+    }
+
+    /// Serializes the list as gzip-compressed JSON.
+    #[cfg(feature = "gzip")]
+    pub fn to_gzip_vec(&self) -> Result<Vec<u8>, Error> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        serde_json::to_writer(&mut encoder, self)?;
+        encoder.finish().map_err(Error::Compression)
+    }
+
+    /// Parses a [`TokenList`] from zstd-compressed JSON, such as the contents
+    /// of a `.json.zst` file.
+    #[cfg(feature = "zstd")]
+    pub fn from_zstd_slice(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_slice(&unzstd(bytes)?)
+    }
+
+    /// Serializes the list as zstd-compressed JSON at the given compression
+    /// level; `0` selects zstd's default.
+    #[cfg(feature = "zstd")]
+    pub fn to_zstd_vec(&self, level: i32) -> Result<Vec<u8>, Error> {
+        let mut encoder = zstd::Encoder::new(Vec::new(), level).map_err(Error::Compression)?;
+        serde_json::to_writer(&mut encoder, self)?;
+        encoder.finish().map_err(Error::Compression)
+    }
+}
+
+/// Decompresses `bytes` if they start with the magic number of an enabled
+/// compression format, and returns them as-is otherwise.
+pub(crate) fn decompress(bytes: &[u8]) -> Result<Cow<'_, [u8]>, Error> {
+    #[cfg(feature = "gzip")]
+    if bytes.starts_with(GZIP_MAGIC) {
+        return gunzip(bytes).map(Cow::Owned);
+    }
+    #[cfg(feature = "zstd")]
+    if bytes.starts_with(ZSTD_MAGIC) {
+        return unzstd(bytes).map(Cow::Owned);
+    }
+    Ok(Cow::Borrowed(bytes))
+}
+
+#[cfg(feature = "gzip")]
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    use std::io::Read;
+
+    let mut decompressed = Vec::new();
+    flate2::read::GzDecoder::new(bytes)
+        .read_to_end(&mut decompressed)
+        .map_err(Error::Compression)?;
+    Ok(decompressed)
+}
+
+#[cfg(feature = "zstd")]
+fn unzstd(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    zstd::decode_all(bytes).map_err(Error::Compression)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures::FULL;
+
+    fn full() -> TokenList {
+        serde_json::from_str(FULL).unwrap()
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn round_trips_gzip() {
+        let bytes = full().to_gzip_vec().unwrap();
+
+        assert!(bytes.starts_with(GZIP_MAGIC));
+        assert_eq!(TokenList::from_gzip_slice(&bytes).unwrap(), full());
+        assert_eq!(TokenList::from_slice(&bytes).unwrap(), full());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn round_trips_zstd() {
+        let bytes = full().to_zstd_vec(0).unwrap();
+
+        assert!(bytes.starts_with(ZSTD_MAGIC));
+        assert_eq!(TokenList::from_zstd_slice(&bytes).unwrap(), full());
+        assert_eq!(TokenList::from_slice(&bytes).unwrap(), full());
+    }
+
+    #[test]
+    fn passes_through_uncompressed() {
+        assert!(matches!(
+            decompress(FULL.as_bytes()).unwrap(),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn reports_corrupt_data() {
+        let mut bytes = full().to_gzip_vec().unwrap();
+        bytes.truncate(bytes.len() / 2);
+
+        assert!(matches!(
+            TokenList::from_slice(&bytes),
+            Err(Error::Compression(_))
+        ));
+    }
+}
@@ -0,0 +1,253 @@
+//! CSV import and export of the tokens in a list.
+
+use std::collections::BTreeSet;
+use std::io;
+
+use url::Url;
+
+use crate::{Error, ExtensionValue, Number, Token, TokenList};
+
+/// The columns holding the fields of each token, in order.
+const COLUMNS: [&str; 7] = [
+    "name", "symbol", "address", "chainId", "decimals", "logoURI", "tags",
+];
+
+/// The prefix of columns holding extension values.
+const EXTENSION_PREFIX: &str = "extensions.";
+
+/// Separates the tag identifiers in the `tags` column.
+const TAG_SEPARATOR: char = ';';
+
+impl TokenList {
+    /// Writes the tokens in the list as CSV, one row per token.
+    ///
+    /// Token fields are written to the `name`, `symbol`, `address`,
+    /// `chainId`, `decimals`, `logoURI`, and `tags` columns, with tags
+    /// separated by `;`. Each extension used by any token gets a column named
+    /// `extensions.<key>`; cells are left empty for tokens without it.
+    pub fn tokens_to_csv<W: io::Write>(&self, writer: W) -> Result<(), Error> {
+        let extensions: BTreeSet<&str> = self
+            .tokens
+            .iter()
+            .flat_map(|token| token.extensions.keys().map(String::as_str))
+            .collect();
+
+        let mut writer = ::csv::Writer::from_writer(writer);
+
+        let extension_columns = extensions
+            .iter()
+            .map(|key| format!("{}{}", EXTENSION_PREFIX, key));
+        let headers: Vec<String> = COLUMNS
+            .iter()
+            .map(|column| column.to_string())
+            .chain(extension_columns)
+            .collect();
+        writer.write_record(&headers)?;
+
+        for token in &self.tokens {
+            let mut record = vec![
+                token.name.clone(),
+                token.symbol.clone(),
+                token.address.clone(),
+                token.chain_id.to_string(),
+                token.decimals.to_string(),
+                token
+                    .logo_uri
+                    .as_ref()
+                    .map(Url::to_string)
+                    .unwrap_or_default(),
+                token.tags.join(&TAG_SEPARATOR.to_string()),
+            ];
+            for key in &extensions {
+                let value = token.extensions.get(*key);
+                record.push(value.map(format_extension).unwrap_or_default());
+            }
+            writer.write_record(&record)?;
+        }
+
+        writer.flush().map_err(::csv::Error::from)?;
+        Ok(())
+    }
+
+    /// Reads tokens from CSV in the format written by
+    /// [`tokens_to_csv`](Self::tokens_to_csv), returning `list_metadata` with
+    /// its tokens replaced by those read.
+    ///
+    /// The `name`, `symbol`, `address`, `chainId`, and `decimals` columns are
+    /// required. Extension cells are read as booleans or numbers where they
+    /// parse as one, and as strings otherwise; empty cells are omitted.
+    pub fn tokens_from_csv<R: io::Read>(
+        reader: R,
+        list_metadata: TokenList,
+    ) -> Result<TokenList, Error> {
+        let mut reader = ::csv::Reader::from_reader(reader);
+        let headers = reader.headers()?.clone();
+
+        let column = |name: &str| headers.iter().position(|header| header == name);
+        let required = |name: &str| {
+            column(name).ok_or_else(|| Error::InvalidCsv {
+                line: 1,
+                message: format!("missing `{}` column", name),
+            })
+        };
+
+        let name = required("name")?;
+        let symbol = required("symbol")?;
+        let address = required("address")?;
+        let chain_id = required("chainId")?;
+        let decimals = required("decimals")?;
+        let logo_uri = column("logoURI");
+        let tags = column("tags");
+        let extensions: Vec<(usize, &str)> = headers
+            .iter()
+            .enumerate()
+            .filter_map(|(i, header)| Some((i, header.strip_prefix(EXTENSION_PREFIX)?)))
+            .collect();
+
+        let mut tokens = Vec::new();
+        for record in reader.records() {
+            let record = record?;
+            let line = record.position().map_or(0, |position| position.line());
+            let invalid = |column: &str| Error::InvalidCsv {
+                line,
+                message: format!("invalid `{}`", column),
+            };
+            let cell = |i: usize| record.get(i).unwrap_or_default();
+            let optional = |i: Option<usize>| i.map(cell).filter(|value| !value.is_empty());
+
+            tokens.push(Token {
+                name: cell(name).to_owned(),
+                symbol: cell(symbol).to_owned(),
+                address: cell(address).to_owned(),
+                chain_id: cell(chain_id).parse().map_err(|_| invalid("chainId"))?,
+                decimals: cell(decimals).parse().map_err(|_| invalid("decimals"))?,
+                logo_uri: optional(logo_uri)
+                    .map(Url::parse)
+                    .transpose()
+                    .map_err(|_| invalid("logoURI"))?,
+                tags: optional(tags)
+                    .map(|tags| tags.split(TAG_SEPARATOR).map(str::to_owned).collect())
+                    .unwrap_or_default(),
+                extensions: extensions
+                    .iter()
+                    .filter(|(i, _)| !cell(*i).is_empty())
+                    .map(|(i, key)| (key.to_string(), parse_extension(cell(*i))))
+                    .collect(),
+                #[cfg(feature = "unknown-fields")]
+                unknown_fields: Default::default(),
+            });
+        }
+
+        Ok(TokenList {
+            tokens,
+            ..list_metadata
+        })
+    }
+}
+
+fn format_extension(value: &ExtensionValue) -> String {
+    match value {
+        ExtensionValue::String(value) => value.clone(),
+        ExtensionValue::Number(Number::Integer(value)) => value.to_string(),
+        ExtensionValue::Number(Number::BigInteger(value)) => value.to_string(),
+        ExtensionValue::Number(Number::Float(value)) => value.to_string(),
+        ExtensionValue::Boolean(value) => value.to_string(),
+        ExtensionValue::Null => String::new(),
+    }
+}
+
+fn parse_extension(cell: &str) -> ExtensionValue {
+    if let Ok(value) = cell.parse() {
+        ExtensionValue::Boolean(value)
+    } else if let Ok(value) = cell.parse() {
+        ExtensionValue::Number(Number::Integer(value))
+    } else if let Ok(value) = cell.parse() {
+        ExtensionValue::Number(Number::BigInteger(value))
+    } else if let Ok(value) = cell.parse() {
+        ExtensionValue::Number(Number::Float(value))
+    } else {
+        ExtensionValue::String(cell.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    fn full() -> TokenList {
+        fixtures::full()
+    }
+
+    #[test]
+    fn round_trips_tokens() {
+        let token_list = full();
+        let mut csv = Vec::new();
+
+        token_list.tokens_to_csv(&mut csv).unwrap();
+        let read = TokenList::tokens_from_csv(&csv[..], token_list.clone()).unwrap();
+
+        assert_eq!(read, token_list);
+    }
+
+    #[test]
+    fn writes_extension_columns() {
+        let mut csv = Vec::new();
+
+        full().tokens_to_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+
+        assert_eq!(
+            lines.next().unwrap(),
+            "name,symbol,address,chainId,decimals,logoURI,tags,extensions.polygonAddress"
+        );
+        assert!(lines
+            .next()
+            .unwrap()
+            .ends_with(",1,2,https://raw.githubusercontent.com/telcoin/token-lists/master/assets/logo-telcoin-250x250.png,telcoin,0xdF7837DE1F2Fa4631D716CF2502f8b230F1dcc32"));
+    }
+
+    #[test]
+    fn infers_extension_types() {
+        assert_eq!(parse_extension("true"), ExtensionValue::Boolean(true));
+        assert_eq!(
+            parse_extension("42"),
+            ExtensionValue::Number(Number::Integer(42))
+        );
+        assert_eq!(
+            parse_extension("18446744073709551616"),
+            ExtensionValue::Number(Number::BigInteger(1 << 64))
+        );
+        assert_eq!(
+            parse_extension("0.5"),
+            ExtensionValue::Number(Number::Float(0.5))
+        );
+        assert_eq!(
+            parse_extension("0xabc"),
+            ExtensionValue::String("0xabc".to_owned())
+        );
+    }
+
+    #[test]
+    fn reports_invalid_cells() {
+        let csv = "name,symbol,address,chainId,decimals\nTelcoin,TEL,0x0,mainnet,2\n";
+
+        match TokenList::tokens_from_csv(csv.as_bytes(), full()) {
+            Err(Error::InvalidCsv { line: 2, message }) => assert_eq!(message, "invalid `chainId`"),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn requires_token_columns() {
+        let csv = "name,symbol,address\n";
+
+        match TokenList::tokens_from_csv(csv.as_bytes(), full()) {
+            Err(Error::InvalidCsv { line: 1, message }) => {
+                assert_eq!(message, "missing `chainId` column")
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+}
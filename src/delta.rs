@@ -0,0 +1,258 @@
+//! Updating a cached token list from JSON Patches rather than refetching it.
+//!
+//! A publisher serves a [`DeltaDocument`] next to the list, at the list's URI
+//! with `.deltas` appended to its path, such as
+//! `https://example.com/list.json.deltas`. The document names the latest
+//! version of the list and its content hash, and holds a JSON Patch
+//! ([RFC 6902](https://www.rfc-editor.org/rfc/rfc6902)) from each earlier
+//! version it supports to the latest.
+
+use indexmap::IndexMap;
+use json_patch::Patch;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::hash::{content_hash, to_hex};
+use crate::{Error, Fetcher, TokenList};
+
+/// JSON Patches bringing earlier versions of a list up to its latest
+/// version.
+///
+/// ```json
+/// {
+///   "version": { "major": 1, "minor": 2, "patch": 0 },
+///   "sha256": "6c0f…",
+///   "deltas": {
+///     "1.1.0": [{ "op": "remove", "path": "/tokens/3" }]
+///   }
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct DeltaDocument {
+    /// The latest version of the list
+    #[serde(with = "crate::version")]
+    pub version: Version,
+
+    /// The SHA-256 digest of the canonical JSON of the latest version, as
+    /// lowercase hexadecimal
+    pub sha256: String,
+
+    /// A patch from each earlier version, keyed by the version as
+    /// `major.minor.patch`, to the latest version
+    pub deltas: IndexMap<String, Patch>,
+}
+
+/// The outcome of [`TokenList::sync_from_fetcher`].
+#[derive(Clone, PartialEq, Debug)]
+pub enum Synced {
+    /// The cached list is the latest version
+    Unchanged,
+
+    /// The cached list was brought up to date with a delta
+    Patched(TokenList),
+
+    /// The latest version was fetched in full
+    Fetched(TokenList),
+}
+
+impl Synced {
+    /// Returns the latest version of the list, given the cached one.
+    pub fn into_token_list(self, cached: TokenList) -> TokenList {
+        match self {
+            Synced::Unchanged => cached,
+            Synced::Patched(token_list) | Synced::Fetched(token_list) => token_list,
+        }
+    }
+}
+
+impl DeltaDocument {
+    /// Creates a document with deltas from each of `previous` to `latest`.
+    pub fn new(latest: &TokenList, previous: &[TokenList]) -> Result<Self, Error> {
+        let target = serde_json::to_value(latest)?;
+        let mut deltas = IndexMap::new();
+        for token_list in previous {
+            let source = serde_json::to_value(token_list)?;
+            deltas.insert(
+                token_list.version.to_string(),
+                json_patch::diff(&source, &target),
+            );
+        }
+        Ok(DeltaDocument {
+            version: latest.version.clone(),
+            sha256: to_hex(&content_hash(latest)?),
+            deltas,
+        })
+    }
+
+    /// Returns the URI the document for the list at `uri` is served at, by
+    /// convention.
+    pub fn uri_for(uri: &str) -> String {
+        match Url::parse(uri) {
+            Ok(mut url) if !url.cannot_be_a_base() => {
+                let path = format!("{}.deltas", url.path());
+                url.set_path(&path);
+                url.into()
+            }
+            _ => format!("{}.deltas", uri),
+        }
+    }
+
+    /// Returns `true` if `token_list` is the latest version, with the same
+    /// content.
+    pub fn is_latest(&self, token_list: &TokenList) -> Result<bool, Error> {
+        Ok(token_list.version == self.version && to_hex(&content_hash(token_list)?) == self.sha256)
+    }
+
+    /// Applies the delta from the version of `token_list` to the latest
+    /// version, or returns `None` if the document has no delta from it.
+    ///
+    /// Fails with [`Error::DeltaMismatch`] if the patched list doesn't have
+    /// the content hash of the latest version.
+    pub fn apply(&self, token_list: &TokenList) -> Result<Option<TokenList>, Error> {
+        let patch = match self.deltas.get(&token_list.version.to_string()) {
+            Some(patch) => patch,
+            None => return Ok(None),
+        };
+        let mut value = serde_json::to_value(token_list)?;
+        json_patch::patch(&mut value, patch)?;
+
+        let patched: TokenList = crate::json::from_value(value)?;
+        if !self.is_latest(&patched)? {
+            return Err(Error::DeltaMismatch);
+        }
+        Ok(Some(patched))
+    }
+}
+
+impl TokenList {
+    /// Brings `cached`, an earlier fetch of the list at `uri`, up to date
+    /// using the provided [`Fetcher`].
+    ///
+    /// The list's [`DeltaDocument`] is fetched first. If the cached list is
+    /// already the latest version, or the document has a delta from its
+    /// version that applies cleanly, the list itself isn't fetched. Otherwise,
+    /// including when the publisher serves no document, the list is fetched
+    /// in full.
+    pub async fn sync_from_fetcher<F: Fetcher + ?Sized>(
+        fetcher: &F,
+        uri: &str,
+        cached: &TokenList,
+    ) -> Result<Synced, Error> {
+        match sync_delta(fetcher, uri, cached).await {
+            Ok(Some(synced)) => return Ok(synced),
+            Ok(None) => {}
+            #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+            Err(err) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(uri, error = %err, "delta sync failed, fetching in full");
+            }
+        }
+        Ok(Synced::Fetched(
+            TokenList::from_fetcher(fetcher, uri).await?,
+        ))
+    }
+}
+
+/// Syncs `cached` from the list's delta document, or returns `None` if the
+/// document has no delta from its version.
+async fn sync_delta<F: Fetcher + ?Sized>(
+    fetcher: &F,
+    uri: &str,
+    cached: &TokenList,
+) -> Result<Option<Synced>, Error> {
+    let body = fetcher.fetch(&DeltaDocument::uri_for(uri)).await?;
+    let document: DeltaDocument = crate::json::from_slice(&body)?;
+
+    if document.is_latest(cached)? {
+        return Ok(Some(Synced::Unchanged));
+    }
+    Ok(document.apply(cached)?.map(Synced::Patched))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{fixtures, MockFetcher, MockResponse};
+
+    const URI: &str = "https://example.com/list.json";
+    const DELTAS_URI: &str = "https://example.com/list.json.deltas";
+
+    fn versions() -> (TokenList, TokenList) {
+        let old = fixtures::full();
+        let mut new = old.clone();
+        new.version = Version::new(1, 0, 0);
+        new.tokens.remove(0);
+        new.tokens[0].name = "Renamed".to_owned();
+        (old, new)
+    }
+
+    fn serve(new: &TokenList, document: Option<&DeltaDocument>) -> MockFetcher {
+        let fetcher = MockFetcher::new();
+        fetcher.respond(URI, MockResponse::json(new));
+        if let Some(document) = document {
+            fetcher.respond(
+                DELTAS_URI,
+                MockResponse::body(serde_json::to_vec(document).unwrap()),
+            );
+        }
+        fetcher
+    }
+
+    #[test]
+    fn derives_delta_uris() {
+        assert_eq!(DeltaDocument::uri_for(URI), DELTAS_URI);
+        assert_eq!(
+            DeltaDocument::uri_for("https://example.com/list?v=1"),
+            "https://example.com/list.deltas?v=1"
+        );
+    }
+
+    #[tokio::test]
+    async fn patches_cached_lists() {
+        let (old, new) = versions();
+        let document = DeltaDocument::new(&new, std::slice::from_ref(&old)).unwrap();
+        let fetcher = serve(&new, Some(&document));
+
+        let synced = TokenList::sync_from_fetcher(&fetcher, URI, &old)
+            .await
+            .unwrap();
+        assert_eq!(synced, Synced::Patched(new.clone()));
+        assert_eq!(fetcher.requests(), [DELTAS_URI]);
+
+        let synced = TokenList::sync_from_fetcher(&fetcher, URI, &new)
+            .await
+            .unwrap();
+        assert_eq!(synced, Synced::Unchanged);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_full_fetches() {
+        let (old, new) = versions();
+
+        // no document
+        let fetcher = serve(&new, None);
+        let synced = TokenList::sync_from_fetcher(&fetcher, URI, &old)
+            .await
+            .unwrap();
+        assert_eq!(synced, Synced::Fetched(new.clone()));
+
+        // no delta from the cached version
+        let document = DeltaDocument::new(&new, &[]).unwrap();
+        let fetcher = serve(&new, Some(&document));
+        let synced = TokenList::sync_from_fetcher(&fetcher, URI, &old)
+            .await
+            .unwrap();
+        assert_eq!(synced, Synced::Fetched(new.clone()));
+
+        // a delta that doesn't produce the advertised content
+        let mut document = DeltaDocument::new(&new, std::slice::from_ref(&old)).unwrap();
+        document.sha256 = to_hex(&[0; 32]);
+        assert!(matches!(document.apply(&old), Err(Error::DeltaMismatch)));
+        let fetcher = serve(&new, Some(&document));
+        let synced = TokenList::sync_from_fetcher(&fetcher, URI, &old)
+            .await
+            .unwrap();
+        assert_eq!(synced, Synced::Fetched(new));
+    }
+}
@@ -0,0 +1,308 @@
+//! Differences between versions of a list, and the version bumps they call
+//! for.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use semver::Version;
+use serde::Serialize;
+
+use crate::{Timestamp, Token, TokenList};
+
+/// The tokens added, removed, and changed between two versions of a list,
+/// found with [`TokenList::diff`].
+///
+/// Tokens are identified by chain and address, compared case-insensitively.
+#[derive(Serialize, PartialEq, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenListDiff {
+    /// Tokens only in the new list, in its order
+    pub added: Vec<Token>,
+
+    /// Tokens only in the old list, in its order
+    pub removed: Vec<Token>,
+
+    /// Tokens in both lists whose metadata differs, in the new list's order
+    pub changed: Vec<TokenChange>,
+}
+
+impl TokenListDiff {
+    /// Returns `true` if the lists have the same tokens with the same
+    /// metadata.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Returns the smallest version bump the changes call for under the
+    /// token list versioning rules: major if tokens were removed, minor if
+    /// tokens were added, and patch if tokens changed.
+    pub fn version_bump(&self) -> VersionBump {
+        if !self.removed.is_empty() {
+            VersionBump::Major
+        } else if !self.added.is_empty() {
+            VersionBump::Minor
+        } else if !self.changed.is_empty() {
+            VersionBump::Patch
+        } else {
+            VersionBump::None
+        }
+    }
+}
+
+/// A token whose metadata differs between two versions of a list.
+#[derive(Serialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenChange {
+    /// The token in the old list
+    pub before: Token,
+
+    /// The token in the new list
+    pub after: Token,
+
+    /// The names of the fields that differ, as in the schema; e.g.
+    /// `logoURI`
+    pub fields: Vec<&'static str>,
+}
+
+/// A change to a semantic version.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum VersionBump {
+    /// The version stays the same
+    None,
+    /// The patch version is incremented
+    Patch,
+    /// The minor version is incremented, and the patch version reset
+    Minor,
+    /// The major version is incremented, and the others reset
+    Major,
+}
+
+impl VersionBump {
+    /// Returns `version` bumped.
+    pub fn apply(self, version: &Version) -> Version {
+        let (major, minor, patch) = (version.major, version.minor, version.patch);
+        match self {
+            VersionBump::None => Version::new(major, minor, patch),
+            VersionBump::Patch => Version::new(major, minor, patch + 1),
+            VersionBump::Minor => Version::new(major, minor + 1, 0),
+            VersionBump::Major => Version::new(major + 1, 0, 0),
+        }
+    }
+}
+
+impl fmt::Display for VersionBump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            VersionBump::None => "none",
+            VersionBump::Patch => "patch",
+            VersionBump::Minor => "minor",
+            VersionBump::Major => "major",
+        })
+    }
+}
+
+impl Token {
+    /// Returns `true` if the tokens are the same asset: they have the same
+    /// chain ID, address (compared case-insensitively), decimals, and symbol.
+    ///
+    /// Presentation-only fields, such as the name, logo, tags and
+    /// extensions, are ignored; these are what lists from different
+    /// publishers tend to disagree on.
+    pub fn semantic_eq(&self, other: &Token) -> bool {
+        self.chain_id == other.chain_id
+            && self.address.eq_ignore_ascii_case(&other.address)
+            && self.decimals == other.decimals
+            && self.symbol == other.symbol
+    }
+}
+
+impl TokenList {
+    /// Returns `true` if the lists have the same tokens by
+    /// [`Token::semantic_eq`], in any order.
+    ///
+    /// The lists' own metadata, such as their names and versions, is
+    /// ignored.
+    pub fn semantic_eq(&self, other: &TokenList) -> bool {
+        let key = |token: &Token| (token.chain_id, token.address.to_lowercase());
+        let tokens: HashMap<_, &Token> = self
+            .tokens
+            .iter()
+            .map(|token| (key(token), token))
+            .collect();
+        let other: HashMap<_, &Token> = other
+            .tokens
+            .iter()
+            .map(|token| (key(token), token))
+            .collect();
+
+        tokens.len() == other.len()
+            && tokens
+                .iter()
+                .all(|(key, token)| other.get(key).is_some_and(|other| token.semantic_eq(other)))
+    }
+
+    /// Compares the tokens of the list with those of `new`, a later version
+    /// of it.
+    pub fn diff(&self, new: &TokenList) -> TokenListDiff {
+        let key = |token: &Token| (token.chain_id, token.address.to_lowercase());
+        let old: HashMap<_, &Token> = self
+            .tokens
+            .iter()
+            .map(|token| (key(token), token))
+            .collect();
+        let new_keys: HashSet<_> = new.tokens.iter().map(key).collect();
+
+        let mut diff = TokenListDiff::default();
+        for token in &new.tokens {
+            match old.get(&key(token)) {
+                None => diff.added.push(token.clone()),
+                Some(before) => {
+                    let fields = changed_fields(before, token);
+                    if !fields.is_empty() {
+                        diff.changed.push(TokenChange {
+                            before: (*before).clone(),
+                            after: token.clone(),
+                            fields,
+                        });
+                    }
+                }
+            }
+        }
+        diff.removed = self
+            .tokens
+            .iter()
+            .filter(|token| !new_keys.contains(&key(token)))
+            .cloned()
+            .collect();
+        diff
+    }
+
+    /// Sets the version and timestamp of the list for its release after
+    /// `previous`, returning the bump applied.
+    ///
+    /// The version is that of `previous` bumped as the tokens changed between
+    /// them call for, by [`TokenListDiff::version_bump`].
+    pub fn bump_from(&mut self, previous: &TokenList, timestamp: Timestamp) -> VersionBump {
+        let bump = previous.diff(self).version_bump();
+        self.version = bump.apply(&previous.version);
+        self.timestamp = timestamp;
+        bump
+    }
+}
+
+/// Returns the names of the fields that differ between `before` and `after`,
+/// which have the same chain and, but for case, address.
+fn changed_fields(before: &Token, after: &Token) -> Vec<&'static str> {
+    let mut fields = Vec::new();
+    if before.name != after.name {
+        fields.push("name");
+    }
+    if before.symbol != after.symbol {
+        fields.push("symbol");
+    }
+    if before.address != after.address {
+        fields.push("address");
+    }
+    if before.decimals != after.decimals {
+        fields.push("decimals");
+    }
+    if before.logo_uri != after.logo_uri {
+        fields.push("logoURI");
+    }
+    if before.tags != after.tags {
+        fields.push("tags");
+    }
+    if before.extensions != after.extensions {
+        fields.push("extensions");
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    #[test]
+    fn diffs_tokens() {
+        let old = fixtures::full();
+        let mut new = old.clone();
+        let removed = new.tokens.remove(0);
+        new.tokens[0].decimals += 1;
+        let mut added = removed.clone();
+        added.chain_id = 100;
+        new.tokens.push(added.clone());
+
+        let diff = old.diff(&new);
+
+        assert_eq!(diff.added, vec![added]);
+        assert_eq!(diff.removed, vec![removed]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].fields, vec!["decimals"]);
+        assert_eq!(diff.version_bump(), VersionBump::Major);
+    }
+
+    #[test]
+    fn ignores_address_case() {
+        let old = fixtures::full();
+        let mut new = old.clone();
+        new.tokens[0].address = new.tokens[0].address.to_lowercase();
+
+        let diff = old.diff(&new);
+
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(diff.changed[0].fields, vec!["address"]);
+        assert_eq!(diff.version_bump(), VersionBump::Patch);
+    }
+
+    #[test]
+    fn compares_identity_fields() {
+        let list = fixtures::full();
+        let mut other = list.clone();
+        other.name = "Other".to_owned();
+        other.tokens.reverse();
+        other.tokens[0].address = other.tokens[0].address.to_lowercase();
+        other.tokens[0].logo_uri = None;
+        other.tokens[0].tags.clear();
+        other.tokens[1].name = "TEL".to_owned();
+        other.tokens[1].extensions.clear();
+
+        assert!(list.semantic_eq(&other));
+        assert!(list.tokens[1].semantic_eq(&other.tokens[0]));
+
+        other.tokens[1].decimals = 18;
+        assert!(!list.semantic_eq(&other));
+
+        other.tokens.pop();
+        assert!(!list.semantic_eq(&other));
+        assert!(!other.semantic_eq(&list));
+    }
+
+    #[test]
+    fn bumps_versions() {
+        let previous = fixtures::full();
+        let mut token_list = previous.clone();
+        let mut added = token_list.tokens[0].clone();
+        added.chain_id = 100;
+        token_list.tokens.push(added);
+        let timestamp = crate::timestamp::parse_rfc3339("2022-01-01T00:00:00Z").unwrap();
+
+        let bump = token_list.bump_from(&previous, timestamp);
+
+        assert_eq!(bump, VersionBump::Minor);
+        assert_eq!(
+            token_list.version,
+            VersionBump::Minor.apply(&previous.version)
+        );
+        assert_eq!(token_list.timestamp, timestamp);
+        assert_eq!(
+            VersionBump::Major.apply(&Version::new(1, 2, 3)),
+            Version::new(2, 0, 0)
+        );
+        assert_eq!(
+            VersionBump::Patch.apply(&Version::new(1, 2, 3)),
+            Version::new(1, 2, 4)
+        );
+    }
+}
@@ -0,0 +1,374 @@
+//! A persistent on-disk cache of fetched token lists.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::hash::{canonical_json, to_hex};
+#[cfg(feature = "storage")]
+use crate::Storage;
+use crate::{Error, FetchOptions, Fetched, Fetcher, ReqwestFetcher, Timestamp, TokenList};
+
+/// A cache of token lists persisted to a directory, so that fetched lists
+/// survive restarts and remain available while offline.
+///
+/// Each list is stored as canonical JSON in a file named after the SHA-256
+/// hash of its URI, next to a `.meta.json` sidecar holding its
+/// [`CacheMetadata`]. Lists are revalidated with their `ETag` when fetched
+/// again.
+#[derive(Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+    fetcher: Arc<dyn Fetcher>,
+    #[cfg(feature = "storage")]
+    storage: Option<Arc<dyn Storage>>,
+}
+
+/// Metadata stored alongside each cached list.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheMetadata {
+    /// The URI the list was fetched from
+    pub uri: String,
+
+    /// The `ETag` of the response the list was read from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+
+    /// When the list was last fetched or revalidated
+    #[serde(with = "crate::timestamp")]
+    pub fetched_at: Timestamp,
+}
+
+/// A token list read from a [`DiskCache`], along with its metadata.
+#[derive(PartialEq, Clone, Debug)]
+pub struct CachedList {
+    /// The cached list
+    pub token_list: TokenList,
+
+    /// Metadata about when and where the list was fetched
+    pub metadata: CacheMetadata,
+}
+
+impl DiskCache {
+    /// Creates a cache that stores lists in `dir`, creating the directory
+    /// when the first list is stored.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self::with_options(dir, FetchOptions::default())
+    }
+
+    /// Creates a cache that fetches lists using the provided
+    /// [`FetchOptions`].
+    pub fn with_options<P: Into<PathBuf>>(dir: P, options: FetchOptions) -> Self {
+        Self::with_fetcher(dir, Arc::new(ReqwestFetcher::new(options)))
+    }
+
+    /// Creates a cache that fetches lists using the provided [`Fetcher`].
+    pub fn with_fetcher<P: Into<PathBuf>>(dir: P, fetcher: Arc<dyn Fetcher>) -> Self {
+        DiskCache {
+            dir: dir.into(),
+            fetcher,
+            #[cfg(feature = "storage")]
+            storage: None,
+        }
+    }
+
+    /// Creates a cache that fetches lists using the provided [`Fetcher`] and
+    /// also puts each new list it fetches in `storage`, e.g. to keep their
+    /// versions in a database shared by other subsystems.
+    ///
+    /// A fetch fails if the list can't be stored.
+    #[cfg(feature = "storage")]
+    pub fn with_storage<P: Into<PathBuf>>(
+        dir: P,
+        fetcher: Arc<dyn Fetcher>,
+        storage: Arc<dyn Storage>,
+    ) -> Self {
+        DiskCache {
+            dir: dir.into(),
+            fetcher,
+            storage: Some(storage),
+        }
+    }
+
+    /// Returns the directory the cache is stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Fetches the list at `uri`, falling back to the last cached snapshot if
+    /// the fetch fails.
+    ///
+    /// **Note**: This must be called from a running tokio >1.0.0 runtime.
+    pub async fn get(&self, uri: &str) -> Result<CachedList, Error> {
+        match self.fetch(uri).await {
+            Ok(cached) => Ok(cached),
+            // a snapshot that can't be read is no fallback
+            Err(err) => self.load(uri).ok().flatten().ok_or(err),
+        }
+    }
+
+    /// Fetches the list at `uri` and stores it in the cache. If a snapshot is
+    /// already cached, the request is conditional on its `ETag`; a snapshot
+    /// that can't be read is fetched again and overwritten.
+    ///
+    /// **Note**: This must be called from a running tokio >1.0.0 runtime.
+    pub async fn fetch(&self, uri: &str) -> Result<CachedList, Error> {
+        let cached = self.load(uri).ok().flatten();
+        let etag = cached
+            .as_ref()
+            .and_then(|cached| cached.metadata.etag.as_deref());
+
+        let (body, etag) = match (self.fetcher.fetch_if_modified(uri, etag).await?, cached) {
+            (Fetched::Modified { body, etag }, _) => (body, etag),
+            (Fetched::NotModified, Some(mut cached)) => {
+                cached.metadata.fetched_at = crate::timestamp::now();
+                self.write_metadata(uri, &cached.metadata)?;
+                return Ok(cached);
+            }
+            // the fetcher ignored that there was nothing to revalidate
+            (Fetched::NotModified, None) => (self.fetcher.fetch(uri).await?, None),
+        };
+
+        let token_list = crate::json::from_slice(&body)?;
+        #[cfg(feature = "storage")]
+        if let Some(storage) = &self.storage {
+            storage.put_list(&token_list).await?;
+        }
+        let metadata = self.store(uri, &token_list, etag)?;
+        Ok(CachedList {
+            token_list,
+            metadata,
+        })
+    }
+
+    /// Reads the cached snapshot for `uri`, if there is one.
+    pub fn load(&self, uri: &str) -> Result<Option<CachedList>, Error> {
+        let (list_path, metadata_path) = self.paths(uri);
+
+        let token_list = match read_if_exists(&list_path)? {
+            Some(bytes) => crate::json::from_slice(&bytes)?,
+            None => return Ok(None),
+        };
+        let metadata = match read_if_exists(&metadata_path)? {
+            Some(bytes) => crate::json::from_slice(&bytes)?,
+            None => return Ok(None),
+        };
+
+        Ok(Some(CachedList {
+            token_list,
+            metadata,
+        }))
+    }
+
+    /// Stores `token_list` as the snapshot for `uri`, returning the metadata
+    /// written alongside it.
+    pub fn store(
+        &self,
+        uri: &str,
+        token_list: &TokenList,
+        etag: Option<String>,
+    ) -> Result<CacheMetadata, Error> {
+        let (list_path, _) = self.paths(uri);
+        let metadata = CacheMetadata {
+            uri: uri.to_owned(),
+            etag,
+            fetched_at: crate::timestamp::now(),
+        };
+
+        fs::create_dir_all(&self.dir)?;
+        write_atomic(&list_path, &canonical_json(token_list)?)?;
+        self.write_metadata(uri, &metadata)?;
+
+        Ok(metadata)
+    }
+
+    /// Removes the snapshot for `uri`, returning `true` if one was cached.
+    pub fn remove(&self, uri: &str) -> Result<bool, Error> {
+        let (list_path, metadata_path) = self.paths(uri);
+        let removed = remove_if_exists(&list_path)?;
+        remove_if_exists(&metadata_path)?;
+        Ok(removed)
+    }
+
+    fn write_metadata(&self, uri: &str, metadata: &CacheMetadata) -> Result<(), Error> {
+        let (_, metadata_path) = self.paths(uri);
+        write_atomic(&metadata_path, &serde_json::to_vec_pretty(metadata)?)?;
+        Ok(())
+    }
+
+    fn paths(&self, uri: &str) -> (PathBuf, PathBuf) {
+        let key = to_hex(&Sha256::digest(uri.as_bytes()));
+
+        (
+            self.dir.join(format!("{}.json", key)),
+            self.dir.join(format!("{}.meta.json", key)),
+        )
+    }
+}
+
+impl fmt::Debug for DiskCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiskCache")
+            .field("dir", &self.dir)
+            .finish_non_exhaustive()
+    }
+}
+
+fn read_if_exists(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<bool> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Writes `bytes` to a temporary file before moving it into place, so readers
+/// never observe a partially written file.
+fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{fixtures, MockFetcher, MockResponse};
+
+    const URI: &str = "https://example.com/list.json";
+
+    #[tokio::test]
+    async fn revalidates_with_etag() {
+        let dir = tempfile::tempdir().unwrap();
+        let fetcher = Arc::new(MockFetcher::new());
+        fetcher.respond(URI, MockResponse::body(fixtures::FULL).with_etag("\"v1\""));
+        let cache = DiskCache::with_fetcher(dir.path(), fetcher.clone());
+
+        let fetched = cache.fetch(URI).await.unwrap();
+        assert_eq!(fetched.metadata.etag.as_deref(), Some("\"v1\""));
+
+        let revalidated = cache.fetch(URI).await.unwrap();
+        assert_eq!(revalidated.token_list, fetched.token_list);
+        assert!(revalidated.metadata.fetched_at >= fetched.metadata.fetched_at);
+        assert_eq!(fetcher.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn serves_snapshot_while_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        let fetcher = Arc::new(MockFetcher::new());
+        fetcher.respond(URI, MockResponse::body(fixtures::FULL));
+
+        DiskCache::with_fetcher(dir.path(), fetcher)
+            .fetch(URI)
+            .await
+            .unwrap();
+
+        // a fresh cache over the same directory, with the list now unreachable
+        let fetcher = Arc::new(MockFetcher::new());
+        fetcher.respond(URI, MockResponse::error("offline"));
+        let cache = DiskCache::with_fetcher(dir.path(), fetcher);
+        let cached = cache.get(URI).await.unwrap();
+
+        assert_eq!(cached.token_list.name, "TELcoins");
+        assert_eq!(cached.metadata.uri, URI);
+    }
+
+    #[cfg(feature = "storage")]
+    #[tokio::test]
+    async fn puts_fetched_lists_in_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let fetcher = Arc::new(MockFetcher::new());
+        fetcher.respond(URI, MockResponse::body(fixtures::FULL));
+        let storage = Arc::new(crate::MemoryStorage::new());
+
+        let cache = DiskCache::with_storage(dir.path(), fetcher, storage.clone());
+        let fetched = cache.fetch(URI).await.unwrap();
+
+        assert_eq!(
+            storage.get_list("TELcoins", None).await.unwrap(),
+            Some(fetched.token_list)
+        );
+    }
+
+    #[tokio::test]
+    async fn refetches_corrupt_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let fetcher = Arc::new(MockFetcher::new());
+        fetcher.respond(URI, MockResponse::body(fixtures::FULL).with_etag("\"v1\""));
+        let cache = DiskCache::with_fetcher(dir.path(), fetcher.clone());
+
+        cache.fetch(URI).await.unwrap();
+        let (list_path, _) = cache.paths(URI);
+        fs::write(&list_path, "{ not json").unwrap();
+        assert!(cache.load(URI).is_err());
+
+        let fetched = cache.fetch(URI).await.unwrap();
+
+        assert_eq!(fetched.token_list, fixtures::full());
+        assert_eq!(cache.load(URI).unwrap(), Some(fetched));
+    }
+
+    #[tokio::test]
+    async fn get_ignores_corrupt_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let fetcher = Arc::new(MockFetcher::new());
+        fetcher.respond(URI, MockResponse::error("offline"));
+        let cache = DiskCache::with_fetcher(dir.path(), fetcher);
+
+        cache.store(URI, &fixtures::full(), None).unwrap();
+        let (list_path, _) = cache.paths(URI);
+        fs::write(&list_path, "{ not json").unwrap();
+
+        assert!(matches!(cache.get(URI).await, Err(Error::Fetch(_))));
+    }
+
+    #[tokio::test]
+    async fn refetches_when_not_modified_without_etag() {
+        // answers every conditional request with Not Modified, even without
+        // an `ETag` to compare
+        struct StaleFetcher;
+
+        #[async_trait::async_trait]
+        impl Fetcher for StaleFetcher {
+            async fn fetch(&self, _: &str) -> Result<Vec<u8>, Error> {
+                Ok(fixtures::FULL.into())
+            }
+
+            async fn fetch_if_modified(&self, _: &str, _: Option<&str>) -> Result<Fetched, Error> {
+                Ok(Fetched::NotModified)
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::with_fetcher(dir.path(), Arc::new(StaleFetcher));
+
+        let fetched = cache.fetch(URI).await.unwrap();
+
+        assert_eq!(fetched.token_list, fixtures::full());
+        assert_eq!(fetched.metadata.etag, None);
+    }
+
+    #[test]
+    fn load_missing_returns_none() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::new(dir.path());
+
+        assert_eq!(cache.load("https://example.com/list.json").unwrap(), None);
+        assert!(!cache.remove("https://example.com/list.json").unwrap());
+    }
+}
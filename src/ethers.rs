@@ -0,0 +1,197 @@
+//! Interoperability with [ethers](https://docs.rs/ethers).
+
+use std::sync::Arc;
+
+use ::ethers::abi::{parse_abi, Abi};
+use ::ethers::contract::Contract;
+use ::ethers::providers::{JsonRpcClient, Middleware, Provider, RpcError};
+use ::ethers::types::Address;
+#[cfg(feature = "registry")]
+use ::ethers::types::{TransactionRequest, TxHash};
+use async_trait::async_trait;
+use serde_json::Value;
+
+#[cfg(feature = "registry")]
+use crate::ContenthashUpdate;
+use crate::{Error, JsonRpc, Token, TokenAddress};
+
+/// The parts of the ERC-20 interface that describe a token.
+const ERC20_ABI: &[&str] = &[
+    "function name() view returns (string)",
+    "function symbol() view returns (string)",
+    "function decimals() view returns (uint8)",
+    "function totalSupply() view returns (uint256)",
+    "function balanceOf(address owner) view returns (uint256)",
+];
+
+/// Returns the ABI of [`ERC20_ABI`].
+fn erc20_abi() -> Abi {
+    parse_abi(ERC20_ABI).expect("ABI is valid")
+}
+
+/// Parses `address` as an ethers [`Address`].
+fn parse(address: &str) -> Result<Address, Error> {
+    address
+        .parse()
+        .map_err(|_| Error::InvalidAddress(address.to_owned()))
+}
+
+impl Token {
+    /// Returns the token's address as an ethers [`Address`].
+    pub fn ethers_address(&self) -> Result<Address, Error> {
+        parse(&self.address)
+    }
+
+    /// Returns an ethers contract instance for the token, exposing `name`,
+    /// `symbol`, `decimals`, `totalSupply`, and `balanceOf` from the ERC-20
+    /// interface.
+    pub fn erc20_contract<M: Middleware>(&self, client: Arc<M>) -> Result<Contract<M>, Error> {
+        Ok(Contract::new(self.ethers_address()?, erc20_abi(), client))
+    }
+}
+
+impl TokenAddress {
+    /// Returns the address as an ethers [`Address`].
+    pub fn ethers_address(&self) -> Result<Address, Error> {
+        parse(&self.address)
+    }
+}
+
+#[cfg(feature = "registry")]
+impl ContenthashUpdate {
+    /// Sends the update through `client`, which must sign transactions from
+    /// the owner of the name; e.g. a `SignerMiddleware`.
+    ///
+    /// Returns the hash of the transaction once it's been submitted, without
+    /// waiting for it to be mined.
+    pub async fn send<M>(&self, client: &M) -> Result<TxHash, Error>
+    where
+        M: Middleware,
+        M::Error: 'static,
+    {
+        let tx = TransactionRequest::new()
+            .to(parse(&self.resolver)?)
+            .data(self.calldata.clone());
+        let pending = client
+            .send_transaction(tx, None)
+            .await
+            .map_err(|err| Error::Transaction(Box::new(err)))?;
+        Ok(pending.tx_hash())
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<P: JsonRpcClient> JsonRpc for Provider<P> {
+    async fn request(&self, method: &str, params: Value) -> Result<Value, Error> {
+        Provider::request(self, method, params)
+            .await
+            .map_err(|err| match err.as_error_response() {
+                Some(response) => Error::Rpc {
+                    code: response.code,
+                    message: response.message.clone(),
+                },
+                None => Error::Provider(err),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ::ethers::abi::{encode, Token as AbiToken};
+    use ::ethers::providers::MockProvider;
+    use ::ethers::types::Bytes;
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    const ADDRESS: &str = "0x467Bccd9d29f223BcE8043b84E8C8B282827790F";
+
+    fn token() -> Token {
+        serde_json::from_value(json!({
+            "name": "Telcoin",
+            "symbol": "TEL",
+            "address": ADDRESS,
+            "chainId": 1,
+            "decimals": 2
+        }))
+        .unwrap()
+    }
+
+    /// Queues `responses` to be returned in order.
+    fn respond(mock: &MockProvider, responses: &[Value]) {
+        // the mock returns the most recently pushed response first
+        for response in responses.iter().rev() {
+            mock.push::<Value, _>(response.clone()).unwrap();
+        }
+    }
+
+    #[test]
+    fn converts_addresses() {
+        let address: Address = ADDRESS.parse().unwrap();
+        let mut token = token();
+
+        assert_eq!(token.ethers_address().unwrap(), address);
+        token.address = "0x467B".to_owned();
+        assert!(matches!(
+            token.ethers_address(),
+            Err(Error::InvalidAddress(_))
+        ));
+    }
+
+    #[test]
+    fn builds_erc20_contracts() {
+        let (provider, _) = Provider::mocked();
+
+        let contract = token().erc20_contract(Arc::new(provider)).unwrap();
+
+        assert_eq!(contract.address(), ADDRESS.parse().unwrap());
+        assert!(contract.abi().function("decimals").is_ok());
+    }
+
+    #[cfg(feature = "registry")]
+    #[tokio::test]
+    async fn sends_contenthash_updates() {
+        let (provider, mock) = Provider::mocked();
+        let hash = TxHash::repeat_byte(0xab);
+        // the gas price and limit are filled in before sending
+        respond(&mock, &[json!("0x1"), json!("0x5208"), json!(hash)]);
+        let update = ContenthashUpdate {
+            name: "tokens.example.eth".to_owned(),
+            resolver: ADDRESS.to_owned(),
+            calldata: vec![1, 2, 3],
+        };
+
+        assert_eq!(update.send(&provider).await.unwrap(), hash);
+    }
+
+    #[tokio::test]
+    async fn verifies_through_providers() {
+        let (provider, mock) = Provider::mocked();
+        let abi = |token: AbiToken| Bytes::from(encode(&[token]));
+        respond(
+            &mock,
+            &[
+                json!("0x1"),
+                // no Multicall3 on the chain
+                json!("0x"),
+                json!(abi(AbiToken::String("Telcoin".to_owned()))),
+                json!(abi(AbiToken::String("TEL".to_owned()))),
+                json!(abi(AbiToken::Uint(18.into()))),
+            ],
+        );
+        let mut token_list = fixtures::minimal();
+        token_list.tokens = vec![token()];
+        let rpcs: HashMap<u32, _> = vec![(1, provider)].into_iter().collect();
+
+        let report = token_list.verify_onchain_with(&rpcs).await;
+
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].onchain, "18");
+        mock.assert_request("eth_chainId", Vec::<()>::new())
+            .unwrap();
+    }
+}
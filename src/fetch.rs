@@ -0,0 +1,1269 @@
+//! Fetching token lists from remote URIs.
+
+#[cfg(feature = "from-uri")]
+use std::sync::Mutex;
+#[cfg(any(
+    feature = "from-uri",
+    feature = "from-uri-blocking",
+    feature = "from-uri-ureq"
+))]
+use std::time::Duration;
+
+use crate::{Error, TokenList};
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+use reqwest::header::{self, HeaderMap, HeaderValue};
+
+#[cfg(feature = "from-uri")]
+use crate::{Fetched, Fetcher, RawTokenList};
+
+/// Options controlling how a [`TokenList`] is fetched.
+///
+/// On wasm, requests are made with the browser's `fetch`, which does not
+/// support the connect timeout, timeout, or redirect limit; these are ignored.
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+#[derive(Clone, Debug)]
+pub struct FetchOptions {
+    /// The policy used to retry failed requests
+    pub retry: RetryPolicy,
+
+    /// The time allowed to establish a connection
+    pub connect_timeout: Option<Duration>,
+
+    /// The time allowed for each attempt, from sending the request until the
+    /// response body has been read
+    pub timeout: Option<Duration>,
+
+    /// The maximum number of redirects to follow; `0` disables redirects
+    pub max_redirects: usize,
+
+    /// The maximum size of the response body, or of a `data:` URI's contents, in
+    /// bytes
+    pub max_body_size: Option<u64>,
+
+    /// The maximum number of requests in flight at once when fetching several
+    /// lists
+    pub concurrency: usize,
+
+    /// Headers sent with every request; e.g. `Authorization` for lists served
+    /// from authenticated endpoints.
+    ///
+    /// Sensitive headers such as `Authorization` are not forwarded when a
+    /// request is redirected to a different host.
+    pub headers: HeaderMap,
+
+    /// The `User-Agent` sent with every request
+    pub user_agent: Option<String>,
+
+    /// Proxies to route requests through; when empty, any proxy set in the
+    /// `HTTP_PROXY` and `HTTPS_PROXY` environment variables is used
+    #[cfg(not(target_arch = "wasm32"))]
+    pub proxies: Vec<reqwest::Proxy>,
+
+    /// Root certificates to trust in addition to the system's; e.g. a private
+    /// CA
+    #[cfg(all(
+        not(target_arch = "wasm32"),
+        any(feature = "native-tls", feature = "rustls-tls")
+    ))]
+    pub root_certificates: Vec<reqwest::Certificate>,
+}
+
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+impl Default for FetchOptions {
+    /// Retries with the default [`RetryPolicy`], a 10s connect timeout, a 60s
+    /// timeout per attempt, up to 10 redirects, a 32 MiB body limit, up to 8
+    /// concurrent requests, no extra headers, and the system's proxy and root
+    /// certificate configuration.
+    fn default() -> Self {
+        FetchOptions {
+            retry: RetryPolicy::default(),
+            connect_timeout: Some(Duration::from_secs(10)),
+            timeout: Some(Duration::from_secs(60)),
+            max_redirects: 10,
+            max_body_size: Some(32 * 1024 * 1024),
+            concurrency: 8,
+            headers: HeaderMap::new(),
+            user_agent: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            proxies: Vec::new(),
+            #[cfg(all(
+                not(target_arch = "wasm32"),
+                any(feature = "native-tls", feature = "rustls-tls")
+            ))]
+            root_certificates: Vec::new(),
+        }
+    }
+}
+
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+impl FetchOptions {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn redirect_policy(&self) -> reqwest::redirect::Policy {
+        match self.max_redirects {
+            0 => reqwest::redirect::Policy::none(),
+            max => reqwest::redirect::Policy::limited(max),
+        }
+    }
+
+    /// Returns the headers to send with each request.
+    fn request_headers(&self) -> Result<HeaderMap, Error> {
+        let mut headers = self.headers.clone();
+        if let Some(user_agent) = &self.user_agent {
+            let user_agent = HeaderValue::from_str(user_agent)
+                .map_err(|_| Error::InvalidHeader(header::USER_AGENT.to_string()))?;
+            headers.insert(header::USER_AGENT, user_agent);
+        }
+        Ok(headers)
+    }
+
+    #[cfg(feature = "from-uri")]
+    fn client(&self) -> Result<reqwest::Client, Error> {
+        // timeouts and redirects are left to the browser on wasm
+        #[cfg(target_arch = "wasm32")]
+        let builder = reqwest::Client::builder();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let builder = {
+            let mut builder = reqwest::Client::builder().redirect(self.redirect_policy());
+            if let Some(timeout) = self.connect_timeout {
+                builder = builder.connect_timeout(timeout);
+            }
+            if let Some(timeout) = self.timeout {
+                builder = builder.timeout(timeout);
+            }
+            for proxy in &self.proxies {
+                builder = builder.proxy(proxy.clone());
+            }
+            #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+            for certificate in &self.root_certificates {
+                builder = builder.add_root_certificate(certificate.clone());
+            }
+            builder
+        };
+
+        Ok(builder.build()?)
+    }
+
+    #[cfg(feature = "from-uri-blocking")]
+    fn blocking_client(&self) -> Result<reqwest::blocking::Client, Error> {
+        let mut builder = reqwest::blocking::Client::builder()
+            .redirect(self.redirect_policy())
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.timeout);
+        for proxy in &self.proxies {
+            builder = builder.proxy(proxy.clone());
+        }
+        #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+        for certificate in &self.root_certificates {
+            builder = builder.add_root_certificate(certificate.clone());
+        }
+        Ok(builder.build()?)
+    }
+}
+
+/// A policy for retrying failed fetches with exponential backoff and jitter.
+///
+/// The delay before retry `n` (starting at zero) is `backoff_base * 2^n`,
+/// capped at `max_backoff`. With `jitter` enabled, the actual delay is chosen
+/// uniformly between zero and that value.
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first; `1` disables
+    /// retries
+    pub max_attempts: u32,
+
+    /// The delay before the first retry; doubled for each subsequent retry
+    pub backoff_base: Duration,
+
+    /// The upper bound on the delay between two attempts
+    pub max_backoff: Duration,
+
+    /// Whether to randomize each delay between zero and its computed value
+    pub jitter: bool,
+
+    /// The classes of failures that are retried
+    pub retry_on: Vec<RetryOn>,
+}
+
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+impl RetryPolicy {
+    /// A policy that makes a single attempt and never retries.
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Returns the delay to wait before retry number `retry` (starting at
+    /// zero), without jitter applied.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.checked_pow(retry).unwrap_or(u32::MAX);
+        self.backoff_base
+            .checked_mul(factor)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+
+    pub(crate) fn delay(&self, retry: u32) -> Duration {
+        let backoff = self.backoff(retry);
+        if self.jitter {
+            let nanos = backoff.as_nanos().min(u64::MAX as u128) as u64;
+            Duration::from_nanos(fastrand::u64(0..=nanos))
+        } else {
+            backoff
+        }
+    }
+
+    fn retries(&self, err: &Error) -> bool {
+        match err.status() {
+            Some(status) => self.retry_on.iter().any(|r| r.matches_status(status)),
+            None => err.is_transport() && self.retry_on.contains(&RetryOn::Transport),
+        }
+    }
+}
+
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+impl Default for RetryPolicy {
+    /// Three attempts, starting with a 250ms backoff capped at 10s, with
+    /// jitter, retrying transport errors, `5xx`, `408`, and `429` responses.
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            backoff_base: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(10),
+            jitter: true,
+            retry_on: vec![
+                RetryOn::Transport,
+                RetryOn::ServerError,
+                RetryOn::Status(408),
+                RetryOn::Status(429),
+            ],
+        }
+    }
+}
+
+/// A class of failure that a [`RetryPolicy`] may retry.
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RetryOn {
+    /// Connection failures, timeouts, and other errors without a response
+    Transport,
+
+    /// Any `4xx` response status
+    ClientError,
+
+    /// Any `5xx` response status
+    ServerError,
+
+    /// A specific response status
+    Status(u16),
+}
+
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+impl RetryOn {
+    fn matches_status(&self, status: u16) -> bool {
+        match self {
+            RetryOn::Transport => false,
+            RetryOn::ClientError => (400..500).contains(&status),
+            RetryOn::ServerError => (500..600).contains(&status),
+            RetryOn::Status(code) => *code == status,
+        }
+    }
+}
+
+/// A record of a single failed fetch attempt.
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FetchAttempt {
+    /// The attempt number, starting at 1
+    pub number: u32,
+
+    /// The response status, if a response was received
+    pub status: Option<u16>,
+
+    /// A description of the failure
+    pub error: String,
+}
+
+/// Accumulates a response body, enforcing a maximum size.
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+struct Body {
+    bytes: Vec<u8>,
+    limit: Option<u64>,
+    exceeded: bool,
+}
+
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+impl Body {
+    /// Creates an empty body, failing early if the declared content length
+    /// already exceeds the limit.
+    fn new(limit: Option<u64>, content_length: Option<u64>) -> Result<Self, Error> {
+        match (limit, content_length) {
+            (Some(limit), Some(len)) if len > limit => Err(Error::TooLarge { limit }),
+            _ => Ok(Body {
+                bytes: Vec::new(),
+                limit,
+                exceeded: false,
+            }),
+        }
+    }
+
+    fn push(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        match self.limit {
+            Some(limit) if (self.bytes.len() + chunk.len()) as u64 > limit => {
+                self.exceeded = true;
+                Err(Error::TooLarge { limit })
+            }
+            _ => {
+                self.bytes.extend_from_slice(chunk);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "from-uri-blocking")]
+impl std::io::Write for Body {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.push(buf)
+            .map(|()| buf.len())
+            .map_err(std::io::Error::other)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Tracks the attempts made under a [`RetryPolicy`].
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+struct Retrier<'a> {
+    policy: &'a RetryPolicy,
+    attempts: Vec<FetchAttempt>,
+}
+
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+impl<'a> Retrier<'a> {
+    fn new(policy: &'a RetryPolicy) -> Self {
+        Retrier {
+            policy,
+            attempts: Vec::new(),
+        }
+    }
+
+    /// Records a failed attempt, returning the delay to wait before the next
+    /// one, or the error to give up with.
+    fn fail(&mut self, err: Error) -> Result<Duration, Error> {
+        let number = self.attempts.len() as u32 + 1;
+        let retry = number < self.policy.max_attempts && self.policy.retries(&err);
+
+        if !retry && self.attempts.is_empty() {
+            return Err(err);
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+            attempt = number,
+            status = err.status(),
+            error = %err,
+            retry,
+            "fetch attempt failed"
+        );
+        #[cfg(feature = "metrics")]
+        crate::telemetry::fetch_attempt(false);
+
+        self.attempts.push(FetchAttempt {
+            number,
+            status: err.status(),
+            error: err.to_string(),
+        });
+
+        if retry {
+            Ok(self.policy.delay(number - 1))
+        } else {
+            Err(Error::RetriesFailed {
+                attempts: std::mem::take(&mut self.attempts),
+                source: Box::new(err),
+            })
+        }
+    }
+}
+
+/// A [`Fetcher`] that makes HTTP requests with reqwest, according to the
+/// provided [`FetchOptions`].
+///
+/// The underlying client is created on first use and shared by subsequent
+/// requests.
+///
+/// **Note**: This must be used from within a running tokio >1.0.0 runtime.
+#[cfg(feature = "from-uri")]
+#[derive(Debug, Default)]
+pub struct ReqwestFetcher {
+    options: FetchOptions,
+    client: Mutex<Option<reqwest::Client>>,
+}
+
+#[cfg(feature = "from-uri")]
+impl ReqwestFetcher {
+    /// Creates a fetcher that makes requests according to `options`.
+    pub fn new(options: FetchOptions) -> Self {
+        ReqwestFetcher {
+            options,
+            client: Mutex::new(None),
+        }
+    }
+
+    /// Creates a fetcher that makes requests with an existing client.
+    ///
+    /// The connect timeout, timeout, redirect limit, proxies, and root
+    /// certificates of `options` are ignored in favour of the client's own
+    /// configuration; the remaining options still apply.
+    pub fn with_client(client: reqwest::Client, options: FetchOptions) -> Self {
+        ReqwestFetcher {
+            options,
+            client: Mutex::new(Some(client)),
+        }
+    }
+
+    /// Returns the options requests are made with.
+    pub fn options(&self) -> &FetchOptions {
+        &self.options
+    }
+
+    fn client(&self) -> Result<reqwest::Client, Error> {
+        let mut client = self.client.lock().unwrap();
+        match &*client {
+            Some(client) => Ok(client.clone()),
+            None => Ok(client.insert(self.options.client()?).clone()),
+        }
+    }
+
+    fn url<T: reqwest::IntoUrl>(&self, uri: T) -> Result<reqwest::Url, Error> {
+        match self.client()?.get(uri).build() {
+            Ok(request) => Ok(request.url().clone()),
+            Err(err) => data_url_or(err),
+        }
+    }
+}
+
+#[cfg(feature = "from-uri")]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl Fetcher for ReqwestFetcher {
+    async fn fetch(&self, uri: &str) -> Result<Vec<u8>, Error> {
+        match self.fetch_if_modified(uri, None).await? {
+            Fetched::Modified { body, .. } => Ok(body),
+            Fetched::NotModified => Err(status_error(304, &[])),
+        }
+    }
+
+    async fn fetch_if_modified(&self, uri: &str, etag: Option<&str>) -> Result<Fetched, Error> {
+        self.fetch_with_retries(uri, etag).await
+    }
+}
+
+#[cfg(feature = "from-uri")]
+impl ReqwestFetcher {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "fetch", skip_all, fields(uri = %uri))
+    )]
+    async fn fetch_with_retries(&self, uri: &str, etag: Option<&str>) -> Result<Fetched, Error> {
+        if let Some(body) = decode_data_uri(uri, self.options.max_body_size) {
+            return Ok(Fetched::Modified {
+                body: body?,
+                etag: None,
+            });
+        }
+
+        let client = self.client()?;
+        let url = self.url(uri)?;
+        let mut retrier = Retrier::new(&self.options.retry);
+        #[cfg(any(feature = "metrics", feature = "tracing"))]
+        let stopwatch = crate::telemetry::Stopwatch::start();
+
+        loop {
+            match fetch(&client, url.clone(), &self.options, etag).await {
+                Ok(fetched) => {
+                    #[cfg(feature = "tracing")]
+                    match &fetched {
+                        Fetched::Modified { body, .. } => tracing::debug!(
+                            bytes = body.len(),
+                            duration_us = stopwatch.elapsed_us(),
+                            "fetched token list"
+                        ),
+                        Fetched::NotModified => tracing::debug!(
+                            duration_us = stopwatch.elapsed_us(),
+                            "token list not modified"
+                        ),
+                    }
+                    #[cfg(feature = "metrics")]
+                    crate::telemetry::fetched(
+                        &stopwatch,
+                        match &fetched {
+                            Fetched::Modified { body, .. } => Some(body.len()),
+                            Fetched::NotModified => None,
+                        },
+                    );
+                    return Ok(fetched);
+                }
+                Err(err) => sleep(retrier.fail(err)?).await,
+            }
+        }
+    }
+}
+
+impl TokenList {
+    /// Constructs a [`TokenList`] from the JSON contents of the specified URI.
+    ///
+    /// Failed requests are retried according to the default [`RetryPolicy`].
+    ///
+    /// **Note**: This must be called from a running tokio >1.0.0 runtime.
+    #[cfg(feature = "from-uri")]
+    pub async fn from_uri<T: reqwest::IntoUrl>(uri: T) -> Result<Self, Error> {
+        Self::from_uri_with_options(uri, &FetchOptions::default()).await
+    }
+
+    /// Constructs a [`TokenList`] from the JSON contents of the specified URI,
+    /// using the provided [`FetchOptions`].
+    ///
+    /// **Note**: This must be called from a running tokio >1.0.0 runtime.
+    #[cfg(feature = "from-uri")]
+    pub async fn from_uri_with_options<T: reqwest::IntoUrl>(
+        uri: T,
+        options: &FetchOptions,
+    ) -> Result<Self, Error> {
+        let fetcher = ReqwestFetcher::new(options.clone());
+        let url = fetcher.url(uri)?;
+        Self::from_fetcher(&fetcher, url.as_str()).await
+    }
+
+    /// Fetches the specified URI, returning the response bytes exactly as
+    /// published and their SHA-256 digest along with the parsed list.
+    ///
+    /// **Note**: This must be called from a running tokio >1.0.0 runtime.
+    #[cfg(feature = "from-uri")]
+    pub async fn fetch_raw<T: reqwest::IntoUrl>(uri: T) -> Result<RawTokenList, Error> {
+        Self::fetch_raw_with_options(uri, &FetchOptions::default()).await
+    }
+
+    /// Fetches the specified URI using the provided [`FetchOptions`],
+    /// returning the response bytes and their SHA-256 digest along with the
+    /// parsed list.
+    ///
+    /// **Note**: This must be called from a running tokio >1.0.0 runtime.
+    #[cfg(feature = "from-uri")]
+    pub async fn fetch_raw_with_options<T: reqwest::IntoUrl>(
+        uri: T,
+        options: &FetchOptions,
+    ) -> Result<RawTokenList, Error> {
+        let fetcher = ReqwestFetcher::new(options.clone());
+        let url = fetcher.url(uri)?;
+        Self::fetch_raw_from_fetcher(&fetcher, url.as_str()).await
+    }
+
+    /// Fetches the lists at each of `uris` concurrently, returning one result
+    /// per URI in the same order. A failure to fetch one list does not affect
+    /// the others.
+    ///
+    /// **Note**: This must be called from a running tokio >1.0.0 runtime.
+    #[cfg(feature = "from-uri")]
+    pub async fn fetch_all<I, T>(uris: I) -> Vec<Result<Self, Error>>
+    where
+        I: IntoIterator<Item = T>,
+        T: reqwest::IntoUrl,
+    {
+        Self::fetch_all_with_options(uris, &FetchOptions::default()).await
+    }
+
+    /// Fetches the lists at each of `uris` using the provided
+    /// [`FetchOptions`], making at most [`FetchOptions::concurrency`] requests
+    /// at once.
+    ///
+    /// **Note**: This must be called from a running tokio >1.0.0 runtime.
+    #[cfg(feature = "from-uri")]
+    pub async fn fetch_all_with_options<I, T>(
+        uris: I,
+        options: &FetchOptions,
+    ) -> Vec<Result<Self, Error>>
+    where
+        I: IntoIterator<Item = T>,
+        T: reqwest::IntoUrl,
+    {
+        use futures::stream::{self, StreamExt};
+
+        let fetcher = ReqwestFetcher::new(options.clone());
+        let fetcher = &fetcher;
+
+        stream::iter(uris)
+            .map(|uri| async move {
+                let url = fetcher.url(uri)?;
+                Self::from_fetcher(fetcher, url.as_str()).await
+            })
+            .buffered(options.concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Constructs a [`TokenList`] from the JSON contents of the specified URI.
+    ///
+    /// Failed requests are retried according to the default [`RetryPolicy`].
+    #[cfg(feature = "from-uri-blocking")]
+    pub fn from_uri_blocking<T: reqwest::IntoUrl>(uri: T) -> Result<Self, Error> {
+        Self::from_uri_blocking_with_options(uri, &FetchOptions::default())
+    }
+
+    /// Constructs a [`TokenList`] from the JSON contents of the specified URI,
+    /// using the provided [`FetchOptions`].
+    #[cfg(feature = "from-uri-blocking")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "fetch", skip_all, fields(uri = tracing::field::Empty))
+    )]
+    pub fn from_uri_blocking_with_options<T: reqwest::IntoUrl>(
+        uri: T,
+        options: &FetchOptions,
+    ) -> Result<Self, Error> {
+        let client = options.blocking_client()?;
+        let url = match client.get(uri).build() {
+            Ok(request) => request.url().clone(),
+            Err(err) => data_url_or(err)?,
+        };
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("uri", url.as_str());
+        if let Some(body) = decode_data_uri(url.as_str(), options.max_body_size) {
+            return Self::from_slice(&body?);
+        }
+
+        let mut retrier = Retrier::new(&options.retry);
+        #[cfg(feature = "metrics")]
+        let stopwatch = crate::telemetry::Stopwatch::start();
+
+        loop {
+            match fetch_blocking(&client, url.clone(), options) {
+                Ok(token_list) => {
+                    #[cfg(feature = "metrics")]
+                    crate::telemetry::fetched(&stopwatch, None);
+                    return Ok(token_list);
+                }
+                Err(err) => std::thread::sleep(retrier.fail(err)?),
+            }
+        }
+    }
+
+    /// Constructs a [`TokenList`] from the JSON contents of the specified URI,
+    /// using a blocking [ureq](https://docs.rs/ureq) agent.
+    ///
+    /// Requests time out after 60s and follow up to 10 redirects, and response
+    /// bodies are limited to 32 MiB. Use
+    /// [`from_uri_ureq_with_agent`](Self::from_uri_ureq_with_agent) to
+    /// configure the agent.
+    #[cfg(feature = "from-uri-ureq")]
+    pub fn from_uri_ureq(uri: &str) -> Result<Self, Error> {
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(Duration::from_secs(10))
+            .timeout(Duration::from_secs(60))
+            .redirects(10)
+            .build();
+
+        Self::from_uri_ureq_with_agent(&agent, uri)
+    }
+
+    /// Constructs a [`TokenList`] from the JSON contents of the specified URI,
+    /// using the provided ureq agent.
+    ///
+    /// Response bodies are limited to 32 MiB.
+    #[cfg(feature = "from-uri-ureq")]
+    pub fn from_uri_ureq_with_agent(agent: &ureq::Agent, uri: &str) -> Result<Self, Error> {
+        use std::io::Read;
+
+        if let Some(body) = decode_data_uri(uri, Some(UREQ_MAX_BODY_SIZE)) {
+            return Self::from_slice(&body?);
+        }
+
+        let response = match agent.get(uri).call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(code, response)) => {
+                let mut body = Vec::new();
+                let _ = response
+                    .into_reader()
+                    .take(BODY_SNIPPET_LEN as u64)
+                    .read_to_end(&mut body);
+                return Err(status_error(code, &body));
+            }
+            Err(err) => return Err(err.into()),
+        };
+        check_content_type(response.header("Content-Type"))?;
+
+        let mut body = Vec::new();
+        response
+            .into_reader()
+            .take(UREQ_MAX_BODY_SIZE + 1)
+            .read_to_end(&mut body)
+            .map_err(ureq::Error::from)?;
+
+        if body.len() as u64 > UREQ_MAX_BODY_SIZE {
+            return Err(Error::TooLarge {
+                limit: UREQ_MAX_BODY_SIZE,
+            });
+        }
+
+        Self::from_slice(&body)
+    }
+}
+
+/// Decodes the contents of a `data:` URI, or returns `None` if `uri` is not
+/// one.
+///
+/// Contents larger than `limit` are rejected, as a response body would be.
+fn decode_data_uri(uri: &str, limit: Option<u64>) -> Option<Result<Vec<u8>, Error>> {
+    let scheme = uri.get(..5)?;
+    if !scheme.eq_ignore_ascii_case("data:") {
+        return None;
+    }
+
+    let body = data_url::DataUrl::process(uri)
+        .ok()
+        .and_then(|data_url| data_url.decode_to_vec().ok())
+        .map(|(body, _)| body)
+        .ok_or(Error::InvalidDataUri)
+        .and_then(|body| match limit {
+            Some(limit) if body.len() as u64 > limit => Err(Error::TooLarge { limit }),
+            _ => Ok(body),
+        });
+    Some(body)
+}
+
+/// Recovers the URL of a request reqwest refused to build because it has no
+/// host, if it is a `data:` URI; these are decoded without a request.
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+fn data_url_or(err: reqwest::Error) -> Result<reqwest::Url, Error> {
+    match err.url() {
+        Some(url) if url.scheme() == "data" => Ok(url.clone()),
+        _ => Err(err.into()),
+    }
+}
+
+/// The number of bytes of an error response's body kept in
+/// [`Error::Status`].
+#[cfg(any(
+    feature = "from-uri",
+    feature = "from-uri-blocking",
+    feature = "from-uri-ureq"
+))]
+const BODY_SNIPPET_LEN: usize = 512;
+
+/// Returns the error for a response with status `code`, whose body starts
+/// with `body`.
+#[cfg(any(
+    feature = "from-uri",
+    feature = "from-uri-blocking",
+    feature = "from-uri-ureq"
+))]
+fn status_error(code: u16, body: &[u8]) -> Error {
+    let body = &body[..body.len().min(BODY_SNIPPET_LEN)];
+    Error::Status {
+        code,
+        body_snippet: String::from_utf8_lossy(body).trim().to_owned(),
+    }
+}
+
+/// Rejects a response whose `Content-Type` shows it isn't JSON, which may be
+/// compressed; e.g. the HTML of an error page served with a `200` status.
+#[cfg(any(
+    feature = "from-uri",
+    feature = "from-uri-blocking",
+    feature = "from-uri-ureq"
+))]
+fn check_content_type(content_type: Option<&str>) -> Result<(), Error> {
+    let content_type = match content_type {
+        Some(content_type) => content_type,
+        None => return Ok(()),
+    };
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    let json = essence.ends_with("/json") || essence.ends_with("+json");
+    // raw file hosts serve lists as plain text, and object stores as bytes
+    let other = matches!(
+        essence.as_str(),
+        "" | "text/plain"
+            | "application/octet-stream"
+            | "binary/octet-stream"
+            | "application/gzip"
+            | "application/x-gzip"
+            | "application/zstd"
+    );
+    match json || other {
+        true => Ok(()),
+        false => Err(Error::InvalidContentType(content_type.to_owned())),
+    }
+}
+
+/// Returns the error for `response` if it has a `4xx` or `5xx` status.
+#[cfg(feature = "from-uri")]
+pub(crate) async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, Error> {
+    let status = response.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return Ok(response);
+    }
+
+    // the wasm backend can only read the body in one piece
+    #[cfg(target_arch = "wasm32")]
+    let body = response.bytes().await.unwrap_or_default().to_vec();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let body = {
+        let mut response = response;
+        let mut body = Vec::new();
+        while body.len() < BODY_SNIPPET_LEN {
+            match response.chunk().await {
+                Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+                _ => break,
+            }
+        }
+        body
+    };
+
+    Err(status_error(status.as_u16(), &body))
+}
+
+/// Returns the `Content-Type` of a response, if it's valid.
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+fn content_type(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+}
+
+/// The largest response body read by the ureq backend.
+#[cfg(feature = "from-uri-ureq")]
+const UREQ_MAX_BODY_SIZE: u64 = 32 * 1024 * 1024;
+
+#[cfg(feature = "from-uri")]
+async fn fetch(
+    client: &reqwest::Client,
+    url: reqwest::Url,
+    options: &FetchOptions,
+    etag: Option<&str>,
+) -> Result<Fetched, Error> {
+    let mut request = client.get(url).headers(options.request_headers()?);
+    if let Some(etag) = etag {
+        request = request.header(header::IF_NONE_MATCH, etag);
+    }
+
+    #[cfg_attr(target_arch = "wasm32", allow(unused_mut))]
+    let mut response = check_status(request.send().await?).await?;
+    if etag.is_some() && response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(Fetched::NotModified);
+    }
+    check_content_type(content_type(response.headers()))?;
+
+    let etag = response
+        .headers()
+        .get(header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_owned);
+    let mut body = Body::new(options.max_body_size, response.content_length())?;
+
+    // the wasm backend can only read the body in one piece
+    #[cfg(target_arch = "wasm32")]
+    body.push(&response.bytes().await?)?;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    while let Some(chunk) = response.chunk().await? {
+        body.push(&chunk)?;
+    }
+
+    Ok(Fetched::Modified {
+        body: body.bytes,
+        etag,
+    })
+}
+
+#[cfg(feature = "from-uri")]
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(target_arch = "wasm32")]
+    gloo_timers::future::sleep(duration).await;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    tokio::time::sleep(duration).await;
+}
+
+#[cfg(feature = "from-uri-blocking")]
+fn fetch_blocking(
+    client: &reqwest::blocking::Client,
+    url: reqwest::Url,
+    options: &FetchOptions,
+) -> Result<TokenList, Error> {
+    use std::io::Read;
+
+    let mut response = client.get(url).headers(options.request_headers()?).send()?;
+    let status = response.status();
+    if status.is_client_error() || status.is_server_error() {
+        let mut body = Vec::new();
+        let _ = response
+            .take(BODY_SNIPPET_LEN as u64)
+            .read_to_end(&mut body);
+        return Err(status_error(status.as_u16(), &body));
+    }
+    check_content_type(content_type(response.headers()))?;
+
+    let mut body = Body::new(options.max_body_size, response.content_length())?;
+
+    match response.copy_to(&mut body) {
+        Err(_) if body.exceeded => Err(Error::TooLarge {
+            limit: body.limit.unwrap_or_default(),
+        }),
+        Err(err) => Err(err.into()),
+        Ok(_) => TokenList::from_slice(&body.bytes),
+    }
+}
+
+#[cfg(all(
+    test,
+    any(
+        feature = "from-uri",
+        feature = "from-uri-blocking",
+        feature = "from-uri-ureq"
+    )
+))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_data_uris() {
+        let base64 = "data:application/json;base64,eyJuYW1lIjoiVEVMY29pbnMifQ==";
+        let percent = "data:application/json,%7B%22name%22%3A%22TELcoins%22%7D";
+
+        for uri in &[base64, percent] {
+            let body = decode_data_uri(uri, None).unwrap().unwrap();
+            assert_eq!(body, br#"{"name":"TELcoins"}"#);
+        }
+
+        assert!(decode_data_uri("https://example.com/list.json", None).is_none());
+        assert!(matches!(
+            decode_data_uri("data:application/json;base64,!!!", None),
+            Some(Err(Error::InvalidDataUri))
+        ));
+    }
+
+    #[test]
+    fn rejects_oversized_data_uris() {
+        let uri = "data:application/json,%7B%22name%22%3A%22TELcoins%22%7D";
+
+        assert!(decode_data_uri(uri, Some(19)).unwrap().is_ok());
+        assert!(matches!(
+            decode_data_uri(uri, Some(18)),
+            Some(Err(Error::TooLarge { limit: 18 }))
+        ));
+    }
+
+    #[cfg(feature = "from-uri")]
+    #[tokio::test]
+    async fn reads_data_uris_without_request() {
+        let uri = format!(
+            "data:application/json,{}",
+            crate::test_server::TOKEN_LIST_JSON.replace('"', "%22")
+        );
+
+        let token_list = TokenList::from_uri(uri.as_str()).await.unwrap();
+
+        assert_eq!(token_list.name, "TELcoins");
+    }
+
+    #[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let policy = RetryPolicy {
+            backoff_base: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            ..RetryPolicy::default()
+        };
+
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff(3), Duration::from_millis(500));
+        assert_eq!(policy.backoff(64), Duration::from_millis(500));
+    }
+
+    #[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+    #[test]
+    fn jitter_stays_within_backoff() {
+        let policy = RetryPolicy::default();
+
+        for retry in 0..8 {
+            assert!(policy.delay(retry) <= policy.backoff(retry));
+        }
+    }
+
+    #[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+    #[test]
+    fn retry_on_matches_status_classes() {
+        assert!(RetryOn::ServerError.matches_status(503));
+        assert!(!RetryOn::ServerError.matches_status(404));
+        assert!(RetryOn::ClientError.matches_status(404));
+        assert!(RetryOn::Status(429).matches_status(429));
+        assert!(!RetryOn::Status(429).matches_status(428));
+        assert!(!RetryOn::Transport.matches_status(500));
+    }
+
+    #[cfg(feature = "from-uri")]
+    #[tokio::test]
+    async fn fetch_all_returns_results_in_order() {
+        use crate::test_server::{serve, status};
+
+        let options = FetchOptions {
+            retry: RetryPolicy::none(),
+            concurrency: 2,
+            ..FetchOptions::default()
+        };
+        let uris = vec![
+            serve(vec![status(200)]),
+            serve(vec![status(404)]),
+            serve(vec![status(200)]),
+        ];
+
+        let results = TokenList::fetch_all_with_options(uris, &options).await;
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert_eq!(results[1].as_ref().unwrap_err().status(), Some(404));
+        assert!(results[2].is_ok());
+    }
+
+    #[test]
+    fn accepts_json_content_types() {
+        for content_type in &[
+            "application/json",
+            "application/json; charset=utf-8",
+            "application/vnd.api+json",
+            "text/plain; charset=utf-8",
+            "application/octet-stream",
+        ] {
+            assert!(check_content_type(Some(content_type)).is_ok());
+        }
+        assert!(check_content_type(None).is_ok());
+
+        match check_content_type(Some("text/html; charset=UTF-8")) {
+            Err(Error::InvalidContentType(content_type)) => {
+                assert_eq!(content_type, "text/html; charset=UTF-8")
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[cfg(feature = "from-uri")]
+    #[tokio::test]
+    async fn reports_error_responses() {
+        use crate::test_server::{response, serve};
+
+        let options = FetchOptions {
+            retry: RetryPolicy::none(),
+            ..FetchOptions::default()
+        };
+
+        let uri = serve(vec![response(503, "", " upstream unavailable\n")]);
+        match TokenList::from_uri_with_options(uri, &options).await {
+            Err(Error::Status { code, body_snippet }) => {
+                assert_eq!(code, 503);
+                assert_eq!(body_snippet, "upstream unavailable");
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+
+        let html = response(200, "Content-Type: text/html\r\n", "<html></html>");
+        let uri = serve(vec![html]);
+        match TokenList::from_uri_with_options(uri, &options).await {
+            Err(Error::InvalidContentType(content_type)) => assert_eq!(content_type, "text/html"),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[cfg(feature = "from-uri-blocking")]
+    mod blocking {
+        use super::*;
+        use crate::test_server::{response, serve, serve_after, status};
+
+        fn options(max_attempts: u32) -> FetchOptions {
+            FetchOptions {
+                retry: RetryPolicy {
+                    max_attempts,
+                    backoff_base: Duration::from_millis(1),
+                    ..RetryPolicy::default()
+                },
+                ..FetchOptions::default()
+            }
+        }
+
+        #[test]
+        fn retries_server_errors() {
+            let uri = serve(vec![status(503), status(500), status(200)]);
+
+            let token_list = TokenList::from_uri_blocking_with_options(uri, &options(3)).unwrap();
+
+            assert_eq!(token_list.name, "TELcoins");
+        }
+
+        #[test]
+        fn records_attempt_history() {
+            let uri = serve(vec![status(503), status(502)]);
+
+            match TokenList::from_uri_blocking_with_options(uri, &options(2)) {
+                Err(Error::RetriesFailed { attempts, .. }) => {
+                    let statuses: Vec<_> = attempts.iter().map(|a| a.status).collect();
+                    assert_eq!(statuses, vec![Some(503), Some(502)]);
+                }
+                res => panic!("unexpected result: {:?}", res),
+            }
+        }
+
+        #[test]
+        fn does_not_retry_client_errors() {
+            let uri = serve(vec![status(404)]);
+
+            match TokenList::from_uri_blocking_with_options(uri, &options(3)) {
+                Err(err) => assert_eq!(err.status(), Some(404)),
+                res => panic!("unexpected result: {:?}", res),
+            }
+        }
+
+        #[test]
+        fn sends_custom_headers() {
+            let (uri, requests) = crate::test_server::serve_recording(vec![status(200)]);
+            let mut options = options(1);
+            options.headers.insert(
+                header::AUTHORIZATION,
+                HeaderValue::from_static("Bearer secret"),
+            );
+            options.user_agent = Some("token-list-tests".to_owned());
+
+            TokenList::from_uri_blocking_with_options(uri, &options).unwrap();
+
+            let request = requests.recv().unwrap().to_lowercase();
+            assert!(request.contains("authorization: bearer secret\r\n"));
+            assert!(request.contains("user-agent: token-list-tests\r\n"));
+        }
+
+        #[test]
+        fn routes_through_proxy() {
+            let (proxy, requests) = crate::test_server::serve_recording(vec![status(200)]);
+            let options = FetchOptions {
+                proxies: vec![reqwest::Proxy::http(&proxy).unwrap()],
+                ..options(1)
+            };
+
+            TokenList::from_uri_blocking_with_options("http://lists.invalid/list.json", &options)
+                .unwrap();
+
+            let request = requests.recv().unwrap();
+            assert!(request.starts_with("GET http://lists.invalid/list.json "));
+        }
+
+        #[test]
+        fn rejects_oversized_bodies() {
+            let uri = serve(vec![status(200)]);
+            let options = FetchOptions {
+                max_body_size: Some(16),
+                ..options(1)
+            };
+
+            match TokenList::from_uri_blocking_with_options(uri, &options) {
+                Err(Error::TooLarge { limit: 16 }) => {}
+                res => panic!("unexpected result: {:?}", res),
+            }
+        }
+
+        #[test]
+        fn times_out_slow_responses() {
+            let uri = serve_after(Duration::from_secs(2), vec![status(200)]);
+            let options = FetchOptions {
+                timeout: Some(Duration::from_millis(100)),
+                ..options(1)
+            };
+
+            match TokenList::from_uri_blocking_with_options(uri, &options) {
+                Err(Error::Timeout(_)) => {}
+                res => panic!("unexpected result: {:?}", res),
+            }
+        }
+
+        #[test]
+        fn reports_error_responses() {
+            let uri = serve(vec![response(500, "", "oops")]);
+
+            match TokenList::from_uri_blocking_with_options(uri, &options(1)) {
+                Err(Error::Status { code, body_snippet }) => {
+                    assert_eq!((code, body_snippet.as_str()), (500, "oops"))
+                }
+                res => panic!("unexpected result: {:?}", res),
+            }
+
+            let uri = serve(vec![response(200, "Content-Type: image/png\r\n", "")]);
+            match TokenList::from_uri_blocking_with_options(uri, &options(1)) {
+                Err(Error::InvalidContentType(_)) => {}
+                res => panic!("unexpected result: {:?}", res),
+            }
+        }
+
+        #[test]
+        fn limits_redirects() {
+            let redirect = || response(302, "Location: /list.json\r\n", "");
+            let uri = serve(vec![redirect(), redirect(), redirect()]);
+            let options = FetchOptions {
+                max_redirects: 1,
+                ..options(1)
+            };
+
+            match TokenList::from_uri_blocking_with_options(uri, &options) {
+                Err(Error::Redirect(_)) => {}
+                res => panic!("unexpected result: {:?}", res),
+            }
+        }
+    }
+
+    #[cfg(feature = "from-uri-ureq")]
+    mod ureq {
+        use super::*;
+        use crate::test_server::{response, serve, status};
+
+        #[test]
+        fn fetches_list() {
+            let uri = serve(vec![status(200)]);
+
+            let token_list = TokenList::from_uri_ureq(&uri).unwrap();
+
+            assert_eq!(token_list.name, "TELcoins");
+        }
+
+        #[test]
+        fn reports_status() {
+            let uri = serve(vec![response(404, "", "Not Found")]);
+
+            match TokenList::from_uri_ureq(&uri) {
+                Err(Error::Status { code, body_snippet }) => {
+                    assert_eq!((code, body_snippet.as_str()), (404, "Not Found"))
+                }
+                res => panic!("unexpected result: {:?}", res),
+            }
+        }
+
+        #[test]
+        fn rejects_oversized_bodies() {
+            let body = " ".repeat(UREQ_MAX_BODY_SIZE as usize + 1);
+            let uri = serve(vec![response(200, "", &body)]);
+
+            match TokenList::from_uri_ureq(&uri) {
+                Err(Error::TooLarge { limit }) => assert_eq!(limit, UREQ_MAX_BODY_SIZE),
+                res => panic!("unexpected result: {:?}", res),
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! A transport-agnostic interface for fetching token lists.
+
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+
+use crate::hash::to_hex;
+use crate::{Error, TokenList};
+
+/// Fetches the raw contents of a URI.
+///
+/// Implement this to fetch token lists with an HTTP stack other than the
+/// reqwest-based [`ReqwestFetcher`](crate::ReqwestFetcher), or to substitute
+/// a test double. Errors specific to an implementation can be reported with
+/// [`Error::Fetch`].
+///
+/// On wasm, the futures returned by fetchers are not required to be [`Send`],
+/// as browser APIs cannot be used across threads.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait Fetcher: Send + Sync {
+    /// Fetches the contents of `uri`.
+    async fn fetch(&self, uri: &str) -> Result<Vec<u8>, Error>;
+
+    /// Fetches the contents of `uri`, unless they are unchanged since they
+    /// were fetched with the given `ETag`.
+    ///
+    /// The default implementation ignores `etag` and always fetches.
+    async fn fetch_if_modified(&self, uri: &str, etag: Option<&str>) -> Result<Fetched, Error> {
+        let _ = etag;
+        Ok(Fetched::Modified {
+            body: self.fetch(uri).await?,
+            etag: None,
+        })
+    }
+}
+
+/// The result of a successful [`Fetcher::fetch_if_modified`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Fetched {
+    /// The contents were fetched.
+    Modified {
+        /// The fetched contents
+        body: Vec<u8>,
+
+        /// The `ETag` identifying this version of the contents, if known
+        etag: Option<String>,
+    },
+
+    /// The contents are unchanged since they were fetched with the given
+    /// `ETag`.
+    NotModified,
+}
+
+/// A token list along with the exact bytes it was parsed from.
+#[derive(PartialEq, Clone, Debug)]
+pub struct RawTokenList {
+    /// The response body, as published
+    pub bytes: Vec<u8>,
+
+    /// The list parsed from `bytes`
+    pub token_list: TokenList,
+
+    /// The SHA-256 digest of `bytes`
+    pub sha256: [u8; 32],
+}
+
+impl RawTokenList {
+    /// Parses `bytes` as a token list, keeping the original bytes and their
+    /// digest.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        let token_list = TokenList::from_slice(&bytes)?;
+        let sha256 = Sha256::digest(&bytes).into();
+        Ok(RawTokenList {
+            bytes,
+            token_list,
+            sha256,
+        })
+    }
+
+    /// Returns the SHA-256 digest of the bytes as lowercase hexadecimal.
+    pub fn sha256_hex(&self) -> String {
+        to_hex(&self.sha256)
+    }
+}
+
+impl TokenList {
+    /// Constructs a [`TokenList`] from the JSON contents of the specified URI,
+    /// using the provided [`Fetcher`].
+    pub async fn from_fetcher<F: Fetcher + ?Sized>(fetcher: &F, uri: &str) -> Result<Self, Error> {
+        TokenList::from_slice(&fetcher.fetch(uri).await?)
+    }
+
+    /// Fetches the specified URI using the provided [`Fetcher`], returning the
+    /// response bytes and their digest along with the parsed list.
+    pub async fn fetch_raw_from_fetcher<F: Fetcher + ?Sized>(
+        fetcher: &F,
+        uri: &str,
+    ) -> Result<RawTokenList, Error> {
+        RawTokenList::from_bytes(fetcher.fetch(uri).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockFetcher, MockResponse};
+
+    /// Returns a fetcher serving `body` for every request.
+    fn fetcher(body: &str) -> MockFetcher {
+        let fetcher = MockFetcher::new();
+        fetcher.respond("memory://list.json", MockResponse::body(body));
+        fetcher
+    }
+
+    #[tokio::test]
+    async fn parses_fetched_contents() {
+        let fetcher = fetcher(
+            r#"{
+                "name": "TELcoins",
+                "timestamp": "2021-07-05T20:25:22+00:00",
+                "version": { "major": 0, "minor": 1, "patch": 0 }
+            }"#,
+        );
+
+        let token_list = TokenList::from_fetcher(&fetcher, "memory://list.json")
+            .await
+            .unwrap();
+
+        assert_eq!(token_list.name, "TELcoins");
+    }
+
+    #[tokio::test]
+    async fn fetch_raw_keeps_original_bytes() {
+        let json = r#"{"name":"TELcoins","timestamp":"2021-07-05T20:25:22+00:00","version":{"major":0,"minor":1,"patch":0}}"#;
+        let fetcher = fetcher(json);
+
+        let raw = TokenList::fetch_raw_from_fetcher(&fetcher, "memory://list.json")
+            .await
+            .unwrap();
+
+        assert_eq!(raw.bytes, json.as_bytes());
+        assert_eq!(raw.token_list.name, "TELcoins");
+        assert_eq!(raw.sha256_hex(), to_hex(&Sha256::digest(json)));
+    }
+
+    #[tokio::test]
+    async fn reports_parse_errors() {
+        let fetcher = fetcher("{}");
+
+        match TokenList::from_fetcher(&fetcher, "memory://list.json").await {
+            Err(Error::Parse(_)) => {}
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+}
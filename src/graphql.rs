@@ -0,0 +1,294 @@
+//! GraphQL queries of token lists, with
+//! [async-graphql](https://docs.rs/async-graphql).
+
+use std::fmt;
+use std::sync::Arc;
+
+use async_graphql::{EmptyMutation, EmptySubscription, Json, Object, Schema, SimpleObject};
+use chrono::{DateTime, FixedOffset};
+use url::Url;
+
+use crate::{Token, TokenList};
+
+/// A schema querying a token list through [`TokenListQuery`].
+pub type TokenListSchema = Schema<TokenListQuery, EmptyMutation, EmptySubscription>;
+
+/// The root query of a token list, which can be served as a
+/// [`TokenListSchema`] or merged into an existing schema.
+///
+/// ```graphql
+/// {
+///   tokenList { name version }
+///   tokens(chainId: 1, tag: "stablecoin") { symbol address decimals }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct TokenListQuery {
+    current: Arc<dyn Fn() -> Option<Arc<TokenList>> + Send + Sync>,
+}
+
+impl TokenListQuery {
+    /// Creates a query of `token_list`.
+    pub fn new(token_list: TokenList) -> Self {
+        let token_list = Arc::new(token_list);
+        Self::from_fn(move || Some(token_list.clone()))
+    }
+
+    /// Creates a query of the list `current` returns on each query, which
+    /// resolves to `null` while it returns `None`.
+    pub fn from_fn<F>(current: F) -> Self
+    where
+        F: Fn() -> Option<Arc<TokenList>> + Send + Sync + 'static,
+    {
+        TokenListQuery {
+            current: Arc::new(current),
+        }
+    }
+
+    /// Returns a schema serving this query.
+    pub fn schema(self) -> TokenListSchema {
+        Schema::new(self, EmptyMutation, EmptySubscription)
+    }
+}
+
+#[Object]
+impl TokenListQuery {
+    /// The token list
+    async fn token_list(&self) -> Option<TokenListObject> {
+        (self.current)().map(TokenListObject)
+    }
+
+    /// The list's tokens, filtered by chain, tag, and symbol
+    async fn tokens(
+        &self,
+        chain_id: Option<u32>,
+        tag: Option<String>,
+        symbol: Option<String>,
+    ) -> Vec<TokenObject> {
+        match (self.current)() {
+            Some(token_list) => filter(&token_list, chain_id, tag, symbol),
+            None => Vec::new(),
+        }
+    }
+
+    /// The token at an address on a chain, compared case-insensitively
+    async fn token(&self, chain_id: u32, address: String) -> Option<TokenObject> {
+        let token_list = (self.current)()?;
+        let index = token_list.tokens.iter().position(|token| {
+            token.chain_id == chain_id && token.address.eq_ignore_ascii_case(&address)
+        })?;
+        Some(TokenObject { token_list, index })
+    }
+}
+
+impl fmt::Debug for TokenListQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenListQuery").finish_non_exhaustive()
+    }
+}
+
+/// Returns the tokens of `token_list` on `chain_id`, with `tag`, and with
+/// `symbol`, compared case-insensitively.
+fn filter(
+    token_list: &Arc<TokenList>,
+    chain_id: Option<u32>,
+    tag: Option<String>,
+    symbol: Option<String>,
+) -> Vec<TokenObject> {
+    token_list
+        .tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| chain_id.iter().all(|&chain_id| token.chain_id == chain_id))
+        .filter(|(_, token)| tag.iter().all(|tag| token.tags.contains(tag)))
+        .filter(|(_, token)| {
+            symbol
+                .iter()
+                .all(|symbol| token.symbol.eq_ignore_ascii_case(symbol))
+        })
+        .map(|(index, _)| TokenObject {
+            token_list: token_list.clone(),
+            index,
+        })
+        .collect()
+}
+
+/// A token list, as a GraphQL object.
+struct TokenListObject(Arc<TokenList>);
+
+#[Object(name = "TokenList")]
+impl TokenListObject {
+    /// The name of the list
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// When the list was last updated
+    async fn timestamp(&self) -> DateTime<FixedOffset> {
+        crate::timestamp::to_chrono(&self.0.timestamp)
+    }
+
+    /// The version of the list, as `major.minor.patch`
+    async fn version(&self) -> String {
+        self.0.version.to_string()
+    }
+
+    /// The URI of the list's logo
+    async fn logo_uri(&self) -> Option<&Url> {
+        self.0.logo_uri.as_ref()
+    }
+
+    /// Keywords describing the list
+    async fn keywords(&self) -> &[String] {
+        &self.0.keywords
+    }
+
+    /// The tags the list's tokens may have
+    async fn tags(&self) -> Vec<TagObject> {
+        self.0
+            .tags
+            .iter()
+            .map(|(id, tag)| TagObject {
+                id: id.clone(),
+                name: tag.name.clone(),
+                description: tag.description.clone(),
+            })
+            .collect()
+    }
+
+    /// The list's tokens, filtered by chain, tag, and symbol
+    async fn tokens(
+        &self,
+        chain_id: Option<u32>,
+        tag: Option<String>,
+        symbol: Option<String>,
+    ) -> Vec<TokenObject> {
+        filter(&self.0, chain_id, tag, symbol)
+    }
+}
+
+/// A tag of a token list, as a GraphQL object.
+#[derive(SimpleObject)]
+#[graphql(name = "Tag")]
+struct TagObject {
+    /// The ID tokens refer to the tag by
+    id: String,
+
+    /// The name of the tag
+    name: String,
+
+    /// A user-friendly description of the tag
+    description: String,
+}
+
+/// A token of a token list, as a GraphQL object.
+struct TokenObject {
+    token_list: Arc<TokenList>,
+    index: usize,
+}
+
+impl TokenObject {
+    fn token(&self) -> &Token {
+        &self.token_list.tokens[self.index]
+    }
+}
+
+#[Object(name = "Token")]
+impl TokenObject {
+    /// The name of the token
+    async fn name(&self) -> &str {
+        &self.token().name
+    }
+
+    /// The symbol of the token
+    async fn symbol(&self) -> &str {
+        &self.token().symbol
+    }
+
+    /// The address of the token's contract
+    async fn address(&self) -> &str {
+        &self.token().address
+    }
+
+    /// The chain the token is deployed on
+    async fn chain_id(&self) -> u32 {
+        self.token().chain_id
+    }
+
+    /// The number of decimals of the token's amounts
+    async fn decimals(&self) -> u8 {
+        self.token().decimals
+    }
+
+    /// The URI of the token's logo
+    async fn logo_uri(&self) -> Option<&Url> {
+        self.token().logo_uri.as_ref()
+    }
+
+    /// The IDs of the token's tags
+    async fn tags(&self) -> &[String] {
+        &self.token().tags
+    }
+
+    /// The token's extensions, as a JSON object
+    async fn extensions(&self) -> Json<&crate::IndexMap<String, crate::ExtensionValue>> {
+        Json(&self.token().extensions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    fn schema() -> TokenListSchema {
+        let token_list = fixtures::full();
+        TokenListQuery::new(token_list).schema()
+    }
+
+    async fn query(query: &str) -> serde_json::Value {
+        let response = schema().execute(query).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    #[tokio::test]
+    async fn queries_the_list() {
+        let data = query("{ tokenList { name version tags { id } tokens { symbol } } }").await;
+
+        let token_list = &data["tokenList"];
+        assert_eq!(token_list["version"], "0.1.0");
+        assert!(!token_list["tags"].as_array().unwrap().is_empty());
+        assert!(!token_list["tokens"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn filters_tokens() {
+        let token_list = fixtures::full();
+        let first = &token_list.tokens[0];
+
+        let data = query(&format!(
+            "{{ tokens(chainId: {}, symbol: \"{}\") {{ address }} }}",
+            first.chain_id,
+            first.symbol.to_lowercase()
+        ))
+        .await;
+        assert_eq!(data["tokens"][0]["address"], json!(first.address));
+
+        let data = query("{ tokens(tag: \"missing\") { address } }").await;
+        assert_eq!(data["tokens"], json!([]));
+
+        let data = query(&format!(
+            "{{ token(chainId: {}, address: \"{}\") {{ symbol chainId }} }}",
+            first.chain_id,
+            first.address.to_uppercase().replace("0X", "0x")
+        ))
+        .await;
+        assert_eq!(
+            data["token"],
+            json!({ "symbol": first.symbol, "chainId": first.chain_id })
+        );
+    }
+}
@@ -0,0 +1,433 @@
+//! A gRPC service distributing token lists, with
+//! [tonic](https://docs.rs/tonic).
+//!
+//! This is the `TokenListService` of `proto/token_list.proto`: a
+//! [`TokenListService`] trait to implement, a [`TokenListServiceServer`] to
+//! mount it with, and a typed [`TokenListServiceClient`]. [`WatchService`]
+//! implements the service for a list held in a tokio watch channel.
+
+use std::convert::{TryFrom, TryInto};
+use std::sync::Arc;
+
+use futures::stream::{self, Stream, StreamExt};
+use tokio::sync::watch;
+use tonic::codegen::{
+    empty_body, http, Body, BoxFuture, BoxStream, Context, Poll, Service, StdError,
+};
+use tonic::{Request, Response, Status};
+
+use crate::proto::{Token, TokenList};
+
+const GET_LIST: &str = "/tokenlist.v1.TokenListService/GetList";
+const GET_TOKEN: &str = "/tokenlist.v1.TokenListService/GetToken";
+const STREAM_UPDATES: &str = "/tokenlist.v1.TokenListService/StreamUpdates";
+
+/// A request for the current list.
+#[derive(Clone, Copy, PartialEq, Eq, prost::Message)]
+pub struct GetListRequest {}
+
+/// A request for a token.
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct GetTokenRequest {
+    /// The chain the token is deployed on
+    #[prost(uint32, tag = "1")]
+    pub chain_id: u32,
+
+    /// The address of the token's contract, compared case-insensitively
+    #[prost(string, tag = "2")]
+    pub address: String,
+}
+
+/// A request for the current list and each new version of it.
+#[derive(Clone, Copy, PartialEq, Eq, prost::Message)]
+pub struct StreamUpdatesRequest {}
+
+/// The `TokenListService` of `proto/token_list.proto`, served with
+/// [`TokenListServiceServer`].
+#[tonic::async_trait]
+pub trait TokenListService: Send + Sync + 'static {
+    /// The stream of lists returned by
+    /// [`stream_updates`](Self::stream_updates).
+    type StreamUpdatesStream: Stream<Item = Result<TokenList, Status>> + Send + 'static;
+
+    /// Returns the current list.
+    async fn get_list(
+        &self,
+        request: Request<GetListRequest>,
+    ) -> Result<Response<TokenList>, Status>;
+
+    /// Returns the token at an address on a chain.
+    async fn get_token(&self, request: Request<GetTokenRequest>)
+        -> Result<Response<Token>, Status>;
+
+    /// Streams the current list, then each new version of it.
+    async fn stream_updates(
+        &self,
+        request: Request<StreamUpdatesRequest>,
+    ) -> Result<Response<Self::StreamUpdatesStream>, Status>;
+}
+
+/// A [`TokenListService`] serving the latest list sent on a tokio watch
+/// channel.
+///
+/// Update streams end when the channel's sender is dropped.
+#[derive(Clone, Debug)]
+pub struct WatchService {
+    receiver: watch::Receiver<Arc<crate::TokenList>>,
+}
+
+impl WatchService {
+    /// Creates a service serving the latest list sent through `receiver`.
+    pub fn new(receiver: watch::Receiver<Arc<crate::TokenList>>) -> Self {
+        WatchService { receiver }
+    }
+
+    /// Creates a service serving `token_list`, whose update streams end after
+    /// the first list.
+    pub fn from_list(token_list: crate::TokenList) -> Self {
+        let (_, receiver) = watch::channel(Arc::new(token_list));
+        Self::new(receiver)
+    }
+
+    fn current(&self) -> Arc<crate::TokenList> {
+        self.receiver.borrow().clone()
+    }
+}
+
+#[tonic::async_trait]
+impl TokenListService for WatchService {
+    type StreamUpdatesStream = BoxStream<TokenList>;
+
+    async fn get_list(&self, _: Request<GetListRequest>) -> Result<Response<TokenList>, Status> {
+        Ok(Response::new(TokenList::from((*self.current()).clone())))
+    }
+
+    async fn get_token(
+        &self,
+        request: Request<GetTokenRequest>,
+    ) -> Result<Response<Token>, Status> {
+        let request = request.into_inner();
+        let token_list = self.current();
+        let token = token_list
+            .tokens
+            .iter()
+            .find(|token| {
+                token.chain_id == request.chain_id
+                    && token.address.eq_ignore_ascii_case(&request.address)
+            })
+            .ok_or_else(|| {
+                Status::not_found(format!(
+                    "{} on chain {} is not listed",
+                    request.address, request.chain_id
+                ))
+            })?;
+        Ok(Response::new(Token::from(token.clone())))
+    }
+
+    async fn stream_updates(
+        &self,
+        _: Request<StreamUpdatesRequest>,
+    ) -> Result<Response<Self::StreamUpdatesStream>, Status> {
+        let mut receiver = self.receiver.clone();
+        receiver.borrow_and_update();
+        let first = TokenList::from((*self.current()).clone());
+        let updates = stream::unfold(receiver, |mut receiver| async move {
+            receiver.changed().await.ok()?;
+            let token_list = receiver.borrow_and_update().clone();
+            Some((Ok(TokenList::from((*token_list).clone())), receiver))
+        });
+        Ok(Response::new(Box::pin(
+            stream::once(async { Ok(first) }).chain(updates),
+        )))
+    }
+}
+
+/// Serves a [`TokenListService`], e.g. with `tonic::transport::Server`.
+#[derive(Debug)]
+pub struct TokenListServiceServer<T> {
+    inner: Arc<T>,
+}
+
+impl<T: TokenListService> TokenListServiceServer<T> {
+    /// Creates a server for `inner`.
+    pub fn new(inner: T) -> Self {
+        Self::from_arc(Arc::new(inner))
+    }
+
+    /// Creates a server for a shared `inner`.
+    pub fn from_arc(inner: Arc<T>) -> Self {
+        TokenListServiceServer { inner }
+    }
+}
+
+impl<T> Clone for TokenListServiceServer<T> {
+    fn clone(&self) -> Self {
+        TokenListServiceServer {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> tonic::server::NamedService for TokenListServiceServer<T> {
+    const NAME: &'static str = "tokenlist.v1.TokenListService";
+}
+
+impl<T, B> Service<http::Request<B>> for TokenListServiceServer<T>
+where
+    T: TokenListService,
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<tonic::body::BoxBody>;
+    type Error = std::convert::Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let inner = self.inner.clone();
+        match request.uri().path() {
+            GET_LIST => Box::pin(async move {
+                let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+                Ok(grpc.unary(GetList(inner), request).await)
+            }),
+            GET_TOKEN => Box::pin(async move {
+                let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+                Ok(grpc.unary(GetToken(inner), request).await)
+            }),
+            STREAM_UPDATES => Box::pin(async move {
+                let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+                Ok(grpc.server_streaming(StreamUpdates(inner), request).await)
+            }),
+            _ => Box::pin(async move {
+                Ok(http::Response::builder()
+                    .status(200)
+                    .header(
+                        "grpc-status",
+                        (tonic::Code::Unimplemented as i32).to_string(),
+                    )
+                    .header(http::header::CONTENT_TYPE, "application/grpc")
+                    .body(empty_body())
+                    .unwrap())
+            }),
+        }
+    }
+}
+
+struct GetList<T>(Arc<T>);
+
+impl<T: TokenListService> tonic::server::UnaryService<GetListRequest> for GetList<T> {
+    type Response = TokenList;
+    type Future = BoxFuture<Response<TokenList>, Status>;
+
+    fn call(&mut self, request: Request<GetListRequest>) -> Self::Future {
+        let inner = self.0.clone();
+        Box::pin(async move { inner.get_list(request).await })
+    }
+}
+
+struct GetToken<T>(Arc<T>);
+
+impl<T: TokenListService> tonic::server::UnaryService<GetTokenRequest> for GetToken<T> {
+    type Response = Token;
+    type Future = BoxFuture<Response<Token>, Status>;
+
+    fn call(&mut self, request: Request<GetTokenRequest>) -> Self::Future {
+        let inner = self.0.clone();
+        Box::pin(async move { inner.get_token(request).await })
+    }
+}
+
+struct StreamUpdates<T>(Arc<T>);
+
+impl<T: TokenListService> tonic::server::ServerStreamingService<StreamUpdatesRequest>
+    for StreamUpdates<T>
+{
+    type Response = TokenList;
+    type ResponseStream = T::StreamUpdatesStream;
+    type Future = BoxFuture<Response<Self::ResponseStream>, Status>;
+
+    fn call(&mut self, request: Request<StreamUpdatesRequest>) -> Self::Future {
+        let inner = self.0.clone();
+        Box::pin(async move { inner.stream_updates(request).await })
+    }
+}
+
+/// A client of a [`TokenListService`].
+#[derive(Clone, Debug)]
+pub struct TokenListServiceClient<T> {
+    inner: tonic::client::Grpc<T>,
+}
+
+impl TokenListServiceClient<tonic::transport::Channel> {
+    /// Connects to the service at `endpoint`, e.g. `http://[::1]:50051`.
+    pub async fn connect<D>(endpoint: D) -> Result<Self, tonic::transport::Error>
+    where
+        D: TryInto<tonic::transport::Endpoint>,
+        D::Error: Into<StdError>,
+    {
+        let channel = tonic::transport::Endpoint::new(endpoint)?.connect().await?;
+        Ok(Self::new(channel))
+    }
+}
+
+impl<T> TokenListServiceClient<T>
+where
+    T: tonic::client::GrpcService<tonic::body::BoxBody>,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = tonic::codegen::Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+{
+    /// Creates a client sending requests through `inner`.
+    pub fn new(inner: T) -> Self {
+        TokenListServiceClient {
+            inner: tonic::client::Grpc::new(inner),
+        }
+    }
+
+    /// Returns the current list.
+    pub async fn get_list(
+        &mut self,
+        request: impl tonic::IntoRequest<GetListRequest>,
+    ) -> Result<Response<TokenList>, Status> {
+        self.ready().await?;
+        let path = http::uri::PathAndQuery::from_static(GET_LIST);
+        self.inner
+            .unary(
+                request.into_request(),
+                path,
+                tonic::codec::ProstCodec::default(),
+            )
+            .await
+    }
+
+    /// Returns the token at an address on a chain.
+    pub async fn get_token(
+        &mut self,
+        request: impl tonic::IntoRequest<GetTokenRequest>,
+    ) -> Result<Response<Token>, Status> {
+        self.ready().await?;
+        let path = http::uri::PathAndQuery::from_static(GET_TOKEN);
+        self.inner
+            .unary(
+                request.into_request(),
+                path,
+                tonic::codec::ProstCodec::default(),
+            )
+            .await
+    }
+
+    /// Streams the current list, then each new version of it.
+    pub async fn stream_updates(
+        &mut self,
+        request: impl tonic::IntoRequest<StreamUpdatesRequest>,
+    ) -> Result<Response<tonic::Streaming<TokenList>>, Status> {
+        self.ready().await?;
+        let path = http::uri::PathAndQuery::from_static(STREAM_UPDATES);
+        self.inner
+            .server_streaming(
+                request.into_request(),
+                path,
+                tonic::codec::ProstCodec::default(),
+            )
+            .await
+    }
+
+    /// Returns the current list, converted from protobuf.
+    pub async fn fetch_list(&mut self) -> Result<crate::TokenList, Status> {
+        let token_list = self.get_list(GetListRequest {}).await?.into_inner();
+        crate::TokenList::try_from(token_list).map_err(|err| Status::internal(err.to_string()))
+    }
+
+    /// Returns the token at `address` on `chain_id`, converted from protobuf.
+    pub async fn fetch_token(
+        &mut self,
+        chain_id: u32,
+        address: &str,
+    ) -> Result<crate::Token, Status> {
+        let request = GetTokenRequest {
+            chain_id,
+            address: address.to_owned(),
+        };
+        let token = self.get_token(request).await?.into_inner();
+        crate::Token::try_from(token).map_err(|err| Status::internal(err.to_string()))
+    }
+
+    async fn ready(&mut self) -> Result<(), Status> {
+        self.inner
+            .ready()
+            .await
+            .map_err(|err| Status::unknown(format!("service was not ready: {}", err.into())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+    use tonic::transport::server::TcpIncoming;
+
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    fn token_list() -> crate::TokenList {
+        fixtures::full()
+    }
+
+    /// Serves `service` on a local port, returning a client of it.
+    async fn serve(service: WatchService) -> TokenListServiceClient<tonic::transport::Channel> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let incoming = TcpIncoming::from_listener(listener, true, None).unwrap();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(TokenListServiceServer::new(service))
+                .serve_with_incoming(incoming),
+        );
+        TokenListServiceClient::connect(format!("http://{}", address))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn serves_lists_and_tokens() {
+        let token_list = token_list();
+        let mut client = serve(WatchService::from_list(token_list.clone())).await;
+
+        assert_eq!(client.fetch_list().await.unwrap(), token_list);
+
+        let first = &token_list.tokens[0];
+        let token = client
+            .fetch_token(first.chain_id, &first.address.to_lowercase())
+            .await
+            .unwrap();
+        assert_eq!(&token, first);
+
+        let status = client.fetch_token(1, "0x0").await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn streams_updates() {
+        let mut token_list = token_list();
+        let (sender, receiver) = watch::channel(Arc::new(token_list.clone()));
+        let mut client = serve(WatchService::new(receiver)).await;
+
+        let mut updates = client
+            .stream_updates(StreamUpdatesRequest {})
+            .await
+            .unwrap()
+            .into_inner();
+        let first = updates.next().await.unwrap().unwrap();
+        assert_eq!(crate::TokenList::try_from(first).unwrap(), token_list);
+
+        token_list.tokens.pop();
+        sender.send(Arc::new(token_list.clone())).unwrap();
+        let second = updates.next().await.unwrap().unwrap();
+        assert_eq!(crate::TokenList::try_from(second).unwrap(), token_list);
+
+        drop(sender);
+        assert!(updates.next().await.is_none());
+    }
+}
@@ -0,0 +1,24 @@
+//! Canonical serialization and content hashing of token lists.
+
+// shared by several optional features, not all of which use every helper
+#![allow(dead_code)]
+
+use sha2::{Digest, Sha256};
+
+use crate::TokenList;
+
+/// Serializes `token_list` as compact JSON with object keys sorted, so that
+/// equal lists always produce identical bytes.
+pub(crate) fn canonical_json(token_list: &TokenList) -> serde_json::Result<Vec<u8>> {
+    serde_json::to_vec(&serde_json::to_value(token_list)?)
+}
+
+/// Returns the SHA-256 digest of the canonical JSON form of `token_list`.
+pub(crate) fn content_hash(token_list: &TokenList) -> serde_json::Result<[u8; 32]> {
+    Ok(Sha256::digest(canonical_json(token_list)?).into())
+}
+
+/// Formats `bytes` as lowercase hexadecimal.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
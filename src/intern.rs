@@ -0,0 +1,151 @@
+//! Interned representations of tokens, sharing the strings many tokens
+//! repeat.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+use url::Url;
+
+use crate::{ExtensionValue, Token, TokenList};
+
+/// A set of shared strings, handing out one [`Arc<str>`] per distinct string.
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    /// Creates an empty interner.
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    /// Returns the shared copy of `string`, adding it if it's new.
+    pub fn intern(&mut self, string: &str) -> Arc<str> {
+        if let Some(interned) = self.strings.get(string) {
+            return interned.clone();
+        }
+        let interned: Arc<str> = Arc::from(string);
+        self.strings.insert(interned.clone());
+        interned
+    }
+
+    /// Returns the number of distinct strings interned.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns whether no strings have been interned.
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Interns the name, symbol, and tags of `token`.
+    pub fn intern_token(&mut self, token: &Token) -> InternedToken {
+        InternedToken {
+            name: self.intern(&token.name),
+            symbol: self.intern(&token.symbol),
+            address: token.address.clone(),
+            chain_id: token.chain_id,
+            decimals: token.decimals,
+            logo_uri: token.logo_uri.clone(),
+            tags: token.tags.iter().map(|tag| self.intern(tag)).collect(),
+            extensions: token.extensions.clone(),
+        }
+    }
+}
+
+/// A [`Token`] whose name, symbol, and tags are shared with other tokens
+/// interned by the same [`Interner`], for long-lived indexes of large
+/// aggregated lists, which repeat them thousands of times.
+///
+/// ```
+/// # use token_list::{Interner, TokenList};
+/// let token_list = TokenList::from_slice(include_bytes!("../fixtures/full.json"))?;
+///
+/// let mut interner = Interner::new();
+/// let tokens = token_list.intern_tokens(&mut interner);
+/// assert!(std::sync::Arc::ptr_eq(&tokens[0].symbol, &tokens[1].symbol));
+/// assert_eq!(tokens[0].to_token(), token_list.tokens[0]);
+/// # Ok::<_, token_list::Error>(())
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct InternedToken {
+    /// The name of the token
+    pub name: Arc<str>,
+
+    /// The symbol for the token
+    pub symbol: Arc<str>,
+
+    /// The checksummed address of the token
+    pub address: String,
+
+    /// The chain ID of the chain where this token lives
+    pub chain_id: u32,
+
+    /// How many decimals the token has
+    pub decimals: u8,
+
+    /// A URI to the token logo asset
+    pub logo_uri: Option<Url>,
+
+    /// The identifiers of the token's tags
+    pub tags: Vec<Arc<str>>,
+
+    /// Token-specific extensions
+    pub extensions: IndexMap<String, ExtensionValue>,
+}
+
+impl InternedToken {
+    /// Returns the token with its strings copied out of the interner.
+    ///
+    /// Unknown fields of the original token aren't kept by interning.
+    pub fn to_token(&self) -> Token {
+        Token {
+            name: self.name.to_string(),
+            symbol: self.symbol.to_string(),
+            address: self.address.clone(),
+            chain_id: self.chain_id,
+            decimals: self.decimals,
+            logo_uri: self.logo_uri.clone(),
+            tags: self.tags.iter().map(|tag| tag.to_string()).collect(),
+            extensions: self.extensions.clone(),
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: Default::default(),
+        }
+    }
+}
+
+impl TokenList {
+    /// Interns the names, symbols, and tags of the list's tokens with
+    /// `interner`, which can be shared across lists.
+    pub fn intern_tokens(&self, interner: &mut Interner) -> Vec<InternedToken> {
+        self.tokens
+            .iter()
+            .map(|token| interner.intern_token(token))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    #[test]
+    fn shares_repeated_strings() {
+        let token_list = fixtures::full();
+        let mut interner = Interner::new();
+
+        let first = token_list.intern_tokens(&mut interner);
+        let len = interner.len();
+        let second = token_list.intern_tokens(&mut interner);
+
+        assert_eq!(interner.len(), len);
+        assert!(Arc::ptr_eq(&first[0].name, &second[0].name));
+        for (token, interned) in token_list.tokens.iter().zip(&second) {
+            assert_eq!(&interned.to_token(), token);
+        }
+    }
+}
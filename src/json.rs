@@ -0,0 +1,183 @@
+//! JSON parsing of token lists, through simd-json when the `simd-json` feature
+//! is enabled and serde_json otherwise.
+//!
+//! Parse errors report the path of the value that failed to deserialize,
+//! such as `tokens[1342].decimals`. Tracking it slows parsing down, so a
+//! document is only parsed again with tracking once it's known to fail.
+
+use serde::de::DeserializeOwned;
+
+use crate::{Error, TokenList};
+
+impl TokenList {
+    /// Parses a token list from JSON bytes.
+    ///
+    /// With the `simd-json` feature enabled this uses simd-json, which is
+    /// considerably faster on large lists; the result is the same either way.
+    ///
+    /// With the `gzip` or `zstd` feature enabled, compressed bytes are
+    /// recognized and decompressed first.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "parse", skip_all, fields(bytes = bytes.len()))
+    )]
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, Error> {
+        #[cfg(any(feature = "metrics", feature = "tracing"))]
+        let stopwatch = crate::telemetry::Stopwatch::start();
+
+        let result = from_slice::<TokenList>(bytes);
+
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(token_list) => tracing::debug!(
+                tokens = token_list.tokens.len(),
+                duration_us = stopwatch.elapsed_us(),
+                "parsed token list"
+            ),
+            Err(err) => tracing::warn!(error = %err, "failed to parse token list"),
+        }
+        #[cfg(feature = "metrics")]
+        crate::telemetry::parsed(&stopwatch, result.is_ok());
+        result
+    }
+}
+
+/// Deserializes `bytes` as JSON. All token list bodies are parsed through
+/// this so that the `simd-json`, `gzip` and `zstd` features apply everywhere.
+pub(crate) fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    let bytes = &*crate::compression::decompress(bytes)?;
+    parse(bytes).map_err(|err| locate::<T>(bytes, err))
+}
+
+/// Deserializes `value`, reporting the path of the value that failed to
+/// deserialize, if any.
+#[cfg(any(feature = "delta", feature = "lenient", feature = "stream"))]
+pub(crate) fn from_value<T: DeserializeOwned>(value: serde_json::Value) -> Result<T, Error> {
+    serde_path_to_error::deserialize(value).map_err(path_error)
+}
+
+/// Prefixes the path of a parse error in `err` with `prefix`, the path of
+/// the value that was parsed within a larger document.
+#[cfg(feature = "stream")]
+pub(crate) fn nest(err: Error, prefix: &str) -> Error {
+    match err {
+        Error::ParseAt { path, source } => Error::ParseAt {
+            path: match path.starts_with('[') {
+                true => format!("{}{}", prefix, path),
+                false => format!("{}.{}", prefix, path),
+            },
+            source,
+        },
+        Error::Parse(source) if source.is_data() => Error::ParseAt {
+            path: prefix.to_owned(),
+            source,
+        },
+        err => err,
+    }
+}
+
+/// Parses `bytes` again, tracking the path to the value that caused `err`.
+fn locate<T: DeserializeOwned>(bytes: &[u8], err: serde_json::Error) -> Error {
+    let mut de = serde_json::Deserializer::from_slice(bytes);
+    match serde_path_to_error::deserialize::<_, T>(&mut de) {
+        Err(err) => path_error(err),
+        // simd-json can reject documents serde_json accepts
+        Ok(_) => Error::Parse(err),
+    }
+}
+
+fn path_error(err: serde_path_to_error::Error<serde_json::Error>) -> Error {
+    let path = err.path().to_string();
+    match path.as_str() {
+        "." => Error::Parse(err.into_inner()),
+        _ => Error::ParseAt {
+            path,
+            source: err.into_inner(),
+        },
+    }
+}
+
+#[cfg(not(feature = "simd-json"))]
+fn parse<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, serde_json::Error> {
+    serde_json::from_slice(bytes)
+}
+
+#[cfg(feature = "simd-json")]
+fn parse<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, serde_json::Error> {
+    use serde::de::Error as _;
+
+    // simd-json parses in place
+    let mut bytes = bytes.to_vec();
+    simd_json::serde::from_slice(&mut bytes).map_err(serde_json::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::test_utils::fixtures;
+    use crate::ExtensionValue;
+
+    #[test]
+    fn matches_serde_json() {
+        for fixture in fixtures::VALID {
+            let expected: TokenList = serde_json::from_str(fixture).unwrap();
+
+            assert_eq!(TokenList::from_slice(fixture.as_bytes()).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn matches_serde_json_on_extension_values() {
+        let json = br#"{"a": "0x1", "b": -42, "c": 0.1, "d": 1e300, "e": true, "f": null}"#;
+
+        let expected: IndexMap<String, ExtensionValue> = serde_json::from_slice(json).unwrap();
+        let parsed: IndexMap<String, ExtensionValue> = from_slice(json).unwrap();
+
+        assert_eq!(parsed, expected);
+        assert_eq!(parsed["f"], ExtensionValue::Null);
+    }
+
+    #[test]
+    fn rejects_invalid_lists() {
+        for fixture in fixtures::INVALID {
+            assert!(TokenList::from_slice(fixture.as_bytes()).is_err());
+        }
+    }
+
+    #[test]
+    fn reports_the_path_of_invalid_values() {
+        let mut list: Value = serde_json::from_str(fixtures::FULL).unwrap();
+        list["tokens"][1]["decimals"] = "two".into();
+
+        let err = TokenList::from_slice(list.to_string().as_bytes()).unwrap_err();
+        match &err {
+            Error::ParseAt { path, source } => {
+                assert_eq!(path, "tokens[1].decimals");
+                assert!(source.is_data());
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+        assert!(err
+            .to_string()
+            .starts_with("failed to parse token list at tokens[1].decimals: invalid type"));
+
+        assert!(matches!(
+            TokenList::from_slice(b"not json"),
+            Err(Error::Parse(_))
+        ));
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn nests_paths() {
+        let err = from_slice::<crate::Token>(br#"{"name": 1}"#).unwrap_err();
+        match nest(err, "tokens[3]") {
+            Error::ParseAt { path, .. } => assert_eq!(path, "tokens[3].name"),
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Keccak-256 hashing, as used throughout Ethereum.
+
+// shared by several optional features, not all of which use every helper
+#![allow(dead_code)]
+
+use tiny_keccak::{Hasher, Keccak};
+
+/// Returns the Keccak-256 digest of `bytes`.
+pub(crate) fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    hasher.update(bytes);
+    let mut digest = [0; 32];
+    hasher.finalize(&mut digest);
+    digest
+}
+
+/// Returns the selector of the function with the given signature; e.g.
+/// `"name()"`.
+pub(crate) fn selector(signature: &str) -> [u8; 4] {
+    let digest = keccak256(signature.as_bytes());
+    [digest[0], digest[1], digest[2], digest[3]]
+}
+
+/// Returns the ENS namehash of `name`.
+pub(crate) fn namehash(name: &str) -> [u8; 32] {
+    let mut node = [0; 32];
+    if name.is_empty() {
+        return node;
+    }
+    for label in name.rsplit('.') {
+        let mut input = node.to_vec();
+        input.extend(&keccak256(label.as_bytes()));
+        node = keccak256(&input);
+    }
+    node
+}
+
+/// Returns `address` with the mixed-case checksum of
+/// [EIP-55](https://eips.ethereum.org/EIPS/eip-55), or `None` if it isn't a
+/// `0x`-prefixed 20-byte hex address.
+pub(crate) fn checksum_address(address: &str) -> Option<String> {
+    let hex = address.strip_prefix("0x")?;
+    if hex.len() != 40 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex = hex.to_ascii_lowercase();
+    let digest = keccak256(hex.as_bytes());
+
+    let mut checksummed = String::with_capacity(42);
+    checksummed.push_str("0x");
+    for (i, c) in hex.chars().enumerate() {
+        let nibble = digest[i / 2] >> (4 * (1 - i % 2)) & 0xf;
+        checksummed.push(match nibble >= 8 {
+            true => c.to_ascii_uppercase(),
+            false => c,
+        });
+    }
+    Some(checksummed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn computes_selectors() {
+        assert_eq!(selector("name()"), [0x06, 0xfd, 0xde, 0x03]);
+        assert_eq!(selector("decimals()"), [0x31, 0x3c, 0xe5, 0x67]);
+    }
+
+    #[test]
+    fn checksums_addresses() {
+        // test vectors from EIP-55
+        for address in &[
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            assert_eq!(
+                checksum_address(&address.to_lowercase()).as_deref(),
+                Some(*address)
+            );
+        }
+        assert_eq!(checksum_address("0x5aAeb6053F3E94C9b9A09f3366"), None);
+        assert_eq!(
+            checksum_address("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"),
+            None
+        );
+    }
+
+    #[test]
+    fn computes_namehashes() {
+        assert_eq!(namehash(""), [0; 32]);
+        assert_eq!(
+            hex(&namehash("eth")),
+            "93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae"
+        );
+        assert_eq!(
+            hex(&namehash("foo.eth")),
+            "de9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f"
+        );
+    }
+}
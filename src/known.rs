@@ -0,0 +1,195 @@
+//! Well-known public lists, and discovery of others through the community
+//! list of lists.
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+use url::Url;
+
+use crate::Error;
+#[cfg(feature = "fetcher")]
+use crate::{Fetcher, TokenList};
+
+/// The community list of lists shown on tokenlists.org, which
+/// [`DiscoveredList::discover`] reads.
+pub const LIST_OF_LISTS_URI: &str =
+    "https://raw.githubusercontent.com/Uniswap/tokenlists-org/master/src/token-lists.json";
+
+/// A well-known public list, for offering users a choice of lists out of the
+/// box.
+///
+/// ```
+/// use token_list::KnownList;
+///
+/// for list in KnownList::ALL {
+///     println!("{}: {}", list.name, list.uri);
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KnownList {
+    /// The list's name
+    pub name: &'static str,
+
+    /// Where the list is published
+    pub uri: &'static str,
+}
+
+impl KnownList {
+    /// Uniswap Labs' default list
+    pub const UNISWAP_DEFAULT: KnownList = KnownList {
+        name: "Uniswap Labs Default",
+        uri: "https://tokens.uniswap.org",
+    };
+
+    /// CoinGecko's list of tokens on Ethereum
+    pub const COINGECKO: KnownList = KnownList {
+        name: "CoinGecko",
+        uri: "https://tokens.coingecko.com/uniswap/all.json",
+    };
+
+    /// CoinMarketCap's list of DeFi tokens
+    pub const CMC_DEFI: KnownList = KnownList {
+        name: "CMC DeFi",
+        uri: "https://api.coinmarketcap.com/data-api/v3/uniswap/all.json",
+    };
+
+    /// Compound's list of its markets
+    pub const COMPOUND: KnownList = KnownList {
+        name: "Compound",
+        uri: "https://raw.githubusercontent.com/compound-finance/token-list/master/compound.tokenlist.json",
+    };
+
+    /// Gemini's list of tokens it supports
+    pub const GEMINI: KnownList = KnownList {
+        name: "Gemini Token List",
+        uri: "https://www.gemini.com/uniswap/manifest.json",
+    };
+
+    /// Optimism's list of bridged tokens
+    pub const OPTIMISM: KnownList = KnownList {
+        name: "Optimism",
+        uri: "https://static.optimism.io/optimism.tokenlist.json",
+    };
+
+    /// Arbitrum's list of bridged tokens on Arbitrum One
+    pub const ARBITRUM: KnownList = KnownList {
+        name: "Arb Whitelist Era",
+        uri: "https://bridge.arbitrum.io/token-list-42161.json",
+    };
+
+    /// Telcoin's list of TEL and the tokens of its ecosystem
+    pub const TELCOIN: KnownList = KnownList {
+        name: "TELcoins",
+        uri: "https://raw.githubusercontent.com/telcoin/token-lists/master/telcoins.json",
+    };
+
+    /// Every well-known list, in the order above
+    pub const ALL: &'static [KnownList] = &[
+        KnownList::UNISWAP_DEFAULT,
+        KnownList::COINGECKO,
+        KnownList::CMC_DEFI,
+        KnownList::COMPOUND,
+        KnownList::GEMINI,
+        KnownList::OPTIMISM,
+        KnownList::ARBITRUM,
+        KnownList::TELCOIN,
+    ];
+
+    /// Fetches the list using the provided [`Fetcher`].
+    #[cfg(feature = "fetcher")]
+    pub async fn fetch<F: Fetcher + ?Sized>(&self, fetcher: &F) -> Result<TokenList, Error> {
+        TokenList::from_fetcher(fetcher, self.uri).await
+    }
+}
+
+/// A list found in the community list of lists.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DiscoveredList {
+    /// Where the list is published, either a URI or an ENS name whose
+    /// contenthash points to it
+    pub source: String,
+
+    /// The list's name, if given
+    pub name: Option<String>,
+
+    /// The homepage of the list's publisher, if given
+    pub homepage: Option<String>,
+}
+
+impl DiscoveredList {
+    /// Reads a list of lists in the format of [`LIST_OF_LISTS_URI`], an
+    /// object keyed by URI or ENS name, in order.
+    pub fn from_list_of_lists_slice(bytes: &[u8]) -> Result<Vec<Self>, Error> {
+        let lists: IndexMap<String, Entry> = crate::json::from_slice(bytes)?;
+        Ok(lists
+            .into_iter()
+            .map(|(source, entry)| DiscoveredList {
+                source,
+                name: entry.name,
+                homepage: entry.homepage,
+            })
+            .collect())
+    }
+
+    /// Fetches and reads the list of lists at [`LIST_OF_LISTS_URI`] using
+    /// the provided [`Fetcher`].
+    #[cfg(feature = "fetcher")]
+    pub async fn discover<F: Fetcher + ?Sized>(fetcher: &F) -> Result<Vec<Self>, Error> {
+        DiscoveredList::from_list_of_lists_slice(&fetcher.fetch(LIST_OF_LISTS_URI).await?)
+    }
+
+    /// Returns the list's URI, or `None` if it's published under an ENS
+    /// name, whose contenthash must be resolved to find the list.
+    pub fn uri(&self) -> Option<Url> {
+        if self.source.contains("://") {
+            Url::parse(&self.source).ok()
+        } else {
+            None
+        }
+    }
+}
+
+/// An entry of the list of lists.
+#[derive(Deserialize)]
+struct Entry {
+    name: Option<String>,
+    homepage: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_lists_have_valid_uris() {
+        for list in KnownList::ALL {
+            assert!(Url::parse(list.uri).is_ok(), "{}", list.uri);
+        }
+    }
+
+    #[test]
+    fn reads_list_of_lists() {
+        let json = br#"{
+            "tokens.uniswap.eth": {
+                "name": "Uniswap Labs Default",
+                "homepage": "https://tokenlists.org/token-list?url=tokens.uniswap.eth"
+            },
+            "https://tokens.coingecko.com/uniswap/all.json": {
+                "name": "CoinGecko",
+                "homepage": "https://www.coingecko.com/"
+            },
+            "https://example.com/list.json": {}
+        }"#;
+
+        let lists = DiscoveredList::from_list_of_lists_slice(json).unwrap();
+
+        assert_eq!(lists.len(), 3);
+        assert_eq!(lists[0].source, "tokens.uniswap.eth");
+        assert_eq!(lists[0].uri(), None);
+        assert_eq!(lists[1].name.as_deref(), Some("CoinGecko"));
+        assert_eq!(
+            lists[1].uri().unwrap().as_str(),
+            "https://tokens.coingecko.com/uniswap/all.json"
+        );
+        assert_eq!(lists[2].name, None);
+    }
+}
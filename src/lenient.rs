@@ -0,0 +1,259 @@
+//! Tolerant parsing of lists that deviate from the schema in common ways.
+
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::{Error, TokenList};
+
+/// The deviations from the token list schema corrected while parsing a list
+/// with [`TokenList::from_slice_lenient`].
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ParseReport {
+    /// The corrected deviations, in document order
+    pub deviations: Vec<Deviation>,
+}
+
+impl ParseReport {
+    /// Returns `true` if the list conformed to the schema.
+    pub fn is_clean(&self) -> bool {
+        self.deviations.is_empty()
+    }
+}
+
+/// A single deviation from the token list schema.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Deviation {
+    /// A JSON pointer to the offending value, e.g. `/tokens/3/chainId`
+    pub path: String,
+
+    /// What was wrong with the value
+    pub kind: DeviationKind,
+}
+
+impl fmt::Display for Deviation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.kind)
+    }
+}
+
+/// The kinds of deviation corrected by lenient parsing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum DeviationKind {
+    /// A chain ID given as a decimal string, such as `"137"`
+    DecimalChainId,
+    /// A chain ID given as a hex string, such as `"0x89"`
+    HexChainId,
+    /// A timestamp given as a number of seconds since the Unix epoch
+    EpochSecondsTimestamp,
+    /// A timestamp given as a number of milliseconds since the Unix epoch
+    EpochMillisTimestamp,
+    /// A decimals value outside `0..=255`, clamped into range
+    DecimalsOutOfRange,
+}
+
+impl fmt::Display for DeviationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DeviationKind::DecimalChainId => "chain ID is a decimal string",
+            DeviationKind::HexChainId => "chain ID is a hex string",
+            DeviationKind::EpochSecondsTimestamp => "timestamp is in Unix epoch seconds",
+            DeviationKind::EpochMillisTimestamp => "timestamp is in Unix epoch milliseconds",
+            DeviationKind::DecimalsOutOfRange => "decimals is outside 0 to 255",
+        })
+    }
+}
+
+impl TokenList {
+    /// Parses a token list from JSON bytes, correcting common deviations from
+    /// the schema instead of rejecting the list.
+    ///
+    /// Chain IDs given as decimal (`"137"`) or hex (`"0x89"`) strings are
+    /// accepted as numbers, and numeric timestamps as Unix epoch seconds, or
+    /// milliseconds if too large to be plausible as seconds. Decimals outside
+    /// the range of a `uint8` are clamped to 0 or 255. Every correction
+    /// is recorded in the returned [`ParseReport`]; lists that still don't
+    /// conform are rejected as by [`from_slice`](Self::from_slice).
+    pub fn from_slice_lenient(bytes: &[u8]) -> Result<(Self, ParseReport), Error> {
+        let mut value: Value = crate::json::from_slice(bytes)?;
+        let mut report = ParseReport::default();
+
+        if let Some(timestamp) = value.get_mut("timestamp") {
+            if let Some(kind) = fix_timestamp(timestamp) {
+                report.deviations.push(Deviation {
+                    path: "/timestamp".to_owned(),
+                    kind,
+                });
+            }
+        }
+
+        if let Some(tokens) = value.get_mut("tokens").and_then(Value::as_array_mut) {
+            for (i, token) in tokens.iter_mut().enumerate() {
+                if let Some(chain_id) = token.get_mut("chainId") {
+                    if let Some(kind) = fix_chain_id(chain_id) {
+                        report.deviations.push(Deviation {
+                            path: format!("/tokens/{}/chainId", i),
+                            kind,
+                        });
+                    }
+                }
+                if let Some(decimals) = token.get_mut("decimals") {
+                    if let Some(kind) = fix_decimals(decimals) {
+                        report.deviations.push(Deviation {
+                            path: format!("/tokens/{}/decimals", i),
+                            kind,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok((crate::json::from_value(value)?, report))
+    }
+}
+
+/// Replaces a string chain ID with the number it represents, if it's valid.
+fn fix_chain_id(chain_id: &mut Value) -> Option<DeviationKind> {
+    let s = chain_id.as_str()?;
+    let (parsed, kind) = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => (u32::from_str_radix(hex, 16), DeviationKind::HexChainId),
+        None => (s.parse(), DeviationKind::DecimalChainId),
+    };
+
+    *chain_id = parsed.ok()?.into();
+    Some(kind)
+}
+
+/// Clamps an integer decimals value that doesn't fit in a `u8`.
+fn fix_decimals(decimals: &mut Value) -> Option<DeviationKind> {
+    let clamped: u8 = match (decimals.as_u64(), decimals.as_i64()) {
+        (Some(n), _) if n > u64::from(u8::MAX) => u8::MAX,
+        (None, Some(n)) if n < 0 => 0,
+        _ => return None,
+    };
+
+    *decimals = clamped.into();
+    Some(DeviationKind::DecimalsOutOfRange)
+}
+
+/// Epoch timestamps at least this large are taken to be in milliseconds; as
+/// seconds, they would be in the year 5138.
+const MIN_EPOCH_MILLIS: i64 = 100_000_000_000;
+
+/// Replaces a numeric timestamp with the RFC 3339 date it represents.
+fn fix_timestamp(timestamp: &mut Value) -> Option<DeviationKind> {
+    let n = timestamp.as_i64()?;
+    let (date, kind) = match n.abs() < MIN_EPOCH_MILLIS {
+        true => (
+            crate::timestamp::from_unix(n, 0, 0),
+            DeviationKind::EpochSecondsTimestamp,
+        ),
+        false => (
+            crate::timestamp::from_unix(
+                n.div_euclid(1000),
+                n.rem_euclid(1000) as u32 * 1_000_000,
+                0,
+            ),
+            DeviationKind::EpochMillisTimestamp,
+        ),
+    };
+
+    *timestamp = crate::timestamp::to_rfc3339(&date?).into();
+    Some(kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures::FULL;
+
+    #[test]
+    fn accepts_conforming_lists() {
+        let (token_list, report) = TokenList::from_slice_lenient(FULL.as_bytes()).unwrap();
+
+        assert_eq!(token_list, TokenList::from_slice(FULL.as_bytes()).unwrap());
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn accepts_string_chain_ids() {
+        let json = FULL
+            .replace(r#""chainId": 1,"#, r#""chainId": "1","#)
+            .replace(r#""chainId": 137,"#, r#""chainId": "0x89","#);
+
+        let (token_list, report) = TokenList::from_slice_lenient(json.as_bytes()).unwrap();
+
+        assert_eq!(token_list, TokenList::from_slice(FULL.as_bytes()).unwrap());
+        assert_eq!(
+            report.deviations,
+            vec![
+                Deviation {
+                    path: "/tokens/0/chainId".to_owned(),
+                    kind: DeviationKind::DecimalChainId,
+                },
+                Deviation {
+                    path: "/tokens/1/chainId".to_owned(),
+                    kind: DeviationKind::HexChainId,
+                },
+            ]
+        );
+        assert!(TokenList::from_slice(json.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn accepts_epoch_timestamps() {
+        let expected = TokenList::from_slice(FULL.as_bytes()).unwrap();
+
+        for (timestamp, kind) in [
+            ("1625516722", DeviationKind::EpochSecondsTimestamp),
+            ("1625516722000", DeviationKind::EpochMillisTimestamp),
+        ] {
+            let json = FULL.replace(r#""2021-07-05T20:25:22+00:00""#, timestamp);
+
+            let (token_list, report) = TokenList::from_slice_lenient(json.as_bytes()).unwrap();
+
+            assert_eq!(token_list, expected);
+            assert_eq!(
+                report.deviations,
+                vec![Deviation {
+                    path: "/timestamp".to_owned(),
+                    kind,
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn clamps_out_of_range_decimals() {
+        let json = FULL
+            .replacen(r#""decimals": 2"#, r#""decimals": 300"#, 1)
+            .replacen(r#""decimals": 2"#, r#""decimals": -1"#, 1);
+
+        let (token_list, report) = TokenList::from_slice_lenient(json.as_bytes()).unwrap();
+
+        assert_eq!(token_list.tokens[0].decimals, 255);
+        assert_eq!(token_list.tokens[1].decimals, 0);
+        assert_eq!(
+            report.deviations,
+            vec![
+                Deviation {
+                    path: "/tokens/0/decimals".to_owned(),
+                    kind: DeviationKind::DecimalsOutOfRange,
+                },
+                Deviation {
+                    path: "/tokens/1/decimals".to_owned(),
+                    kind: DeviationKind::DecimalsOutOfRange,
+                },
+            ]
+        );
+        assert!(TokenList::from_slice(json.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_chain_ids() {
+        let json = FULL.replace(r#""chainId": 1,"#, r#""chainId": "mainnet","#);
+
+        assert!(TokenList::from_slice_lenient(json.as_bytes()).is_err());
+    }
+}
@@ -8,6 +8,7 @@
 //! ```no_run
 //! use token_list::TokenList;
 //!
+//! # #[cfg(feature = "from-uri")]
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     // requires enabling the `from-uri` feature
@@ -17,19 +18,301 @@
 //!     
 //!     Ok(())
 //! }
+//! # #[cfg(not(feature = "from-uri"))]
+//! # fn main() {}
+//! ```
+//!
+//! # Fetching
+//!
+//! The core of the crate makes no assumption about the async runtime in use.
+//! Lists are fetched through the [`Fetcher`] trait, enabled by the `fetcher`
+//! feature, which can be implemented for any HTTP client and driven by any
+//! executor. Ready-made implementations are provided by optional features:
+//!
+//! - `from-uri`: [`ReqwestFetcher`] and [`TokenList::from_uri`], using reqwest,
+//!   which requires a tokio >1.0.0 runtime (or none on wasm)
+//! - `from-uri-blocking`: [`TokenList::from_uri_blocking`], using reqwest's
+//!   blocking client
+//! - `from-uri-ureq`: [`TokenList::from_uri_ureq`], a synchronous backend with
+//!   a minimal dependency tree
+//!
+//! ```
+//! # #[cfg(feature = "fetcher")]
+//! # mod example {
+//! use async_trait::async_trait;
+//! use token_list::{Error, Fetcher};
+//!
+//! struct MyFetcher;
+//!
+//! #[async_trait]
+//! impl Fetcher for MyFetcher {
+//!     async fn fetch(&self, uri: &str) -> Result<Vec<u8>, Error> {
+//!         // make the request with the client of your choice
+//!         # let _ = uri;
+//!         # unimplemented!()
+//!     }
+//! }
+//! # }
 //! ```
 
-use std::collections::HashMap;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
 
-use chrono::{DateTime, FixedOffset};
 use semver::Version;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+// lets `include_token_list!` refer to `::token_list` within this crate
+#[cfg(feature = "macros")]
+extern crate self as token_list;
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(feature = "archive")]
+mod archive;
+#[cfg(feature = "arrow")]
+mod arrow;
+#[cfg(feature = "axum")]
+mod axum;
+mod baseline;
+mod borrowed;
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "cbor")]
+mod cbor;
+mod chain_map;
+mod chains;
+mod classify;
+#[cfg(feature = "codegen")]
+mod codegen;
+#[cfg(feature = "coingecko")]
+mod coingecko;
+mod compare;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+mod compression;
+#[cfg(feature = "csv")]
+mod csv;
+#[cfg(feature = "delta")]
+mod delta;
+mod diff;
+#[cfg(feature = "disk-cache")]
+mod disk_cache;
+#[cfg(feature = "ethers")]
+mod ethers;
+#[cfg(any(
+    feature = "from-uri",
+    feature = "from-uri-blocking",
+    feature = "from-uri-ureq"
+))]
+mod fetch;
+#[cfg(feature = "fetcher")]
+mod fetcher;
+#[cfg(feature = "async-graphql")]
+mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "fetcher")]
+mod hash;
+#[cfg(feature = "intern")]
+mod intern;
+mod json;
+#[cfg(feature = "tiny-keccak")]
+mod keccak;
+mod known;
+#[cfg(feature = "lenient")]
+mod lenient;
+#[cfg(feature = "logos")]
+mod logos;
+mod macros;
+mod merge;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+#[cfg(feature = "normalize")]
+mod normalize;
+mod number;
+#[cfg(feature = "onchain")]
+mod onchain;
+#[cfg(feature = "oneinch")]
+mod oneinch;
+mod policy;
+#[cfg(feature = "postgres")]
+mod postgres;
+#[cfg(feature = "proto")]
+pub mod proto;
+#[cfg(feature = "rate-limit")]
+mod rate_limit;
+#[cfg(feature = "redis")]
+mod redis;
+#[cfg(feature = "registry")]
+mod registry;
+#[cfg(feature = "repair")]
+mod repair;
+mod rewrite;
+mod risk;
+mod search;
+#[cfg(feature = "service")]
+mod service;
+#[cfg(feature = "snapshot")]
+mod snapshot;
+#[cfg(feature = "solana")]
+mod solana;
+#[cfg(feature = "from-uri")]
+mod source;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "storage")]
+mod storage;
+#[cfg(feature = "arbitrary")]
+pub mod strategies;
+#[cfg(feature = "stream")]
+mod stream;
+#[cfg(any(feature = "metrics", feature = "tracing"))]
+mod telemetry;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+pub mod timestamp;
+#[cfg(feature = "tokenlog")]
+mod tokenlog;
+#[cfg(feature = "toml")]
+mod toml;
+#[cfg(feature = "trust-wallet")]
+mod trust_wallet;
+#[cfg(feature = "validate")]
+mod validate;
+mod value;
+mod version;
+mod wallet;
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "yaml")]
+mod yaml;
+
+#[cfg(all(
+    test,
+    any(
+        feature = "from-uri",
+        feature = "from-uri-blocking",
+        feature = "from-uri-ureq"
+    )
+))]
+#[allow(dead_code)]
+mod test_server;
+
+#[cfg(feature = "archive")]
+pub use archive::{ArchiveEntry, ListArchive};
+#[cfg(feature = "axum")]
+pub use axum::TokenListHandler;
+pub use baseline::{AddressChange, BaselineReport, MatchedOn};
+pub use borrowed::{TokenListRef, TokenRef};
+#[cfg(feature = "cache")]
+pub use cache::TokenListCache;
+pub use chain_map::ChainMap;
+pub use chains::{Chain, ChainRegistry, NativeCurrency, CHAINLIST_URI};
+pub use classify::{STABLECOIN_TAG, WRAPPED_NATIVE_TAG};
+#[cfg(feature = "codegen")]
+pub use codegen::Codegen;
+#[cfg(feature = "coingecko")]
+pub use coingecko::{CoinGeckoImport, COINGECKO_ID_EXTENSION};
+pub use compare::{compare_lists, ListComparison, TokenPresence};
+#[cfg(feature = "delta")]
+pub use delta::{DeltaDocument, Synced};
+pub use diff::{TokenChange, TokenListDiff, VersionBump};
+#[cfg(feature = "disk-cache")]
+pub use disk_cache::{CacheMetadata, CachedList, DiskCache};
+#[cfg(feature = "from-uri")]
+pub use fetch::ReqwestFetcher;
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+pub use fetch::{FetchAttempt, FetchOptions, RetryOn, RetryPolicy};
+#[cfg(feature = "fetcher")]
+pub use fetcher::{Fetched, Fetcher, RawTokenList};
+#[cfg(feature = "async-graphql")]
+pub use graphql::{TokenListQuery, TokenListSchema};
+pub use indexmap::IndexMap;
+#[cfg(feature = "intern")]
+pub use intern::{InternedToken, Interner};
+pub use known::{DiscoveredList, KnownList, LIST_OF_LISTS_URI};
+#[cfg(feature = "lenient")]
+pub use lenient::{Deviation, DeviationKind, ParseReport};
+#[cfg(feature = "logos")]
+pub use logos::{
+    LogoDownloadOptions, LogoDownloadReport, LogoFailure, LogoPolicy, LogoViolation,
+    TokenLogoViolation,
+};
+pub use merge::{MergeConflict, MergeReport, MergeStrategy};
+#[cfg(feature = "macros")]
+pub use once_cell::sync::Lazy;
+#[cfg(feature = "onchain")]
+pub use onchain::{
+    ContractCheck, ContractKind, ContractReport, FailedCall, JsonRpc, MetadataField, Mismatch,
+    OnchainReport, ProxyStandard, RpcClient, TokenAddress, UnreachableChain, MULTICALL3_ADDRESS,
+};
+pub use policy::{
+    Policy, PolicyAction, PolicyReason, PolicyReport, PolicyRule, PolicyViolation, TokenMatcher,
+};
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresStore;
+#[cfg(feature = "rate-limit")]
+pub use rate_limit::{RateLimit, RateLimitMode, RateLimitedFetcher};
+#[cfg(feature = "redis")]
+pub use redis::{RedisCache, RedisStore};
+#[cfg(feature = "registry")]
+pub use registry::{
+    ipfs_gateway_uri, ContenthashUpdate, ListRegistry, ListRelease, RegisteredList,
+    ENS_REGISTRY_ADDRESS, REGISTRY_TEXT_KEY,
+};
+#[cfg(feature = "repair")]
+pub use repair::{Repair, RepairKind, RepairReport, MAX_KEYWORDS};
+pub use rewrite::{LogoRewrite, IPFS_GATEWAY_URI};
+pub use risk::{
+    RiskAssessment, RiskContext, RiskEngine, RiskReport, RiskScorer, TokenRisk,
+    RISK_LABELS_EXTENSION, RISK_SCORE_EXTENSION,
+};
+pub use search::Registry;
+#[cfg(feature = "service")]
+pub use service::TokenListService;
+#[cfg(feature = "solana")]
+pub use solana::{SOLANA_CLUSTER_EXTENSION, SOLANA_MINT_EXTENSION};
+#[cfg(feature = "from-uri")]
+pub use source::{HealthReport, SourceFailure, SourceHealth, SourceSet, SourcedList};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+#[cfg(feature = "storage")]
+pub use storage::{FileStorage, MemoryStorage, Storage};
+#[cfg(feature = "stream")]
+pub use stream::{TokenListHeader, TokenStream};
+pub use timestamp::Timestamp;
+#[cfg(feature = "macros")]
+pub use token_list_macros::include_token_list;
+#[cfg(feature = "tokenlog")]
+pub use tokenlog::{LogRecord, Replay, TokenLogReader, TokenLogWriter};
+#[cfg(feature = "trust-wallet")]
+pub use trust_wallet::{TrustWalletImport, TRUST_WALLET_ASSETS_URI};
+#[cfg(feature = "validate")]
+pub use validate::{Diagnostic, DiagnosticKind, Severity, ValidationReport, MAX_TOKENS};
+pub use version::{VersionFormat, WithVersionFormat};
+pub use wallet::{WatchAssetOptions, WatchAssetParams};
+#[cfg(feature = "watch")]
+pub use watch::TokenListWatcher;
+
+/// Items used by the expansions of this crate's macros.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::macros::{timestamp, token, token_list, url, version, IntoExtensionValue};
+    #[cfg(feature = "macros")]
+    pub use once_cell::sync::Lazy;
+    #[cfg(feature = "macros")]
+    pub use serde_json;
+}
+
 /// A list of Ethereum token metadata conforming to the [token list schema].
 ///
 /// [token list schema]: https://uniswap.org/tokenlist.schema.json
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema, utoipa::ToResponse))]
 #[serde(rename_all = "camelCase")]
 pub struct TokenList {
     /// The name of the token list
@@ -37,10 +320,24 @@ pub struct TokenList {
 
     /// The timestamp of this list version; i.e. when this immutable version of
     /// the list was created
-    pub timestamp: DateTime<FixedOffset>,
+    #[serde(with = "timestamp")]
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(schema_with = "crate::timestamp::json_schema")
+    )]
+    #[cfg_attr(
+        feature = "utoipa",
+        schema(value_type = String, format = DateTime)
+    )]
+    pub timestamp: Timestamp,
 
     /// The version of the list, used in change detection
     #[serde(with = "version")]
+    #[cfg_attr(feature = "schemars", schemars(with = "crate::version::VersionSchema"))]
+    #[cfg_attr(
+        feature = "utoipa",
+        schema(inline, value_type = crate::version::VersionSchema)
+    )]
     pub version: Version,
 
     /// A URI for the logo of the token list; prefer SVG or PNG of size 256x256
@@ -53,51 +350,24 @@ pub struct TokenList {
     pub keywords: Vec<String>,
 
     /// A mapping of tag identifiers to their name and description
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub tags: HashMap<String, Tag>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub tags: IndexMap<String, Tag>,
 
     /// The list of tokens included in the list
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub tokens: Vec<Token>,
-}
-
-impl TokenList {
-    /// Constructs a [`TokenList`] from the JSON contents of the specified URI.
-    ///
-    /// **Note**: This must be called from a running tokio >1.0.0 runtime.
-    #[cfg(feature = "from-uri")]
-    pub async fn from_uri<T: reqwest::IntoUrl>(uri: T) -> Result<Self, Error> {
-        Ok(reqwest::get(uri).await?.error_for_status()?.json().await?)
-    }
 
-    /// Constructs a [`TokenList`] from the JSON contents of the specified URI.
-    #[cfg(feature = "from-uri-blocking")]
-    pub fn from_uri_blocking<T: reqwest::IntoUrl>(uri: T) -> Result<Self, Error> {
-        Ok(reqwest::blocking::get(uri)?.error_for_status()?.json()?)
-    }
-
-    /// Constructs a [`TokenList`] from the JSON contents of the specified URI.
-    ///
-    /// **Note**: This must be called from a running tokio 0.1.x runtime.
-    #[cfg(feature = "from-uri-compat")]
-    pub async fn from_uri_compat<T: reqwest09::IntoUrl>(uri: T) -> Result<Self, Error> {
-        use futures::compat::Future01CompatExt;
-        use futures01::Future;
-        use reqwest09::r#async::{Client, Response};
-
-        let fut = Client::new()
-            .get(uri)
-            .send()
-            .and_then(Response::error_for_status)
-            .and_then(|mut res| res.json())
-            .compat();
-
-        Ok(fut.await?)
-    }
+    /// Fields not defined by the token list schema, emitted back as-is on
+    /// serialization
+    #[cfg(feature = "unknown-fields")]
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Metadata for a single token in a token list
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema, utoipa::ToResponse))]
 #[serde(rename_all = "camelCase")]
 pub struct Token {
     /// The name of the token
@@ -113,7 +383,7 @@ pub struct Token {
     pub chain_id: u32,
 
     /// The number of decimals for the token balance
-    pub decimals: u16,
+    pub decimals: u8,
 
     /// A URI to the token logo asset; if not set, interface will attempt to
     /// find a logo based on the token address; suggest SVG or PNG of size 64x64
@@ -126,8 +396,129 @@ pub struct Token {
     pub tags: Vec<String>,
 
     /// An object containing any arbitrary or vendor-specific token metadata
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub extensions: HashMap<String, Option<ExtensionValue>>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    // utoipa can't express maps of nullable values
+    #[cfg_attr(feature = "utoipa", schema(value_type = HashMap<String, ExtensionValue>))]
+    pub extensions: IndexMap<String, ExtensionValue>,
+
+    /// Fields not defined by the token list schema, emitted back as-is on
+    /// serialization
+    #[cfg(feature = "unknown-fields")]
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Default for TokenList {
+    /// An empty list with no name, at version `0.1.0`, timestamped now.
+    fn default() -> Self {
+        TokenList {
+            name: String::new(),
+            timestamp: timestamp::now(),
+            version: Version::new(0, 1, 0),
+            logo_uri: None,
+            keywords: Vec::new(),
+            tags: IndexMap::new(),
+            tokens: Vec::new(),
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: Default::default(),
+        }
+    }
+}
+
+impl fmt::Display for TokenList {
+    /// Formats the list as its name, version and number of tokens; e.g.
+    /// `TELcoins v0.1.0 (2 tokens)`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} v{} ({} token",
+            self.name,
+            self.version,
+            self.tokens.len()
+        )?;
+        match self.tokens.len() {
+            1 => f.write_str(")"),
+            _ => f.write_str("s)"),
+        }
+    }
+}
+
+impl FromStr for TokenList {
+    type Err = Error;
+
+    /// Parses a token list from JSON, as [`TokenList::from_slice`].
+    fn from_str(json: &str) -> Result<Self, Error> {
+        Self::from_slice(json.as_bytes())
+    }
+}
+
+impl TokenList {
+    /// Appends the tokens in `tokens` that don't share a chain ID and address,
+    /// compared case-insensitively, with a token already in the list or
+    /// appended before them, returning the number appended.
+    ///
+    /// Use [`extend`](Extend::extend) to append every token.
+    pub fn extend_unique<I: IntoIterator<Item = Token>>(&mut self, tokens: I) -> usize {
+        let mut seen: HashSet<_> = self
+            .tokens
+            .iter()
+            .map(|token| (token.chain_id, token.address.to_lowercase()))
+            .collect();
+        let len = self.tokens.len();
+        self.tokens.extend(
+            tokens
+                .into_iter()
+                .filter(|token| seen.insert((token.chain_id, token.address.to_lowercase()))),
+        );
+        self.tokens.len() - len
+    }
+}
+
+impl Deref for TokenList {
+    type Target = [Token];
+
+    fn deref(&self) -> &[Token] {
+        &self.tokens
+    }
+}
+
+impl DerefMut for TokenList {
+    fn deref_mut(&mut self) -> &mut [Token] {
+        &mut self.tokens
+    }
+}
+
+impl IntoIterator for TokenList {
+    type Item = Token;
+    type IntoIter = std::vec::IntoIter<Token>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.tokens.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a TokenList {
+    type Item = &'a Token;
+    type IntoIter = std::slice::Iter<'a, Token>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.tokens.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut TokenList {
+    type Item = &'a mut Token;
+    type IntoIter = std::slice::IterMut<'a, Token>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.tokens.iter_mut()
+    }
+}
+
+impl Extend<Token> for TokenList {
+    fn extend<I: IntoIterator<Item = Token>>(&mut self, tokens: I) {
+        self.tokens.extend(tokens);
+    }
 }
 
 impl Token {
@@ -136,12 +527,34 @@ impl Token {
     pub fn polygon_address(&self) -> Option<&str> {
         self.extensions
             .get("polygonAddress")
-            .and_then(|val| val.as_ref().and_then(|v| v.as_str()))
+            .and_then(ExtensionValue::as_str)
+    }
+}
+
+impl fmt::Display for Token {
+    /// Formats the token as its symbol, abbreviated address and chain; e.g.
+    /// `TEL (0x467b…790f) on chain 1`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let address = self.address.to_lowercase();
+        let chars: Vec<char> = address.chars().collect();
+        write!(f, "{} (", self.symbol)?;
+        match chars.len() {
+            0..=10 => f.write_str(&address)?,
+            len => write!(
+                f,
+                "{}…{}",
+                chars[..6].iter().collect::<String>(),
+                chars[len - 4..].iter().collect::<String>()
+            )?,
+        }
+        write!(f, ") on chain {}", self.chain_id)
     }
 }
 
 /// Definition of a tag that can be associated with a token via its identifier
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct Tag {
     /// The name of the tag
@@ -152,16 +565,27 @@ pub struct Tag {
 }
 
 /// The value for a user-defined extension.
+///
+/// An extension explicitly set to `null` is kept as `Null`, so it's
+/// serialized back as `null`, rather than dropped.
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
 #[serde(untagged)]
 #[allow(missing_docs)]
 pub enum ExtensionValue {
     String(String),
     Number(Number),
     Boolean(bool),
+    Null,
 }
 
 impl ExtensionValue {
+    /// Returns `true` if the `ExtensionValue` is `Null`.
+    pub fn is_null(&self) -> bool {
+        matches!(self, ExtensionValue::Null)
+    }
+
     /// If the `ExtensionValue` is a `String`, returns the associated `str`.
     /// Returns `None` otherwise.
     pub fn as_str(&self) -> Option<&str> {
@@ -169,6 +593,7 @@ impl ExtensionValue {
             ExtensionValue::String(val) => Some(val),
             ExtensionValue::Number(_) => None,
             ExtensionValue::Boolean(_) => None,
+            ExtensionValue::Null => None,
         }
     }
 
@@ -179,6 +604,18 @@ impl ExtensionValue {
             ExtensionValue::String(_) => None,
             ExtensionValue::Number(_) => None,
             ExtensionValue::Boolean(val) => Some(*val),
+            ExtensionValue::Null => None,
+        }
+    }
+
+    /// If the `ExtensionValue` is a `Number`, returns the associated
+    /// `Number`. Returns `None` otherwise.
+    pub fn as_number(&self) -> Option<&Number> {
+        match self {
+            ExtensionValue::String(_) => None,
+            ExtensionValue::Number(val) => Some(val),
+            ExtensionValue::Boolean(_) => None,
+            ExtensionValue::Null => None,
         }
     }
 
@@ -189,6 +626,24 @@ impl ExtensionValue {
             ExtensionValue::String(_) => None,
             ExtensionValue::Number(val) => val.as_i64(),
             ExtensionValue::Boolean(_) => None,
+            ExtensionValue::Null => None,
+        }
+    }
+
+    /// If the `ExtensionValue` is a `Number` and an integer that fits in a
+    /// `u64`, returns it as a `u64`. Returns `None` otherwise.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number()?.as_u64()
+    }
+
+    /// If the `ExtensionValue` is a `Number` and a non-negative integer,
+    /// returns it as a `u128`. Returns `None` otherwise.
+    pub fn as_u128(&self) -> Option<u128> {
+        match self {
+            ExtensionValue::String(_) => None,
+            ExtensionValue::Number(val) => val.as_u128(),
+            ExtensionValue::Boolean(_) => None,
+            ExtensionValue::Null => None,
         }
     }
 
@@ -199,16 +654,25 @@ impl ExtensionValue {
             ExtensionValue::String(_) => None,
             ExtensionValue::Number(val) => val.as_f64(),
             ExtensionValue::Boolean(_) => None,
+            ExtensionValue::Null => None,
         }
     }
 }
 
 /// A number
-#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+///
+/// Integers are read as `Integer` where they fit in an `i64`, and as
+/// `BigInteger` where they only fit in a `u128`. `serde_json` reads integers
+/// larger than a `u64` as floats unless the `arbitrary-precision` feature is
+/// enabled.
+#[derive(Serialize, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
 #[serde(untagged)]
 #[allow(missing_docs)]
 pub enum Number {
     Integer(i64),
+    BigInteger(u128),
     Float(f64),
 }
 
@@ -218,6 +682,27 @@ impl Number {
     pub fn as_i64(&self) -> Option<i64> {
         match self {
             Number::Integer(val) => Some(*val),
+            Number::BigInteger(_) => None,
+            Number::Float(_) => None,
+        }
+    }
+
+    /// If the `Number` is an integer that fits in a `u64`, returns it as a
+    /// `u64`. Returns `None` otherwise.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Number::Integer(val) => u64::try_from(*val).ok(),
+            Number::BigInteger(val) => u64::try_from(*val).ok(),
+            Number::Float(_) => None,
+        }
+    }
+
+    /// If the `Number` is a non-negative integer, returns it as a `u128`.
+    /// Returns `None` otherwise.
+    pub fn as_u128(&self) -> Option<u128> {
+        match self {
+            Number::Integer(val) => u128::try_from(*val).ok(),
+            Number::BigInteger(val) => Some(*val),
             Number::Float(_) => None,
         }
     }
@@ -227,67 +712,429 @@ impl Number {
     pub fn as_f64(&self) -> Option<f64> {
         match self {
             Number::Integer(_) => None,
+            Number::BigInteger(_) => None,
             Number::Float(val) => Some(*val),
         }
     }
+
+    /// Returns the `Number` as an `f64`, rounding integers that an `f64`
+    /// can't represent exactly.
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Number::Integer(val) => *val as f64,
+            Number::BigInteger(val) => *val as f64,
+            Number::Float(val) => *val,
+        }
+    }
 }
 
 /// Represents all errors that can occur when using this library.
-#[cfg(any(
-    feature = "from-uri",
-    feature = "from-uri-blocking",
-    feature = "from-uri-compat"
-))]
 #[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// HTTP/TCP etc. transport level error.
-    #[cfg(feature = "from-uri")]
+    #[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
     #[error(transparent)]
-    Transport(#[from] reqwest::Error),
+    Transport(reqwest::Error),
+
+    /// The request or connection timed out.
+    #[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+    #[error("request timed out")]
+    Timeout(#[source] reqwest::Error),
+
+    /// The request exceeded the redirect limit.
+    #[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+    #[error("too many redirects")]
+    Redirect(#[source] reqwest::Error),
+
+    /// The server responded with an error status.
+    #[cfg(any(
+        feature = "from-uri",
+        feature = "from-uri-blocking",
+        feature = "from-uri-ureq"
+    ))]
+    #[error("server responded with status {code}")]
+    Status {
+        /// The HTTP status code
+        code: u16,
+        /// The start of the response body, e.g. an error message
+        body_snippet: String,
+    },
+
+    /// The response body exceeded the maximum allowed size.
+    #[cfg(any(
+        feature = "from-uri",
+        feature = "from-uri-blocking",
+        feature = "from-uri-ureq"
+    ))]
+    #[error("response body exceeds the limit of {limit} bytes")]
+    TooLarge {
+        /// The maximum allowed size in bytes
+        limit: u64,
+    },
+
+    /// The response has a `Content-Type` that a token list isn't served as,
+    /// such as the HTML of a captive portal or error page.
+    #[cfg(any(
+        feature = "from-uri",
+        feature = "from-uri-blocking",
+        feature = "from-uri-ureq"
+    ))]
+    #[error("unexpected content type `{0}`")]
+    InvalidContentType(String),
+
+    /// A header configured in [`FetchOptions`] has an invalid value.
+    #[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+    #[error("invalid value for header `{0}`")]
+    InvalidHeader(String),
+
+    /// A `data:` URI is malformed.
+    #[cfg(any(
+        feature = "from-uri",
+        feature = "from-uri-blocking",
+        feature = "from-uri-ureq"
+    ))]
+    #[error("invalid data URI")]
+    InvalidDataUri,
+
+    /// The JSON is not a valid token list.
+    #[error("failed to parse token list: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    /// A value in the JSON is not valid where it appears in a token list.
+    #[error("failed to parse token list at {path}: {source}")]
+    ParseAt {
+        /// The path to the value, such as `tokens[1342].decimals`
+        path: String,
+        /// The error the value caused
+        source: serde_json::Error,
+    },
+
+    /// The list violates the token list schema.
+    #[cfg(feature = "validate")]
+    #[error(
+        "token list failed validation with {} error(s)",
+        .0.errors().count()
+    )]
+    Validation(ValidationReport),
+
+    /// A delta could not be applied to a list.
+    #[cfg(any(feature = "delta", feature = "tokenlog"))]
+    #[error("failed to apply delta: {0}")]
+    Delta(#[from] json_patch::PatchError),
+
+    /// A list patched with a delta doesn't have the content hash of the
+    /// version the delta leads to.
+    #[cfg(feature = "delta")]
+    #[error("patched list doesn't match the delta's content hash")]
+    DeltaMismatch,
+
+    /// A list could not be compressed or decompressed.
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    #[error("failed to compress or decompress token list")]
+    Compression(#[source] std::io::Error),
+
+    /// A list could not be read from or written as YAML.
+    #[cfg(feature = "yaml")]
+    #[error("invalid YAML token list: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
+    /// A list could not be read from TOML.
+    #[cfg(feature = "toml")]
+    #[error("invalid TOML token list: {0}")]
+    Toml(#[from] ::toml::de::Error),
+
+    /// A list could not be written as TOML.
+    #[cfg(feature = "toml")]
+    #[error("failed to write token list as TOML: {0}")]
+    TomlSerialize(#[from] ::toml::ser::Error),
+
+    /// Tokens could not be converted to Arrow.
+    #[cfg(feature = "arrow")]
+    #[error(transparent)]
+    Arrow(#[from] arrow_schema::ArrowError),
+
+    /// Tokens could not be written as Parquet.
+    #[cfg(feature = "arrow")]
+    #[error(transparent)]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    /// A list could not be read from CBOR.
+    #[cfg(feature = "cbor")]
+    #[error("invalid CBOR token list: {0}")]
+    Cbor(#[from] ciborium::de::Error<std::io::Error>),
+
+    /// A list could not be written as CBOR.
+    #[cfg(feature = "cbor")]
+    #[error("failed to write token list as CBOR: {0}")]
+    CborSerialize(#[from] ciborium::ser::Error<std::io::Error>),
+
+    /// A list could not be read from MessagePack.
+    #[cfg(feature = "msgpack")]
+    #[error("invalid MessagePack token list: {0}")]
+    Msgpack(#[from] rmp_serde::decode::Error),
+
+    /// A list could not be written as MessagePack.
+    #[cfg(feature = "msgpack")]
+    #[error("failed to write token list as MessagePack: {0}")]
+    MsgpackSerialize(#[from] rmp_serde::encode::Error),
+
+    /// A protobuf message does not describe a valid token list.
+    #[cfg(feature = "proto")]
+    #[error("invalid protobuf token list: {0}")]
+    InvalidProto(String),
+
+    /// A list could not be stored in or loaded from SQLite.
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    /// A list could not be stored in or loaded from Postgres.
+    #[cfg(feature = "postgres")]
+    #[error("Postgres error: {0}")]
+    Postgres(#[from] sqlx::Error),
+
+    /// A list could not be stored in or loaded from Redis.
+    #[cfg(feature = "redis")]
+    #[error("Redis error: {0}")]
+    Redis(#[from] ::redis::RedisError),
+
+    /// Tokens could not be read from or written as CSV.
+    #[cfg(feature = "csv")]
+    #[error(transparent)]
+    Csv(#[from] ::csv::Error),
+
+    /// A CSV record does not describe a valid token.
+    #[cfg(feature = "csv")]
+    #[error("invalid CSV on line {line}: {message}")]
+    InvalidCsv {
+        /// The line of the offending record
+        line: u64,
+
+        /// What is wrong with the record
+        message: String,
+    },
+
+    /// An error reported by a custom [`Fetcher`].
+    #[cfg(feature = "fetcher")]
+    #[error(transparent)]
+    Fetch(Box<dyn std::error::Error + Send + Sync>),
+
+    /// A request was rejected by a [`RateLimitedFetcher`] because the limit
+    /// for its host was exceeded.
+    #[cfg(feature = "rate-limit")]
+    #[error("rate limit exceeded for {host}")]
+    RateLimited {
+        /// The host whose limit was exceeded
+        host: String,
+    },
+
+    /// Reading or writing a cached, stored, archived, logged, or snapshotted
+    /// list, or reading files to import, failed.
+    #[cfg(any(
+        feature = "archive",
+        feature = "disk-cache",
+        feature = "logos",
+        feature = "snapshot",
+        feature = "storage",
+        feature = "tokenlog",
+        feature = "trust-wallet"
+    ))]
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    /// A `.tlbin` snapshot is malformed.
+    #[cfg(feature = "snapshot")]
+    #[error("invalid snapshot: {0}")]
+    InvalidSnapshot(String),
+
+    /// A `.tokenlog` is malformed.
+    #[cfg(feature = "tokenlog")]
+    #[error("invalid token log: {0}")]
+    InvalidLog(String),
+
+    /// An asset in Trust Wallet's assets repository is malformed.
+    #[cfg(feature = "trust-wallet")]
+    #[error("invalid asset at {path}: {message}")]
+    InvalidAsset {
+        /// The path of the asset's file or directory
+        path: String,
+
+        /// What is wrong with the asset
+        message: String,
+    },
+
+    /// A JSON-RPC endpoint returned an error.
+    #[cfg(feature = "onchain")]
+    #[error("JSON-RPC error {code}: {message}")]
+    Rpc {
+        /// The JSON-RPC error code
+        code: i64,
+
+        /// The error message
+        message: String,
+    },
+
+    /// A JSON-RPC endpoint returned a result that could not be interpreted.
+    #[cfg(feature = "onchain")]
+    #[error("invalid JSON-RPC response: {0}")]
+    InvalidRpcResponse(String),
+
+    /// A JSON-RPC endpoint serves a different chain than requested.
+    #[cfg(feature = "onchain")]
+    #[error("RPC endpoint serves chain {actual}, not {expected}")]
+    ChainMismatch {
+        /// The requested chain
+        expected: u32,
+
+        /// The chain served by the endpoint
+        actual: u64,
+    },
+
+    /// There is no contract at an address.
+    #[cfg(feature = "onchain")]
+    #[error("no contract at {0}")]
+    NoContract(String),
+
+    /// An address is not 20 hex-encoded bytes.
+    #[cfg(feature = "onchain")]
+    #[error("invalid address {0}")]
+    InvalidAddress(String),
+
+    /// An ENS name has no resolver.
+    #[cfg(feature = "registry")]
+    #[error("ENS name {0} has no resolver")]
+    EnsNotFound(String),
+
+    /// An ENS name has no contenthash, or one that is not an IPFS or IPNS
+    /// hash.
+    #[cfg(feature = "registry")]
+    #[error("ENS name {0} has no supported contenthash")]
+    InvalidContenthash(String),
+
+    /// An ethers provider failed.
+    #[cfg(feature = "ethers")]
+    #[error(transparent)]
+    Provider(::ethers::providers::ProviderError),
+
+    /// An ethers middleware failed to send a transaction.
+    #[cfg(feature = "ethers")]
+    #[error("failed to send transaction: {0}")]
+    Transaction(#[source] Box<dyn std::error::Error + Send + Sync>),
 
     /// HTTP/TCP etc. transport level error.
-    #[cfg(feature = "from-uri-compat")]
+    #[cfg(feature = "from-uri-ureq")]
     #[error(transparent)]
-    TransportCompat(#[from] reqwest09::Error),
+    TransportUreq(Box<ureq::Error>),
+
+    /// A fetch failed on every attempt allowed by its [`RetryPolicy`].
+    #[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+    #[error("fetch failed after {} attempts", attempts.len())]
+    RetriesFailed {
+        /// Every attempt made, in order
+        attempts: Vec<FetchAttempt>,
+
+        /// The error from the final attempt
+        source: Box<Error>,
+    },
+
+    /// A list could not be fetched from any source of a [`SourceSet`].
+    #[cfg(feature = "from-uri")]
+    #[error("fetch failed from all {} sources", failures.len())]
+    SourcesFailed {
+        /// The failure of each source, in the order they failed
+        failures: Vec<SourceFailure>,
+    },
+
+    /// A mirror serves a version of a list with different content than its
+    /// primary source served.
+    #[cfg(feature = "from-uri")]
+    #[error("mirror {uri} serves different content for version {version}")]
+    MirrorMismatch {
+        /// The URI of the mirror
+        uri: String,
+
+        /// The version of the list
+        version: semver::Version,
+    },
 }
 
-mod version {
-    use semver::Version;
-    use serde::{de, ser::SerializeStruct, Deserialize};
-
-    pub fn serialize<S>(value: &Version, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let mut version = serializer.serialize_struct("Version", 3)?;
-        version.serialize_field("major", &value.major)?;
-        version.serialize_field("minor", &value.minor)?;
-        version.serialize_field("patch", &value.patch)?;
-        version.end()
-    }
-
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<Version, D::Error>
-    where
-        D: de::Deserializer<'de>,
-    {
-        #[derive(Deserialize)]
-        struct InternalVersion {
-            major: u64,
-            minor: u64,
-            patch: u64,
+#[cfg(any(
+    feature = "from-uri",
+    feature = "from-uri-blocking",
+    feature = "from-uri-ureq"
+))]
+impl Error {
+    /// Returns the HTTP status of the response that caused the error, if any.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            #[cfg(any(
+                feature = "from-uri",
+                feature = "from-uri-blocking",
+                feature = "from-uri-ureq"
+            ))]
+            Error::Status { code, .. } => Some(*code),
+            #[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+            Error::Transport(err) => err.status().map(|status| status.as_u16()),
+            #[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+            Error::RetriesFailed { source, .. } => source.status(),
+            #[cfg(feature = "from-uri-ureq")]
+            Error::TransportUreq(err) => match **err {
+                ureq::Error::Status(status, _) => Some(status),
+                ureq::Error::Transport(_) => None,
+            },
+            #[allow(unreachable_patterns)]
+            _ => None,
         }
+    }
 
-        InternalVersion::deserialize(deserializer).map(|v| Version::new(v.major, v.minor, v.patch))
+    /// Returns `true` if the error occurred in transport, before a response
+    /// was received; e.g. a connection failure or timeout.
+    #[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+    fn is_transport(&self) -> bool {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Error::Transport(err) => err.is_connect() || err.is_request() || err.is_body(),
+            #[cfg(target_arch = "wasm32")]
+            Error::Transport(err) => err.is_request() || err.is_body(),
+            Error::Timeout(_) => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+}
+
+#[cfg(any(feature = "from-uri", feature = "from-uri-blocking"))]
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Error::Timeout(err)
+        } else if err.is_redirect() {
+            Error::Redirect(err)
+        } else {
+            Error::Transport(err)
+        }
+    }
+}
+
+#[cfg(feature = "from-uri-ureq")]
+impl From<ureq::Error> for Error {
+    fn from(err: ureq::Error) -> Self {
+        Error::TransportUreq(Box::new(err))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use chrono::TimeZone;
     use serde_json::json;
 
     use super::*;
 
+    #[cfg(any(
+        feature = "from-uri",
+        feature = "from-uri-blocking",
+        feature = "from-uri-ureq"
+    ))]
     const TELCOINS_TOKEN_LIST_URI: &str =
         "https://raw.githubusercontent.com/telcoin/token-lists/e6a4cd7/telcoins.json";
 
@@ -303,20 +1150,10 @@ mod tests {
         let _token_list = TokenList::from_uri_blocking(TELCOINS_TOKEN_LIST_URI).unwrap();
     }
 
-    #[cfg(feature = "from-uri-compat")]
+    #[cfg(feature = "from-uri-ureq")]
     #[test]
-    fn from_uri_compat() {
-        use futures::future::{FutureExt, TryFutureExt};
-        use tokio01::runtime::Runtime;
-
-        let mut rt = Runtime::new().unwrap();
-
-        rt.block_on(
-            TokenList::from_uri_compat(TELCOINS_TOKEN_LIST_URI)
-                .boxed()
-                .compat(),
-        )
-        .unwrap();
+    fn from_uri_ureq() {
+        let _token_list = TokenList::from_uri_ureq(TELCOINS_TOKEN_LIST_URI).unwrap();
     }
 
     #[test]
@@ -338,11 +1175,11 @@ mod tests {
 
         let data_rs = TokenList {
             name: "TELcoins".to_owned(),
-            timestamp: FixedOffset::west(0).ymd(2021, 7, 5).and_hms(20, 25, 22),
+            timestamp: timestamp::parse_rfc3339("2021-07-05T20:25:22+00:00").unwrap(),
             version: Version::new(0, 1, 0),
             logo_uri: None,
             keywords: vec![],
-            tags: HashMap::new(),
+            tags: IndexMap::new(),
             tokens: vec![Token {
                 name: "Telcoin".to_owned(),
                 symbol: "TEL".to_owned(),
@@ -351,15 +1188,23 @@ mod tests {
                 decimals: 2,
                 logo_uri: None,
                 tags: vec![],
-                extensions: HashMap::new(),
+                extensions: IndexMap::new(),
+                #[cfg(feature = "unknown-fields")]
+                unknown_fields: Default::default(),
             }],
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: Default::default(),
         };
 
-        assert_eq!(serde_json::to_value(&data_rs).unwrap(), data_json);
-
-        let token_list: TokenList = serde_json::from_value(data_json).unwrap();
+        let token_list: TokenList = serde_json::from_value(data_json.clone()).unwrap();
 
         assert_eq!(token_list, data_rs);
+
+        // UTC is serialized as `Z` rather than `+00:00`
+        let mut data_json = data_json;
+        data_json["timestamp"] = json!("2021-07-05T20:25:22Z");
+
+        assert_eq!(serde_json::to_value(&data_rs).unwrap(), data_json);
     }
 
     #[test]
@@ -397,7 +1242,7 @@ mod tests {
         let logo_uri: Url = "https://raw.githubusercontent.com/telcoin/token-lists/master/assets/logo-telcoin-250x250.png".parse().unwrap();
         let data_rs = TokenList {
             name: "TELcoins".to_owned(),
-            timestamp: FixedOffset::west(0).ymd(2021, 7, 5).and_hms(20, 25, 22),
+            timestamp: timestamp::parse_rfc3339("2021-07-05T20:25:22+00:00").unwrap(),
             version: Version::new(0, 1, 0),
             logo_uri: Some(logo_uri.clone()),
             keywords: vec!["defi".to_owned(), "telcoin".to_owned()],
@@ -421,28 +1266,259 @@ mod tests {
                 extensions: vec![
                     (
                         "is_mapped_to_polygon".to_owned(),
-                        Some(ExtensionValue::Boolean(true)),
+                        ExtensionValue::Boolean(true),
                     ),
                     (
                         "polygon_address".to_owned(),
-                        Some(ExtensionValue::String(
+                        ExtensionValue::String(
                             "0xdf7837de1f2fa4631d716cf2502f8b230f1dcc32".to_owned(),
-                        )),
+                        ),
                     ),
                     (
                         "polygon_chain_id".to_owned(),
-                        Some(ExtensionValue::Number(Number::Integer(137))),
+                        ExtensionValue::Number(Number::Integer(137)),
                     ),
                 ]
                 .into_iter()
                 .collect(),
+                #[cfg(feature = "unknown-fields")]
+                unknown_fields: Default::default(),
+            }],
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: Default::default(),
+        };
+
+        let token_list: TokenList = serde_json::from_value(data_json.clone()).unwrap();
+
+        assert_eq!(token_list, data_rs);
+
+        // UTC is serialized as `Z` rather than `+00:00`
+        let mut data_json = data_json;
+        data_json["timestamp"] = json!("2021-07-05T20:25:22Z");
+
+        assert_eq!(serde_json::to_value(&data_rs).unwrap(), data_json);
+    }
+
+    #[test]
+    fn macros_build_required_fields() {
+        let data_rs = token_list! {
+            name: "TELcoins",
+            timestamp: "2021-07-05T20:25:22Z",
+            version: "0.1.0",
+            tokens: [token! {
+                name: "Telcoin",
+                symbol: "TEL",
+                address: "0x467bccd9d29f223bce8043b84e8c8b282827790f",
+                chain_id: 1,
+                decimals: 2,
             }],
         };
 
-        assert_eq!(serde_json::to_value(&data_rs).unwrap(), data_json,);
+        let token_list: TokenList = serde_json::from_value(json!({
+            "name": "TELcoins",
+            "timestamp": "2021-07-05T20:25:22Z",
+            "version": { "major": 0, "minor": 1, "patch": 0 },
+            "tokens": [
+                {
+                    "name": "Telcoin",
+                    "symbol": "TEL",
+                    "address": "0x467bccd9d29f223bce8043b84e8c8b282827790f",
+                    "chainId": 1,
+                    "decimals": 2
+                }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(token_list, data_rs);
+    }
+
+    #[test]
+    fn macros_build_all_fields() {
+        let logo_uri = "https://raw.githubusercontent.com/telcoin/token-lists/master/assets/logo-telcoin-250x250.png";
+        let data_rs = token_list! {
+            name: "TELcoins",
+            timestamp: "2021-07-05T20:25:22Z",
+            version: "0.1.0",
+            logo_uri: logo_uri,
+            keywords: ["defi", "telcoin"],
+            tags: { "telcoin" => ("telcoin", "Part of the Telcoin ecosystem.") },
+            tokens: [token! {
+                name: "Telcoin",
+                symbol: "TEL",
+                address: "0x467bccd9d29f223bce8043b84e8c8b282827790f",
+                chain_id: 1,
+                decimals: 2,
+                logo_uri: logo_uri,
+                tags: ["telcoin"],
+                extensions: {
+                    "is_mapped_to_polygon" => true,
+                    "polygon_address" => "0xdf7837de1f2fa4631d716cf2502f8b230f1dcc32",
+                    "polygon_chain_id" => 137,
+                },
+            }],
+        };
 
-        let token_list: TokenList = serde_json::from_value(data_json).unwrap();
+        let token_list: TokenList = serde_json::from_value(json!({
+            "name": "TELcoins",
+            "timestamp": "2021-07-05T20:25:22Z",
+            "version": { "major": 0, "minor": 1, "patch": 0 },
+            "logoURI": logo_uri,
+            "keywords": ["defi", "telcoin"],
+            "tags": {
+                "telcoin": {
+                    "description": "Part of the Telcoin ecosystem.",
+                    "name": "telcoin"
+                }
+            },
+            "tokens": [
+                {
+                    "name": "Telcoin",
+                    "symbol": "TEL",
+                    "address": "0x467bccd9d29f223bce8043b84e8c8b282827790f",
+                    "chainId": 1,
+                    "decimals": 2,
+                    "logoURI": logo_uri,
+                    "tags": ["telcoin"],
+                    "extensions": {
+                        "is_mapped_to_polygon": true,
+                        "polygon_address": "0xdf7837de1f2fa4631d716cf2502f8b230f1dcc32",
+                        "polygon_chain_id": 137
+                    }
+                }
+            ]
+        }))
+        .unwrap();
 
         assert_eq!(token_list, data_rs);
     }
+
+    #[test]
+    fn preserves_tag_and_extension_order() {
+        let data_json = r#"{"name":"TELcoins","timestamp":"2021-07-05T20:25:22Z","version":{"major":0,"minor":1,"patch":0},"tags":{"stablecoin":{"name":"stablecoin","description":"Pegged to a fiat currency."},"compound":{"name":"compound","description":"Part of the Compound protocol."}},"tokens":[{"name":"Telcoin","symbol":"TEL","address":"0x467bccd9d29f223bce8043b84e8c8b282827790f","chainId":1,"decimals":2,"extensions":{"polygonAddress":"0xdf7837de1f2fa4631d716cf2502f8b230f1dcc32","bridgeInfo":"pos","isMapped":true}}]}"#;
+
+        let token_list: TokenList = serde_json::from_str(data_json).unwrap();
+
+        assert_eq!(serde_json::to_string(&token_list).unwrap(), data_json);
+    }
+
+    #[test]
+    fn formats_and_parses_lists() {
+        let token_list: TokenList = include_str!("../fixtures/full.json").parse().unwrap();
+
+        assert_eq!(token_list.to_string(), "TELcoins v0.1.0 (2 tokens)");
+        assert_eq!(
+            token_list.tokens[0].to_string(),
+            "TEL (0x467b…790f) on chain 1"
+        );
+        assert!("not json".parse::<TokenList>().is_err());
+
+        let default = TokenList::default();
+        assert_eq!(default.to_string(), " v0.1.0 (0 tokens)");
+        assert!(timestamp::elapsed(&default.timestamp) < chrono::Duration::minutes(1));
+    }
+
+    #[test]
+    fn behaves_as_a_collection_of_tokens() {
+        let mut token_list: TokenList = include_str!("../fixtures/full.json").parse().unwrap();
+        let tokens = token_list.tokens.clone();
+
+        assert_eq!(token_list.len(), 2);
+        assert_eq!(token_list[1].chain_id, 137);
+        assert_eq!((&token_list).into_iter().count(), 2);
+        for token in &mut token_list {
+            token.decimals = 18;
+        }
+        assert!(token_list.iter().all(|token| token.decimals == 18));
+
+        let mut duplicate = tokens[0].clone();
+        duplicate.address = duplicate.address.to_lowercase();
+        let mut new = tokens[0].clone();
+        new.chain_id = 10;
+        assert_eq!(
+            token_list.extend_unique(vec![duplicate.clone(), new.clone(), new]),
+            1
+        );
+        token_list.extend(vec![duplicate]);
+        assert_eq!(token_list.len(), 4);
+
+        let chains: Vec<_> = token_list.into_iter().map(|token| token.chain_id).collect();
+        assert_eq!(chains, [1, 137, 10, 1]);
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn generates_json_schema() {
+        let schema = serde_json::to_value(schemars::schema_for!(TokenList)).unwrap();
+
+        assert_eq!(schema["title"], "TokenList");
+        assert_eq!(schema["required"], json!(["name", "timestamp", "version"]));
+        assert!(schema["properties"]["logoURI"].is_object());
+        assert!(schema["definitions"]["Token"]["properties"]["chainId"].is_object());
+        assert!(schema["definitions"]["Version"]["anyOf"].is_array());
+    }
+
+    #[cfg(feature = "utoipa")]
+    #[test]
+    fn generates_openapi_schema() {
+        use utoipa::{ToResponse, ToSchema};
+
+        let (name, schema) = TokenList::schema();
+        let schema = serde_json::to_value(schema).unwrap();
+
+        assert_eq!(name, "TokenList");
+        assert_eq!(schema["required"], json!(["name", "timestamp", "version"]));
+        assert!(schema["properties"]["logoURI"].is_object());
+        assert!(schema["properties"]["version"]["oneOf"].is_array());
+        assert_eq!(
+            schema["properties"]["tokens"]["items"]["$ref"],
+            "#/components/schemas/Token"
+        );
+
+        let (name, _) = Token::response();
+        assert_eq!(name, "Token");
+    }
+
+    #[cfg(feature = "unknown-fields")]
+    #[test]
+    fn preserves_unknown_fields() {
+        let data_json = json!({
+            "name": "TELcoins",
+            "timestamp": "2021-07-05T20:25:22Z",
+            "version": { "major": 0, "minor": 1, "patch": 0 },
+            "$schema": "https://uniswap.org/tokenlist.schema.json",
+            "tokens": [
+                {
+                    "name": "Telcoin",
+                    "symbol": "TEL",
+                    "address": "0x467bccd9d29f223bce8043b84e8c8b282827790f",
+                    "chainId": 1,
+                    "decimals": 2,
+                    "coingeckoId": "telcoin"
+                }
+            ]
+        });
+
+        let token_list: TokenList = serde_json::from_value(data_json.clone()).unwrap();
+
+        assert_eq!(
+            token_list.unknown_fields["$schema"],
+            "https://uniswap.org/tokenlist.schema.json"
+        );
+        assert_eq!(
+            token_list.tokens[0].unknown_fields["coingeckoId"],
+            "telcoin"
+        );
+        assert_eq!(serde_json::to_value(&token_list).unwrap(), data_json);
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn includes_token_lists() {
+        static EMBEDDED: Lazy<TokenList> = include_token_list!("fixtures/full.json");
+
+        let token_list: TokenList =
+            serde_json::from_str(include_str!("../fixtures/full.json")).unwrap();
+        assert_eq!(*EMBEDDED, token_list);
+    }
 }
@@ -0,0 +1,554 @@
+//! Downloading the logos of a list, for self-hosting, and checking them
+//! against a policy.
+
+use std::convert::TryInto;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use futures::stream::{self, StreamExt};
+use indexmap::{IndexMap, IndexSet};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::hash::to_hex;
+use crate::{Error, Fetcher, TokenList};
+
+/// Options for [`TokenList::download_logos`].
+#[derive(Clone, Debug)]
+pub struct LogoDownloadOptions {
+    /// The maximum number of logos fetched at once
+    pub concurrency: usize,
+
+    /// Where the directory of logos will be served from; if set, the logo
+    /// URIs of the list and its tokens are rewritten to point there
+    pub base_uri: Option<Url>,
+}
+
+impl Default for LogoDownloadOptions {
+    /// Fetches up to 8 logos at once and leaves logo URIs as they are.
+    fn default() -> Self {
+        LogoDownloadOptions {
+            concurrency: 8,
+            base_uri: None,
+        }
+    }
+}
+
+/// The outcome of [`TokenList::download_logos`].
+#[derive(Debug, Default)]
+pub struct LogoDownloadReport {
+    /// The name of the file each downloaded logo was written to, by its
+    /// original URI
+    pub files: IndexMap<Url, String>,
+
+    /// The logos that couldn't be downloaded, whose URIs were left as they
+    /// were
+    pub failures: Vec<LogoFailure>,
+}
+
+/// A logo that couldn't be downloaded.
+#[derive(Debug)]
+pub struct LogoFailure {
+    /// The logo's URI
+    pub uri: Url,
+
+    /// Why the download failed
+    pub error: Error,
+}
+
+impl TokenList {
+    /// Downloads the logos of the list and its tokens into `dir`, fetching
+    /// each distinct URI once using the provided [`Fetcher`].
+    ///
+    /// Files are named after the SHA-256 digest of their contents, with an
+    /// extension going by the image format, so logos served at several URIs
+    /// are only written once and running the download again writes the same
+    /// files. If [`LogoDownloadOptions::base_uri`] is set, logo URIs are
+    /// rewritten to the downloaded files under it. Logos that fail to
+    /// download are reported rather than failing the download; errors
+    /// writing to `dir` do fail it.
+    pub async fn download_logos<F: Fetcher + ?Sized>(
+        &mut self,
+        fetcher: &F,
+        dir: impl AsRef<Path>,
+        options: &LogoDownloadOptions,
+    ) -> Result<LogoDownloadReport, Error> {
+        let dir = dir.as_ref();
+        let uris: IndexSet<&Url> = self
+            .logo_uri
+            .iter()
+            .chain(
+                self.tokens
+                    .iter()
+                    .filter_map(|token| token.logo_uri.as_ref()),
+            )
+            .collect();
+
+        let mut downloads = stream::iter(uris.into_iter().cloned())
+            .map(|uri| async move {
+                let body = fetcher.fetch(uri.as_str()).await;
+                (uri, body)
+            })
+            .buffered(options.concurrency.max(1));
+
+        let mut report = LogoDownloadReport::default();
+        fs::create_dir_all(dir)?;
+        while let Some((uri, body)) = downloads.next().await {
+            match body {
+                Ok(body) => {
+                    let name = file_name(&uri, &body);
+                    let path = dir.join(&name);
+                    if !path.exists() {
+                        fs::write(path, &body)?;
+                    }
+                    report.files.insert(uri, name);
+                }
+                Err(error) => report.failures.push(LogoFailure { uri, error }),
+            }
+        }
+        drop(downloads);
+
+        if let Some(base_uri) = &options.base_uri {
+            let base_uri = directory(base_uri);
+            let logo_uris = self
+                .tokens
+                .iter_mut()
+                .map(|token| &mut token.logo_uri)
+                .chain(std::iter::once(&mut self.logo_uri));
+            for logo_uri in logo_uris.flatten() {
+                if let Some(name) = report.files.get(logo_uri) {
+                    *logo_uri = base_uri.join(name).expect("file names are valid paths");
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// The number of logos fetched at once by [`TokenList::check_logos`].
+const CHECK_CONCURRENCY: usize = 8;
+
+/// The formats, dimensions, and sizes logos must have to pass
+/// [`TokenList::check_logos`].
+///
+/// Only PNG and SVG logos are accepted. SVGs are only checked to be square,
+/// going by their `viewBox` or else their `width` and `height`, since they
+/// scale to any size.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LogoPolicy {
+    /// The smallest width and height allowed, in pixels
+    pub min_dimension: u32,
+
+    /// The largest width and height allowed, in pixels
+    pub max_dimension: u32,
+
+    /// Whether logos must be as wide as they are tall
+    pub require_square: bool,
+
+    /// The largest file allowed, in bytes
+    pub max_bytes: usize,
+}
+
+impl Default for LogoPolicy {
+    /// Square logos from 64×64 to 1024×1024 pixels, of up to 100 KiB.
+    fn default() -> Self {
+        LogoPolicy {
+            min_dimension: 64,
+            max_dimension: 1024,
+            require_square: true,
+            max_bytes: 100 * 1024,
+        }
+    }
+}
+
+impl LogoPolicy {
+    /// Checks the contents of a logo, returning the ways it violates the
+    /// policy.
+    pub fn check(&self, body: &[u8]) -> Vec<LogoViolation> {
+        let mut violations = Vec::new();
+        if body.len() > self.max_bytes {
+            violations.push(LogoViolation::FileTooLarge {
+                bytes: body.len(),
+                max: self.max_bytes,
+            });
+        }
+
+        let (width, height) = match sniff(body) {
+            Some("png") => match png_dimensions(body) {
+                Some((width, height)) => {
+                    let (min, max) = (self.min_dimension, self.max_dimension);
+                    if width < min || height < min {
+                        violations.push(LogoViolation::TooSmall { width, height });
+                    }
+                    if width > max || height > max {
+                        violations.push(LogoViolation::TooLarge { width, height });
+                    }
+                    (f64::from(width), f64::from(height))
+                }
+                None => {
+                    violations.push(LogoViolation::Unreadable);
+                    return violations;
+                }
+            },
+            Some("svg") => match svg_dimensions(body) {
+                Some(dimensions) => dimensions,
+                None => return violations,
+            },
+            _ => {
+                violations.push(LogoViolation::UnsupportedFormat);
+                return violations;
+            }
+        };
+        if self.require_square && (width - height).abs() > f64::EPSILON {
+            violations.push(LogoViolation::NotSquare);
+        }
+        violations
+    }
+}
+
+/// A way a logo violates a [`LogoPolicy`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum LogoViolation {
+    /// The logo couldn't be fetched
+    Unreachable(String),
+    /// The logo is neither a PNG nor an SVG
+    UnsupportedFormat,
+    /// The logo's header couldn't be read
+    Unreadable,
+    /// The logo isn't square
+    NotSquare,
+    /// The logo is narrower or shorter than the minimum
+    TooSmall {
+        /// The logo's width, in pixels
+        width: u32,
+        /// The logo's height, in pixels
+        height: u32,
+    },
+    /// The logo is wider or taller than the maximum
+    TooLarge {
+        /// The logo's width, in pixels
+        width: u32,
+        /// The logo's height, in pixels
+        height: u32,
+    },
+    /// The logo's file is larger than the maximum
+    FileTooLarge {
+        /// The size of the file, in bytes
+        bytes: usize,
+        /// The largest size allowed, in bytes
+        max: usize,
+    },
+}
+
+impl fmt::Display for LogoViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogoViolation::Unreachable(message) => write!(f, "failed to fetch logo: {}", message),
+            LogoViolation::UnsupportedFormat => f.write_str("logo is not a PNG or SVG"),
+            LogoViolation::Unreadable => f.write_str("logo is not a valid image"),
+            LogoViolation::NotSquare => f.write_str("logo is not square"),
+            LogoViolation::TooSmall { width, height } => {
+                write!(f, "logo is too small at {}×{}", width, height)
+            }
+            LogoViolation::TooLarge { width, height } => {
+                write!(f, "logo is too large at {}×{}", width, height)
+            }
+            LogoViolation::FileTooLarge { bytes, max } => {
+                write!(f, "logo is {} bytes, over the maximum of {}", bytes, max)
+            }
+        }
+    }
+}
+
+/// A violation of a [`LogoPolicy`] by a token's logo.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TokenLogoViolation {
+    /// The index of the token in the list
+    pub index: usize,
+
+    /// The token's chain ID
+    pub chain_id: u32,
+
+    /// The token's address
+    pub address: String,
+
+    /// The URI of the token's logo
+    pub uri: Url,
+
+    /// How the logo violates the policy
+    pub violation: LogoViolation,
+}
+
+impl TokenList {
+    /// Fetches the logos of the list's tokens using the provided [`Fetcher`],
+    /// fetching each distinct URI once, and checks them against `policy`.
+    ///
+    /// Returns the violations in the order of the tokens; tokens without a
+    /// logo are skipped.
+    pub async fn check_logos<F: Fetcher + ?Sized>(
+        &self,
+        fetcher: &F,
+        policy: &LogoPolicy,
+    ) -> Vec<TokenLogoViolation> {
+        let uris: IndexSet<&Url> = self
+            .tokens
+            .iter()
+            .filter_map(|token| token.logo_uri.as_ref())
+            .collect();
+
+        let checks: IndexMap<&Url, Vec<LogoViolation>> = stream::iter(uris)
+            .map(|uri| async move {
+                let violations = match fetcher.fetch(uri.as_str()).await {
+                    Ok(body) => policy.check(&body),
+                    Err(err) => vec![LogoViolation::Unreachable(err.to_string())],
+                };
+                (uri, violations)
+            })
+            .buffered(CHECK_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut violations = Vec::new();
+        for (index, token) in self.tokens.iter().enumerate() {
+            let uri = match &token.logo_uri {
+                Some(uri) => uri,
+                None => continue,
+            };
+            for violation in &checks[uri] {
+                violations.push(TokenLogoViolation {
+                    index,
+                    chain_id: token.chain_id,
+                    address: token.address.clone(),
+                    uri: uri.clone(),
+                    violation: violation.clone(),
+                });
+            }
+        }
+        violations
+    }
+}
+
+/// Reads the width and height from a PNG's `IHDR` chunk.
+fn png_dimensions(body: &[u8]) -> Option<(u32, u32)> {
+    if body.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(body.get(16..20)?.try_into().ok()?);
+    let height = u32::from_be_bytes(body.get(20..24)?.try_into().ok()?);
+    Some((width, height))
+}
+
+/// Reads the width and height of an SVG from the `viewBox` of its root
+/// element, or else its `width` and `height`.
+fn svg_dimensions(body: &[u8]) -> Option<(f64, f64)> {
+    let text = String::from_utf8_lossy(body);
+    let start = text.find("<svg")?;
+    let tag = &text[start..start + text[start..].find('>')?];
+
+    let attribute = |name: &str| {
+        let pattern = format!(" {}=", name);
+        let value = &tag[tag.find(&pattern)? + pattern.len()..];
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &value[1..];
+        Some(&value[..value.find(quote)?])
+    };
+    let length = |value: &str| value.trim().trim_end_matches("px").parse::<f64>().ok();
+
+    if let Some(view_box) = attribute("viewBox") {
+        let numbers: Vec<f64> = view_box
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .map(|part| part.parse().ok())
+            .collect::<Option<_>>()?;
+        if let [_, _, width, height] = numbers[..] {
+            return Some((width, height));
+        }
+    }
+    Some((length(attribute("width")?)?, length(attribute("height")?)?))
+}
+
+/// Returns the name of the file holding a logo: its digest, and an extension
+/// going by its contents or else its URI.
+fn file_name(uri: &Url, body: &[u8]) -> String {
+    let extension = sniff(body)
+        .or_else(|| {
+            let (_, extension) = uri.path().rsplit_once('.')?;
+            let known = ["png", "svg", "jpg", "jpeg", "gif", "webp"];
+            known
+                .iter()
+                .copied()
+                .find(|known| known.eq_ignore_ascii_case(extension))
+        })
+        .unwrap_or("bin");
+    format!("{}.{}", to_hex(&Sha256::digest(body)), extension)
+}
+
+/// Returns the extension of the image format of `body`, if recognized.
+fn sniff(body: &[u8]) -> Option<&'static str> {
+    let text = String::from_utf8_lossy(&body[..body.len().min(256)]);
+    let text = text.trim_start();
+    if body.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if body.starts_with(b"\xff\xd8\xff") {
+        Some("jpg")
+    } else if body.starts_with(b"GIF87a") || body.starts_with(b"GIF89a") {
+        Some("gif")
+    } else if body.starts_with(b"RIFF") && body.get(8..12) == Some(b"WEBP") {
+        Some("webp")
+    } else if text.starts_with("<svg") || (text.starts_with("<?xml") && text.contains("<svg")) {
+        Some("svg")
+    } else {
+        None
+    }
+}
+
+/// Returns `uri` with a trailing slash, so that joining a file name appends it
+/// rather than replacing the last segment.
+fn directory(uri: &Url) -> Url {
+    let mut uri = uri.clone();
+    if !uri.path().ends_with('/') {
+        let path = format!("{}/", uri.path());
+        uri.set_path(&path);
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{fixtures, MockFetcher, MockResponse};
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    const SVG: &[u8] = br#"<svg xmlns="http://www.w3.org/2000/svg"></svg>"#;
+
+    #[tokio::test]
+    async fn downloads_and_rewrites_logos() {
+        let mut token_list = fixtures::full();
+        let list_logo = token_list.logo_uri.clone().unwrap();
+        token_list.tokens[1].logo_uri = Some(Url::parse("https://example.com/tel.svg").unwrap());
+        token_list.tokens.push(token_list.tokens[0].clone());
+        token_list.tokens[2].logo_uri = Some(Url::parse("https://example.com/missing").unwrap());
+        let fetcher = MockFetcher::new();
+        fetcher
+            .respond(list_logo.as_str(), MockResponse::body(PNG))
+            .respond("https://example.com/tel.svg", MockResponse::body(SVG));
+        let dir = tempfile::tempdir().unwrap();
+        let options = LogoDownloadOptions {
+            base_uri: Some(Url::parse("https://cdn.example/logos").unwrap()),
+            ..LogoDownloadOptions::default()
+        };
+
+        let report = token_list
+            .download_logos(&fetcher, dir.path(), &options)
+            .await
+            .unwrap();
+
+        let png = format!("{}.png", to_hex(&Sha256::digest(PNG)));
+        let svg = format!("{}.svg", to_hex(&Sha256::digest(SVG)));
+        assert_eq!(report.files.values().collect::<Vec<_>>(), [&png, &svg]);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(fs::read(dir.path().join(&png)).unwrap(), PNG);
+        assert_eq!(fs::read(dir.path().join(&svg)).unwrap(), SVG);
+
+        let logo = |uri: &Option<Url>| uri.as_ref().unwrap().as_str().to_owned();
+        let png_uri = format!("https://cdn.example/logos/{}", png);
+        assert_eq!(logo(&token_list.logo_uri), png_uri);
+        assert_eq!(logo(&token_list.tokens[0].logo_uri), png_uri);
+        assert_eq!(
+            logo(&token_list.tokens[1].logo_uri),
+            format!("https://cdn.example/logos/{}", svg)
+        );
+        assert_eq!(
+            logo(&token_list.tokens[2].logo_uri),
+            "https://example.com/missing"
+        );
+    }
+
+    /// Returns the signature and `IHDR` chunk of a PNG of the given size.
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut body = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        body.extend_from_slice(&width.to_be_bytes());
+        body.extend_from_slice(&height.to_be_bytes());
+        body
+    }
+
+    #[test]
+    fn checks_logos_against_policy() {
+        let policy = LogoPolicy::default();
+
+        assert!(policy.check(&png(256, 256)).is_empty());
+        assert_eq!(
+            policy.check(&png(32, 64)),
+            [
+                LogoViolation::TooSmall {
+                    width: 32,
+                    height: 64
+                },
+                LogoViolation::NotSquare
+            ]
+        );
+        assert_eq!(
+            policy.check(&png(2048, 2048)),
+            [LogoViolation::TooLarge {
+                width: 2048,
+                height: 2048
+            }]
+        );
+        assert_eq!(policy.check(PNG), [LogoViolation::Unreadable]);
+        assert_eq!(
+            policy.check(b"\xff\xd8\xff\xe0"),
+            [LogoViolation::UnsupportedFormat]
+        );
+        assert_eq!(
+            policy.check(&vec![0; policy.max_bytes + 1])[0],
+            LogoViolation::FileTooLarge {
+                bytes: policy.max_bytes + 1,
+                max: policy.max_bytes
+            }
+        );
+
+        assert!(policy.check(SVG).is_empty());
+        assert!(policy
+            .check(br#"<?xml version="1.0"?><svg viewBox="0 0 32 32"></svg>"#)
+            .is_empty());
+        assert_eq!(
+            policy.check(br#"<svg width="64px" height='32px'></svg>"#),
+            [LogoViolation::NotSquare]
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_violations_per_token() {
+        let mut token_list = fixtures::full();
+        token_list.tokens[0].logo_uri = Some(Url::parse("https://example.com/small.png").unwrap());
+        token_list.tokens[1].logo_uri = Some(Url::parse("https://example.com/missing").unwrap());
+        token_list.tokens.push(token_list.tokens[0].clone());
+        let fetcher = MockFetcher::new();
+        fetcher.respond(
+            "https://example.com/small.png",
+            MockResponse::body(png(16, 16)),
+        );
+
+        let violations = token_list
+            .check_logos(&fetcher, &LogoPolicy::default())
+            .await;
+
+        let indices: Vec<_> = violations.iter().map(|v| v.index).collect();
+        assert_eq!(indices, [0, 1, 2]);
+        assert_eq!(
+            violations[0].violation,
+            LogoViolation::TooSmall {
+                width: 16,
+                height: 16
+            }
+        );
+        assert!(matches!(
+            violations[1].violation,
+            LogoViolation::Unreachable(_)
+        ));
+        assert_eq!(violations[2].address, token_list.tokens[2].address);
+    }
+}
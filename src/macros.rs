@@ -0,0 +1,356 @@
+//! Declarative macros for writing lists and tokens concisely, such as in
+//! tests and fixtures.
+
+use std::convert::TryFrom;
+
+use indexmap::IndexMap;
+use semver::Version;
+
+use crate::{timestamp, ExtensionValue, Number, Timestamp, Token, TokenList};
+
+/// Creates a [`Token`](crate::Token).
+///
+/// The `name`, `symbol`, `address`, `chain_id` and `decimals` fields are
+/// required, in that order. They may be followed, in any order, by
+/// `logo_uri`, `tags` as an array, and `extensions` as `{ key => value }`
+/// pairs whose values are strings, numbers, booleans, or `None`.
+///
+/// ```
+/// use token_list::token;
+///
+/// let token = token! {
+///     name: "Telcoin",
+///     symbol: "TEL",
+///     address: "0x467Bccd9d29f223BcE8043b84E8C8B282827790F",
+///     chain_id: 1,
+///     decimals: 2,
+///     tags: ["telcoin"],
+///     extensions: { "polygonAddress" => "0xdF7837DE1F2Fa4631D716CF2502f8b230F1dcc32" },
+/// };
+///
+/// assert_eq!(token.tags, ["telcoin"]);
+/// assert_eq!(token.logo_uri, None);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `logo_uri` isn't a valid URI.
+#[macro_export]
+macro_rules! token {
+    (
+        name: $name:expr,
+        symbol: $symbol:expr,
+        address: $address:expr,
+        chain_id: $chain_id:expr,
+        decimals: $decimals:expr
+        $(, $($rest:tt)*)?
+    ) => {{
+        #[allow(unused_mut)]
+        let mut token = $crate::__private::token($name, $symbol, $address, $chain_id, $decimals);
+        $crate::token!(@set token; $($($rest)*)?);
+        token
+    }};
+
+    (@set $token:ident; ) => {};
+    (@set $token:ident; logo_uri: $logo_uri:expr $(, $($rest:tt)*)?) => {
+        $token.logo_uri = ::std::option::Option::Some($crate::__private::url($logo_uri));
+        $crate::token!(@set $token; $($($rest)*)?);
+    };
+    (@set $token:ident; tags: [$($tag:expr),* $(,)?] $(, $($rest:tt)*)?) => {
+        $token.tags = ::std::vec![$(::std::string::String::from($tag)),*];
+        $crate::token!(@set $token; $($($rest)*)?);
+    };
+    (@set $token:ident; extensions: {$($key:expr => $value:expr),* $(,)?} $(, $($rest:tt)*)?) => {
+        $(
+            $token.extensions.insert(
+                ::std::string::String::from($key),
+                $crate::__private::IntoExtensionValue::into_extension_value($value),
+            );
+        )*
+        $crate::token!(@set $token; $($($rest)*)?);
+    };
+}
+
+/// Creates a [`TokenList`](crate::TokenList).
+///
+/// The `name` field is required and comes first. It may be followed, in any
+/// order, by `timestamp` in RFC 3339 form, `version` as a semver string,
+/// `logo_uri`, `keywords` as an array, `tags` as `{ id => (name,
+/// description) }` pairs, and `tokens` as an array of [`Token`](crate::Token)s.
+/// The timestamp defaults to the Unix epoch, and the version to `1.0.0`.
+///
+/// ```
+/// use token_list::{token, token_list};
+///
+/// let token_list = token_list! {
+///     name: "TELcoins",
+///     version: "0.1.0",
+///     tags: { "telcoin" => ("telcoin", "Part of the Telcoin ecosystem.") },
+///     tokens: [
+///         token! {
+///             name: "Telcoin",
+///             symbol: "TEL",
+///             address: "0x467Bccd9d29f223BcE8043b84E8C8B282827790F",
+///             chain_id: 1,
+///             decimals: 2,
+///             tags: ["telcoin"],
+///         },
+///     ],
+/// };
+///
+/// assert_eq!(token_list.tokens.len(), 1);
+/// assert_eq!(token_list.version.to_string(), "0.1.0");
+/// ```
+///
+/// # Panics
+///
+/// Panics if `timestamp`, `version`, or `logo_uri` is invalid.
+#[macro_export]
+macro_rules! token_list {
+    (name: $name:expr $(, $($rest:tt)*)?) => {{
+        #[allow(unused_mut)]
+        let mut token_list = $crate::__private::token_list($name);
+        $crate::token_list!(@set token_list; $($($rest)*)?);
+        token_list
+    }};
+
+    (@set $token_list:ident; ) => {};
+    (@set $token_list:ident; timestamp: $timestamp:expr $(, $($rest:tt)*)?) => {
+        $token_list.timestamp = $crate::__private::timestamp($timestamp);
+        $crate::token_list!(@set $token_list; $($($rest)*)?);
+    };
+    (@set $token_list:ident; version: $version:expr $(, $($rest:tt)*)?) => {
+        $token_list.version = $crate::__private::version($version);
+        $crate::token_list!(@set $token_list; $($($rest)*)?);
+    };
+    (@set $token_list:ident; logo_uri: $logo_uri:expr $(, $($rest:tt)*)?) => {
+        $token_list.logo_uri = ::std::option::Option::Some($crate::__private::url($logo_uri));
+        $crate::token_list!(@set $token_list; $($($rest)*)?);
+    };
+    (@set $token_list:ident; keywords: [$($keyword:expr),* $(,)?] $(, $($rest:tt)*)?) => {
+        $token_list.keywords = ::std::vec![$(::std::string::String::from($keyword)),*];
+        $crate::token_list!(@set $token_list; $($($rest)*)?);
+    };
+    (
+        @set $token_list:ident;
+        tags: {$($id:expr => ($tag_name:expr, $description:expr)),* $(,)?}
+        $(, $($rest:tt)*)?
+    ) => {
+        $(
+            $token_list.tags.insert(
+                ::std::string::String::from($id),
+                $crate::Tag {
+                    name: ::std::string::String::from($tag_name),
+                    description: ::std::string::String::from($description),
+                },
+            );
+        )*
+        $crate::token_list!(@set $token_list; $($($rest)*)?);
+    };
+    (@set $token_list:ident; tokens: [$($token:expr),* $(,)?] $(, $($rest:tt)*)?) => {
+        $token_list.tokens = ::std::vec![$($token),*];
+        $crate::token_list!(@set $token_list; $($($rest)*)?);
+    };
+}
+
+/// Builds a token with no logo, tags or extensions.
+pub fn token(
+    name: impl Into<String>,
+    symbol: impl Into<String>,
+    address: impl Into<String>,
+    chain_id: u32,
+    decimals: u8,
+) -> Token {
+    Token {
+        name: name.into(),
+        symbol: symbol.into(),
+        address: address.into(),
+        chain_id,
+        decimals,
+        logo_uri: None,
+        tags: Vec::new(),
+        extensions: IndexMap::new(),
+        #[cfg(feature = "unknown-fields")]
+        unknown_fields: Default::default(),
+    }
+}
+
+/// Builds an empty list at version `1.0.0`, timestamped at the Unix epoch.
+pub fn token_list(name: impl Into<String>) -> TokenList {
+    TokenList {
+        name: name.into(),
+        timestamp: timestamp::parse_rfc3339("1970-01-01T00:00:00Z").expect("valid timestamp"),
+        version: Version::new(1, 0, 0),
+        logo_uri: None,
+        keywords: Vec::new(),
+        tags: IndexMap::new(),
+        tokens: Vec::new(),
+        #[cfg(feature = "unknown-fields")]
+        unknown_fields: Default::default(),
+    }
+}
+
+/// Parses an RFC 3339 timestamp.
+pub fn timestamp(timestamp: &str) -> Timestamp {
+    timestamp::parse_rfc3339(timestamp).expect("invalid timestamp")
+}
+
+/// Parses a semver version.
+pub fn version(version: &str) -> Version {
+    Version::parse(version).expect("invalid version")
+}
+
+/// Parses a logo URI.
+pub fn url(uri: &str) -> url::Url {
+    url::Url::parse(uri).expect("invalid logo URI")
+}
+
+/// Converts the values given for extensions in [`token!`].
+pub trait IntoExtensionValue {
+    /// Returns the value.
+    fn into_extension_value(self) -> ExtensionValue;
+}
+
+impl IntoExtensionValue for &str {
+    fn into_extension_value(self) -> ExtensionValue {
+        ExtensionValue::String(self.to_owned())
+    }
+}
+
+impl IntoExtensionValue for String {
+    fn into_extension_value(self) -> ExtensionValue {
+        ExtensionValue::String(self)
+    }
+}
+
+impl IntoExtensionValue for bool {
+    fn into_extension_value(self) -> ExtensionValue {
+        ExtensionValue::Boolean(self)
+    }
+}
+
+impl IntoExtensionValue for i32 {
+    fn into_extension_value(self) -> ExtensionValue {
+        i64::from(self).into_extension_value()
+    }
+}
+
+impl IntoExtensionValue for i64 {
+    fn into_extension_value(self) -> ExtensionValue {
+        ExtensionValue::Number(Number::Integer(self))
+    }
+}
+
+impl IntoExtensionValue for u64 {
+    fn into_extension_value(self) -> ExtensionValue {
+        u128::from(self).into_extension_value()
+    }
+}
+
+impl IntoExtensionValue for u128 {
+    fn into_extension_value(self) -> ExtensionValue {
+        ExtensionValue::Number(match i64::try_from(self) {
+            Ok(value) => Number::Integer(value),
+            Err(_) => Number::BigInteger(self),
+        })
+    }
+}
+
+impl IntoExtensionValue for f64 {
+    fn into_extension_value(self) -> ExtensionValue {
+        ExtensionValue::Number(Number::Float(self))
+    }
+}
+
+impl IntoExtensionValue for ExtensionValue {
+    fn into_extension_value(self) -> ExtensionValue {
+        self
+    }
+}
+
+/// `None` is converted to [`ExtensionValue::Null`].
+impl IntoExtensionValue for Option<ExtensionValue> {
+    fn into_extension_value(self) -> ExtensionValue {
+        self.unwrap_or(ExtensionValue::Null)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    #[test]
+    fn builds_the_fixture() {
+        let token_list = crate::token_list! {
+            name: "TELcoins",
+            timestamp: "2021-07-05T20:25:22+00:00",
+            version: "0.1.0",
+            logo_uri: "https://raw.githubusercontent.com/telcoin/token-lists/master/assets/logo-telcoin-250x250.png",
+            keywords: ["defi", "telcoin"],
+            tags: { "telcoin" => ("telcoin", "Part of the Telcoin ecosystem.") },
+            tokens: [
+                crate::token! {
+                    name: "Telcoin",
+                    symbol: "TEL",
+                    address: "0x467Bccd9d29f223BcE8043b84E8C8B282827790F",
+                    chain_id: 1,
+                    decimals: 2,
+                    logo_uri: "https://raw.githubusercontent.com/telcoin/token-lists/master/assets/logo-telcoin-250x250.png",
+                    tags: ["telcoin"],
+                    extensions: { "polygonAddress" => "0xdF7837DE1F2Fa4631D716CF2502f8b230F1dcc32" },
+                },
+                crate::token! {
+                    name: "Telcoin (PoS)",
+                    symbol: "TEL",
+                    address: "0xdF7837DE1F2Fa4631D716CF2502f8b230F1dcc32",
+                    chain_id: 137,
+                    decimals: 2,
+                    tags: ["telcoin"],
+                    logo_uri: "https://raw.githubusercontent.com/telcoin/token-lists/master/assets/logo-telcoin-250x250.png",
+                },
+            ],
+        };
+
+        let fixture = fixtures::full();
+        assert_eq!(token_list, fixture);
+    }
+
+    #[test]
+    fn defaults_optional_fields() {
+        let token_list = crate::token_list! { name: "Empty" };
+        assert_eq!(token_list.version, Version::new(1, 0, 0));
+        assert_eq!(
+            timestamp::to_rfc3339(&token_list.timestamp),
+            "1970-01-01T00:00:00Z"
+        );
+        assert!(token_list.tokens.is_empty() && token_list.tags.is_empty());
+
+        let token = crate::token! {
+            name: "Telcoin",
+            symbol: "TEL",
+            address: "0x467Bccd9d29f223BcE8043b84E8C8B282827790F",
+            chain_id: 1,
+            decimals: 2,
+            extensions: {
+                "bridged" => true,
+                "rank" => 3,
+                "supply" => u128::MAX,
+                "weight" => 0.5,
+                "note" => None,
+            },
+        };
+        assert_eq!(token.logo_uri, None);
+        assert!(token.tags.is_empty());
+        assert_eq!(
+            token.extensions.values().cloned().collect::<Vec<_>>(),
+            [
+                ExtensionValue::Boolean(true),
+                ExtensionValue::Number(Number::Integer(3)),
+                ExtensionValue::Number(Number::BigInteger(u128::MAX)),
+                ExtensionValue::Number(Number::Float(0.5)),
+                ExtensionValue::Null,
+            ]
+        );
+    }
+}
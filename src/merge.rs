@@ -0,0 +1,245 @@
+//! Merging of several lists into one.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Token, TokenList};
+
+/// How [`TokenList::merge`] picks between lists that disagree about a token.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MergeStrategy {
+    /// Take the token from the first list that has it
+    PreferFirst,
+
+    /// Take the token from the list with the latest timestamp that has it,
+    /// or the first of those with the latest
+    PreferNewest,
+
+    /// Include only tokens in at least this many lists, taking the metadata
+    /// most of them agree on, or the first list's on a tie
+    Quorum(usize),
+}
+
+impl fmt::Display for MergeStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeStrategy::PreferFirst => f.write_str("prefer-first"),
+            MergeStrategy::PreferNewest => f.write_str("prefer-newest"),
+            MergeStrategy::Quorum(n) => write!(f, "quorum={}", n),
+        }
+    }
+}
+
+impl FromStr for MergeStrategy {
+    type Err = String;
+
+    /// Parses a strategy in the form it's displayed in: `prefer-first`,
+    /// `prefer-newest`, or `quorum=N`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "prefer-first" => Ok(MergeStrategy::PreferFirst),
+            "prefer-newest" => Ok(MergeStrategy::PreferNewest),
+            _ => match s.strip_prefix("quorum=").map(str::parse) {
+                Some(Ok(n)) if n > 0 => Ok(MergeStrategy::Quorum(n)),
+                _ => Err(format!(
+                    "invalid merge strategy `{}`; expected `prefer-first`, `prefer-newest`, \
+                     or `quorum=N`",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+/// What happened to the tokens of the lists given to [`TokenList::merge`].
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct MergeReport {
+    /// Tokens whose metadata differs between lists, in merged order
+    pub conflicts: Vec<MergeConflict>,
+
+    /// Tokens left out for being in fewer lists than the quorum, in order of
+    /// first appearance
+    pub below_quorum: Vec<MergeConflict>,
+}
+
+/// A token found in several of the lists given to [`TokenList::merge`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MergeConflict {
+    /// The token's chain ID
+    pub chain_id: u32,
+
+    /// The token's address, as in the first list that has it
+    pub address: String,
+
+    /// The indices of the lists that have the token
+    pub sources: Vec<usize>,
+
+    /// The index of the list the merged token was taken from, if it was
+    /// included
+    pub chosen: Option<usize>,
+}
+
+impl TokenList {
+    /// Merges the tokens of `lists` into a single list, resolving lists that
+    /// disagree about a token with `strategy`.
+    ///
+    /// Tokens are identified by chain and address, compared
+    /// case-insensitively, and kept in order of first appearance. The merged
+    /// list takes its name, version, logo, and keywords from the first list,
+    /// the latest timestamp of any list, and the tags of every list, the first
+    /// definition of each winning; set them as needed before publishing.
+    /// Returns `None` if `lists` is empty.
+    pub fn merge(lists: &[TokenList], strategy: MergeStrategy) -> Option<(TokenList, MergeReport)> {
+        let first = lists.first()?;
+
+        // every list's version of each token, in order of first appearance
+        let mut order = Vec::new();
+        let mut versions: HashMap<(u32, String), Vec<(usize, &Token)>> = HashMap::new();
+        for (i, token_list) in lists.iter().enumerate() {
+            for token in &token_list.tokens {
+                let key = (token.chain_id, token.address.to_lowercase());
+                let entry = versions.entry(key.clone()).or_default();
+                if entry.is_empty() {
+                    order.push(key);
+                }
+                // a list repeating a token is counted once
+                if entry.last().map(|(j, _)| *j) != Some(i) {
+                    entry.push((i, token));
+                }
+            }
+        }
+
+        let mut report = MergeReport::default();
+        let mut tokens = Vec::with_capacity(order.len());
+        for key in &order {
+            let versions = &versions[key];
+            let conflict = |chosen| MergeConflict {
+                chain_id: key.0,
+                address: versions[0].1.address.clone(),
+                sources: versions.iter().map(|(i, _)| *i).collect(),
+                chosen,
+            };
+
+            let chosen = match strategy {
+                MergeStrategy::PreferFirst => versions[0],
+                MergeStrategy::PreferNewest => *versions
+                    .iter()
+                    .rev()
+                    .max_by_key(|(i, _)| lists[*i].timestamp)
+                    .expect("every token has a version"),
+                MergeStrategy::Quorum(quorum) if versions.len() < quorum => {
+                    report.below_quorum.push(conflict(None));
+                    continue;
+                }
+                MergeStrategy::Quorum(_) => most_common(versions),
+            };
+            if versions.iter().any(|(_, token)| *token != chosen.1) {
+                report.conflicts.push(conflict(Some(chosen.0)));
+            }
+            tokens.push(chosen.1.clone());
+        }
+
+        let mut tags = first.tags.clone();
+        for token_list in &lists[1..] {
+            for (id, tag) in &token_list.tags {
+                tags.entry(id.clone()).or_insert_with(|| tag.clone());
+            }
+        }
+
+        let merged = TokenList {
+            name: first.name.clone(),
+            timestamp: lists
+                .iter()
+                .map(|token_list| token_list.timestamp)
+                .max()
+                .expect("there is a list"),
+            version: first.version.clone(),
+            logo_uri: first.logo_uri.clone(),
+            keywords: first.keywords.clone(),
+            tags,
+            tokens,
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: first.unknown_fields.clone(),
+        };
+        Some((merged, report))
+    }
+}
+
+/// Returns the version of a token most lists agree on, or the first of the
+/// most common on a tie.
+fn most_common<'a>(versions: &[(usize, &'a Token)]) -> (usize, &'a Token) {
+    let count = |token: &Token| versions.iter().filter(|(_, other)| *other == token).count();
+    let mut best = versions[0];
+    let mut best_count = count(best.1);
+    for &version in &versions[1..] {
+        let count = count(version.1);
+        if count > best_count {
+            best = version;
+            best_count = count;
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    /// Three lists with the same tokens, the last two renaming the first
+    /// token, and the last the newest.
+    fn lists() -> Vec<TokenList> {
+        let first = fixtures::full();
+        let mut second = first.clone();
+        second.tokens[0].name = "Renamed".to_owned();
+        let mut third = second.clone();
+        third.timestamp = crate::timestamp::parse_rfc3339("2021-07-06T20:25:22Z").unwrap();
+        vec![first, second, third]
+    }
+
+    #[test]
+    fn prefers_first_or_newest() {
+        let lists = lists();
+
+        let (merged, report) = TokenList::merge(&lists, MergeStrategy::PreferFirst).unwrap();
+        assert_eq!(merged.tokens, lists[0].tokens);
+        assert_eq!(merged.timestamp, lists[2].timestamp);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].sources, vec![0, 1, 2]);
+        assert_eq!(report.conflicts[0].chosen, Some(0));
+
+        let (merged, report) = TokenList::merge(&lists, MergeStrategy::PreferNewest).unwrap();
+        assert_eq!(merged.tokens, lists[2].tokens);
+        assert_eq!(report.conflicts[0].chosen, Some(2));
+    }
+
+    #[test]
+    fn takes_majority_within_quorum() {
+        let mut lists = lists();
+        let mut extra = lists[0].tokens[0].clone();
+        extra.chain_id = 100;
+        lists[0].tokens.push(extra);
+
+        let (merged, report) = TokenList::merge(&lists, MergeStrategy::Quorum(2)).unwrap();
+
+        assert_eq!(merged.tokens, lists[1].tokens);
+        assert_eq!(report.conflicts[0].chosen, Some(1));
+        assert_eq!(report.below_quorum.len(), 1);
+        assert_eq!(report.below_quorum[0].chain_id, 100);
+    }
+
+    #[test]
+    fn parses_strategies() {
+        for strategy in &[
+            MergeStrategy::PreferFirst,
+            MergeStrategy::PreferNewest,
+            MergeStrategy::Quorum(3),
+        ] {
+            assert_eq!(strategy.to_string().parse(), Ok(*strategy));
+        }
+        assert!("quorum=0".parse::<MergeStrategy>().is_err());
+        assert!("newest".parse::<MergeStrategy>().is_err());
+        assert!(TokenList::merge(&[], MergeStrategy::PreferFirst).is_none());
+    }
+}
@@ -0,0 +1,62 @@
+//! MessagePack serialization of token lists.
+
+use crate::{Error, TokenList};
+
+impl TokenList {
+    /// Parses a [`TokenList`] from MessagePack.
+    pub fn from_msgpack_slice(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+
+    /// Serializes the list as MessagePack.
+    ///
+    /// Structs are written as maps keyed by field name, as in the JSON form,
+    /// so that optional fields can be omitted.
+    pub fn to_msgpack_vec(&self) -> Result<Vec<u8>, Error> {
+        Ok(rmp_serde::to_vec_named(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures::FULL;
+
+    #[test]
+    fn round_trips_through_msgpack() {
+        let token_list: TokenList = serde_json::from_str(FULL).unwrap();
+
+        let msgpack = token_list.to_msgpack_vec().unwrap();
+
+        assert_eq!(TokenList::from_msgpack_slice(&msgpack).unwrap(), token_list);
+        assert!(msgpack.len() < serde_json::to_vec(&token_list).unwrap().len());
+    }
+
+    #[test]
+    fn round_trips_extension_values() {
+        let mut token_list: TokenList = serde_json::from_str(FULL).unwrap();
+        let extensions = &mut token_list.tokens[0].extensions;
+        extensions.insert("bridged".to_owned(), crate::ExtensionValue::Boolean(true));
+        extensions.insert(
+            "rank".to_owned(),
+            crate::ExtensionValue::Number(crate::Number::Integer(3)),
+        );
+        extensions.insert(
+            "weight".to_owned(),
+            crate::ExtensionValue::Number(crate::Number::Float(0.5)),
+        );
+        extensions.insert("note".to_owned(), crate::ExtensionValue::Null);
+
+        let msgpack = token_list.to_msgpack_vec().unwrap();
+
+        assert_eq!(TokenList::from_msgpack_slice(&msgpack).unwrap(), token_list);
+    }
+
+    #[test]
+    fn reports_invalid_msgpack() {
+        match TokenList::from_msgpack_slice(&[0xc1]) {
+            Err(Error::Msgpack(_)) => {}
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Canonicalization of lists into a reproducible form.
+
+use std::collections::HashSet;
+
+use crate::keccak::checksum_address;
+use crate::{Error, TokenList};
+
+impl TokenList {
+    /// Brings the list into canonical form, so that lists with the same
+    /// content serialize to the same bytes with
+    /// [`to_normalized_vec`](Self::to_normalized_vec).
+    ///
+    /// Addresses are given their EIP-55 checksum; tokens with the same chain
+    /// and address as an earlier token are removed; tokens are sorted by chain
+    /// and address, their tags sorted and deduplicated, and their extensions
+    /// sorted by key; the list's tags are sorted by identifier; and the
+    /// timestamp is truncated to whole seconds. Keywords keep their order.
+    pub fn normalize(&mut self) {
+        self.timestamp = crate::timestamp::trunc_seconds(&self.timestamp);
+        self.tags.sort_keys();
+
+        for token in &mut self.tokens {
+            if let Some(checksummed) = checksum_address(&token.address) {
+                token.address = checksummed;
+            }
+            token.tags.sort();
+            token.tags.dedup();
+            token.extensions.sort_keys();
+        }
+
+        let mut seen = HashSet::new();
+        self.tokens
+            .retain(|token| seen.insert((token.chain_id, token.address.to_lowercase())));
+        self.tokens.sort_by(|a, b| {
+            (a.chain_id, a.address.to_lowercase()).cmp(&(b.chain_id, b.address.to_lowercase()))
+        });
+    }
+
+    /// Serializes a [normalized](Self::normalize) copy of the list as compact
+    /// JSON with object keys sorted, giving identical bytes for lists with the
+    /// same content.
+    pub fn to_normalized_vec(&self) -> Result<Vec<u8>, Error> {
+        let mut token_list = self.clone();
+        token_list.normalize();
+        // going through `Value` sorts the keys of every object
+        Ok(serde_json::to_vec(&serde_json::to_value(&token_list)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    #[test]
+    fn normalizes_lists() {
+        let mut token_list = fixtures::full();
+        token_list.timestamp = crate::timestamp::parse_rfc3339("2021-07-05T20:25:22.25Z").unwrap();
+        let len = token_list.tokens.len();
+        token_list.tokens.reverse();
+        let duplicate = token_list.tokens[0].clone();
+        token_list.tokens[0].address = token_list.tokens[0].address.to_lowercase();
+        token_list.tokens.push(duplicate);
+
+        token_list.normalize();
+
+        assert_eq!(
+            crate::timestamp::to_rfc3339(&token_list.timestamp),
+            "2021-07-05T20:25:22Z"
+        );
+        assert_eq!(token_list.tokens.len(), len);
+        let keys: Vec<_> = token_list
+            .tokens
+            .iter()
+            .map(|token| (token.chain_id, token.address.to_lowercase()))
+            .collect();
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+        for token in &token_list.tokens {
+            assert_eq!(
+                checksum_address(&token.address),
+                Some(token.address.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn serializes_equal_content_identically() {
+        let token_list = fixtures::full();
+        let mut shuffled = token_list.clone();
+        shuffled.tokens.reverse();
+        shuffled.tags.reverse();
+        for token in &mut shuffled.tokens {
+            token.address = token.address.to_lowercase();
+            token.extensions.reverse();
+        }
+
+        assert_eq!(
+            shuffled.to_normalized_vec().unwrap(),
+            token_list.to_normalized_vec().unwrap()
+        );
+    }
+}
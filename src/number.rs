@@ -0,0 +1,131 @@
+//! Deserialization of [`Number`]s, keeping integers exact where possible.
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Unexpected, Visitor};
+
+use crate::Number;
+
+/// The key under which `serde_json` passes the text of numbers when its
+/// `arbitrary_precision` feature is enabled.
+const SERDE_JSON_NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+impl<'de> Deserialize<'de> for Number {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NumberVisitor)
+    }
+}
+
+struct NumberVisitor;
+
+impl<'de> Visitor<'de> for NumberVisitor {
+    type Value = Number;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a number")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Number, E> {
+        Ok(Number::Integer(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Number, E> {
+        self.visit_u128(value.into())
+    }
+
+    fn visit_i128<E: de::Error>(self, value: i128) -> Result<Number, E> {
+        match u128::try_from(value) {
+            Ok(value) => self.visit_u128(value),
+            Err(_) => match i64::try_from(value) {
+                Ok(value) => Ok(Number::Integer(value)),
+                Err(_) => Ok(Number::Float(value as f64)),
+            },
+        }
+    }
+
+    fn visit_u128<E: de::Error>(self, value: u128) -> Result<Number, E> {
+        Ok(match i64::try_from(value) {
+            Ok(value) => Number::Integer(value),
+            Err(_) => Number::BigInteger(value),
+        })
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Number, E> {
+        Ok(Number::Float(value))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Number, A::Error> {
+        match map.next_key::<String>()? {
+            Some(key) if key == SERDE_JSON_NUMBER_TOKEN => {
+                let text: String = map.next_value()?;
+                parse(&text).ok_or_else(|| de::Error::invalid_value(Unexpected::Str(&text), &self))
+            }
+            _ => Err(de::Error::invalid_type(Unexpected::Map, &self)),
+        }
+    }
+}
+
+/// Parses the text of a JSON number.
+fn parse(text: &str) -> Option<Number> {
+    if !text.contains(['.', 'e', 'E']) {
+        if let Ok(value) = text.parse() {
+            return Some(Number::Integer(value));
+        }
+        if let Ok(value) = text.parse() {
+            return Some(Number::BigInteger(value));
+        }
+    }
+    text.parse().ok().map(Number::Float)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_integers_exactly() {
+        let cases = [
+            ("-42", Number::Integer(-42)),
+            ("9223372036854775807", Number::Integer(i64::MAX)),
+            ("18446744073709551615", Number::BigInteger(u64::MAX.into())),
+            ("0.5", Number::Float(0.5)),
+        ];
+
+        for (json, expected) in &cases {
+            let number: Number = serde_json::from_str(json).unwrap();
+
+            assert_eq!(number, *expected);
+            assert_eq!(serde_json::to_string(&number).unwrap(), *json);
+        }
+    }
+
+    #[cfg(feature = "arbitrary-precision")]
+    #[test]
+    fn reads_integers_beyond_u64() {
+        let json = r#"{"totalSupply": 1000000000000000000000000000}"#;
+
+        let extensions: crate::IndexMap<String, crate::ExtensionValue> =
+            serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            extensions["totalSupply"].as_u128(),
+            Some(1_000_000_000_000_000_000_000_000_000)
+        );
+        assert_eq!(
+            serde_json::to_string(&extensions).unwrap(),
+            json.replace(' ', "")
+        );
+    }
+
+    #[test]
+    fn parses_number_text() {
+        assert_eq!(parse("7"), Some(Number::Integer(7)));
+        assert_eq!(
+            parse("340282366920938463463374607431768211455"),
+            Some(Number::BigInteger(u128::MAX))
+        );
+        assert_eq!(parse("1e3"), Some(Number::Float(1000.0)));
+        assert_eq!(parse("x"), None);
+    }
+}
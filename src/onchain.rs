@@ -0,0 +1,1357 @@
+//! Verification of token metadata against the token contracts, over Ethereum
+//! JSON-RPC.
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{Error, Token, TokenList};
+
+/// The address of the Multicall3 contract, which is deployed at the same
+/// address on most chains.
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+/// The selector of Multicall3's `aggregate3((address,bool,bytes)[])`.
+const AGGREGATE3: [u8; 4] = [0x82, 0xad, 0x56, 0xcb];
+
+/// The number of tokens checked in each multicall.
+const MULTICALL_BATCH_SIZE: usize = 100;
+
+/// A client for an Ethereum JSON-RPC endpoint.
+#[derive(Clone, Debug)]
+pub struct RpcClient {
+    client: reqwest::Client,
+    url: reqwest::Url,
+}
+
+impl RpcClient {
+    /// Creates a client for the endpoint at `url`.
+    pub fn new<T: reqwest::IntoUrl>(url: T) -> Result<Self, Error> {
+        Ok(Self::with_client(reqwest::Client::new(), url.into_url()?))
+    }
+
+    /// Creates a client for the endpoint at `url` that sends requests with
+    /// `client`.
+    pub fn with_client(client: reqwest::Client, url: reqwest::Url) -> Self {
+        RpcClient { client, url }
+    }
+
+    /// Returns the URL of the endpoint.
+    pub fn url(&self) -> &reqwest::Url {
+        &self.url
+    }
+}
+
+/// Sends Ethereum JSON-RPC requests.
+///
+/// This is implemented by [`RpcClient`], and by ethers' `Provider` with the
+/// `ethers` feature; implement it to check tokens through another client.
+/// Only [`request`](Self::request) is required.
+///
+/// On wasm, the futures returned by clients are not required to be [`Send`].
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait JsonRpc: Send + Sync {
+    /// Sends a JSON-RPC request and returns its result.
+    async fn request(&self, method: &str, params: Value) -> Result<Value, Error>;
+
+    /// Returns the ID of the chain served by the endpoint.
+    async fn chain_id(&self) -> Result<u64, Error> {
+        let result = self.request("eth_chainId", json!([])).await?;
+        result
+            .as_str()
+            .and_then(parse_quantity)
+            .ok_or_else(|| invalid_response("eth_chainId", &result))
+    }
+
+    /// Calls the contract at `to` with the ABI-encoded `data`, in the latest
+    /// block, and returns the data it returns.
+    async fn call(&self, to: &str, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let params = json!([{ "to": to, "data": encode_hex(data) }, "latest"]);
+        let result = self.request("eth_call", params).await?;
+        result
+            .as_str()
+            .and_then(decode_hex)
+            .ok_or_else(|| invalid_response("eth_call", &result))
+    }
+
+    /// Returns the code deployed at `address`, which is empty for accounts
+    /// without a contract.
+    async fn get_code(&self, address: &str) -> Result<Vec<u8>, Error> {
+        let result = self
+            .request("eth_getCode", json!([address, "latest"]))
+            .await?;
+        result
+            .as_str()
+            .and_then(decode_hex)
+            .ok_or_else(|| invalid_response("eth_getCode", &result))
+    }
+
+    /// Returns the 32-byte word in storage `slot` of the contract at
+    /// `address`.
+    async fn get_storage_at(&self, address: &str, slot: &str) -> Result<[u8; 32], Error> {
+        let result = self
+            .request("eth_getStorageAt", json!([address, slot, "latest"]))
+            .await?;
+        let word = result.as_str().and_then(decode_hex).and_then(|bytes| {
+            let mut word = [0; 32];
+            // some nodes strip leading zeros
+            let start = word.len().checked_sub(bytes.len())?;
+            word[start..].copy_from_slice(&bytes);
+            Some(word)
+        });
+        word.ok_or_else(|| invalid_response("eth_getStorageAt", &result))
+    }
+
+    /// Returns the number of transactions sent from `address`.
+    async fn transaction_count(&self, address: &str) -> Result<u64, Error> {
+        let result = self
+            .request("eth_getTransactionCount", json!([address, "latest"]))
+            .await?;
+        result
+            .as_str()
+            .and_then(parse_quantity)
+            .ok_or_else(|| invalid_response("eth_getTransactionCount", &result))
+    }
+
+    /// Makes each of `calls`, given as the contract address and the
+    /// ABI-encoded data, through the [Multicall3](https://www.multicall3.com)
+    /// contract in a single `eth_call`.
+    ///
+    /// Returns the data each call returned, or `None` if it reverted. Fails
+    /// with [`Error::NoContract`] if Multicall3 isn't deployed on the chain.
+    async fn multicall(&self, calls: &[(&str, &[u8])]) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        let calls = calls
+            .iter()
+            .map(|&(to, data)| match parse_address(to) {
+                Some(address) => Ok((address, data)),
+                None => Err(Error::InvalidAddress(to.to_owned())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let data = self
+            .call(MULTICALL3_ADDRESS, &encode_aggregate3(&calls))
+            .await?;
+        if data.is_empty() {
+            return Err(Error::NoContract(MULTICALL3_ADDRESS.to_owned()));
+        }
+        decode_aggregate3(&data)
+            .filter(|results| results.len() == calls.len())
+            .ok_or_else(|| Error::InvalidRpcResponse("undecodable multicall result".to_owned()))
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl JsonRpc for RpcClient {
+    /// Sends a JSON-RPC request and returns its result.
+    async fn request(&self, method: &str, params: Value) -> Result<Value, Error> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let response = self
+            .client
+            .post(self.url.clone())
+            .json(&request)
+            .send()
+            .await?;
+        let body = crate::fetch::check_status(response).await?.bytes().await?;
+
+        let response: Response = crate::json::from_slice(&body)?;
+        match response.error {
+            Some(RpcError { code, message }) => Err(Error::Rpc { code, message }),
+            None => Ok(response.result.unwrap_or(Value::Null)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Response {
+    result: Option<Value>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+fn invalid_response(method: &str, result: &Value) -> Error {
+    Error::InvalidRpcResponse(format!("unexpected {} result {}", method, result))
+}
+
+/// The result of checking a list's tokens against their contracts, with
+/// [`TokenList::verify_onchain`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OnchainReport {
+    /// Listed metadata that differs from the contract's
+    pub mismatches: Vec<Mismatch>,
+
+    /// Tokens without a contract at their address
+    pub missing_contracts: Vec<TokenAddress>,
+
+    /// Metadata that could not be read from a contract; e.g. because the
+    /// call reverted
+    pub failed_calls: Vec<FailedCall>,
+
+    /// Chains whose tokens could not be checked
+    pub unreachable_chains: Vec<UnreachableChain>,
+}
+
+impl OnchainReport {
+    /// Returns `true` if every token was checked and matches its contract.
+    pub fn is_clean(&self) -> bool {
+        self.mismatches.is_empty()
+            && self.missing_contracts.is_empty()
+            && self.failed_calls.is_empty()
+            && self.unreachable_chains.is_empty()
+    }
+
+    fn merge(&mut self, other: OnchainReport) {
+        self.mismatches.extend(other.mismatches);
+        self.missing_contracts.extend(other.missing_contracts);
+        self.failed_calls.extend(other.failed_calls);
+        self.unreachable_chains.extend(other.unreachable_chains);
+    }
+}
+
+/// The location of a token's contract.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TokenAddress {
+    /// The chain the contract is deployed on
+    pub chain_id: u32,
+
+    /// The address of the contract, as listed
+    pub address: String,
+}
+
+impl TokenAddress {
+    fn of(token: &Token) -> Self {
+        TokenAddress {
+            chain_id: token.chain_id,
+            address: token.address.clone(),
+        }
+    }
+}
+
+impl fmt::Display for TokenAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} on chain {}", self.address, self.chain_id)
+    }
+}
+
+/// A piece of token metadata that is read from the token's contract.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MetadataField {
+    /// The token's `name()`
+    Name,
+
+    /// The token's `symbol()`
+    Symbol,
+
+    /// The token's `decimals()`
+    Decimals,
+}
+
+impl MetadataField {
+    const ALL: [MetadataField; 3] = [
+        MetadataField::Name,
+        MetadataField::Symbol,
+        MetadataField::Decimals,
+    ];
+
+    /// The ABI-encoded call reading the field.
+    fn selector(self) -> &'static [u8] {
+        match self {
+            MetadataField::Name => &[0x06, 0xfd, 0xde, 0x03],
+            MetadataField::Symbol => &[0x95, 0xd8, 0x9b, 0x41],
+            MetadataField::Decimals => &[0x31, 0x3c, 0xe5, 0x67],
+        }
+    }
+
+    fn listed(self, token: &Token) -> String {
+        match self {
+            MetadataField::Name => token.name.clone(),
+            MetadataField::Symbol => token.symbol.clone(),
+            MetadataField::Decimals => token.decimals.to_string(),
+        }
+    }
+
+    fn decode(self, data: &[u8]) -> Option<String> {
+        match self {
+            MetadataField::Name | MetadataField::Symbol => decode_string(data),
+            MetadataField::Decimals => decode_uint(data).map(|decimals| decimals.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for MetadataField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            MetadataField::Name => "name",
+            MetadataField::Symbol => "symbol",
+            MetadataField::Decimals => "decimals",
+        })
+    }
+}
+
+/// Listed metadata that differs from the token contract's.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The token's contract
+    pub token: TokenAddress,
+
+    /// The differing field
+    pub field: MetadataField,
+
+    /// The value in the list
+    pub listed: String,
+
+    /// The value read from the contract
+    pub onchain: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} is listed as {:?} but is {:?} on chain",
+            self.token, self.field, self.listed, self.onchain
+        )
+    }
+}
+
+/// Metadata that could not be read from a token contract.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FailedCall {
+    /// The token's contract
+    pub token: TokenAddress,
+
+    /// The field that could not be read
+    pub field: MetadataField,
+
+    /// Why the call failed
+    pub reason: String,
+}
+
+impl fmt::Display for FailedCall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: failed to read {}: {}",
+            self.token, self.field, self.reason
+        )
+    }
+}
+
+/// A chain whose tokens could not be checked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnreachableChain {
+    /// The chain's ID
+    pub chain_id: u32,
+
+    /// Why the chain could not be reached
+    pub reason: String,
+}
+
+impl fmt::Display for UnreachableChain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "chain {} is unreachable: {}", self.chain_id, self.reason)
+    }
+}
+
+impl TokenList {
+    /// Checks the `name`, `symbol`, and `decimals` of each token against its
+    /// contract, through the JSON-RPC endpoint in `rpc_urls` for its chain.
+    ///
+    /// Tokens are checked in batches of 100 through [Multicall3], or with a
+    /// call per field on chains without it, and chains are checked
+    /// concurrently. A chain is reported as unreachable if it has no
+    /// endpoint, its endpoint serves a different chain, or its endpoint
+    /// fails; any of its tokens checked before the failure are still reported.
+    ///
+    /// [Multicall3]: https://www.multicall3.com
+    pub async fn verify_onchain<U: AsRef<str>>(&self, rpc_urls: &HashMap<u32, U>) -> OnchainReport {
+        let rpcs = clients(rpc_urls);
+        self.verify_chains(|chain_id| endpoint(&rpcs, chain_id))
+            .await
+    }
+
+    /// Checks each token against its contract as
+    /// [`verify_onchain`](Self::verify_onchain) does, through the client in
+    /// `rpcs` for its chain.
+    pub async fn verify_onchain_with<R: JsonRpc>(&self, rpcs: &HashMap<u32, R>) -> OnchainReport {
+        self.verify_chains(|chain_id| rpcs.get(&chain_id).ok_or_else(no_endpoint))
+            .await
+    }
+
+    async fn verify_chains<'a, R, F>(&'a self, rpc: F) -> OnchainReport
+    where
+        R: JsonRpc + 'a,
+        F: Fn(u32) -> Result<&'a R, String>,
+    {
+        let reports = join_all(
+            self.tokens_by_chain()
+                .into_iter()
+                .map(|(chain_id, tokens)| verify_chain(chain_id, rpc(chain_id), tokens)),
+        )
+        .await;
+
+        let mut report = OnchainReport::default();
+        for chain_report in reports {
+            report.merge(chain_report);
+        }
+        report
+    }
+
+    /// Groups the tokens by chain, in order of chain ID.
+    fn tokens_by_chain(&self) -> BTreeMap<u32, Vec<&Token>> {
+        let mut chains: BTreeMap<u32, Vec<&Token>> = BTreeMap::new();
+        for token in &self.tokens {
+            chains.entry(token.chain_id).or_default().push(token);
+        }
+        chains
+    }
+}
+
+async fn verify_chain<R: JsonRpc>(
+    chain_id: u32,
+    rpc: Result<&R, String>,
+    tokens: Vec<&Token>,
+) -> OnchainReport {
+    let mut report = OnchainReport::default();
+    let rpc = match connect(chain_id, rpc).await {
+        Ok(rpc) => rpc,
+        Err(reason) => {
+            report
+                .unreachable_chains
+                .push(UnreachableChain { chain_id, reason });
+            return report;
+        }
+    };
+
+    if let Err(err) = verify_tokens(rpc, &tokens, &mut report).await {
+        report.unreachable_chains.push(UnreachableChain {
+            chain_id,
+            reason: err.to_string(),
+        });
+    }
+    report
+}
+
+/// Creates a client for each of `rpc_urls`, or gives the reason it can't be.
+fn clients<U: AsRef<str>>(rpc_urls: &HashMap<u32, U>) -> HashMap<u32, Result<RpcClient, String>> {
+    rpc_urls
+        .iter()
+        .map(|(&chain_id, url)| {
+            let rpc = RpcClient::new(url.as_ref()).map_err(|err| err.to_string());
+            (chain_id, rpc)
+        })
+        .collect()
+}
+
+/// Returns the client in `rpcs` for `chain_id`.
+fn endpoint(
+    rpcs: &HashMap<u32, Result<RpcClient, String>>,
+    chain_id: u32,
+) -> Result<&RpcClient, String> {
+    match rpcs.get(&chain_id) {
+        Some(rpc) => rpc.as_ref().map_err(Clone::clone),
+        None => Err(no_endpoint()),
+    }
+}
+
+fn no_endpoint() -> String {
+    "no RPC endpoint given".to_owned()
+}
+
+/// Checks that `rpc` serves `chain_id`.
+async fn connect<R: JsonRpc>(chain_id: u32, rpc: Result<&R, String>) -> Result<&R, String> {
+    let rpc = rpc?;
+    match rpc.chain_id().await {
+        Ok(id) if id == u64::from(chain_id) => Ok(rpc),
+        Ok(actual) => Err(Error::ChainMismatch {
+            expected: chain_id,
+            actual,
+        }
+        .to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Checks `tokens` against their contracts in batches through Multicall3,
+/// falling back to a call per field if the chain doesn't have it. Fails only
+/// if the endpoint does.
+async fn verify_tokens<R: JsonRpc + ?Sized>(
+    rpc: &R,
+    tokens: &[&Token],
+    report: &mut OnchainReport,
+) -> Result<(), Error> {
+    // tokens with malformed addresses can't be encoded into a batch
+    let (batchable, unbatchable): (Vec<&Token>, Vec<&Token>) = tokens
+        .iter()
+        .partition(|token| parse_address(&token.address).is_some());
+
+    let mut batches = batchable.chunks(MULTICALL_BATCH_SIZE);
+    for batch in &mut batches {
+        match verify_batch(rpc, batch, report).await {
+            Ok(()) => {}
+            Err(Error::Rpc { .. })
+            | Err(Error::InvalidRpcResponse(_))
+            | Err(Error::NoContract(_)) => {
+                for token in batch {
+                    verify_token(rpc, token, report).await?;
+                }
+                break;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    for token in batches.flatten().chain(&unbatchable) {
+        verify_token(rpc, token, report).await?;
+    }
+    Ok(())
+}
+
+/// Checks `tokens` against their contracts in a single multicall.
+async fn verify_batch<R: JsonRpc + ?Sized>(
+    rpc: &R,
+    tokens: &[&Token],
+    report: &mut OnchainReport,
+) -> Result<(), Error> {
+    let calls: Vec<(&str, &[u8])> = tokens
+        .iter()
+        .flat_map(|token| {
+            MetadataField::ALL
+                .iter()
+                .map(move |field| (token.address.as_str(), field.selector()))
+        })
+        .collect();
+    let results = rpc.multicall(&calls).await?;
+
+    for (token, results) in tokens.iter().zip(results.chunks(MetadataField::ALL.len())) {
+        for (&field, result) in MetadataField::ALL.iter().zip(results) {
+            let result = result.clone().ok_or_else(|| "call reverted".to_owned());
+            if !record(report, token, field, result) {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks `token` against its contract with a call per field.
+async fn verify_token<R: JsonRpc + ?Sized>(
+    rpc: &R,
+    token: &Token,
+    report: &mut OnchainReport,
+) -> Result<(), Error> {
+    for &field in &MetadataField::ALL {
+        let result = match rpc.call(&token.address, field.selector()).await {
+            Ok(data) => Ok(data),
+            Err(err @ Error::Rpc { .. }) | Err(err @ Error::InvalidRpcResponse(_)) => {
+                Err(err.to_string())
+            }
+            Err(err) => return Err(err),
+        };
+        if !record(report, token, field, result) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Records the result of reading `field` from the contract of `token`,
+/// returning `false` if the token's other fields shouldn't be read.
+fn record(
+    report: &mut OnchainReport,
+    token: &Token,
+    field: MetadataField,
+    result: Result<Vec<u8>, String>,
+) -> bool {
+    let onchain = match result {
+        // calls to addresses without code succeed with no data
+        Ok(data) if data.is_empty() && field == MetadataField::Name => {
+            report.missing_contracts.push(TokenAddress::of(token));
+            return false;
+        }
+        Ok(data) => field
+            .decode(&data)
+            .ok_or_else(|| "undecodable return data".to_owned()),
+        Err(reason) => Err(reason),
+    };
+
+    match onchain {
+        Ok(onchain) => {
+            let listed = field.listed(token);
+            if listed != onchain {
+                report.mismatches.push(Mismatch {
+                    token: TokenAddress::of(token),
+                    field,
+                    listed,
+                    onchain,
+                });
+            }
+        }
+        Err(reason) => report.failed_calls.push(FailedCall {
+            token: TokenAddress::of(token),
+            field,
+            reason,
+        }),
+    }
+    true
+}
+
+/// The storage slot holding the implementation address of an EIP-1967 proxy.
+const EIP1967_IMPLEMENTATION_SLOT: &str =
+    "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+
+/// The storage slot holding the beacon address of an EIP-1967 beacon proxy.
+const EIP1967_BEACON_SLOT: &str =
+    "0xa3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50";
+
+/// The selector of a beacon's `implementation()`.
+const IMPLEMENTATION: [u8; 4] = [0x5c, 0x60, 0xda, 0x1b];
+
+/// The code of an EIP-1167 minimal proxy, before and after the
+/// implementation address.
+const EIP1167_PREFIX: &[u8] = &[0x36, 0x3d, 0x3d, 0x37, 0x3d, 0x3d, 0x3d, 0x36, 0x3d, 0x73];
+const EIP1167_SUFFIX: &[u8] = &[
+    0x5a, 0xf4, 0x3d, 0x82, 0x80, 0x3e, 0x90, 0x3d, 0x91, 0x60, 0x2b, 0x57, 0xfd, 0x5b, 0xf3,
+];
+
+/// The result of checking what is deployed at each token's address, with
+/// [`TokenList::check_contracts`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ContractReport {
+    /// What is deployed at each token's address, in list order within each
+    /// chain
+    pub contracts: Vec<ContractCheck>,
+
+    /// Chains whose tokens could not be checked
+    pub unreachable_chains: Vec<UnreachableChain>,
+}
+
+impl ContractReport {
+    /// Returns the checks of tokens without a contract at their address.
+    pub fn flagged(&self) -> impl Iterator<Item = &ContractCheck> {
+        self.contracts
+            .iter()
+            .filter(|check| !check.kind.is_contract())
+    }
+}
+
+/// What is deployed at a token's address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractCheck {
+    /// The token's address
+    pub token: TokenAddress,
+
+    /// What is deployed there
+    pub kind: ContractKind,
+}
+
+impl fmt::Display for ContractCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.token, self.kind)
+    }
+}
+
+/// What is deployed at an address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ContractKind {
+    /// A contract that isn't a recognized proxy
+    Contract,
+
+    /// A proxy delegating to another contract
+    Proxy {
+        /// The pattern the proxy follows
+        standard: ProxyStandard,
+
+        /// The address of the contract it delegates to
+        implementation: String,
+    },
+
+    /// An externally owned account; i.e. an address without code that has
+    /// sent transactions
+    ExternallyOwned,
+
+    /// An address without code or transactions; e.g. a contract that
+    /// self-destructed, or one that was never deployed
+    Empty,
+}
+
+impl ContractKind {
+    /// Returns `true` if there is a contract at the address.
+    pub fn is_contract(&self) -> bool {
+        matches!(self, ContractKind::Contract | ContractKind::Proxy { .. })
+    }
+}
+
+impl fmt::Display for ContractKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContractKind::Contract => f.write_str("contract"),
+            ContractKind::Proxy {
+                standard,
+                implementation,
+            } => write!(f, "{} proxy for {}", standard, implementation),
+            ContractKind::ExternallyOwned => f.write_str("externally owned account"),
+            ContractKind::Empty => f.write_str("no code or transactions"),
+        }
+    }
+}
+
+/// A proxy pattern.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ProxyStandard {
+    /// An [EIP-1967](https://eips.ethereum.org/EIPS/eip-1967) proxy, with its
+    /// implementation address in storage
+    Eip1967,
+
+    /// An [EIP-1967](https://eips.ethereum.org/EIPS/eip-1967) beacon proxy,
+    /// taking its implementation address from a beacon contract
+    Eip1967Beacon,
+
+    /// An [EIP-1167](https://eips.ethereum.org/EIPS/eip-1167) minimal proxy,
+    /// with its implementation address in its code
+    Eip1167,
+}
+
+impl fmt::Display for ProxyStandard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ProxyStandard::Eip1967 => "EIP-1967",
+            ProxyStandard::Eip1967Beacon => "EIP-1967 beacon",
+            ProxyStandard::Eip1167 => "EIP-1167",
+        })
+    }
+}
+
+impl TokenList {
+    /// Checks that each token's address holds a contract, through the
+    /// JSON-RPC endpoint in `rpc_urls` for its chain, and detects EIP-1967
+    /// and EIP-1167 proxies.
+    ///
+    /// Chains are checked concurrently, and unreachable chains are reported as
+    /// by [`verify_onchain`](Self::verify_onchain).
+    pub async fn check_contracts<U: AsRef<str>>(
+        &self,
+        rpc_urls: &HashMap<u32, U>,
+    ) -> ContractReport {
+        let rpcs = clients(rpc_urls);
+        self.check_chains(|chain_id| endpoint(&rpcs, chain_id))
+            .await
+    }
+
+    /// Checks that each token's address holds a contract as
+    /// [`check_contracts`](Self::check_contracts) does, through the client in
+    /// `rpcs` for its chain.
+    pub async fn check_contracts_with<R: JsonRpc>(&self, rpcs: &HashMap<u32, R>) -> ContractReport {
+        self.check_chains(|chain_id| rpcs.get(&chain_id).ok_or_else(no_endpoint))
+            .await
+    }
+
+    async fn check_chains<'a, R, F>(&'a self, rpc: F) -> ContractReport
+    where
+        R: JsonRpc + 'a,
+        F: Fn(u32) -> Result<&'a R, String>,
+    {
+        let reports = join_all(
+            self.tokens_by_chain()
+                .into_iter()
+                .map(|(chain_id, tokens)| check_chain(chain_id, rpc(chain_id), tokens)),
+        )
+        .await;
+
+        let mut report = ContractReport::default();
+        for chain_report in reports {
+            report.contracts.extend(chain_report.contracts);
+            report
+                .unreachable_chains
+                .extend(chain_report.unreachable_chains);
+        }
+        report
+    }
+}
+
+async fn check_chain<R: JsonRpc>(
+    chain_id: u32,
+    rpc: Result<&R, String>,
+    tokens: Vec<&Token>,
+) -> ContractReport {
+    let mut report = ContractReport::default();
+    let rpc = match connect(chain_id, rpc).await {
+        Ok(rpc) => rpc,
+        Err(reason) => {
+            report
+                .unreachable_chains
+                .push(UnreachableChain { chain_id, reason });
+            return report;
+        }
+    };
+
+    for token in tokens {
+        match check_contract(rpc, &token.address).await {
+            Ok(kind) => report.contracts.push(ContractCheck {
+                token: TokenAddress::of(token),
+                kind,
+            }),
+            Err(err) => {
+                report.unreachable_chains.push(UnreachableChain {
+                    chain_id,
+                    reason: err.to_string(),
+                });
+                break;
+            }
+        }
+    }
+    report
+}
+
+/// Determines what is deployed at `address`.
+async fn check_contract<R: JsonRpc + ?Sized>(
+    rpc: &R,
+    address: &str,
+) -> Result<ContractKind, Error> {
+    let code = rpc.get_code(address).await?;
+    if code.is_empty() {
+        return Ok(match rpc.transaction_count(address).await? {
+            0 => ContractKind::Empty,
+            _ => ContractKind::ExternallyOwned,
+        });
+    }
+
+    if code.len() == EIP1167_PREFIX.len() + 20 + EIP1167_SUFFIX.len()
+        && code.starts_with(EIP1167_PREFIX)
+        && code.ends_with(EIP1167_SUFFIX)
+    {
+        let implementation = &code[EIP1167_PREFIX.len()..EIP1167_PREFIX.len() + 20];
+        return Ok(ContractKind::Proxy {
+            standard: ProxyStandard::Eip1167,
+            implementation: encode_hex(implementation),
+        });
+    }
+
+    if let Some(implementation) = slot_address(rpc, address, EIP1967_IMPLEMENTATION_SLOT).await? {
+        return Ok(ContractKind::Proxy {
+            standard: ProxyStandard::Eip1967,
+            implementation,
+        });
+    }
+    if let Some(beacon) = slot_address(rpc, address, EIP1967_BEACON_SLOT).await? {
+        let data = rpc.call(&beacon, &IMPLEMENTATION).await?;
+        let implementation = data.get(12..32).ok_or_else(|| {
+            Error::InvalidRpcResponse(format!("undecodable implementation of beacon {}", beacon))
+        })?;
+        return Ok(ContractKind::Proxy {
+            standard: ProxyStandard::Eip1967Beacon,
+            implementation: encode_hex(implementation),
+        });
+    }
+    Ok(ContractKind::Contract)
+}
+
+/// Reads the address stored in `slot` of the contract at `address`, or
+/// `None` if it's zero.
+async fn slot_address<R: JsonRpc + ?Sized>(
+    rpc: &R,
+    address: &str,
+    slot: &str,
+) -> Result<Option<String>, Error> {
+    let word = rpc.get_storage_at(address, slot).await?;
+    Ok(match word.iter().all(|&byte| byte == 0) {
+        true => None,
+        false => Some(encode_hex(&word[12..])),
+    })
+}
+
+impl Token {
+    /// Reads the metadata of the ERC-20 contract at `address` on `chain_id`
+    /// through `rpc`, and returns it as a token without a logo, tags, or
+    /// extensions.
+    ///
+    /// Fails with [`Error::ChainMismatch`] if `rpc` serves a different chain,
+    /// and with [`Error::NoContract`] if there is no contract at `address`.
+    pub async fn from_contract<R: JsonRpc + ?Sized>(
+        rpc: &R,
+        chain_id: u32,
+        address: &str,
+    ) -> Result<Self, Error> {
+        let actual = rpc.chain_id().await?;
+        if actual != u64::from(chain_id) {
+            return Err(Error::ChainMismatch {
+                expected: chain_id,
+                actual,
+            });
+        }
+
+        let name = read(rpc, address, MetadataField::Name).await?;
+        let symbol = read(rpc, address, MetadataField::Symbol).await?;
+        let decimals = read(rpc, address, MetadataField::Decimals).await?;
+        let decimals = decimals.parse().map_err(|_| {
+            Error::InvalidRpcResponse(format!(
+                "decimals of {} out of range: {}",
+                address, decimals
+            ))
+        })?;
+
+        Ok(Token {
+            name,
+            symbol,
+            address: address.to_owned(),
+            chain_id,
+            decimals,
+            logo_uri: None,
+            tags: Vec::new(),
+            extensions: Default::default(),
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: Default::default(),
+        })
+    }
+}
+
+/// Reads `field` from the contract at `address`.
+async fn read<R: JsonRpc + ?Sized>(
+    rpc: &R,
+    address: &str,
+    field: MetadataField,
+) -> Result<String, Error> {
+    let data = rpc.call(address, field.selector()).await?;
+    if data.is_empty() {
+        return Err(Error::NoContract(address.to_owned()));
+    }
+    field
+        .decode(&data)
+        .ok_or_else(|| Error::InvalidRpcResponse(format!("undecodable {} of {}", field, address)))
+}
+
+/// Decodes an ABI-encoded `string`, or a `bytes32` padded with zeros as
+/// returned by legacy tokens such as MKR.
+fn decode_string(data: &[u8]) -> Option<String> {
+    if data.len() == 32 {
+        let len = data.iter().position(|&byte| byte == 0).unwrap_or(32);
+        return String::from_utf8(data[..len].to_vec()).ok();
+    }
+
+    String::from_utf8(decode_bytes(data)?.to_vec()).ok()
+}
+
+/// Decodes ABI-encoded `bytes`.
+pub(crate) fn decode_bytes(data: &[u8]) -> Option<&[u8]> {
+    let offset = word_at(data, 0)?;
+    let start = offset.checked_add(32)?;
+    data.get(start..start.checked_add(word_at(data, offset)?)?)
+}
+
+/// Decodes an ABI-encoded unsigned integer small enough for a `u64`.
+fn decode_uint(data: &[u8]) -> Option<u64> {
+    let word = data.get(..32)?;
+    if word[..24].iter().any(|&byte| byte != 0) {
+        return None;
+    }
+    Some(
+        word[24..]
+            .iter()
+            .fold(0, |n, &byte| n << 8 | u64::from(byte)),
+    )
+}
+
+/// Decodes the 32-byte word at `offset` in `data`, holding an offset or
+/// length.
+pub(crate) fn word_at(data: &[u8], offset: usize) -> Option<usize> {
+    decode_uint(data.get(offset..)?).and_then(|n| usize::try_from(n).ok())
+}
+
+/// Encodes `n` as a 32-byte word.
+pub(crate) fn word(n: usize) -> [u8; 32] {
+    let mut word = [0; 32];
+    word[24..].copy_from_slice(&(n as u64).to_be_bytes());
+    word
+}
+
+/// Encodes a call to `aggregate3` making each of `calls`, allowing them to
+/// fail.
+fn encode_aggregate3(calls: &[([u8; 20], &[u8])]) -> Vec<u8> {
+    let tuples: Vec<Vec<u8>> = calls
+        .iter()
+        .map(|(address, data)| {
+            let mut tuple = vec![0; 12];
+            tuple.extend(address);
+            tuple.extend(&word(1));
+            tuple.extend(&word(96));
+            tuple.extend(&word(data.len()));
+            tuple.extend(*data);
+            tuple.resize(128 + data.len().div_ceil(32) * 32, 0);
+            tuple
+        })
+        .collect();
+
+    let mut encoded = AGGREGATE3.to_vec();
+    encoded.extend(&word(32));
+    encoded.extend(&word(calls.len()));
+    let mut offset = 32 * tuples.len();
+    for tuple in &tuples {
+        encoded.extend(&word(offset));
+        offset += tuple.len();
+    }
+    for tuple in tuples {
+        encoded.extend(tuple);
+    }
+    encoded
+}
+
+/// Decodes the `(bool,bytes)[]` returned by `aggregate3`.
+fn decode_aggregate3(data: &[u8]) -> Option<Vec<Option<Vec<u8>>>> {
+    let array = word_at(data, 0)?;
+    let items = array.checked_add(32)?;
+    (0..word_at(data, array)?)
+        .map(|i| {
+            let tuple = items.checked_add(word_at(data, items.checked_add(i * 32)?)?)?;
+            let success = decode_uint(data.get(tuple..)?)? != 0;
+            let bytes = tuple.checked_add(word_at(data, tuple.checked_add(32)?)?)?;
+            let start = bytes.checked_add(32)?;
+            let returned = data.get(start..start.checked_add(word_at(data, bytes)?)?)?;
+            Some(success.then(|| returned.to_vec()))
+        })
+        .collect()
+}
+
+/// Parses a hex-encoded 20-byte address.
+fn parse_address(text: &str) -> Option<[u8; 20]> {
+    let bytes = decode_hex(text)?;
+    let mut address = [0; 20];
+    if bytes.len() != address.len() {
+        return None;
+    }
+    address.copy_from_slice(&bytes);
+    Some(address)
+}
+
+/// Parses a hex-encoded JSON-RPC quantity.
+fn parse_quantity(text: &str) -> Option<u64> {
+    u64::from_str_radix(text.strip_prefix("0x")?, 16).ok()
+}
+
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(2 + bytes.len() * 2);
+    hex.push_str("0x");
+    for byte in bytes {
+        hex.push_str(&format!("{:02x}", byte));
+    }
+    hex
+}
+
+pub(crate) fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let digits = text.strip_prefix("0x")?.as_bytes();
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{response, serve, serve_recording};
+
+    const ADDRESS: &str = "0x467Bccd9d29f223BcE8043b84E8C8B282827790F";
+
+    fn token(chain_id: u32) -> Token {
+        serde_json::from_value(json!({
+            "name": "Telcoin",
+            "symbol": "TEL",
+            "address": ADDRESS,
+            "chainId": chain_id,
+            "decimals": 2
+        }))
+        .unwrap()
+    }
+
+    fn token_list(tokens: Vec<Token>) -> TokenList {
+        let mut token_list: TokenList =
+            serde_json::from_str(crate::test_server::TOKEN_LIST_JSON).unwrap();
+        token_list.tokens = tokens;
+        token_list
+    }
+
+    fn result(result: &str) -> String {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "result": result }).to_string();
+        response(200, "", &body)
+    }
+
+    fn abi_string(text: &str) -> String {
+        let mut data = vec![0; 64];
+        data[31] = 32;
+        data[63] = text.len() as u8;
+        data.extend(text.as_bytes());
+        data.resize(64 + text.len().div_ceil(32) * 32, 0);
+        encode_hex(&data)
+    }
+
+    fn abi_uint(n: u8) -> String {
+        let mut data = vec![0; 32];
+        data[31] = n;
+        encode_hex(&data)
+    }
+
+    /// Encodes the `(bool,bytes)[]` returned by `aggregate3`.
+    fn abi_results(results: &[Option<String>]) -> String {
+        let tuples: Vec<Vec<u8>> = results
+            .iter()
+            .map(|result| {
+                let data = result
+                    .as_deref()
+                    .map_or(Vec::new(), |hex| decode_hex(hex).unwrap());
+                let mut tuple = word(result.is_some() as usize).to_vec();
+                tuple.extend(&word(64));
+                tuple.extend(&word(data.len()));
+                tuple.extend(data);
+                tuple
+            })
+            .collect();
+
+        let mut data = word(32).to_vec();
+        data.extend(&word(results.len()));
+        let mut offset = 32 * tuples.len();
+        for tuple in &tuples {
+            data.extend(&word(offset));
+            offset += tuple.len();
+        }
+        data.extend(tuples.concat());
+        encode_hex(&data)
+    }
+
+    #[tokio::test]
+    async fn reports_mismatched_metadata() {
+        let (url, requests) = serve_recording(vec![
+            result("0x1"),
+            result(&abi_results(&[
+                Some(abi_string("Telcoin")),
+                Some(abi_string("TELCOIN")),
+                Some(abi_uint(2)),
+                Some(abi_string("Telcoin")),
+                None,
+                Some(abi_uint(2)),
+            ])),
+        ]);
+        let rpc_urls: HashMap<u32, String> = vec![(1, url)].into_iter().collect();
+        let mut other = token(1);
+        other.address = "0xdF7837DE1F2Fa4631D716CF2502f8b230F1dcc32".to_owned();
+
+        let report = token_list(vec![token(1), other.clone()])
+            .verify_onchain(&rpc_urls)
+            .await;
+
+        requests.recv().unwrap();
+        let multicall = requests.recv().unwrap();
+        assert!(multicall.contains(MULTICALL3_ADDRESS));
+        assert!(multicall.contains(r#""data":"0x82ad56cb"#));
+        assert_eq!(
+            report,
+            OnchainReport {
+                mismatches: vec![Mismatch {
+                    token: TokenAddress::of(&token(1)),
+                    field: MetadataField::Symbol,
+                    listed: "TEL".to_owned(),
+                    onchain: "TELCOIN".to_owned(),
+                }],
+                failed_calls: vec![FailedCall {
+                    token: TokenAddress::of(&other),
+                    field: MetadataField::Symbol,
+                    reason: "call reverted".to_owned(),
+                }],
+                ..OnchainReport::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn calls_tokens_individually_without_multicall() {
+        let reverted = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": { "code": 3, "message": "execution reverted" }
+        });
+        let url = serve(vec![
+            result("0x1"),
+            // no Multicall3 on the chain
+            result("0x"),
+            result("0x"),
+            result(&abi_string("Telcoin")),
+            response(200, "", &reverted.to_string()),
+            result(&abi_uint(2)),
+        ]);
+        let rpc_urls: HashMap<u32, String> = vec![(1, url)].into_iter().collect();
+        let mut other = token(1);
+        other.address = "0xdF7837DE1F2Fa4631D716CF2502f8b230F1dcc32".to_owned();
+
+        let report = token_list(vec![token(1), other.clone()])
+            .verify_onchain(&rpc_urls)
+            .await;
+
+        assert_eq!(report.missing_contracts, vec![TokenAddress::of(&token(1))]);
+        assert_eq!(
+            report.failed_calls,
+            vec![FailedCall {
+                token: TokenAddress::of(&other),
+                field: MetadataField::Symbol,
+                reason: "JSON-RPC error 3: execution reverted".to_owned(),
+            }]
+        );
+        assert!(report.mismatches.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reports_unreachable_chains() {
+        let (url, requests) = serve_recording(vec![result("0x89")]);
+        let rpc_urls: HashMap<u32, String> = vec![(1, url)].into_iter().collect();
+
+        let report = token_list(vec![token(1), token(10)])
+            .verify_onchain(&rpc_urls)
+            .await;
+
+        assert!(requests
+            .recv()
+            .unwrap()
+            .contains(r#""method":"eth_chainId""#));
+        assert_eq!(
+            report.unreachable_chains,
+            vec![
+                UnreachableChain {
+                    chain_id: 1,
+                    reason: "RPC endpoint serves chain 137, not 1".to_owned(),
+                },
+                UnreachableChain {
+                    chain_id: 10,
+                    reason: "no RPC endpoint given".to_owned(),
+                },
+            ]
+        );
+        assert!(!report.is_clean());
+    }
+
+    #[tokio::test]
+    async fn reads_tokens_from_contracts() {
+        let mut bytes32 = b"Maker".to_vec();
+        bytes32.resize(32, 0);
+        let url = serve(vec![
+            result("0x1"),
+            result(&encode_hex(&bytes32)),
+            result(&abi_string("MKR")),
+            result(&abi_uint(18)),
+        ]);
+        let rpc = RpcClient::new(url.as_str()).unwrap();
+
+        let token = Token::from_contract(&rpc, 1, ADDRESS).await.unwrap();
+
+        assert_eq!(token.name, "Maker");
+        assert_eq!(token.symbol, "MKR");
+        assert_eq!(token.address, ADDRESS);
+        assert_eq!(token.chain_id, 1);
+        assert_eq!(token.decimals, 18);
+    }
+
+    #[tokio::test]
+    async fn rejects_contracts_on_other_chains_or_missing() {
+        let url = serve(vec![result("0x89"), result("0x1"), result("0x")]);
+        let rpc = RpcClient::new(url.as_str()).unwrap();
+
+        assert!(matches!(
+            Token::from_contract(&rpc, 1, ADDRESS).await,
+            Err(Error::ChainMismatch {
+                expected: 1,
+                actual: 137
+            })
+        ));
+        assert!(matches!(
+            Token::from_contract(&rpc, 1, ADDRESS).await,
+            Err(Error::NoContract(address)) if address == ADDRESS
+        ));
+    }
+
+    #[tokio::test]
+    async fn detects_proxies_and_accounts_without_code() {
+        let implementation = "0xdf7837de1f2fa4631d716cf2502f8b230f1dcc32";
+        let mut minimal_proxy = EIP1167_PREFIX.to_vec();
+        minimal_proxy.extend(decode_hex(implementation).unwrap());
+        minimal_proxy.extend(EIP1167_SUFFIX);
+        let zero = encode_hex(&[0; 32]);
+        let url = serve(vec![
+            result("0x1"),
+            // an EIP-1967 proxy
+            result("0x6080"),
+            result(&format!("0x{:0>64}", &implementation[2..])),
+            // an EIP-1167 proxy
+            result(&encode_hex(&minimal_proxy)),
+            // a plain contract
+            result("0x6080"),
+            result(&zero),
+            result(&zero),
+            // an EOA
+            result("0x"),
+            result("0x2a"),
+            // an empty account
+            result("0x"),
+            result("0x0"),
+        ]);
+        let rpc_urls: HashMap<u32, String> = vec![(1, url)].into_iter().collect();
+
+        let report = token_list(vec![token(1); 5])
+            .check_contracts(&rpc_urls)
+            .await;
+
+        let kinds: Vec<ContractKind> = report
+            .contracts
+            .iter()
+            .map(|check| check.kind.clone())
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ContractKind::Proxy {
+                    standard: ProxyStandard::Eip1967,
+                    implementation: implementation.to_owned(),
+                },
+                ContractKind::Proxy {
+                    standard: ProxyStandard::Eip1167,
+                    implementation: implementation.to_owned(),
+                },
+                ContractKind::Contract,
+                ContractKind::ExternallyOwned,
+                ContractKind::Empty,
+            ]
+        );
+        assert_eq!(report.flagged().count(), 2);
+        assert!(report.unreachable_chains.is_empty());
+    }
+
+    #[test]
+    fn decodes_abi_values() {
+        let name = decode_hex(&abi_string("Telcoin")).unwrap();
+
+        assert_eq!(decode_string(&name), Some("Telcoin".to_owned()));
+        assert_eq!(decode_string(&name[..70]), None);
+        assert_eq!(decode_uint(&decode_hex(&abi_uint(18)).unwrap()), Some(18));
+        assert_eq!(decode_uint(&[0xff; 32]), None);
+        assert_eq!(parse_quantity("0x89"), Some(137));
+        assert_eq!(decode_hex("0x0"), None);
+        assert_eq!(parse_address(ADDRESS).map(|address| address[0]), Some(0x46));
+        assert_eq!(parse_address("0x467B"), None);
+    }
+}
@@ -0,0 +1,133 @@
+//! Import of tokens from 1inch's token API.
+
+use std::convert::TryFrom;
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+use url::Url;
+
+use crate::{Error, ExtensionValue, Token, TokenList};
+
+impl TokenList {
+    /// Reads the response of 1inch's `tokens` endpoint for the chain
+    /// `chain_id` into a list named `name`.
+    ///
+    /// The response maps addresses to tokens, optionally wrapped in a
+    /// `tokens` object as the swap API does. Fields 1inch adds to tokens,
+    /// such as `eip2612` and `isFoT`, are kept as extensions where their
+    /// values are strings, numbers, or booleans; 1inch's tags and other
+    /// arrays and objects are dropped.
+    ///
+    /// ```
+    /// # use token_list::TokenList;
+    /// let json = br#"{
+    ///     "tokens": {
+    ///         "0x467bccd9d29f223bce8043b84e8c8b282827790f": {
+    ///             "symbol": "TEL",
+    ///             "name": "Telcoin",
+    ///             "address": "0x467bccd9d29f223bce8043b84e8c8b282827790f",
+    ///             "decimals": 2,
+    ///             "logoURI": "https://tokens.1inch.io/0x467bccd9d29f223bce8043b84e8c8b282827790f.png",
+    ///             "eip2612": false,
+    ///             "tags": ["tokens"]
+    ///         }
+    ///     }
+    /// }"#;
+    ///
+    /// let token_list = TokenList::from_oneinch_slice(json, 1, "1inch")?;
+    /// assert_eq!(token_list.tokens[0].symbol, "TEL");
+    /// assert_eq!(token_list.tokens[0].extensions["eip2612"].as_bool(), Some(false));
+    /// # Ok::<_, token_list::Error>(())
+    /// ```
+    pub fn from_oneinch_slice(bytes: &[u8], chain_id: u32, name: &str) -> Result<Self, Error> {
+        let tokens = match crate::json::from_slice(bytes)? {
+            Response::Wrapped { tokens } | Response::Bare(tokens) => tokens,
+        };
+
+        Ok(TokenList {
+            name: name.to_owned(),
+            tokens: tokens
+                .into_iter()
+                .map(|(address, token)| Token {
+                    name: token.name,
+                    symbol: token.symbol,
+                    address: token.address.unwrap_or(address),
+                    chain_id,
+                    decimals: token.decimals,
+                    logo_uri: token.logo_uri,
+                    tags: Vec::new(),
+                    extensions: token
+                        .extensions
+                        .into_iter()
+                        .filter_map(|(key, value)| {
+                            Some((key, ExtensionValue::try_from(value).ok()?))
+                        })
+                        .collect(),
+                    #[cfg(feature = "unknown-fields")]
+                    unknown_fields: Default::default(),
+                })
+                .collect(),
+            ..TokenList::default()
+        })
+    }
+}
+
+/// The response of the `tokens` endpoint.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Response {
+    Wrapped {
+        tokens: IndexMap<String, OneInchToken>,
+    },
+    Bare(IndexMap<String, OneInchToken>),
+}
+
+/// A token in 1inch's format.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OneInchToken {
+    symbol: String,
+    name: String,
+    address: Option<String>,
+    decimals: u8,
+    #[serde(rename = "logoURI")]
+    logo_uri: Option<Url>,
+    #[serde(flatten)]
+    extensions: serde_json::Map<String, serde_json::Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_bare_maps() {
+        let json = br#"{
+            "0xdf7837de1f2fa4631d716cf2502f8b230f1dcc32": {
+                "symbol": "TEL",
+                "name": "Telcoin",
+                "decimals": 2,
+                "isFoT": false,
+                "domainVersion": "1",
+                "providers": ["1inch", "CoinGecko"]
+            }
+        }"#;
+
+        let token_list = TokenList::from_oneinch_slice(json, 137, "1inch").unwrap();
+
+        let token = &token_list.tokens[0];
+        assert_eq!(token.address, "0xdf7837de1f2fa4631d716cf2502f8b230f1dcc32");
+        assert_eq!(token.chain_id, 137);
+        assert_eq!(token.logo_uri, None);
+        assert_eq!(token.extensions.len(), 2);
+        assert_eq!(token.extensions["isFoT"].as_bool(), Some(false));
+        assert_eq!(token.extensions["domainVersion"].as_str(), Some("1"));
+    }
+
+    #[test]
+    fn rejects_invalid_tokens() {
+        let json = br#"{ "tokens": { "0x0": { "symbol": "TEL", "decimals": 2 } } }"#;
+
+        assert!(TokenList::from_oneinch_slice(json, 1, "1inch").is_err());
+    }
+}
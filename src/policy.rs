@@ -0,0 +1,385 @@
+//! Allowlists and denylists applied to the tokens of a list.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Token, TokenList};
+
+/// Rules restricting which tokens a list may include, for compliance with
+/// allowlists and denylists.
+///
+/// A policy can be built in code, or read from any format serde supports:
+///
+/// ```
+/// use token_list::{Policy, TokenMatcher};
+///
+/// let policy = Policy::default()
+///     .allow_chains(vec![1, 137])
+///     .deny(TokenMatcher::Address {
+///         chain_id: None,
+///         address: "0x0000000000000000000000000000000000000bad".to_owned(),
+///     })
+///     .flag(TokenMatcher::Symbol("*USD*".to_owned()));
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Policy {
+    /// The chains tokens must be on, or `None` to allow every chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_chains: Option<Vec<u32>>,
+
+    /// The rules dropping or flagging matching tokens, in order of precedence
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<PolicyRule>,
+}
+
+impl Policy {
+    /// Restricts tokens to `chain_ids`, in addition to any chains already
+    /// allowed.
+    pub fn allow_chains<I: IntoIterator<Item = u32>>(mut self, chain_ids: I) -> Self {
+        self.allowed_chains
+            .get_or_insert_with(Vec::new)
+            .extend(chain_ids);
+        self
+    }
+
+    /// Adds a rule dropping tokens matching `matcher`.
+    pub fn deny(mut self, matcher: TokenMatcher) -> Self {
+        self.rules.push(PolicyRule {
+            matcher,
+            action: PolicyAction::Drop,
+        });
+        self
+    }
+
+    /// Adds a rule keeping tokens matching `matcher`, but reporting them.
+    pub fn flag(mut self, matcher: TokenMatcher) -> Self {
+        self.rules.push(PolicyRule {
+            matcher,
+            action: PolicyAction::Flag,
+        });
+        self
+    }
+
+    /// Applies the policy to `token_list`, returning the list without the
+    /// tokens it drops, and a report of the tokens dropped and flagged.
+    ///
+    /// Tokens on chains that aren't allowed are dropped before any rule is
+    /// applied. Otherwise, a token is dropped if any rule dropping it matches,
+    /// and flagged by the first rule flagging it that matches.
+    pub fn apply(&self, token_list: &TokenList) -> (TokenList, PolicyReport) {
+        let mut report = PolicyReport::default();
+        let mut tokens = Vec::with_capacity(token_list.tokens.len());
+
+        for (index, token) in token_list.tokens.iter().enumerate() {
+            let violation = |reason| PolicyViolation {
+                index,
+                token: token.clone(),
+                reason,
+            };
+
+            if let Some(allowed_chains) = &self.allowed_chains {
+                if !allowed_chains.contains(&token.chain_id) {
+                    report
+                        .removed
+                        .push(violation(PolicyReason::ChainNotAllowed));
+                    continue;
+                }
+            }
+            if let Some(rule) = self.first_match(token, PolicyAction::Drop) {
+                report
+                    .removed
+                    .push(violation(PolicyReason::Matched(rule.matcher.clone())));
+                continue;
+            }
+            if let Some(rule) = self.first_match(token, PolicyAction::Flag) {
+                report
+                    .flagged
+                    .push(violation(PolicyReason::Matched(rule.matcher.clone())));
+            }
+            tokens.push(token.clone());
+        }
+
+        let filtered = TokenList {
+            name: token_list.name.clone(),
+            timestamp: token_list.timestamp,
+            version: token_list.version.clone(),
+            logo_uri: token_list.logo_uri.clone(),
+            keywords: token_list.keywords.clone(),
+            tags: token_list.tags.clone(),
+            tokens,
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: token_list.unknown_fields.clone(),
+        };
+        (filtered, report)
+    }
+
+    fn first_match(&self, token: &Token, action: PolicyAction) -> Option<&PolicyRule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.action == action && rule.matcher.matches(token))
+    }
+}
+
+/// A rule of a [`Policy`].
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyRule {
+    /// The tokens the rule applies to
+    #[serde(rename = "match")]
+    pub matcher: TokenMatcher,
+
+    /// What happens to matching tokens
+    pub action: PolicyAction,
+}
+
+/// What happens to tokens matching a [`PolicyRule`].
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum PolicyAction {
+    /// The token is removed from the list
+    Drop,
+
+    /// The token is kept, but reported
+    Flag,
+}
+
+/// Selects tokens for a [`PolicyRule`].
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum TokenMatcher {
+    /// Tokens on the chain with this ID
+    Chain(u32),
+
+    /// Tokens at an address, compared case-insensitively
+    #[serde(rename_all = "camelCase")]
+    Address {
+        /// The chain of the address, or `None` to match it on every chain
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        chain_id: Option<u32>,
+
+        /// The address
+        address: String,
+    },
+
+    /// Tokens whose symbols match a case-insensitive pattern, in which `*`
+    /// matches any characters and `?` any single character
+    Symbol(String),
+}
+
+impl TokenMatcher {
+    /// Returns `true` if `token` matches.
+    pub fn matches(&self, token: &Token) -> bool {
+        match self {
+            TokenMatcher::Chain(chain_id) => token.chain_id == *chain_id,
+            TokenMatcher::Address { chain_id, address } => {
+                chain_id.iter().all(|&chain_id| token.chain_id == chain_id)
+                    && token.address.eq_ignore_ascii_case(address)
+            }
+            TokenMatcher::Symbol(pattern) => glob_match(
+                &pattern.to_lowercase().chars().collect::<Vec<_>>(),
+                &token.symbol.to_lowercase().chars().collect::<Vec<_>>(),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for TokenMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenMatcher::Chain(chain_id) => write!(f, "chain {}", chain_id),
+            TokenMatcher::Address {
+                chain_id: Some(chain_id),
+                address,
+            } => write!(f, "address {} on chain {}", address, chain_id),
+            TokenMatcher::Address {
+                chain_id: None,
+                address,
+            } => write!(f, "address {}", address),
+            TokenMatcher::Symbol(pattern) => write!(f, "symbol `{}`", pattern),
+        }
+    }
+}
+
+/// The tokens dropped and flagged by [`Policy::apply`], for audits.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct PolicyReport {
+    /// The tokens removed from the list, in list order
+    pub removed: Vec<PolicyViolation>,
+
+    /// The tokens kept in the list but flagged, in list order
+    pub flagged: Vec<PolicyViolation>,
+}
+
+impl PolicyReport {
+    /// Returns `true` if no token was removed or flagged.
+    pub fn is_clean(&self) -> bool {
+        self.removed.is_empty() && self.flagged.is_empty()
+    }
+}
+
+/// A token dropped or flagged by a [`Policy`].
+#[derive(PartialEq, Clone, Debug)]
+pub struct PolicyViolation {
+    /// The index of the token in the original list
+    pub index: usize,
+
+    /// The token
+    pub token: Token,
+
+    /// Why the policy applied to it
+    pub reason: PolicyReason,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({} on chain {}): {}",
+            self.token.symbol, self.token.address, self.token.chain_id, self.reason
+        )
+    }
+}
+
+/// Why a [`Policy`] applied to a token.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[non_exhaustive]
+pub enum PolicyReason {
+    /// The token's chain isn't allowed
+    ChainNotAllowed,
+
+    /// The token matched a rule
+    Matched(TokenMatcher),
+}
+
+impl fmt::Display for PolicyReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyReason::ChainNotAllowed => f.write_str("chain is not allowed"),
+            PolicyReason::Matched(matcher) => write!(f, "matched {}", matcher),
+        }
+    }
+}
+
+/// Matches `text` against a pattern of literal characters, `*`, and `?`.
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        Some(('?', rest)) => !text.is_empty() && glob_match(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && glob_match(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    fn token_list() -> TokenList {
+        let mut token_list = fixtures::full();
+        let token = token_list.tokens[0].clone();
+        token_list.tokens = vec![
+            Token {
+                symbol: "USDC".to_owned(),
+                address: "0x000000000000000000000000000000000000000A".to_owned(),
+                ..token.clone()
+            },
+            Token {
+                symbol: "BAD".to_owned(),
+                address: "0x000000000000000000000000000000000000000B".to_owned(),
+                ..token.clone()
+            },
+            Token {
+                symbol: "WETH".to_owned(),
+                chain_id: 56,
+                ..token
+            },
+        ];
+        token_list
+    }
+
+    #[test]
+    fn drops_and_flags_tokens() {
+        let token_list = token_list();
+        let chain_id = token_list.tokens[0].chain_id;
+        let policy = Policy::default()
+            .allow_chains(vec![chain_id])
+            .deny(TokenMatcher::Address {
+                chain_id: Some(chain_id),
+                address: "0x000000000000000000000000000000000000000b".to_owned(),
+            })
+            .flag(TokenMatcher::Symbol("*usd?".to_owned()));
+
+        let (filtered, report) = policy.apply(&token_list);
+
+        assert_eq!(filtered.tokens, vec![token_list.tokens[0].clone()]);
+        assert_eq!(filtered.name, token_list.name);
+        assert_eq!(report.removed.len(), 2);
+        assert_eq!(report.removed[0].index, 1);
+        assert!(matches!(
+            report.removed[0].reason,
+            PolicyReason::Matched(TokenMatcher::Address { .. })
+        ));
+        assert_eq!(report.removed[1].index, 2);
+        assert_eq!(report.removed[1].reason, PolicyReason::ChainNotAllowed);
+        assert_eq!(report.flagged.len(), 1);
+        assert_eq!(report.flagged[0].token.symbol, "USDC");
+    }
+
+    #[test]
+    fn drops_take_precedence_over_flags() {
+        let policy = Policy::default()
+            .flag(TokenMatcher::Symbol("*".to_owned()))
+            .deny(TokenMatcher::Symbol("BAD".to_owned()));
+
+        let (filtered, report) = policy.apply(&token_list());
+
+        assert_eq!(filtered.tokens.len(), 2);
+        assert_eq!(report.removed.len(), 1);
+        assert_eq!(report.flagged.len(), 2);
+    }
+
+    #[test]
+    fn deserializes_policies() {
+        let policy: Policy = serde_json::from_str(
+            r#"{
+                "allowedChains": [1],
+                "rules": [
+                    { "match": { "chain": 56 }, "action": "drop" },
+                    { "match": { "address": { "address": "0xabc" } }, "action": "flag" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            policy,
+            Policy::default()
+                .allow_chains(vec![1])
+                .deny(TokenMatcher::Chain(56))
+                .flag(TokenMatcher::Address {
+                    chain_id: None,
+                    address: "0xabc".to_owned()
+                })
+        );
+    }
+
+    #[test]
+    fn matches_globs() {
+        let glob = |pattern: &str, text: &str| {
+            glob_match(
+                &pattern.chars().collect::<Vec<_>>(),
+                &text.chars().collect::<Vec<_>>(),
+            )
+        };
+
+        assert!(glob("*", ""));
+        assert!(glob("us*", "usdc"));
+        assert!(glob("*d?", "usdc"));
+        assert!(!glob("*d?", "usd"));
+        assert!(!glob("usd", "usdc"));
+    }
+}
@@ -0,0 +1,387 @@
+//! Storing token lists in Postgres, with [sqlx](https://docs.rs/sqlx).
+
+use std::convert::TryFrom;
+
+use chrono::{DateTime, Utc};
+use semver::Version;
+use sha2::{Digest, Sha256};
+use sqlx::postgres::{PgPool, PgRow};
+use sqlx::Row;
+use url::Url;
+
+use crate::{Error, Tag, Token, TokenList};
+
+/// Token lists stored in Postgres, in the schema of the migrations in
+/// `migrations/postgres`, which are run when the store is created.
+///
+/// As with [`SqliteStore`](crate::SqliteStore), a list is identified by its
+/// name and version, storing a version that is already stored replaces it, and
+/// tokens are shared between the versions they are unchanged in. Tokens are
+/// upserted in a single statement keyed by chain ID and address, with their
+/// extensions stored as `jsonb`, which doesn't keep the order of their keys.
+#[derive(Clone, Debug)]
+pub struct PostgresStore {
+    pool: PgPool,
+}
+
+impl PostgresStore {
+    /// Connects to the database at `url`, running any pending migrations.
+    pub async fn connect(url: &str) -> Result<Self, Error> {
+        Self::new(PgPool::connect(url).await?).await
+    }
+
+    /// Stores lists through `pool`, running any pending migrations.
+    pub async fn new(pool: PgPool) -> Result<Self, Error> {
+        sqlx::migrate!("migrations/postgres")
+            .run(&pool)
+            .await
+            .map_err(|err| sqlx::Error::Migrate(Box::new(err)))?;
+        Ok(PostgresStore { pool })
+    }
+
+    /// Returns the underlying pool, e.g. to query tokens with SQL.
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Stores `token_list`, replacing any list with the same name and version.
+    pub async fn store(&self, token_list: &TokenList) -> Result<(), Error> {
+        let version = &token_list.version;
+        let mut transaction = self.pool.begin().await?;
+
+        let list_id: i64 = sqlx::query_scalar(
+            "INSERT INTO lists (name, version, version_key, timestamp, logo_uri, keywords)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (name, version) DO UPDATE SET
+                 timestamp = excluded.timestamp,
+                 logo_uri = excluded.logo_uri,
+                 keywords = excluded.keywords
+             RETURNING id",
+        )
+        .bind(&token_list.name)
+        .bind(version.to_string())
+        .bind(crate::version::sort_key(version))
+        .bind(crate::timestamp::to_chrono(&token_list.timestamp))
+        .bind(token_list.logo_uri.as_ref().map(Url::as_str))
+        .bind(&token_list.keywords)
+        .fetch_one(&mut *transaction)
+        .await?;
+        for table in &["tags", "list_tokens"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE list_id = $1", table))
+                .bind(list_id)
+                .execute(&mut *transaction)
+                .await?;
+        }
+
+        let (ids, tags): (Vec<_>, Vec<_>) = token_list.tags.iter().unzip();
+        sqlx::query(
+            "INSERT INTO tags (list_id, position, id, name, description)
+             SELECT $1, position - 1, id, name, description
+             FROM UNNEST($2::text[], $3::text[], $4::text[])
+                 WITH ORDINALITY AS tag (id, name, description, position)",
+        )
+        .bind(list_id)
+        .bind(ids)
+        .bind(tags.iter().map(|tag| &tag.name).collect::<Vec<_>>())
+        .bind(tags.iter().map(|tag| &tag.description).collect::<Vec<_>>())
+        .execute(&mut *transaction)
+        .await?;
+
+        let tokens = TokenColumns::new(&token_list.tokens)?;
+        sqlx::query(
+            "INSERT INTO tokens (list_name, chain_id, address, name, symbol, decimals, logo_uri,
+                 tags, extensions, content_hash)
+             SELECT $1, chain_id, address, name, symbol, decimals, logo_uri,
+                 ARRAY(SELECT jsonb_array_elements_text(tags)), extensions, content_hash
+             FROM UNNEST($2::bigint[], $3::text[], $4::text[], $5::text[], $6::integer[],
+                 $7::text[], $8::jsonb[], $9::jsonb[], $10::bytea[])
+                 AS token (chain_id, address, name, symbol, decimals, logo_uri, tags,
+                     extensions, content_hash)
+             ON CONFLICT (list_name, chain_id, address, content_hash) DO NOTHING",
+        )
+        .bind(&token_list.name)
+        .bind(&tokens.chain_ids)
+        .bind(&tokens.addresses)
+        .bind(&tokens.names)
+        .bind(&tokens.symbols)
+        .bind(&tokens.decimals)
+        .bind(&tokens.logo_uris)
+        .bind(&tokens.tags)
+        .bind(&tokens.extensions)
+        .bind(&tokens.content_hashes)
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query(
+            "INSERT INTO list_tokens (list_id, position, token_id)
+             SELECT $1, token.position - 1, tokens.id
+             FROM UNNEST($3::bigint[], $4::text[], $5::bytea[])
+                 WITH ORDINALITY AS token (chain_id, address, content_hash, position)
+             JOIN tokens ON tokens.list_name = $2
+                 AND tokens.chain_id = token.chain_id
+                 AND tokens.address = token.address
+                 AND tokens.content_hash = token.content_hash",
+        )
+        .bind(list_id)
+        .bind(&token_list.name)
+        .bind(&tokens.chain_ids)
+        .bind(&tokens.addresses)
+        .bind(&tokens.content_hashes)
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Loads the list named `name` at `version`, if it's stored.
+    pub async fn load(&self, name: &str, version: &Version) -> Result<Option<TokenList>, Error> {
+        let row = sqlx::query("SELECT * FROM lists WHERE name = $1 AND version = $2")
+            .bind(name)
+            .bind(version.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+        match row {
+            Some(row) => Ok(Some(self.load_list(row).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Loads the latest version of the list named `name`, if any is stored.
+    pub async fn load_latest(&self, name: &str) -> Result<Option<TokenList>, Error> {
+        let row = sqlx::query(
+            "SELECT * FROM lists WHERE name = $1
+             ORDER BY version_key DESC, id DESC LIMIT 1",
+        )
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+        match row {
+            Some(row) => Ok(Some(self.load_list(row).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the stored versions of the list named `name`, oldest first.
+    pub async fn versions(&self, name: &str) -> Result<Vec<Version>, Error> {
+        let versions: Vec<String> = sqlx::query_scalar(
+            "SELECT version FROM lists WHERE name = $1 ORDER BY version_key, id",
+        )
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await?;
+        versions
+            .iter()
+            .map(|version| parse_version(version))
+            .collect()
+    }
+
+    async fn load_list(&self, row: PgRow) -> Result<TokenList, Error> {
+        let list_id: i64 = row.try_get("id")?;
+
+        let tags: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT id, name, description FROM tags WHERE list_id = $1 ORDER BY position",
+        )
+        .bind(list_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let tokens = sqlx::query(
+            "SELECT tokens.name, tokens.symbol, tokens.address, tokens.chain_id,
+                 tokens.decimals, tokens.logo_uri, tokens.tags, tokens.extensions::text
+             FROM list_tokens JOIN tokens ON tokens.id = list_tokens.token_id
+             WHERE list_tokens.list_id = $1 ORDER BY list_tokens.position",
+        )
+        .bind(list_id)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(token_from_row)
+        .collect::<Result<_, _>>()?;
+
+        Ok(TokenList {
+            name: row.try_get("name")?,
+            timestamp: crate::timestamp::from_chrono(
+                row.try_get::<DateTime<Utc>, _>("timestamp")?.into(),
+            )
+            .ok_or_else(|| decode_error("timestamp is out of range".into()))?,
+            version: parse_version(row.try_get("version")?)?,
+            logo_uri: parse_uri(row.try_get("logo_uri")?)?,
+            keywords: row.try_get("keywords")?,
+            tags: tags
+                .into_iter()
+                .map(|(id, name, description)| (id, Tag { name, description }))
+                .collect(),
+            tokens,
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: Default::default(),
+        })
+    }
+}
+
+/// The columns of tokens, for binding to `UNNEST`.
+struct TokenColumns {
+    chain_ids: Vec<i64>,
+    addresses: Vec<String>,
+    names: Vec<String>,
+    symbols: Vec<String>,
+    decimals: Vec<i32>,
+    logo_uris: Vec<Option<String>>,
+    tags: Vec<String>,
+    extensions: Vec<String>,
+    content_hashes: Vec<Vec<u8>>,
+}
+
+impl TokenColumns {
+    fn new(tokens: &[Token]) -> Result<Self, Error> {
+        let mut columns = TokenColumns {
+            chain_ids: Vec::with_capacity(tokens.len()),
+            addresses: Vec::with_capacity(tokens.len()),
+            names: Vec::with_capacity(tokens.len()),
+            symbols: Vec::with_capacity(tokens.len()),
+            decimals: Vec::with_capacity(tokens.len()),
+            logo_uris: Vec::with_capacity(tokens.len()),
+            tags: Vec::with_capacity(tokens.len()),
+            extensions: Vec::with_capacity(tokens.len()),
+            content_hashes: Vec::with_capacity(tokens.len()),
+        };
+        for token in tokens {
+            columns.chain_ids.push(token.chain_id.into());
+            columns.addresses.push(token.address.clone());
+            columns.names.push(token.name.clone());
+            columns.symbols.push(token.symbol.clone());
+            columns.decimals.push(token.decimals.into());
+            columns
+                .logo_uris
+                .push(token.logo_uri.as_ref().map(Url::to_string));
+            columns.tags.push(serde_json::to_string(&token.tags)?);
+            columns
+                .extensions
+                .push(serde_json::to_string(&token.extensions)?);
+            columns
+                .content_hashes
+                .push(Sha256::digest(serde_json::to_vec(&serde_json::to_value(token)?)?).to_vec());
+        }
+        Ok(columns)
+    }
+}
+
+fn token_from_row(row: &PgRow) -> Result<Token, Error> {
+    let chain_id: i64 = row.try_get("chain_id")?;
+    let decimals: i32 = row.try_get("decimals")?;
+    let extensions: String = row.try_get("extensions")?;
+    Ok(Token {
+        name: row.try_get("name")?,
+        symbol: row.try_get("symbol")?,
+        address: row.try_get("address")?,
+        chain_id: u32::try_from(chain_id).map_err(|err| decode_error(Box::new(err)))?,
+        decimals: u8::try_from(decimals).map_err(|err| decode_error(Box::new(err)))?,
+        logo_uri: parse_uri(row.try_get("logo_uri")?)?,
+        tags: row.try_get("tags")?,
+        extensions: serde_json::from_str(&extensions)?,
+        #[cfg(feature = "unknown-fields")]
+        unknown_fields: Default::default(),
+    })
+}
+
+fn parse_version(version: &str) -> Result<Version, Error> {
+    Version::parse(version).map_err(|err| decode_error(Box::new(err)))
+}
+
+fn parse_uri(uri: Option<String>) -> Result<Option<Url>, Error> {
+    uri.map(|uri| Url::parse(&uri).map_err(|err| decode_error(Box::new(err))))
+        .transpose()
+}
+
+fn decode_error(err: Box<dyn std::error::Error + Send + Sync>) -> Error {
+    Error::Postgres(sqlx::Error::Decode(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    /// Connects to the database at `POSTGRES_URL`, or returns `None` to skip
+    /// the test if it isn't set.
+    async fn store() -> Option<PostgresStore> {
+        let url = std::env::var("POSTGRES_URL").ok()?;
+        let store = PostgresStore::connect(&url).await.unwrap();
+        sqlx::query("TRUNCATE lists, tokens CASCADE")
+            .execute(store.pool())
+            .await
+            .unwrap();
+        Some(store)
+    }
+
+    async fn count(store: &PostgresStore, table: &str) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+            .fetch_one(store.pool())
+            .await
+            .unwrap()
+    }
+
+    // a single test, so that tests sharing the database don't interfere
+    #[tokio::test]
+    async fn stores_lists() {
+        let store = match store().await {
+            Some(store) => store,
+            None => return,
+        };
+        let first = fixtures::full();
+        store.store(&first).await.unwrap();
+        assert_eq!(
+            store.load(&first.name, &first.version).await.unwrap(),
+            Some(first.clone())
+        );
+        let tokens = count(&store, "tokens").await;
+
+        let mut second = first.clone();
+        second.version = Version::new(1, 0, 0);
+        second.tokens[0].name = "Renamed".to_owned();
+        store.store(&second).await.unwrap();
+        assert_eq!(count(&store, "tokens").await, tokens + 1);
+        assert_eq!(
+            store.versions(&first.name).await.unwrap(),
+            vec![first.version.clone(), second.version.clone()]
+        );
+        assert_eq!(
+            store.load_latest(&first.name).await.unwrap(),
+            Some(second.clone())
+        );
+
+        second.tokens.pop();
+        store.store(&second).await.unwrap();
+        assert_eq!(count(&store, "lists").await, 2);
+        assert_eq!(
+            store.load_latest(&first.name).await.unwrap(),
+            Some(second.clone())
+        );
+        assert_eq!(
+            store.load(&first.name, &first.version).await.unwrap(),
+            Some(first.clone())
+        );
+
+        let mut release_candidate = first.clone();
+        release_candidate.version = Version::parse("1.0.0-rc.1+build.5").unwrap();
+        store.store(&release_candidate).await.unwrap();
+        assert_eq!(count(&store, "lists").await, 3);
+        assert_eq!(
+            store.versions(&first.name).await.unwrap(),
+            vec![
+                first.version.clone(),
+                release_candidate.version.clone(),
+                second.version.clone()
+            ]
+        );
+        assert_eq!(
+            store
+                .load(&first.name, &release_candidate.version)
+                .await
+                .unwrap()
+                .unwrap()
+                .version
+                .to_string(),
+            "1.0.0-rc.1+build.5"
+        );
+        assert_eq!(store.load_latest(&first.name).await.unwrap(), Some(second));
+    }
+}
@@ -0,0 +1,364 @@
+//! Protobuf messages mirroring the token list model.
+//!
+//! These types match the schema in `proto/token_list.proto`, and convert to
+//! and from the types at the root of this crate; e.g. to serve lists from a
+//! gRPC service.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use url::Url;
+
+use crate::Error;
+
+/// A list of Ethereum token metadata.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TokenList {
+    /// The name of the token list
+    #[prost(string, tag = "1")]
+    pub name: String,
+
+    /// The timestamp of this list version, in RFC 3339 format
+    #[prost(string, tag = "2")]
+    pub timestamp: String,
+
+    /// The version of the list
+    #[prost(message, optional, tag = "3")]
+    pub version: Option<Version>,
+
+    /// A URI for the logo of the token list
+    #[prost(string, optional, tag = "4")]
+    pub logo_uri: Option<String>,
+
+    /// Keywords associated with the contents of the list
+    #[prost(string, repeated, tag = "5")]
+    pub keywords: Vec<String>,
+
+    /// A mapping of tag identifiers to their name and description
+    #[prost(map = "string, message", tag = "6")]
+    pub tags: HashMap<String, Tag>,
+
+    /// The list of tokens included in the list
+    #[prost(message, repeated, tag = "7")]
+    pub tokens: Vec<Token>,
+}
+
+/// The version of a token list.
+#[derive(Clone, Copy, PartialEq, Eq, prost::Message)]
+pub struct Version {
+    /// The major version
+    #[prost(uint64, tag = "1")]
+    pub major: u64,
+
+    /// The minor version
+    #[prost(uint64, tag = "2")]
+    pub minor: u64,
+
+    /// The patch version
+    #[prost(uint64, tag = "3")]
+    pub patch: u64,
+}
+
+/// Definition of a tag that can be associated with a token.
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct Tag {
+    /// The name of the tag
+    #[prost(string, tag = "1")]
+    pub name: String,
+
+    /// A user-friendly description of the tag
+    #[prost(string, tag = "2")]
+    pub description: String,
+}
+
+/// Metadata for a single token in a token list.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Token {
+    /// The name of the token
+    #[prost(string, tag = "1")]
+    pub name: String,
+
+    /// The symbol for the token
+    #[prost(string, tag = "2")]
+    pub symbol: String,
+
+    /// The checksummed address of the token on the specified chain ID
+    #[prost(string, tag = "3")]
+    pub address: String,
+
+    /// The chain ID of the Ethereum network where this token is deployed
+    #[prost(uint32, tag = "4")]
+    pub chain_id: u32,
+
+    /// The number of decimals for the token balance
+    #[prost(uint32, tag = "5")]
+    pub decimals: u32,
+
+    /// A URI to the token logo asset
+    #[prost(string, optional, tag = "6")]
+    pub logo_uri: Option<String>,
+
+    /// Tag identifiers associated with the token
+    #[prost(string, repeated, tag = "7")]
+    pub tags: Vec<String>,
+
+    /// Arbitrary or vendor-specific token metadata
+    #[prost(map = "string, message", tag = "8")]
+    pub extensions: HashMap<String, ExtensionValue>,
+}
+
+/// The value for a user-defined extension; no `kind` represents `null`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExtensionValue {
+    /// The value
+    #[prost(oneof = "extension_value::Kind", tags = "1, 2, 3, 4, 5")]
+    pub kind: Option<extension_value::Kind>,
+}
+
+/// Nested types of [`ExtensionValue`].
+pub mod extension_value {
+    /// The value of an extension.
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Kind {
+        /// A string
+        #[prost(string, tag = "1")]
+        StringValue(String),
+
+        /// An integer
+        #[prost(int64, tag = "2")]
+        IntegerValue(i64),
+
+        /// A floating point number
+        #[prost(double, tag = "3")]
+        FloatValue(f64),
+
+        /// A boolean
+        #[prost(bool, tag = "4")]
+        BoolValue(bool),
+
+        /// The decimal digits of a non-negative integer too large for an
+        /// `IntegerValue`
+        #[prost(string, tag = "5")]
+        BigIntegerValue(String),
+    }
+}
+
+impl From<crate::TokenList> for TokenList {
+    fn from(token_list: crate::TokenList) -> Self {
+        TokenList {
+            name: token_list.name,
+            timestamp: crate::timestamp::to_rfc3339(&token_list.timestamp),
+            version: Some(Version {
+                major: token_list.version.major,
+                minor: token_list.version.minor,
+                patch: token_list.version.patch,
+            }),
+            logo_uri: token_list.logo_uri.map(String::from),
+            keywords: token_list.keywords,
+            tags: token_list
+                .tags
+                .into_iter()
+                .map(|(id, tag)| (id, tag.into()))
+                .collect(),
+            tokens: token_list.tokens.into_iter().map(Token::from).collect(),
+        }
+    }
+}
+
+impl TryFrom<TokenList> for crate::TokenList {
+    type Error = Error;
+
+    fn try_from(token_list: TokenList) -> Result<Self, Error> {
+        let version = token_list
+            .version
+            .ok_or_else(|| invalid("missing version"))?;
+
+        Ok(crate::TokenList {
+            name: token_list.name,
+            timestamp: crate::timestamp::parse_rfc3339(&token_list.timestamp)
+                .ok_or_else(|| invalid("invalid timestamp"))?,
+            version: semver::Version::new(version.major, version.minor, version.patch),
+            logo_uri: parse_uri(token_list.logo_uri)?,
+            keywords: token_list.keywords,
+            tags: token_list
+                .tags
+                .into_iter()
+                .map(|(id, tag)| (id, tag.into()))
+                .collect(),
+            tokens: token_list
+                .tokens
+                .into_iter()
+                .map(crate::Token::try_from)
+                .collect::<Result<_, _>>()?,
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: Default::default(),
+        })
+    }
+}
+
+impl From<crate::Tag> for Tag {
+    fn from(tag: crate::Tag) -> Self {
+        Tag {
+            name: tag.name,
+            description: tag.description,
+        }
+    }
+}
+
+impl From<Tag> for crate::Tag {
+    fn from(tag: Tag) -> Self {
+        crate::Tag {
+            name: tag.name,
+            description: tag.description,
+        }
+    }
+}
+
+impl From<crate::Token> for Token {
+    fn from(token: crate::Token) -> Self {
+        Token {
+            name: token.name,
+            symbol: token.symbol,
+            address: token.address,
+            chain_id: token.chain_id,
+            decimals: token.decimals.into(),
+            logo_uri: token.logo_uri.map(String::from),
+            tags: token.tags,
+            extensions: token
+                .extensions
+                .into_iter()
+                .map(|(key, value)| (key, value.into()))
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<Token> for crate::Token {
+    type Error = Error;
+
+    fn try_from(token: Token) -> Result<Self, Error> {
+        Ok(crate::Token {
+            decimals: u8::try_from(token.decimals).map_err(|_| invalid("decimals out of range"))?,
+            logo_uri: parse_uri(token.logo_uri)?,
+            name: token.name,
+            symbol: token.symbol,
+            address: token.address,
+            chain_id: token.chain_id,
+            tags: token.tags,
+            extensions: token
+                .extensions
+                .into_iter()
+                .map(|(key, value)| (key, value.into()))
+                .collect(),
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: Default::default(),
+        })
+    }
+}
+
+impl From<crate::ExtensionValue> for ExtensionValue {
+    fn from(value: crate::ExtensionValue) -> Self {
+        use extension_value::Kind;
+
+        let kind = match value {
+            crate::ExtensionValue::String(value) => Some(Kind::StringValue(value)),
+            crate::ExtensionValue::Number(crate::Number::Integer(value)) => {
+                Some(Kind::IntegerValue(value))
+            }
+            crate::ExtensionValue::Number(crate::Number::BigInteger(value)) => {
+                Some(Kind::BigIntegerValue(value.to_string()))
+            }
+            crate::ExtensionValue::Number(crate::Number::Float(value)) => {
+                Some(Kind::FloatValue(value))
+            }
+            crate::ExtensionValue::Boolean(value) => Some(Kind::BoolValue(value)),
+            crate::ExtensionValue::Null => None,
+        };
+
+        ExtensionValue { kind }
+    }
+}
+
+impl From<ExtensionValue> for crate::ExtensionValue {
+    fn from(value: ExtensionValue) -> Self {
+        use extension_value::Kind;
+
+        let kind = match value.kind {
+            Some(kind) => kind,
+            None => return crate::ExtensionValue::Null,
+        };
+        match kind {
+            Kind::StringValue(value) => crate::ExtensionValue::String(value),
+            Kind::IntegerValue(value) => {
+                crate::ExtensionValue::Number(crate::Number::Integer(value))
+            }
+            Kind::FloatValue(value) => crate::ExtensionValue::Number(crate::Number::Float(value)),
+            Kind::BoolValue(value) => crate::ExtensionValue::Boolean(value),
+            // kept as a string if it isn't a valid integer
+            Kind::BigIntegerValue(value) => match value.parse() {
+                Ok(value) => crate::ExtensionValue::Number(crate::Number::BigInteger(value)),
+                Err(_) => crate::ExtensionValue::String(value),
+            },
+        }
+    }
+}
+
+fn parse_uri(uri: Option<String>) -> Result<Option<Url>, Error> {
+    uri.map(|uri| Url::parse(&uri))
+        .transpose()
+        .map_err(|_| invalid("invalid logo URI"))
+}
+
+fn invalid(message: &str) -> Error {
+    Error::InvalidProto(message.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    fn full() -> crate::TokenList {
+        fixtures::full()
+    }
+
+    #[test]
+    fn round_trips_through_protobuf() {
+        let mut token_list = full();
+        token_list.tokens[0]
+            .extensions
+            .insert("note".to_owned(), crate::ExtensionValue::Null);
+        token_list.tokens[0].extensions.insert(
+            "totalSupply".to_owned(),
+            crate::ExtensionValue::Number(crate::Number::BigInteger(u128::MAX)),
+        );
+
+        let bytes = TokenList::from(token_list.clone()).encode_to_vec();
+        let decoded = TokenList::decode(&bytes[..]).unwrap();
+
+        assert_eq!(crate::TokenList::try_from(decoded).unwrap(), token_list);
+    }
+
+    #[test]
+    fn rejects_out_of_range_decimals() {
+        let mut token_list = TokenList::from(full());
+        token_list.tokens[0].decimals = 70_000;
+
+        match crate::TokenList::try_from(token_list) {
+            Err(Error::InvalidProto(message)) => assert_eq!(message, "decimals out of range"),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn requires_version() {
+        let token_list = TokenList {
+            version: None,
+            ..TokenList::from(full())
+        };
+
+        assert!(crate::TokenList::try_from(token_list).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! Per-host rate limiting of fetches.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+// `std::time::Instant` panics on wasm32
+use instant::Instant;
+
+use crate::{Error, Fetched, Fetcher};
+
+/// A token bucket limit on the rate of requests made to each host.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RateLimit {
+    /// The number of requests allowed per `per`, once the burst is used up
+    pub requests: u32,
+
+    /// The period over which `requests` are allowed
+    pub per: Duration,
+
+    /// The number of requests that can be made at once before the limit
+    /// applies
+    pub burst: u32,
+
+    /// What to do with requests made while the limit is exceeded
+    pub mode: RateLimitMode,
+}
+
+impl Default for RateLimit {
+    /// Allows 10 requests per second to each host, in bursts of up to 10,
+    /// queueing requests beyond that.
+    fn default() -> Self {
+        RateLimit {
+            requests: 10,
+            per: Duration::from_secs(1),
+            burst: 10,
+            mode: RateLimitMode::Wait,
+        }
+    }
+}
+
+impl RateLimit {
+    /// Returns the time it takes for the bucket to regain one request.
+    fn interval(&self) -> Duration {
+        self.per / self.requests.max(1)
+    }
+}
+
+/// How a [`RateLimitedFetcher`] handles requests made while the limit is
+/// exceeded.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RateLimitMode {
+    /// Queue the request until the limit allows it, in the order requests
+    /// were made.
+    Wait,
+
+    /// Fail the request immediately with [`Error::RateLimited`].
+    Reject,
+}
+
+/// A [`Fetcher`] that limits the rate of requests made to each host by
+/// another fetcher.
+///
+/// Each host has its own token bucket, so polling many lists from one gateway
+/// does not delay fetches from others.
+pub struct RateLimitedFetcher {
+    fetcher: Arc<dyn Fetcher>,
+    limit: RateLimit,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// When the bucket will next be full; requests reserve time from it
+    full_at: Instant,
+}
+
+impl RateLimitedFetcher {
+    /// Creates a fetcher that makes requests with `fetcher`, subject to
+    /// `limit`.
+    pub fn new(fetcher: Arc<dyn Fetcher>, limit: RateLimit) -> Self {
+        RateLimitedFetcher {
+            fetcher,
+            limit,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the limit requests are subject to.
+    pub fn limit(&self) -> &RateLimit {
+        &self.limit
+    }
+
+    /// Waits until the limit allows a request to `uri`.
+    async fn acquire(&self, uri: &str) -> Result<(), Error> {
+        let host = host(uri);
+        let delay = self.reserve(&host)?;
+        if delay > Duration::from_millis(0) {
+            futures_timer::Delay::new(delay).await;
+        }
+        Ok(())
+    }
+
+    /// Reserves a request to `host`, returning how long the caller must wait
+    /// before making it.
+    fn reserve(&self, host: &str) -> Result<Duration, Error> {
+        let interval = self.limit.interval();
+        let burst = interval * self.limit.burst.max(1);
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets
+            .entry(host.to_owned())
+            .or_insert(Bucket { full_at: now });
+
+        // time already reserved beyond the burst must be waited out
+        let full_at = bucket.full_at.max(now) + interval;
+        let delay = full_at.saturating_duration_since(now).saturating_sub(burst);
+
+        if delay > Duration::from_millis(0) && self.limit.mode == RateLimitMode::Reject {
+            return Err(Error::RateLimited {
+                host: host.to_owned(),
+            });
+        }
+
+        bucket.full_at = full_at;
+        Ok(delay)
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Fetcher for RateLimitedFetcher {
+    async fn fetch(&self, uri: &str) -> Result<Vec<u8>, Error> {
+        self.acquire(uri).await?;
+        self.fetcher.fetch(uri).await
+    }
+
+    async fn fetch_if_modified(&self, uri: &str, etag: Option<&str>) -> Result<Fetched, Error> {
+        self.acquire(uri).await?;
+        self.fetcher.fetch_if_modified(uri, etag).await
+    }
+}
+
+impl fmt::Debug for RateLimitedFetcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimitedFetcher")
+            .field("limit", &self.limit)
+            .field("buckets", &self.buckets)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the host requests to `uri` are limited under.
+fn host(uri: &str) -> String {
+    url::Url::parse(uri)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_owned))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockFetcher, MockResponse};
+
+    fn fetcher(mode: RateLimitMode) -> RateLimitedFetcher {
+        let mock = MockFetcher::new();
+        for uri in [
+            "https://a.example/1.json",
+            "https://a.example/2.json",
+            "https://a.example/3.json",
+            "https://a.example/list.json",
+            "https://b.example/1.json",
+        ] {
+            mock.respond(uri, MockResponse::body(""));
+        }
+        RateLimitedFetcher::new(
+            Arc::new(mock),
+            RateLimit {
+                requests: 1,
+                per: Duration::from_millis(50),
+                burst: 2,
+                mode,
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn rejects_requests_beyond_burst() {
+        let fetcher = fetcher(RateLimitMode::Reject);
+
+        fetcher.fetch("https://a.example/1.json").await.unwrap();
+        fetcher.fetch("https://a.example/2.json").await.unwrap();
+        match fetcher.fetch("https://a.example/3.json").await {
+            Err(Error::RateLimited { host }) => assert_eq!(host, "a.example"),
+            res => panic!("unexpected result: {:?}", res),
+        }
+
+        // other hosts have their own bucket
+        fetcher.fetch("https://b.example/1.json").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn queues_requests_beyond_burst() {
+        let fetcher = fetcher(RateLimitMode::Wait);
+        let start = Instant::now();
+
+        for _ in 0..4 {
+            fetcher.fetch("https://a.example/list.json").await.unwrap();
+        }
+
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
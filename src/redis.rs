@@ -0,0 +1,414 @@
+//! Storing and sharing fetched token lists in Redis, with
+//! [redis-rs](https://docs.rs/redis).
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ::redis::aio::ConnectionManager;
+use ::redis::{AsyncCommands, Client, Pipeline, Script};
+use async_trait::async_trait;
+use semver::Version;
+use sha2::{Digest, Sha256};
+
+use crate::hash::to_hex;
+use crate::{Error, Fetcher, Storage, TokenList};
+
+/// How often a [`RedisCache`] checks whether another process has stored a
+/// list it's waiting for.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Deletes the lock in `KEYS[1]` only if it's still held with the token in
+/// `ARGV[1]`, so an expired lock taken by another process isn't released.
+const RELEASE_LOCK: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0
+"#;
+
+/// Token lists stored in Redis.
+///
+/// Each version of a list is stored as JSON under
+/// `{prefix}list:{name}:{version}`, and the versions of a list are kept in a
+/// set under `{prefix}versions:{name}`. Storing a version that is already
+/// stored replaces it.
+#[derive(Clone)]
+pub struct RedisStore {
+    connection: ConnectionManager,
+    prefix: String,
+}
+
+impl RedisStore {
+    /// The prefix of the keys lists are stored under, unless set with
+    /// [`with_prefix`](Self::with_prefix).
+    pub const DEFAULT_PREFIX: &'static str = "tokenlist:";
+
+    /// Connects to the server at `url`, such as `redis://localhost:6379`.
+    pub async fn connect(url: &str) -> Result<Self, Error> {
+        let client = Client::open(url)?;
+        Ok(Self::new(ConnectionManager::new(client).await?))
+    }
+
+    /// Stores lists through `connection`.
+    pub fn new(connection: ConnectionManager) -> Self {
+        RedisStore {
+            connection,
+            prefix: Self::DEFAULT_PREFIX.to_owned(),
+        }
+    }
+
+    /// Sets the prefix of the keys lists are stored under, e.g. to share a
+    /// server between applications.
+    pub fn with_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Returns the prefix of the keys lists are stored under.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Stores `token_list`, replacing any list with the same name and version.
+    pub async fn store(&self, token_list: &TokenList) -> Result<(), Error> {
+        let mut pipeline = ::redis::pipe();
+        self.add_list(
+            &mut pipeline,
+            token_list,
+            &serde_json::to_string(token_list)?,
+        );
+        pipeline
+            .atomic()
+            .query_async::<()>(&mut self.connection.clone())
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the list named `name` at `version`, if it's stored.
+    pub async fn load(&self, name: &str, version: &Version) -> Result<Option<TokenList>, Error> {
+        let json: Option<String> = self
+            .connection
+            .clone()
+            .get(self.list_key(name, version))
+            .await?;
+        Ok(json.map(|json| serde_json::from_str(&json)).transpose()?)
+    }
+
+    /// Returns the latest version of the list named `name`, if any is stored.
+    pub async fn load_latest(&self, name: &str) -> Result<Option<TokenList>, Error> {
+        match self.versions(name).await?.pop() {
+            Some(version) => self.load(name, &version).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the stored versions of the list named `name`, oldest first.
+    pub async fn versions(&self, name: &str) -> Result<Vec<Version>, Error> {
+        let members: Vec<String> = self
+            .connection
+            .clone()
+            .smembers(self.versions_key(name))
+            .await?;
+        let mut versions: Vec<_> = members
+            .iter()
+            .filter_map(|version| Version::parse(version).ok())
+            .collect();
+        versions.sort();
+        Ok(versions)
+    }
+
+    /// Adds the commands storing `token_list`, serialized as `json`, to
+    /// `pipeline`.
+    fn add_list(&self, pipeline: &mut Pipeline, token_list: &TokenList, json: &str) {
+        pipeline
+            .set(self.list_key(&token_list.name, &token_list.version), json)
+            .ignore()
+            .sadd(
+                self.versions_key(&token_list.name),
+                token_list.version.to_string(),
+            )
+            .ignore();
+    }
+
+    fn list_key(&self, name: &str, version: &Version) -> String {
+        format!("{}list:{}:{}", self.prefix, name, version)
+    }
+
+    fn versions_key(&self, name: &str) -> String {
+        format!("{}versions:{}", self.prefix, name)
+    }
+}
+
+impl fmt::Debug for RedisStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisStore")
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Storage for RedisStore {
+    async fn put_list(&self, token_list: &TokenList) -> Result<(), Error> {
+        self.store(token_list).await
+    }
+
+    async fn get_list(
+        &self,
+        name: &str,
+        version: Option<&Version>,
+    ) -> Result<Option<TokenList>, Error> {
+        match version {
+            Some(version) => self.load(name, version).await,
+            None => self.load_latest(name).await,
+        }
+    }
+
+    async fn list_versions(&self, name: &str) -> Result<Vec<Version>, Error> {
+        self.versions(name).await
+    }
+}
+
+/// A cache of token lists keyed by URI, shared through Redis so that
+/// processes serving the same lists fetch each one once per time-to-live
+/// rather than each polling its URI.
+///
+/// The list last fetched from a URI is kept under `{prefix}uri:{hash}`,
+/// where `hash` is the SHA-256 of the URI, and expires after the
+/// time-to-live. Each version fetched is also stored as in [`RedisStore`], so
+/// it stays available after the entry expires.
+///
+/// When the entry is missing, the process that takes the lock under
+/// `{prefix}lock:{hash}` fetches the list while the others wait for it to be
+/// stored. If the lock expires first, e.g. because its holder exited, the
+/// next process to take it fetches the list instead.
+#[derive(Clone)]
+pub struct RedisCache {
+    store: RedisStore,
+    fetcher: Arc<dyn Fetcher>,
+    ttl: Duration,
+    lock_timeout: Duration,
+}
+
+impl RedisCache {
+    /// The time a lock is held for before it expires, unless set with
+    /// [`with_lock_timeout`](Self::with_lock_timeout).
+    pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Creates a cache in `store` whose entries are fetched with `fetcher`
+    /// and expire after `ttl`.
+    pub fn new(store: RedisStore, fetcher: Arc<dyn Fetcher>, ttl: Duration) -> Self {
+        RedisCache {
+            store,
+            fetcher,
+            ttl,
+            lock_timeout: Self::DEFAULT_LOCK_TIMEOUT,
+        }
+    }
+
+    /// Sets how long a process may fetch a list for before others stop
+    /// waiting for it, which should exceed the time a fetch can take.
+    pub fn with_lock_timeout(mut self, lock_timeout: Duration) -> Self {
+        self.lock_timeout = lock_timeout;
+        self
+    }
+
+    /// Returns the store lists are kept in, e.g. to load older versions.
+    pub fn store(&self) -> &RedisStore {
+        &self.store
+    }
+
+    /// Returns the cached list for `uri` without fetching it.
+    pub async fn get(&self, uri: &str) -> Result<Option<TokenList>, Error> {
+        let json: Option<String> = self
+            .store
+            .connection
+            .clone()
+            .get(self.key("uri", uri))
+            .await?;
+        Ok(json.map(|json| serde_json::from_str(&json)).transpose()?)
+    }
+
+    /// Returns the cached list for `uri`, fetching it first if no process
+    /// has fetched it within the time-to-live.
+    pub async fn get_or_fetch(&self, uri: &str) -> Result<TokenList, Error> {
+        if let Some(token_list) = self.get(uri).await? {
+            #[cfg(feature = "metrics")]
+            crate::telemetry::cache_request("redis", "hit");
+            return Ok(token_list);
+        }
+        #[cfg(feature = "metrics")]
+        crate::telemetry::cache_request("redis", "miss");
+
+        let lock_key = self.key("lock", uri);
+        let token = lock_token();
+        let mut connection = self.store.connection.clone();
+        loop {
+            let locked: Option<String> = ::redis::cmd("SET")
+                .arg(&lock_key)
+                .arg(&token)
+                .arg("NX")
+                .arg("PX")
+                .arg(millis(self.lock_timeout))
+                .query_async(&mut connection)
+                .await?;
+            if locked.is_some() {
+                let result = self.refresh(uri).await;
+                Script::new(RELEASE_LOCK)
+                    .key(&lock_key)
+                    .arg(&token)
+                    .invoke_async::<()>(&mut connection)
+                    .await?;
+                return result;
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+            if let Some(token_list) = self.get(uri).await? {
+                return Ok(token_list);
+            }
+        }
+    }
+
+    /// Fetches `uri` now, storing the list in the cache if successful.
+    pub async fn refresh(&self, uri: &str) -> Result<TokenList, Error> {
+        let token_list = TokenList::from_fetcher(&*self.fetcher, uri).await?;
+        let json = serde_json::to_string(&token_list)?;
+
+        let mut pipeline = ::redis::pipe();
+        pipeline
+            .pset_ex(self.key("uri", uri), &json, millis(self.ttl))
+            .ignore();
+        self.store.add_list(&mut pipeline, &token_list, &json);
+        pipeline
+            .atomic()
+            .query_async::<()>(&mut self.store.connection.clone())
+            .await?;
+        Ok(token_list)
+    }
+
+    /// Removes the entry for `uri`, so the next call to
+    /// [`get_or_fetch`](Self::get_or_fetch) fetches it again.
+    pub async fn remove(&self, uri: &str) -> Result<(), Error> {
+        self.store
+            .connection
+            .clone()
+            .del::<_, ()>(self.key("uri", uri))
+            .await?;
+        Ok(())
+    }
+
+    fn key(&self, kind: &str, uri: &str) -> String {
+        format!(
+            "{}{}:{}",
+            self.store.prefix,
+            kind,
+            to_hex(&Sha256::digest(uri))
+        )
+    }
+}
+
+impl fmt::Debug for RedisCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisCache")
+            .field("store", &self.store)
+            .field("ttl", &self.ttl)
+            .field("lock_timeout", &self.lock_timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns a token identifying a lock holder, unique across processes.
+fn lock_token() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!(
+        "{}-{}-{}",
+        std::process::id(),
+        nanos,
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Returns `duration` in whole milliseconds, at least one as Redis rejects
+/// an expiry of zero.
+fn millis(duration: Duration) -> u64 {
+    (duration.as_millis() as u64).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{fixtures, MockFetcher, MockResponse};
+
+    /// Connects to the server at `REDIS_URL` with a prefix unique to the
+    /// test, or returns `None` to skip the test if it isn't set.
+    async fn store(test: &str) -> Option<RedisStore> {
+        let url = std::env::var("REDIS_URL").ok()?;
+        let prefix = format!("tokenlist-test:{}:{}:", test, lock_token());
+        Some(RedisStore::connect(&url).await.unwrap().with_prefix(prefix))
+    }
+
+    #[tokio::test]
+    async fn stores_lists() {
+        let store = match store("stores_lists").await {
+            Some(store) => store,
+            None => return,
+        };
+        let first = fixtures::full();
+        let mut second = first.clone();
+        second.version = Version::new(1, 0, 0);
+
+        store.store(&second).await.unwrap();
+        store.store(&first).await.unwrap();
+
+        assert_eq!(
+            store.versions(&first.name).await.unwrap(),
+            vec![first.version.clone(), second.version.clone()]
+        );
+        assert_eq!(store.load_latest(&first.name).await.unwrap(), Some(second));
+        assert_eq!(
+            store.load(&first.name, &first.version).await.unwrap(),
+            Some(first)
+        );
+        assert_eq!(store.load_latest("missing").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn fetches_once_across_caches() {
+        let store = match store("fetches_once_across_caches").await {
+            Some(store) => store,
+            None => return,
+        };
+        let uri = "memory://list.json";
+        let fetcher = Arc::new(MockFetcher::new());
+        fetcher.respond(
+            uri,
+            MockResponse::body(fixtures::FULL).with_latency(Duration::from_millis(200)),
+        );
+        let cache = || RedisCache::new(store.clone(), fetcher.clone(), Duration::from_secs(60));
+        let caches = [cache(), cache(), cache()];
+
+        let (first, second, third) = tokio::join!(
+            caches[0].get_or_fetch(uri),
+            caches[1].get_or_fetch(uri),
+            caches[2].get_or_fetch(uri)
+        );
+        let lists = [first.unwrap(), second.unwrap(), third.unwrap()];
+
+        assert_eq!(fetcher.requests().len(), 1);
+        assert!(lists.windows(2).all(|pair| pair[0] == pair[1]));
+        assert_eq!(
+            store.load_latest(&lists[0].name).await.unwrap().as_ref(),
+            Some(&lists[0])
+        );
+
+        caches[0].remove(uri).await.unwrap();
+        assert_eq!(caches[1].get(uri).await.unwrap(), None);
+    }
+}
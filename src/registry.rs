@@ -0,0 +1,551 @@
+//! Discovery of token lists published through ENS.
+//!
+//! A registry is an ENS name whose `token-lists` text record holds a
+//! whitespace- or comma-separated list of entries, each either a URI or an
+//! ENS name whose contenthash points to a list.
+
+use futures::future::join_all;
+use sha2::{Digest, Sha256};
+
+use crate::hash::{canonical_json, to_hex};
+use crate::keccak::{namehash, selector};
+use crate::onchain::{decode_bytes, encode_hex, word};
+use crate::{Error, Fetcher, JsonRpc, RawTokenList, RpcClient, TokenList};
+
+/// The address of the ENS registry, which is the same on Ethereum mainnet and
+/// its testnets.
+pub const ENS_REGISTRY_ADDRESS: &str = "0x00000000000C2E074eC69A0dFb2997BA6C7d2e1e";
+
+/// The text record listing a registry's entries, unless configured otherwise.
+pub const REGISTRY_TEXT_KEY: &str = "token-lists";
+
+/// Reads registries of token lists published through ENS, and fetches the
+/// lists they point to.
+///
+/// ```no_run
+/// # async fn example() -> Result<(), token_list::Error> {
+/// use token_list::{ListRegistry, ReqwestFetcher, RpcClient};
+///
+/// let registry = ListRegistry::new(RpcClient::new("https://eth.llamarpc.com")?);
+/// let fetcher = ReqwestFetcher::default();
+///
+/// for list in registry.fetch_lists("lists.example.eth", &fetcher).await? {
+///     let list = list?;
+///     println!("{} from {}", list.list.token_list.name, list.uri);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ListRegistry<R = RpcClient> {
+    rpc: R,
+    ens_registry: String,
+    text_key: String,
+    ipfs_gateway: String,
+}
+
+impl<R: JsonRpc> ListRegistry<R> {
+    /// Creates a client reading ENS through `rpc`, which must serve Ethereum
+    /// mainnet or a chain with the ENS registry at [`ENS_REGISTRY_ADDRESS`].
+    pub fn new(rpc: R) -> Self {
+        ListRegistry {
+            rpc,
+            ens_registry: ENS_REGISTRY_ADDRESS.to_owned(),
+            text_key: REGISTRY_TEXT_KEY.to_owned(),
+            ipfs_gateway: "https://ipfs.io".to_owned(),
+        }
+    }
+
+    /// Reads ENS from the registry at `address` rather than
+    /// [`ENS_REGISTRY_ADDRESS`].
+    pub fn with_ens_registry<A: Into<String>>(mut self, address: A) -> Self {
+        self.ens_registry = address.into();
+        self
+    }
+
+    /// Reads the entries of registries from the text record `key` rather
+    /// than [`REGISTRY_TEXT_KEY`].
+    pub fn with_text_key<K: Into<String>>(mut self, key: K) -> Self {
+        self.text_key = key.into();
+        self
+    }
+
+    /// Fetches `ipfs://` and `ipns://` URIs through the HTTP gateway at
+    /// `gateway` rather than `https://ipfs.io`.
+    pub fn with_ipfs_gateway<G: Into<String>>(mut self, gateway: G) -> Self {
+        self.ipfs_gateway = gateway.into().trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Returns the entries of the registry at the ENS name `registry`.
+    pub async fn entries(&self, registry: &str) -> Result<Vec<String>, Error> {
+        let text = self.text(registry, &self.text_key).await?;
+        Ok(text
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|entry| !entry.is_empty())
+            .map(str::to_owned)
+            .collect())
+    }
+
+    /// Resolves the ENS name `name` to the URI of its contenthash; e.g.
+    /// `ipfs://bafy...`.
+    pub async fn resolve(&self, name: &str) -> Result<String, Error> {
+        let node = namehash(&name.to_lowercase());
+        let resolver = self.resolver(name, &node).await?;
+
+        let mut data = selector("contenthash(bytes32)").to_vec();
+        data.extend(&node);
+        let returned = self.rpc.call(&resolver, &data).await?;
+
+        decode_bytes(&returned)
+            .and_then(contenthash_uri)
+            .ok_or_else(|| Error::InvalidContenthash(name.to_owned()))
+    }
+
+    /// Prepares the transaction setting the contenthash of the ENS name `name`
+    /// to `release`, to be signed and sent by its owner.
+    ///
+    /// With the `ethers` feature, [`ContenthashUpdate::send`] sends it
+    /// through a signing middleware.
+    pub async fn contenthash_update(
+        &self,
+        name: &str,
+        release: &ListRelease,
+    ) -> Result<ContenthashUpdate, Error> {
+        let node = namehash(&name.to_lowercase());
+        let resolver = self.resolver(name, &node).await?;
+        let contenthash = release.contenthash();
+
+        let mut calldata = selector("setContenthash(bytes32,bytes)").to_vec();
+        calldata.extend(&node);
+        calldata.extend(&word(64));
+        calldata.extend(&word(contenthash.len()));
+        calldata.extend(&contenthash);
+        calldata.resize(4 + 96 + contenthash.len().div_ceil(32) * 32, 0);
+
+        Ok(ContenthashUpdate {
+            name: name.to_owned(),
+            resolver,
+            calldata,
+        })
+    }
+
+    /// Fetches the lists of the registry at the ENS name `registry` with
+    /// `fetcher`, in the order of its entries.
+    ///
+    /// Fails only if the registry's entries can't be read; each entry that
+    /// can't be resolved or fetched gives an error in its place.
+    pub async fn fetch_lists<F: Fetcher + ?Sized>(
+        &self,
+        registry: &str,
+        fetcher: &F,
+    ) -> Result<Vec<Result<RegisteredList, Error>>, Error> {
+        let entries = self.entries(registry).await?;
+        Ok(join_all(
+            entries
+                .into_iter()
+                .map(|entry| self.fetch_entry(registry, entry, fetcher)),
+        )
+        .await)
+    }
+
+    async fn fetch_entry<F: Fetcher + ?Sized>(
+        &self,
+        registry: &str,
+        entry: String,
+        fetcher: &F,
+    ) -> Result<RegisteredList, Error> {
+        let (ens_name, uri) = match entry.contains("://") {
+            true => (None, entry.clone()),
+            false => (Some(entry.clone()), self.resolve(&entry).await?),
+        };
+        let list = RawTokenList::from_bytes(fetcher.fetch(&self.gateway_uri(&uri)).await?)?;
+
+        Ok(RegisteredList {
+            registry: registry.to_owned(),
+            entry,
+            ens_name,
+            uri,
+            list,
+        })
+    }
+
+    /// Returns the address of the resolver of `name`.
+    async fn resolver(&self, name: &str, node: &[u8; 32]) -> Result<String, Error> {
+        let mut data = selector("resolver(bytes32)").to_vec();
+        data.extend(node);
+        let returned = self.rpc.call(&self.ens_registry, &data).await?;
+
+        match returned.get(12..32) {
+            Some(address) if address.iter().any(|&byte| byte != 0) => Ok(encode_hex(address)),
+            _ => Err(Error::EnsNotFound(name.to_owned())),
+        }
+    }
+
+    /// Reads the text record `key` of `name`.
+    async fn text(&self, name: &str, key: &str) -> Result<String, Error> {
+        let node = namehash(&name.to_lowercase());
+        let resolver = self.resolver(name, &node).await?;
+
+        let mut data = selector("text(bytes32,string)").to_vec();
+        data.extend(&node);
+        data.extend(&word(64));
+        data.extend(&word(key.len()));
+        data.extend(key.as_bytes());
+        data.resize(4 + 96 + key.len().div_ceil(32) * 32, 0);
+        let returned = self.rpc.call(&resolver, &data).await?;
+
+        decode_bytes(&returned)
+            .and_then(|text| String::from_utf8(text.to_vec()).ok())
+            .ok_or_else(|| {
+                Error::InvalidRpcResponse(format!("undecodable text record {} of {}", key, name))
+            })
+    }
+
+    /// Returns the URI `uri` is fetched from, through the IPFS gateway for
+    /// IPFS URIs.
+    fn gateway_uri(&self, uri: &str) -> String {
+        ipfs_gateway_uri(uri, &self.ipfs_gateway)
+    }
+}
+
+/// A token list found through a registry, with where it came from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegisteredList {
+    /// The ENS name of the registry
+    pub registry: String,
+
+    /// The registry's entry for the list, as published
+    pub entry: String,
+
+    /// The ENS name the list was resolved from, if the entry is one
+    pub ens_name: Option<String>,
+
+    /// The URI of the list; e.g. the `ipfs://` URI of an ENS contenthash
+    pub uri: String,
+
+    /// The list, with the bytes it was parsed from and their digest
+    pub list: RawTokenList,
+}
+
+/// A token list serialized for publishing on IPFS, with
+/// [`TokenList::ipfs_release`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListRelease {
+    /// The canonical JSON of the list, to be pinned
+    pub bytes: Vec<u8>,
+
+    /// The CIDv1 of `bytes` as a single raw block; e.g. `bafkrei...`
+    pub cid: String,
+}
+
+impl ListRelease {
+    /// Returns the `ipfs://` URI of the list.
+    pub fn uri(&self) -> String {
+        format!("ipfs://{}", self.cid)
+    }
+
+    /// Returns the SHA-256 digest of the canonical JSON as lowercase
+    /// hexadecimal.
+    pub fn sha256_hex(&self) -> String {
+        to_hex(&Sha256::digest(&self.bytes))
+    }
+
+    /// Returns the [EIP-1577](https://eips.ethereum.org/EIPS/eip-1577)
+    /// contenthash pointing to the list.
+    pub fn contenthash(&self) -> Vec<u8> {
+        let mut hash = vec![0xe3, 0x01];
+        hash.extend(raw_cid(&self.bytes));
+        hash
+    }
+}
+
+impl TokenList {
+    /// Serializes the list as canonical JSON and computes its IPFS CID, for
+    /// publishing it under an ENS name.
+    ///
+    /// The CID is that of the bytes as a single block with the raw codec, as
+    /// given by `ipfs block put --cid-codec raw`, or by `ipfs add
+    /// --cid-version 1` for lists that fit in a single chunk.
+    pub fn ipfs_release(&self) -> Result<ListRelease, Error> {
+        let bytes = canonical_json(self)?;
+        let cid = format!("b{}", base32(&raw_cid(&bytes)));
+        Ok(ListRelease { bytes, cid })
+    }
+}
+
+/// A transaction setting the contenthash of an ENS name, with
+/// [`ListRegistry::contenthash_update`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContenthashUpdate {
+    /// The ENS name being updated
+    pub name: String,
+
+    /// The address of the name's resolver, which the transaction is sent to
+    pub resolver: String,
+
+    /// The calldata of the call to the resolver's `setContenthash`
+    pub calldata: Vec<u8>,
+}
+
+/// Returns the URI `uri` is fetched from: through the HTTP gateway at
+/// `gateway`, such as `https://ipfs.io`, for `ipfs://` and `ipns://` URIs, and
+/// `uri` itself otherwise.
+pub fn ipfs_gateway_uri(uri: &str, gateway: &str) -> String {
+    for scheme in &["ipfs", "ipns"] {
+        if let Some(path) = uri.strip_prefix(&format!("{}://", scheme)) {
+            return format!("{}/{}/{}", gateway.trim_end_matches('/'), scheme, path);
+        }
+    }
+    uri.to_owned()
+}
+
+/// Returns the binary CIDv1 of `bytes` as a block with the raw codec and a
+/// SHA-256 multihash.
+fn raw_cid(bytes: &[u8]) -> Vec<u8> {
+    let mut cid = vec![0x01, 0x55, 0x12, 0x20];
+    cid.extend(Sha256::digest(bytes));
+    cid
+}
+
+/// Converts an [EIP-1577](https://eips.ethereum.org/EIPS/eip-1577)
+/// contenthash to a URI, if it's an IPFS or IPNS hash.
+fn contenthash_uri(hash: &[u8]) -> Option<String> {
+    let (scheme, cid) = match hash {
+        [0xe3, 0x01, cid @ ..] => ("ipfs", cid),
+        [0xe5, 0x01, cid @ ..] => ("ipns", cid),
+        _ => return None,
+    };
+    // only CIDv1 is valid in a contenthash
+    if cid.first() != Some(&0x01) {
+        return None;
+    }
+    Some(format!("{}://b{}", scheme, base32(cid)))
+}
+
+/// Encodes `bytes` as unpadded lowercase RFC 4648 base32, as used by CIDs.
+pub(crate) fn base32(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let (mut buffer, mut bits) = (0u16, 0);
+    for &byte in bytes {
+        buffer = buffer << 8 | u16::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(char::from(ALPHABET[usize::from(buffer >> bits & 31)]));
+        }
+    }
+    if bits > 0 {
+        encoded.push(char::from(ALPHABET[usize::from(buffer << (5 - bits) & 31)]));
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::onchain::decode_hex;
+    use crate::test_utils::{fixtures, MockFetcher, MockResponse};
+
+    /// Answers ENS calls with the resolver at `0x...01`, and the text records
+    /// and contenthashes given, recording the calldata of each call.
+    struct Ens {
+        text: String,
+        contenthash: Vec<u8>,
+        calls: Arc<Mutex<Vec<Vec<u8>>>>,
+    }
+
+    fn abi_bytes(bytes: &[u8]) -> String {
+        let mut data = word(32).to_vec();
+        data.extend(&word(bytes.len()));
+        data.extend(bytes);
+        data.resize(64 + bytes.len().div_ceil(32) * 32, 0);
+        encode_hex(&data)
+    }
+
+    #[async_trait]
+    impl JsonRpc for Ens {
+        async fn request(&self, _: &str, params: Value) -> Result<Value, Error> {
+            let call = decode_hex(params[0]["data"].as_str().unwrap()).unwrap();
+            self.calls.lock().unwrap().push(call.clone());
+            let result = match &call[..4] {
+                s if *s == selector("resolver(bytes32)") => encode_hex(&word(1)),
+                s if *s == selector("text(bytes32,string)") => abi_bytes(self.text.as_bytes()),
+                s if *s == selector("contenthash(bytes32)") => abi_bytes(&self.contenthash),
+                _ => panic!("unexpected call"),
+            };
+            Ok(json!(result))
+        }
+    }
+
+    /// An IPFS contenthash of a CIDv1 with the dag-pb codec and a SHA-256
+    /// multihash.
+    fn contenthash() -> Vec<u8> {
+        let mut hash = vec![0xe3, 0x01, 0x01, 0x70, 0x12, 0x20];
+        hash.extend(&[0xab; 32]);
+        hash
+    }
+
+    #[tokio::test]
+    async fn fetches_registered_lists() {
+        let registry = ListRegistry::new(Ens {
+            text: "https://example.com/list.json, tokens.example.eth".to_owned(),
+            contenthash: contenthash(),
+            calls: Default::default(),
+        });
+        let ipfs_uri = contenthash_uri(&contenthash()).unwrap();
+        let fetcher = MockFetcher::new();
+        fetcher
+            .respond(
+                "https://example.com/list.json",
+                MockResponse::body(fixtures::FULL),
+            )
+            .respond(
+                &registry.gateway_uri(&ipfs_uri),
+                MockResponse::body(fixtures::FULL),
+            );
+
+        let lists = registry
+            .fetch_lists("lists.example.eth", &fetcher)
+            .await
+            .unwrap();
+
+        assert_eq!(lists.len(), 2);
+        let list = lists[0].as_ref().unwrap();
+        assert_eq!(list.ens_name, None);
+        assert_eq!(list.uri, "https://example.com/list.json");
+        let list = lists[1].as_ref().unwrap();
+        assert_eq!(list.registry, "lists.example.eth");
+        assert_eq!(list.ens_name.as_deref(), Some("tokens.example.eth"));
+        assert_eq!(list.uri, ipfs_uri);
+        assert!(list.uri.starts_with("ipfs://bafybei"));
+    }
+
+    #[tokio::test]
+    async fn encodes_text_calls() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let registry = ListRegistry::new(Ens {
+            text: "https://example.com/list.json".to_owned(),
+            contenthash: Vec::new(),
+            calls: calls.clone(),
+        });
+
+        let text = registry.text("Lists.example.eth", "url").await.unwrap();
+
+        assert_eq!(text, "https://example.com/list.json");
+        let node = namehash("lists.example.eth");
+        let mut key = b"url".to_vec();
+        key.resize(32, 0);
+        let expected = [
+            &selector("text(bytes32,string)")[..],
+            &node,
+            &word(64),
+            &word(3),
+            &key,
+        ]
+        .concat();
+        assert_eq!(calls.lock().unwrap()[1], expected);
+    }
+
+    #[test]
+    fn computes_ipfs_releases() {
+        let token_list = fixtures::full();
+
+        let release = token_list.ipfs_release().unwrap();
+
+        assert_eq!(release.bytes, canonical_json(&token_list).unwrap());
+        assert!(release.cid.starts_with("bafkrei"));
+        assert_eq!(contenthash_uri(&release.contenthash()), Some(release.uri()));
+        assert_eq!(
+            release.sha256_hex(),
+            to_hex(&crate::hash::content_hash(&token_list).unwrap())
+        );
+    }
+
+    #[test]
+    fn computes_raw_cids() {
+        // `echo -n hello | ipfs block put --cid-codec raw`
+        assert_eq!(
+            format!("b{}", base32(&raw_cid(b"hello"))),
+            "bafkreibm6jg3ux5qumhcn2b3flc3tyu6dmlb4xa7u5bf44yegnrjhc4yeq"
+        );
+    }
+
+    #[tokio::test]
+    async fn prepares_contenthash_updates() {
+        let registry = ListRegistry::new(Ens {
+            text: String::new(),
+            contenthash: Vec::new(),
+            calls: Default::default(),
+        });
+        let release = ListRelease {
+            bytes: b"{}".to_vec(),
+            cid: String::new(),
+        };
+
+        let update = registry
+            .contenthash_update("Tokens.example.eth", &release)
+            .await
+            .unwrap();
+
+        assert_eq!(update.resolver, encode_hex(&word(1)[12..]));
+        let calldata = &update.calldata;
+        assert_eq!(calldata[..4], selector("setContenthash(bytes32,bytes)"));
+        assert_eq!(calldata[4..36], namehash("tokens.example.eth"));
+        assert_eq!(calldata[36..68], word(64));
+        assert_eq!(calldata[68..100], word(38));
+        assert_eq!(calldata[100..138], release.contenthash()[..]);
+        assert_eq!(calldata.len(), 164);
+    }
+
+    #[test]
+    fn rewrites_ipfs_uris_to_gateway() {
+        let registry = ListRegistry::new(RpcClient::new("http://localhost").unwrap())
+            .with_ipfs_gateway("https://gateway.example.com/");
+
+        assert_eq!(
+            registry.gateway_uri("ipfs://bafy/list.json"),
+            "https://gateway.example.com/ipfs/bafy/list.json"
+        );
+        assert_eq!(
+            registry.gateway_uri("https://example.com"),
+            "https://example.com"
+        );
+    }
+
+    #[test]
+    fn rewrites_ipfs_uris_to_given_gateway() {
+        assert_eq!(
+            ipfs_gateway_uri("ipns://tokens.example/list.json", "https://dweb.link/"),
+            "https://dweb.link/ipns/tokens.example/list.json"
+        );
+        assert_eq!(
+            ipfs_gateway_uri("list.json", "https://dweb.link"),
+            "list.json"
+        );
+    }
+
+    #[test]
+    fn encodes_base32() {
+        let cases = [
+            ("", ""),
+            ("f", "my"),
+            ("fo", "mzxq"),
+            ("foo", "mzxw6"),
+            ("foob", "mzxw6yq"),
+            ("fooba", "mzxw6ytb"),
+            ("foobar", "mzxw6ytboi"),
+        ];
+
+        for (input, expected) in &cases {
+            assert_eq!(base32(input.as_bytes()), *expected);
+        }
+    }
+}
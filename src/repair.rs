@@ -0,0 +1,260 @@
+//! Safe fixes for common problems in lists, applied before publishing.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::keccak::checksum_address;
+use crate::TokenList;
+
+/// The most keywords a list may have under the token list schema.
+pub const MAX_KEYWORDS: usize = 20;
+
+/// The fixes applied to a list by [`TokenList::repair`].
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct RepairReport {
+    /// The fixes applied, in document order
+    pub repairs: Vec<Repair>,
+}
+
+impl RepairReport {
+    /// Returns `true` if the list needed no fixes.
+    pub fn is_clean(&self) -> bool {
+        self.repairs.is_empty()
+    }
+}
+
+/// A single fix applied to a list.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Repair {
+    /// A JSON pointer to the fixed value in the original list, e.g.
+    /// `/tokens/3/address`
+    pub path: String,
+
+    /// What was fixed
+    pub kind: RepairKind,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.kind)
+    }
+}
+
+/// The kinds of fix applied by [`TokenList::repair`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum RepairKind {
+    /// Leading or trailing whitespace was trimmed from a string
+    TrimmedWhitespace,
+    /// An address was given its EIP-55 checksum
+    ChecksummedAddress,
+    /// A token with the same chain and address as an earlier one was removed
+    RemovedDuplicateToken,
+    /// A token's reference to a tag the list doesn't define was removed
+    RemovedDanglingTag,
+    /// Keywords beyond [`MAX_KEYWORDS`] were removed
+    TruncatedKeywords,
+}
+
+impl fmt::Display for RepairKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RepairKind::TrimmedWhitespace => "trimmed whitespace",
+            RepairKind::ChecksummedAddress => "checksummed address",
+            RepairKind::RemovedDuplicateToken => "removed duplicate token",
+            RepairKind::RemovedDanglingTag => "removed reference to undefined tag",
+            RepairKind::TruncatedKeywords => "removed keywords beyond the limit",
+        })
+    }
+}
+
+impl TokenList {
+    /// Applies fixes to common problems that can't change what the list
+    /// means, returning a report of every fix.
+    ///
+    /// Leading and trailing whitespace is trimmed from names, symbols,
+    /// addresses, keywords, and tag definitions; addresses are given their
+    /// EIP-55 checksum; tokens with the same chain and address as an earlier
+    /// token are removed, as are references to undefined tags; and keywords
+    /// beyond [`MAX_KEYWORDS`] are dropped. Malformed addresses are left as
+    /// they are.
+    pub fn repair(&mut self) -> RepairReport {
+        let mut report = RepairReport::default();
+        let mut fix = |path: String, kind| report.repairs.push(Repair { path, kind });
+
+        if trim(&mut self.name) {
+            fix("/name".to_owned(), RepairKind::TrimmedWhitespace);
+        }
+        for (i, keyword) in self.keywords.iter_mut().enumerate() {
+            if trim(keyword) {
+                fix(format!("/keywords/{}", i), RepairKind::TrimmedWhitespace);
+            }
+        }
+        if self.keywords.len() > MAX_KEYWORDS {
+            self.keywords.truncate(MAX_KEYWORDS);
+            fix("/keywords".to_owned(), RepairKind::TruncatedKeywords);
+        }
+        for (id, tag) in &mut self.tags {
+            if trim(&mut tag.name) {
+                fix(
+                    format!("/tags/{}/name", escape(id)),
+                    RepairKind::TrimmedWhitespace,
+                );
+            }
+            if trim(&mut tag.description) {
+                fix(
+                    format!("/tags/{}/description", escape(id)),
+                    RepairKind::TrimmedWhitespace,
+                );
+            }
+        }
+
+        let tags = &self.tags;
+        let mut seen = HashSet::new();
+        let mut index = 0;
+        self.tokens.retain_mut(|token| {
+            let i = index;
+            index += 1;
+
+            for (field, value) in [
+                ("name", &mut token.name),
+                ("symbol", &mut token.symbol),
+                ("address", &mut token.address),
+            ] {
+                if trim(value) {
+                    fix(
+                        format!("/tokens/{}/{}", i, field),
+                        RepairKind::TrimmedWhitespace,
+                    );
+                }
+            }
+            if let Some(checksummed) = checksum_address(&token.address) {
+                if checksummed != token.address {
+                    token.address = checksummed;
+                    fix(
+                        format!("/tokens/{}/address", i),
+                        RepairKind::ChecksummedAddress,
+                    );
+                }
+            }
+
+            let mut j = 0;
+            token.tags.retain(|tag| {
+                let defined = tags.contains_key(tag);
+                if !defined {
+                    fix(
+                        format!("/tokens/{}/tags/{}", i, j),
+                        RepairKind::RemovedDanglingTag,
+                    );
+                }
+                j += 1;
+                defined
+            });
+
+            let unique = seen.insert((token.chain_id, token.address.to_lowercase()));
+            if !unique {
+                fix(format!("/tokens/{}", i), RepairKind::RemovedDuplicateToken);
+            }
+            unique
+        });
+
+        report
+    }
+}
+
+/// Trims leading and trailing whitespace from `value`, returning `true` if
+/// there was any.
+fn trim(value: &mut String) -> bool {
+    let trimmed = value.trim();
+    if trimmed.len() == value.len() {
+        return false;
+    }
+    *value = trimmed.to_owned();
+    true
+}
+
+/// Escapes `key` for use as a JSON pointer segment.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+    use crate::Tag;
+
+    #[test]
+    fn repairs_lists() {
+        let mut token_list = fixtures::full();
+        let expected = token_list.clone();
+        token_list.name = format!(" {}\n", token_list.name);
+        token_list.tokens[0].address = token_list.tokens[0].address.to_lowercase();
+        token_list.tokens[0].tags.push("undefined".to_owned());
+        token_list.tokens.push(token_list.tokens[0].clone());
+
+        let report = token_list.repair();
+
+        assert_eq!(token_list, expected);
+        let repairs: Vec<String> = report.repairs.iter().map(|r| r.to_string()).collect();
+        let last = expected.tokens.len();
+        assert_eq!(
+            repairs,
+            vec![
+                "/name: trimmed whitespace".to_owned(),
+                "/tokens/0/address: checksummed address".to_owned(),
+                format!(
+                    "/tokens/0/tags/{}: removed reference to undefined tag",
+                    expected.tokens[0].tags.len()
+                ),
+                format!("/tokens/{}/address: checksummed address", last),
+                format!(
+                    "/tokens/{}/tags/{}: removed reference to undefined tag",
+                    last,
+                    expected.tokens[0].tags.len()
+                ),
+                format!("/tokens/{}: removed duplicate token", last),
+            ]
+        );
+    }
+
+    #[test]
+    fn truncates_keywords_and_trims_tags() {
+        let mut token_list = fixtures::full();
+        token_list.keywords = (0..25).map(|i| i.to_string()).collect();
+        token_list.tags.insert(
+            "a/b".to_owned(),
+            Tag {
+                name: "ab ".to_owned(),
+                description: "A and B".to_owned(),
+            },
+        );
+
+        let report = token_list.repair();
+
+        assert_eq!(token_list.keywords.len(), MAX_KEYWORDS);
+        assert_eq!(token_list.tags["a/b"].name, "ab");
+        assert_eq!(
+            report.repairs,
+            vec![
+                Repair {
+                    path: "/keywords".to_owned(),
+                    kind: RepairKind::TruncatedKeywords,
+                },
+                Repair {
+                    path: "/tags/a~1b/name".to_owned(),
+                    kind: RepairKind::TrimmedWhitespace,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_clean_lists_alone() {
+        let mut token_list = fixtures::full();
+        let expected = token_list.clone();
+
+        assert!(token_list.repair().is_clean());
+        assert_eq!(token_list, expected);
+    }
+}
@@ -0,0 +1,182 @@
+//! Rewriting of the logo URIs of lists, to serve logos from elsewhere.
+
+use url::Url;
+
+use crate::TokenList;
+
+/// The IPFS gateway [`LogoRewrite::IpfsGateway`] is usually given.
+pub const IPFS_GATEWAY_URI: &str = "https://ipfs.io/";
+
+/// A built-in rewrite of logo URIs, for use with
+/// [`TokenList::rewrite_logo_uris`].
+///
+/// ```
+/// # use token_list::{LogoRewrite, TokenList};
+/// # use url::Url;
+/// let mut token_list = TokenList::from_slice(br#"{
+///     "name": "TELcoins",
+///     "logoURI": "ipfs://QmQeYmzwTR9XQSJVLxrZGT6p7ob1iN1hLxkjGS7z5yxpdb/tel.png",
+///     "timestamp": "2021-07-05T20:25:22+00:00",
+///     "version": { "major": 0, "minor": 1, "patch": 0 },
+///     "tokens": []
+/// }"#)?;
+///
+/// let gateway = LogoRewrite::IpfsGateway(Url::parse("https://ipfs.io/")?);
+/// token_list.rewrite_logo_uris(|uri| gateway.rewrite(uri));
+/// assert_eq!(
+///     token_list.logo_uri.unwrap().as_str(),
+///     "https://ipfs.io/ipfs/QmQeYmzwTR9XQSJVLxrZGT6p7ob1iN1hLxkjGS7z5yxpdb/tel.png"
+/// );
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum LogoRewrite {
+    /// Serves files from `raw.githubusercontent.com` through jsDelivr's
+    /// GitHub CDN, at the same commit, branch, or tag
+    GitHubToJsDelivr,
+    /// Serves `ipfs://` and `ipns://` URIs through an HTTP gateway, such as
+    /// [`IPFS_GATEWAY_URI`]
+    IpfsGateway(Url),
+}
+
+impl LogoRewrite {
+    /// Returns the rewritten `uri`, or `None` if the rewrite doesn't apply
+    /// to it.
+    pub fn rewrite(&self, uri: &Url) -> Option<Url> {
+        match self {
+            LogoRewrite::GitHubToJsDelivr => {
+                if uri.host_str() != Some("raw.githubusercontent.com") {
+                    return None;
+                }
+                let mut segments = uri.path_segments()?;
+                let owner = segments.next().filter(|s| !s.is_empty())?;
+                let repo = segments.next().filter(|s| !s.is_empty())?;
+                let reference = segments.next().filter(|s| !s.is_empty())?;
+                let path = segments.collect::<Vec<_>>().join("/");
+                if path.is_empty() {
+                    return None;
+                }
+                Url::parse(&format!(
+                    "https://cdn.jsdelivr.net/gh/{}/{}@{}/{}",
+                    owner, repo, reference, path
+                ))
+                .ok()
+            }
+            LogoRewrite::IpfsGateway(gateway) => {
+                let scheme = uri.scheme();
+                if scheme != "ipfs" && scheme != "ipns" {
+                    return None;
+                }
+                // `ipfs://<cid>/<path>` parses with the CID as its host
+                let root = uri.host_str().filter(|host| !host.is_empty())?;
+                let mut gateway = gateway.clone();
+                if !gateway.path().ends_with('/') {
+                    let path = format!("{}/", gateway.path());
+                    gateway.set_path(&path);
+                }
+                let mut rewritten = gateway
+                    .join(&format!("{}/{}{}", scheme, root, uri.path()))
+                    .ok()?;
+                rewritten.set_query(uri.query());
+                Some(rewritten)
+            }
+        }
+    }
+}
+
+impl TokenList {
+    /// Replaces the logo URIs of the list and its tokens with those returned
+    /// by `f`, leaving a URI as it is where `f` returns `None`.
+    ///
+    /// Returns the number of URIs rewritten.
+    pub fn rewrite_logo_uris(&mut self, mut f: impl FnMut(&Url) -> Option<Url>) -> usize {
+        let mut rewritten = 0;
+        let uris = self.logo_uri.iter_mut().chain(
+            self.tokens
+                .iter_mut()
+                .filter_map(|token| token.logo_uri.as_mut()),
+        );
+        for uri in uris {
+            if let Some(new_uri) = f(uri) {
+                *uri = new_uri;
+                rewritten += 1;
+            }
+        }
+        rewritten
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    fn rewrite(rewrite: &LogoRewrite, uri: &str) -> Option<String> {
+        rewrite
+            .rewrite(&Url::parse(uri).unwrap())
+            .map(|uri| uri.to_string())
+    }
+
+    #[test]
+    fn rewrites_github_to_jsdelivr() {
+        assert_eq!(
+            rewrite(
+                &LogoRewrite::GitHubToJsDelivr,
+                "https://raw.githubusercontent.com/trustwallet/assets/master/blockchains/ethereum/info/logo.png"
+            )
+            .as_deref(),
+            Some("https://cdn.jsdelivr.net/gh/trustwallet/assets@master/blockchains/ethereum/info/logo.png")
+        );
+        assert_eq!(
+            rewrite(
+                &LogoRewrite::GitHubToJsDelivr,
+                "https://raw.githubusercontent.com/trustwallet/assets"
+            ),
+            None
+        );
+        assert_eq!(
+            rewrite(
+                &LogoRewrite::GitHubToJsDelivr,
+                "https://example.com/a/b/c/d.png"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn rewrites_ipfs_to_gateway() {
+        let gateway = LogoRewrite::IpfsGateway(Url::parse("https://gateway.example/base").unwrap());
+
+        assert_eq!(
+            rewrite(&gateway, "ipfs://bafybeibc5sgo2plmjkq2tzmhrn54bk3crhnc23zd2msg4ea7a4pxrkgfna/logo.svg").as_deref(),
+            Some("https://gateway.example/base/ipfs/bafybeibc5sgo2plmjkq2tzmhrn54bk3crhnc23zd2msg4ea7a4pxrkgfna/logo.svg")
+        );
+        assert_eq!(
+            rewrite(&gateway, "ipns://tokens.example/tel.png").as_deref(),
+            Some("https://gateway.example/base/ipns/tokens.example/tel.png")
+        );
+        assert_eq!(rewrite(&gateway, "https://example.com/tel.png"), None);
+    }
+
+    #[test]
+    fn rewrites_list_and_token_logos() {
+        let mut token_list = fixtures::full();
+        token_list.tokens[0].logo_uri = Some(Url::parse("ipfs://QmTEL/tel.png").unwrap());
+        let gateway = LogoRewrite::IpfsGateway(Url::parse(IPFS_GATEWAY_URI).unwrap());
+
+        assert_eq!(token_list.rewrite_logo_uris(|uri| gateway.rewrite(uri)), 1);
+        assert_eq!(
+            token_list.tokens[0].logo_uri.as_ref().unwrap().as_str(),
+            "https://ipfs.io/ipfs/QmTEL/tel.png"
+        );
+
+        let logos = token_list
+            .tokens
+            .iter()
+            .filter(|t| t.logo_uri.is_some())
+            .count();
+        let count = token_list.rewrite_logo_uris(|uri| uri.join("/logo.png").ok());
+        assert_eq!(count, logos + token_list.logo_uri.iter().count());
+    }
+}
@@ -0,0 +1,318 @@
+//! Pluggable risk scoring of the tokens of a list.
+
+#[cfg(feature = "onchain")]
+use std::collections::HashMap;
+
+#[cfg(feature = "onchain")]
+use crate::{ContractKind, ContractReport};
+use crate::{ExtensionValue, Number, Token, TokenList};
+
+/// The extension [`RiskReport::annotate`] sets to a token's score.
+pub const RISK_SCORE_EXTENSION: &str = "riskScore";
+
+/// The extension [`RiskReport::annotate`] sets to a token's labels, separated
+/// by commas.
+pub const RISK_LABELS_EXTENSION: &str = "riskLabels";
+
+/// A heuristic assessing the risk of holding or trading a token.
+///
+/// Scorers are run by a [`RiskEngine`]. Closures taking a token and its
+/// context are scorers too:
+///
+/// ```
+/// use token_list::{RiskAssessment, RiskContext, RiskEngine, Token};
+///
+/// let engine = RiskEngine::new().with_scorer(|token: &Token, _: &RiskContext<'_>| {
+///     match token.logo_uri {
+///         Some(_) => RiskAssessment::default(),
+///         None => RiskAssessment::new(0.1).with_label("no-logo"),
+///     }
+/// });
+/// ```
+pub trait RiskScorer: Send + Sync {
+    /// Assesses `token`, given where it appears and what's known about it.
+    fn score(&self, token: &Token, context: &RiskContext<'_>) -> RiskAssessment;
+}
+
+impl<F> RiskScorer for F
+where
+    F: Fn(&Token, &RiskContext<'_>) -> RiskAssessment + Send + Sync,
+{
+    fn score(&self, token: &Token, context: &RiskContext<'_>) -> RiskAssessment {
+        self(token, context)
+    }
+}
+
+/// What's known about a token being scored, besides the token itself.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct RiskContext<'a> {
+    /// The list the token is in
+    pub token_list: &'a TokenList,
+
+    /// The index of the token in the list
+    pub index: usize,
+
+    /// What is deployed at the token's address, if checked with
+    /// [`TokenList::check_contracts`]
+    #[cfg(feature = "onchain")]
+    pub contract: Option<&'a ContractKind>,
+}
+
+/// A scorer's assessment of a token.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct RiskAssessment {
+    /// How risky the token is, from `0.0` for no known risk to `1.0`
+    pub score: f64,
+
+    /// Short identifiers for the risks found; e.g. `unverified-contract`
+    pub labels: Vec<String>,
+}
+
+impl RiskAssessment {
+    /// Creates an assessment with `score`, clamped to `0.0..=1.0`, and no
+    /// labels.
+    pub fn new(score: f64) -> Self {
+        RiskAssessment {
+            score: score.clamp(0.0, 1.0),
+            labels: Vec::new(),
+        }
+    }
+
+    /// Adds `label` to the assessment.
+    pub fn with_label<L: Into<String>>(mut self, label: L) -> Self {
+        self.labels.push(label.into());
+        self
+    }
+}
+
+/// Runs [`RiskScorer`]s over the tokens of lists.
+#[derive(Default)]
+pub struct RiskEngine {
+    scorers: Vec<Box<dyn RiskScorer>>,
+}
+
+impl RiskEngine {
+    /// Creates an engine without scorers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `scorer` to the scorers run on each token.
+    pub fn with_scorer<S: RiskScorer + 'static>(mut self, scorer: S) -> Self {
+        self.scorers.push(Box::new(scorer));
+        self
+    }
+
+    /// Scores each token of `token_list` with every scorer.
+    ///
+    /// A token's score is the highest any scorer gives it, and its labels
+    /// those of every scorer, without duplicates.
+    pub fn assess(&self, token_list: &TokenList) -> RiskReport {
+        self.assess_tokens(token_list, |index, _| RiskContext {
+            token_list,
+            index,
+            #[cfg(feature = "onchain")]
+            contract: None,
+        })
+    }
+
+    /// Scores each token of `token_list` as by [`assess`](Self::assess),
+    /// giving scorers what `contracts` found deployed at its address.
+    #[cfg(feature = "onchain")]
+    pub fn assess_with_contracts(
+        &self,
+        token_list: &TokenList,
+        contracts: &ContractReport,
+    ) -> RiskReport {
+        let kinds: HashMap<(u32, &str), &ContractKind> = contracts
+            .contracts
+            .iter()
+            .map(|check| {
+                (
+                    (check.token.chain_id, check.token.address.as_str()),
+                    &check.kind,
+                )
+            })
+            .collect();
+
+        self.assess_tokens(token_list, |index, token| RiskContext {
+            token_list,
+            index,
+            contract: kinds
+                .get(&(token.chain_id, token.address.as_str()))
+                .copied(),
+        })
+    }
+
+    fn assess_tokens<'a, C>(&self, token_list: &'a TokenList, context: C) -> RiskReport
+    where
+        C: Fn(usize, &'a Token) -> RiskContext<'a>,
+    {
+        let tokens = token_list
+            .tokens
+            .iter()
+            .enumerate()
+            .map(|(index, token)| {
+                let context = context(index, token);
+                let mut risk = TokenRisk {
+                    index,
+                    chain_id: token.chain_id,
+                    address: token.address.clone(),
+                    score: 0.0,
+                    labels: Vec::new(),
+                };
+                for scorer in &self.scorers {
+                    let assessment = scorer.score(token, &context);
+                    risk.score = risk.score.max(assessment.score.clamp(0.0, 1.0));
+                    for label in assessment.labels {
+                        if !risk.labels.contains(&label) {
+                            risk.labels.push(label);
+                        }
+                    }
+                }
+                risk
+            })
+            .collect();
+        RiskReport { tokens }
+    }
+}
+
+/// The risk of each token of a list, assessed by a [`RiskEngine`].
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct RiskReport {
+    /// The risk of each token, in list order
+    pub tokens: Vec<TokenRisk>,
+}
+
+impl RiskReport {
+    /// Returns the tokens scored at least `threshold`.
+    pub fn flagged(&self, threshold: f64) -> impl Iterator<Item = &TokenRisk> {
+        self.tokens
+            .iter()
+            .filter(move |risk| risk.score >= threshold)
+    }
+
+    /// Records each token's score and labels in its [`RISK_SCORE_EXTENSION`]
+    /// and [`RISK_LABELS_EXTENSION`] extensions, leaving the labels unset
+    /// where there are none.
+    ///
+    /// `token_list` must be the list the report was made from; tokens are
+    /// matched by index, chain, and address, and others are left untouched.
+    pub fn annotate(&self, token_list: &mut TokenList) {
+        for risk in &self.tokens {
+            let token = match token_list.tokens.get_mut(risk.index) {
+                Some(token) if token.chain_id == risk.chain_id && token.address == risk.address => {
+                    token
+                }
+                _ => continue,
+            };
+            token.extensions.insert(
+                RISK_SCORE_EXTENSION.to_owned(),
+                ExtensionValue::Number(Number::Float(risk.score)),
+            );
+            match risk.labels.is_empty() {
+                true => token.extensions.shift_remove(RISK_LABELS_EXTENSION),
+                false => token.extensions.insert(
+                    RISK_LABELS_EXTENSION.to_owned(),
+                    ExtensionValue::String(risk.labels.join(",")),
+                ),
+            };
+        }
+    }
+}
+
+/// The assessed risk of a token.
+#[derive(PartialEq, Clone, Debug)]
+pub struct TokenRisk {
+    /// The index of the token in its list
+    pub index: usize,
+
+    /// The token's chain ID
+    pub chain_id: u32,
+
+    /// The token's address
+    pub address: String,
+
+    /// The highest score any scorer gave the token
+    pub score: f64,
+
+    /// The labels every scorer gave the token
+    pub labels: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    fn engine() -> RiskEngine {
+        RiskEngine::new()
+            .with_scorer(|token: &Token, _: &RiskContext<'_>| match token.chain_id {
+                1 => RiskAssessment::new(0.2).with_label("mainnet"),
+                _ => RiskAssessment::default(),
+            })
+            .with_scorer(|_: &Token, context: &RiskContext<'_>| {
+                RiskAssessment::new(context.index as f64).with_label("mainnet")
+            })
+    }
+
+    #[test]
+    fn combines_scorers() {
+        let token_list = fixtures::full();
+
+        let report = engine().assess(&token_list);
+
+        assert_eq!(report.tokens.len(), token_list.tokens.len());
+        assert_eq!(report.tokens[0].score, 0.2);
+        assert_eq!(report.tokens[0].labels, vec!["mainnet".to_owned()]);
+        assert_eq!(report.tokens[1].score, 1.0);
+        assert_eq!(report.flagged(0.5).count(), token_list.tokens.len() - 1);
+    }
+
+    #[test]
+    fn annotates_tokens() {
+        let mut token_list = fixtures::full();
+
+        engine().assess(&token_list).annotate(&mut token_list);
+
+        let extensions = &token_list.tokens[0].extensions;
+        assert_eq!(
+            extensions[RISK_SCORE_EXTENSION],
+            ExtensionValue::Number(Number::Float(0.2))
+        );
+        assert_eq!(
+            extensions[RISK_LABELS_EXTENSION],
+            ExtensionValue::String("mainnet".to_owned())
+        );
+    }
+
+    #[cfg(feature = "onchain")]
+    #[test]
+    fn passes_contracts_to_scorers() {
+        use crate::{ContractCheck, TokenAddress};
+
+        let token_list = fixtures::full();
+        let contracts = ContractReport {
+            contracts: vec![ContractCheck {
+                token: TokenAddress {
+                    chain_id: token_list.tokens[0].chain_id,
+                    address: token_list.tokens[0].address.clone(),
+                },
+                kind: ContractKind::Empty,
+            }],
+            unreachable_chains: Vec::new(),
+        };
+        let engine = RiskEngine::new().with_scorer(|_: &Token, context: &RiskContext<'_>| {
+            match context.contract {
+                Some(kind) if !kind.is_contract() => RiskAssessment::new(1.0),
+                _ => RiskAssessment::default(),
+            }
+        });
+
+        let report = engine.assess_with_contracts(&token_list, &contracts);
+
+        assert_eq!(report.tokens[0].score, 1.0);
+        assert_eq!(report.tokens[1].score, 0.0);
+    }
+}
@@ -0,0 +1,155 @@
+//! Search of several lists by their names, keywords, tags, and chains.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::iter::FromIterator;
+
+use crate::TokenList;
+
+/// Several fetched lists, indexed by their metadata so that UIs can browse
+/// them by category, such as stablecoins, DeFi, or a chain.
+///
+/// Unlike the ENS `ListRegistry` of the `registry` feature, which discovers
+/// lists, this only indexes lists already at hand.
+///
+/// ```
+/// # use token_list::{Registry, TokenList};
+/// let mut token_list = TokenList::from_slice(include_bytes!("../fixtures/full.json"))?;
+/// token_list.keywords = vec!["DeFi".to_owned()];
+///
+/// let registry: Registry = std::iter::once(token_list).collect();
+/// assert_eq!(registry.find_lists("defi").len(), 1);
+/// assert_eq!(registry.find_lists("telcoins").len(), 1);
+/// assert!(registry.find_lists("nft").is_empty());
+/// # Ok::<_, token_list::Error>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Registry {
+    lists: Vec<TokenList>,
+    terms: BTreeMap<String, BTreeSet<usize>>,
+    chains: BTreeMap<u32, BTreeSet<usize>>,
+}
+
+impl Registry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    /// Adds `token_list` to the registry, returning its index.
+    pub fn insert(&mut self, token_list: TokenList) -> usize {
+        let index = self.lists.len();
+        let words = token_list
+            .name
+            .split(|c: char| !c.is_alphanumeric())
+            .chain(token_list.keywords.iter().map(String::as_str))
+            .chain(
+                token_list
+                    .tags
+                    .iter()
+                    .flat_map(|(id, tag)| [id.as_str(), tag.name.as_str()]),
+            );
+        for word in words.filter(|word| !word.is_empty()) {
+            self.terms
+                .entry(word.to_lowercase())
+                .or_default()
+                .insert(index);
+        }
+        for token in &token_list.tokens {
+            self.chains.entry(token.chain_id).or_default().insert(index);
+        }
+        self.lists.push(token_list);
+        index
+    }
+
+    /// Returns the lists whose name has the word `keyword`, or that have
+    /// `keyword` as a keyword or as the identifier or name of a tag, compared
+    /// case-insensitively, in the order they were added.
+    pub fn find_lists(&self, keyword: &str) -> Vec<&TokenList> {
+        self.lookup(self.terms.get(&keyword.to_lowercase()))
+    }
+
+    /// Returns the lists with tokens on `chain_id`, in the order they were
+    /// added.
+    pub fn find_lists_on_chain(&self, chain_id: u32) -> Vec<&TokenList> {
+        self.lookup(self.chains.get(&chain_id))
+    }
+
+    /// Returns every indexed term, lowercased, with the number of lists it
+    /// finds, in alphabetical order.
+    pub fn terms(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.terms
+            .iter()
+            .map(|(term, lists)| (term.as_str(), lists.len()))
+    }
+
+    /// Returns the lists, in the order they were added.
+    pub fn lists(&self) -> &[TokenList] {
+        &self.lists
+    }
+
+    fn lookup(&self, indices: Option<&BTreeSet<usize>>) -> Vec<&TokenList> {
+        indices
+            .into_iter()
+            .flatten()
+            .map(|&index| &self.lists[index])
+            .collect()
+    }
+}
+
+impl FromIterator<TokenList> for Registry {
+    fn from_iter<I: IntoIterator<Item = TokenList>>(iter: I) -> Self {
+        let mut registry = Registry::new();
+        registry.extend(iter);
+        registry
+    }
+}
+
+impl Extend<TokenList> for Registry {
+    fn extend<I: IntoIterator<Item = TokenList>>(&mut self, iter: I) {
+        for token_list in iter {
+            self.insert(token_list);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+    use crate::Tag;
+
+    #[test]
+    fn finds_lists_by_metadata() {
+        let mut stablecoins = fixtures::full();
+        stablecoins.name = "Stable Coins".to_owned();
+        stablecoins.keywords.clear();
+        stablecoins.tags.clear();
+        stablecoins.tags.insert(
+            "usd".to_owned(),
+            Tag {
+                name: "Stablecoin".to_owned(),
+                description: "Pegged to the US dollar".to_owned(),
+            },
+        );
+        stablecoins.tokens.retain(|token| token.chain_id == 1);
+        let mut defi = fixtures::full();
+        defi.name = "Top DeFi".to_owned();
+        defi.keywords = vec!["DeFi".to_owned()];
+        defi.tokens.retain(|token| token.chain_id != 1);
+
+        let registry: Registry = vec![stablecoins, defi].into_iter().collect();
+
+        let names = |lists: Vec<&TokenList>| -> Vec<String> {
+            lists.into_iter().map(|list| list.name.clone()).collect()
+        };
+        assert_eq!(names(registry.find_lists("STABLECOIN")), ["Stable Coins"]);
+        assert_eq!(names(registry.find_lists("usd")), ["Stable Coins"]);
+        assert_eq!(names(registry.find_lists("defi")), ["Top DeFi"]);
+        assert_eq!(names(registry.find_lists("coins")), ["Stable Coins"]);
+        assert!(registry.find_lists("").is_empty());
+        assert_eq!(names(registry.find_lists_on_chain(1)), ["Stable Coins"]);
+        assert!(registry
+            .terms()
+            .any(|(term, count)| term == "top" && count == 1));
+    }
+}
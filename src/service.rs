@@ -0,0 +1,389 @@
+//! Keeping a token list up to date in the background.
+
+use std::fmt;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use arc_swap::ArcSwapOption;
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+
+#[cfg(feature = "storage")]
+use crate::Storage;
+use crate::{
+    Error, FetchOptions, Fetched, Fetcher, ReqwestFetcher, RetryPolicy, TokenList, TokenListDiff,
+};
+
+/// The fraction of the interval by which each wait between refreshes is
+/// randomly lengthened or shortened, so that processes started together
+/// don't poll the list's URI in lockstep.
+const INTERVAL_JITTER: f64 = 0.1;
+
+/// The number of diffs buffered for each subscriber of
+/// [`TokenListService::diffs`].
+const DIFF_CAPACITY: usize = 16;
+
+/// A token list refreshed in the background, shared by cheap clones of a
+/// handle.
+///
+/// The list is fetched as soon as the service is spawned, then every
+/// interval. A failed refresh keeps the current list and is retried with
+/// exponential backoff, capped at the interval, before polling resumes.
+/// Handlers read the list with [`current`](Self::current), which doesn't
+/// block or wait on a lock, while subsystems that react to changes can
+/// [`subscribe`](Self::subscribe) to new lists or to their
+/// [`diffs`](Self::diffs).
+///
+/// The background task stops once every handle has been dropped.
+///
+/// **Note**: This must be spawned from within a running tokio >1.0.0 runtime.
+#[derive(Clone)]
+pub struct TokenListService {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    uri: String,
+    current: ArcSwapOption<TokenList>,
+    updates: watch::Sender<Option<Arc<TokenList>>>,
+    diffs: broadcast::Sender<TokenListDiff>,
+    task: Mutex<Option<JoinHandle<()>>>,
+    #[cfg(feature = "storage")]
+    storage: Option<Arc<dyn Storage>>,
+}
+
+impl TokenListService {
+    /// Fetches the list at `uri` in the background, refreshing it every
+    /// `interval`.
+    pub fn spawn(uri: &str, interval: Duration) -> Self {
+        Self::spawn_with_options(uri, interval, FetchOptions::default())
+    }
+
+    /// Fetches the list at `uri` in the background using the provided
+    /// [`FetchOptions`], refreshing it every `interval`, and retrying failed
+    /// refreshes with the backoff of their [`RetryPolicy`].
+    pub fn spawn_with_options(uri: &str, interval: Duration, options: FetchOptions) -> Self {
+        let retry = options.retry.clone();
+        Self::spawn_with_fetcher(Arc::new(ReqwestFetcher::new(options)), uri, interval, retry)
+    }
+
+    /// Fetches the list at `uri` in the background using the provided
+    /// [`Fetcher`], refreshing it every `interval`, and retrying failed
+    /// refreshes with the backoff of `retry`.
+    pub fn spawn_with_fetcher(
+        fetcher: Arc<dyn Fetcher>,
+        uri: &str,
+        interval: Duration,
+        retry: RetryPolicy,
+    ) -> Self {
+        let shared = Shared {
+            uri: uri.to_owned(),
+            current: ArcSwapOption::empty(),
+            updates: watch::channel(None).0,
+            diffs: broadcast::channel(DIFF_CAPACITY).0,
+            task: Mutex::new(None),
+            #[cfg(feature = "storage")]
+            storage: None,
+        };
+        Self::start(shared, fetcher, interval, retry)
+    }
+
+    /// Fetches the list at `uri` in the background using the provided
+    /// [`Fetcher`], refreshing it every `interval`, and putting each new list
+    /// in `storage`.
+    ///
+    /// A refresh whose list can't be stored fails, and is retried with the
+    /// backoff of `retry`.
+    #[cfg(feature = "storage")]
+    pub fn spawn_with_storage(
+        fetcher: Arc<dyn Fetcher>,
+        storage: Arc<dyn Storage>,
+        uri: &str,
+        interval: Duration,
+        retry: RetryPolicy,
+    ) -> Self {
+        let shared = Shared {
+            uri: uri.to_owned(),
+            current: ArcSwapOption::empty(),
+            updates: watch::channel(None).0,
+            diffs: broadcast::channel(DIFF_CAPACITY).0,
+            task: Mutex::new(None),
+            storage: Some(storage),
+        };
+        Self::start(shared, fetcher, interval, retry)
+    }
+
+    fn start(
+        shared: Shared,
+        fetcher: Arc<dyn Fetcher>,
+        interval: Duration,
+        retry: RetryPolicy,
+    ) -> Self {
+        let shared = Arc::new(shared);
+        let task = tokio::spawn(run(Arc::downgrade(&shared), fetcher, interval, retry));
+        *shared.task.lock().unwrap() = Some(task);
+        TokenListService { shared }
+    }
+
+    /// Returns the latest list fetched, or `None` until the first fetch
+    /// succeeds.
+    pub fn current(&self) -> Option<Arc<TokenList>> {
+        self.shared.current.load_full()
+    }
+
+    /// Returns a receiver of the latest list, which is marked as changed each
+    /// time a refresh fetches a list that differs from the current one.
+    pub fn subscribe(&self) -> watch::Receiver<Option<Arc<TokenList>>> {
+        self.shared.updates.subscribe()
+    }
+
+    /// Returns a receiver of the changes between each list and the one it
+    /// replaces, starting with the next change.
+    ///
+    /// Diffs are only computed while there are receivers. A receiver that
+    /// falls more than a few diffs behind skips the oldest, receiving
+    /// [`RecvError::Lagged`](broadcast::error::RecvError::Lagged) instead.
+    pub fn diffs(&self) -> broadcast::Receiver<TokenListDiff> {
+        self.shared.diffs.subscribe()
+    }
+
+    /// Returns the URI the list is fetched from.
+    pub fn uri(&self) -> &str {
+        &self.shared.uri
+    }
+}
+
+impl fmt::Debug for TokenListService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenListService")
+            .field("uri", &self.shared.uri)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.get_mut().unwrap().take() {
+            task.abort();
+        }
+    }
+}
+
+impl Shared {
+    /// Replaces the current list with `token_list` if they differ, notifying
+    /// subscribers.
+    fn update(&self, token_list: TokenList) {
+        let previous = self.current.load_full();
+        if previous.as_deref() == Some(&token_list) {
+            return;
+        }
+        if let Some(previous) = &previous {
+            if self.diffs.receiver_count() > 0 {
+                let _ = self.diffs.send(previous.diff(&token_list));
+            }
+        }
+
+        let token_list = Arc::new(token_list);
+        self.current.store(Some(token_list.clone()));
+        self.updates.send_replace(Some(token_list));
+    }
+}
+
+/// Refreshes the list until the service is dropped.
+async fn run(
+    shared: Weak<Shared>,
+    fetcher: Arc<dyn Fetcher>,
+    interval: Duration,
+    retry: RetryPolicy,
+) {
+    let mut etag = None;
+    let mut failures = 0;
+    loop {
+        let shared_ref = match shared.upgrade() {
+            Some(shared) => shared,
+            None => return,
+        };
+        let result = refresh(&*fetcher, &shared_ref.uri, etag.as_deref()).await;
+        #[cfg(feature = "storage")]
+        let result = match (result, &shared_ref.storage) {
+            (Ok(Some(fetched)), Some(storage)) => {
+                storage.put_list(&fetched.0).await.map(|()| Some(fetched))
+            }
+            (result, _) => result,
+        };
+        match result {
+            Ok(Some((token_list, new_etag))) => {
+                #[cfg(feature = "metrics")]
+                crate::telemetry::refreshed(&shared_ref.uri, Ok(Some(&token_list)));
+                shared_ref.update(token_list);
+                etag = new_etag;
+                failures = 0;
+            }
+            Ok(None) => {
+                #[cfg(feature = "metrics")]
+                crate::telemetry::refreshed(&shared_ref.uri, Ok(None));
+                failures = 0;
+            }
+            Err(_) => {
+                #[cfg(feature = "metrics")]
+                crate::telemetry::refreshed(&shared_ref.uri, Err(()));
+                failures += 1;
+            }
+        }
+        drop(shared_ref);
+
+        let delay = if failures > 0 && failures < retry.max_attempts {
+            retry.delay(failures - 1).min(interval)
+        } else {
+            failures = 0;
+            jitter(interval)
+        };
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Fetches `uri` unless it's unchanged since `etag`, returning the list and
+/// its new entity tag.
+async fn refresh(
+    fetcher: &dyn Fetcher,
+    uri: &str,
+    etag: Option<&str>,
+) -> Result<Option<(TokenList, Option<String>)>, Error> {
+    match fetcher.fetch_if_modified(uri, etag).await? {
+        Fetched::Modified { body, etag } => Ok(Some((TokenList::from_slice(&body)?, etag))),
+        Fetched::NotModified => Ok(None),
+    }
+}
+
+/// Randomly lengthens or shortens `interval` by up to [`INTERVAL_JITTER`].
+fn jitter(interval: Duration) -> Duration {
+    interval.mul_f64(1.0 + INTERVAL_JITTER * (2.0 * fastrand::f64() - 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+
+    use super::*;
+    use crate::test_server::TOKEN_LIST_JSON;
+    use crate::test_utils::{fixtures, MockFetcher, MockResponse};
+
+    const URI: &str = "memory://list.json";
+
+    /// Returns a fetcher that fails its first fetch, then serves versions
+    /// `0.1.0`, `0.2.0`, and `0.3.0` from then on.
+    fn flaky_fetcher() -> MockFetcher {
+        let version = |minor: u32| {
+            let json = TOKEN_LIST_JSON.replace("\"minor\": 1", &format!("\"minor\": {}", minor));
+            MockResponse::body(json)
+        };
+        let fetcher = MockFetcher::new();
+        fetcher
+            .respond_once(URI, MockResponse::error("connection reset"))
+            .respond_once(URI, version(1))
+            .respond_once(URI, version(2))
+            .respond(URI, version(3));
+        fetcher
+    }
+
+    fn retry() -> RetryPolicy {
+        RetryPolicy {
+            backoff_base: Duration::from_millis(10),
+            ..RetryPolicy::default()
+        }
+    }
+
+    async fn wait_for(service: &TokenListService, version: Version) {
+        for _ in 0..200 {
+            if service
+                .current()
+                .is_some_and(|token_list| token_list.version >= version)
+            {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("timed out waiting for version {}", version);
+    }
+
+    #[tokio::test]
+    async fn retries_then_refreshes() {
+        let fetcher = Arc::new(flaky_fetcher());
+        let service = TokenListService::spawn_with_fetcher(
+            fetcher.clone(),
+            URI,
+            Duration::from_millis(50),
+            retry(),
+        );
+        assert_eq!(service.current(), None);
+
+        wait_for(&service, Version::new(0, 1, 0)).await;
+        wait_for(&service, Version::new(0, 3, 0)).await;
+        assert_eq!(service.clone().uri(), URI);
+
+        drop(service);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let fetches = fetcher.requests().len();
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(fetcher.requests().len(), fetches);
+    }
+
+    #[tokio::test]
+    async fn publishes_updates_and_diffs() {
+        let first = fixtures::full();
+        let mut second = first.clone();
+        second.version = Version::new(1, 0, 0);
+        let removed = second.tokens.remove(0);
+        let fetcher = MockFetcher::new();
+        fetcher
+            .respond_once(URI, MockResponse::json(&first))
+            .respond_once(URI, MockResponse::json(&first))
+            .respond(URI, MockResponse::json(&second));
+
+        let service = TokenListService::spawn_with_fetcher(
+            Arc::new(fetcher),
+            URI,
+            Duration::from_millis(10),
+            retry(),
+        );
+        let mut updates = service.subscribe();
+        let mut diffs = service.diffs();
+
+        updates.changed().await.unwrap();
+        assert!(updates.borrow_and_update().is_some());
+
+        let diff = diffs.recv().await.unwrap();
+        assert_eq!(diff.removed, vec![removed]);
+        assert!(diff.added.is_empty() && diff.changed.is_empty());
+
+        updates.changed().await.unwrap();
+        assert_eq!(updates.borrow().as_deref(), Some(&second));
+        assert_eq!(service.current().as_deref(), Some(&second));
+    }
+
+    #[cfg(feature = "storage")]
+    #[tokio::test]
+    async fn puts_new_lists_in_storage() {
+        let storage = Arc::new(crate::MemoryStorage::new());
+        let service = TokenListService::spawn_with_storage(
+            Arc::new(flaky_fetcher()),
+            storage.clone(),
+            URI,
+            Duration::from_millis(50),
+            retry(),
+        );
+
+        wait_for(&service, Version::new(0, 1, 0)).await;
+        let versions = storage.list_versions("TELcoins").await.unwrap();
+        assert!(versions.contains(&Version::new(0, 1, 0)));
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let interval = Duration::from_secs(60);
+        for _ in 0..100 {
+            let delay = jitter(interval);
+            assert!(delay >= Duration::from_secs(54) && delay <= Duration::from_secs(66));
+        }
+    }
+}
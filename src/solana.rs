@@ -0,0 +1,224 @@
+//! Conversion of lists in the legacy Solana token list format.
+
+use std::convert::TryFrom;
+
+use indexmap::IndexMap;
+use semver::Version;
+use serde::Deserialize;
+use url::Url;
+
+use crate::{timestamp, Error, ExtensionValue, Tag, Token, TokenList};
+
+/// The extension holding the Solana cluster of converted tokens, such as
+/// `mainnet-beta`.
+pub const SOLANA_CLUSTER_EXTENSION: &str = "solanaCluster";
+
+/// The extension holding the mint address of converted tokens.
+pub const SOLANA_MINT_EXTENSION: &str = "solanaMint";
+
+/// The chain IDs Solana token lists use for its clusters.
+const CLUSTERS: &[(u32, &str)] = &[(101, "mainnet-beta"), (102, "testnet"), (103, "devnet")];
+
+impl TokenList {
+    /// Converts a list in the legacy Solana token list format, as published
+    /// by the `solana-labs/token-list` repository.
+    ///
+    /// Tokens keep Solana's chain IDs, `101` to `103`, and have their cluster
+    /// and mint address recorded in the [`SOLANA_CLUSTER_EXTENSION`] and
+    /// [`SOLANA_MINT_EXTENSION`] extensions, so that they can be told apart
+    /// from EVM tokens. Timestamps with offsets written without a colon, such
+    /// as `+0000`, are accepted, and extensions holding arrays or objects
+    /// are dropped.
+    pub fn from_solana_slice(bytes: &[u8]) -> Result<Self, Error> {
+        let list: SolanaTokenList = crate::json::from_slice(bytes)?;
+
+        let timestamp = timestamp::parse_rfc3339(&list.timestamp)
+            .or_else(|| timestamp::parse_rfc3339(&with_offset_colon(&list.timestamp)?))
+            .ok_or_else(|| invalid_timestamp(&list.timestamp))?;
+
+        let tokens = list
+            .tokens
+            .into_iter()
+            .map(|token| {
+                let mut extensions: IndexMap<String, ExtensionValue> = token
+                    .extensions
+                    .into_iter()
+                    .filter_map(|(key, value)| Some((key, ExtensionValue::try_from(value).ok()?)))
+                    .collect();
+                let chain_id = token.chain_id;
+                if let Some(&(_, cluster)) = CLUSTERS.iter().find(|(id, _)| *id == chain_id) {
+                    extensions.insert(
+                        SOLANA_CLUSTER_EXTENSION.to_owned(),
+                        ExtensionValue::String(cluster.to_owned()),
+                    );
+                }
+                extensions.insert(
+                    SOLANA_MINT_EXTENSION.to_owned(),
+                    ExtensionValue::String(token.address.clone()),
+                );
+
+                Token {
+                    name: token.name,
+                    symbol: token.symbol,
+                    address: token.address,
+                    chain_id: token.chain_id,
+                    decimals: token.decimals,
+                    logo_uri: token.logo_uri.and_then(|uri| Url::parse(&uri).ok()),
+                    tags: token.tags,
+                    extensions,
+                    #[cfg(feature = "unknown-fields")]
+                    unknown_fields: Default::default(),
+                }
+            })
+            .collect();
+
+        Ok(TokenList {
+            name: list.name,
+            timestamp,
+            version: list.version,
+            logo_uri: list.logo_uri.and_then(|uri| Url::parse(&uri).ok()),
+            keywords: list.keywords,
+            tags: list.tags,
+            tokens,
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: Default::default(),
+        })
+    }
+}
+
+/// Inserts the colon missing from an offset such as `+0000` at the end of
+/// `timestamp`, or returns `None` if it doesn't end with one.
+fn with_offset_colon(timestamp: &str) -> Option<String> {
+    let bytes = timestamp.as_bytes();
+    let offset = bytes.len().checked_sub(5)?;
+    let is_offset =
+        matches!(bytes[offset], b'+' | b'-') && bytes[offset + 1..].iter().all(u8::is_ascii_digit);
+    if !is_offset {
+        return None;
+    }
+    // the offset is ASCII, so splitting within it splits at a char boundary
+    let (rest, minutes) = timestamp.split_at(offset + 3);
+    Some(format!("{}:{}", rest, minutes))
+}
+
+fn invalid_timestamp(timestamp: &str) -> Error {
+    Error::Parse(serde::de::Error::custom(format_args!(
+        "invalid timestamp `{}`",
+        timestamp
+    )))
+}
+
+/// A list in the legacy Solana format, which differs from the token list
+/// schema in its timestamps and in allowing logo URIs that aren't URLs.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SolanaTokenList {
+    name: String,
+    #[serde(rename = "logoURI")]
+    logo_uri: Option<String>,
+    #[serde(default)]
+    keywords: Vec<String>,
+    #[serde(default)]
+    tags: IndexMap<String, Tag>,
+    timestamp: String,
+    #[serde(with = "crate::version")]
+    version: Version,
+    tokens: Vec<SolanaToken>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SolanaToken {
+    chain_id: u32,
+    address: String,
+    symbol: String,
+    name: String,
+    decimals: u8,
+    #[serde(rename = "logoURI")]
+    logo_uri: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    extensions: IndexMap<String, serde_json::Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOLANA: &str = r#"{
+        "name": "Solana Token List",
+        "logoURI": "https://cdn.jsdelivr.net/gh/trustwallet/assets@master/blockchains/solana/info/logo.png",
+        "keywords": ["solana", "spl"],
+        "tags": {
+            "stablecoin": { "name": "stablecoin", "description": "Tokens that are fixed to an external asset" }
+        },
+        "timestamp": "2021-03-03T19:57:21+0000",
+        "tokens": [
+            {
+                "chainId": 101,
+                "address": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+                "symbol": "USDC",
+                "name": "USD Coin",
+                "decimals": 6,
+                "logoURI": "https://raw.githubusercontent.com/solana-labs/token-list/main/assets/mainnet/EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v/logo.png",
+                "tags": ["stablecoin"],
+                "extensions": {
+                    "coingeckoId": "usd-coin",
+                    "website": "https://www.centre.io/",
+                    "serumV3Usdt": "77quYg4MGneUdjgXCunt9GgM1usmrxKY31twEy3WHwcS",
+                    "bridgeContract": { "ethereum": "0x0" }
+                }
+            },
+            {
+                "chainId": 103,
+                "address": "So11111111111111111111111111111111111111112",
+                "symbol": "SOL",
+                "name": "Wrapped SOL",
+                "decimals": 9,
+                "logoURI": "ipfs/not-a-url"
+            }
+        ],
+        "version": { "major": 0, "minor": 2, "patch": 2 }
+    }"#;
+
+    #[test]
+    fn converts_solana_lists() {
+        let token_list = TokenList::from_solana_slice(SOLANA.as_bytes()).unwrap();
+
+        assert_eq!(
+            timestamp::to_rfc3339(&token_list.timestamp),
+            "2021-03-03T19:57:21Z"
+        );
+        assert_eq!(token_list.version, Version::new(0, 2, 2));
+        assert!(token_list.tags.contains_key("stablecoin"));
+
+        let usdc = &token_list.tokens[0];
+        assert_eq!(usdc.chain_id, 101);
+        assert_eq!(usdc.tags, ["stablecoin"]);
+        assert_eq!(
+            usdc.extensions[SOLANA_CLUSTER_EXTENSION].as_str(),
+            Some("mainnet-beta")
+        );
+        assert_eq!(
+            usdc.extensions[SOLANA_MINT_EXTENSION].as_str(),
+            Some("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v")
+        );
+        assert_eq!(usdc.extensions["coingeckoId"].as_str(), Some("usd-coin"));
+        assert!(!usdc.extensions.contains_key("bridgeContract"));
+
+        let sol = &token_list.tokens[1];
+        assert_eq!(
+            sol.extensions[SOLANA_CLUSTER_EXTENSION].as_str(),
+            Some("devnet")
+        );
+        assert_eq!(sol.logo_uri, None);
+    }
+
+    #[test]
+    fn rejects_invalid_timestamps() {
+        let json = SOLANA.replace("2021-03-03T19:57:21+0000", "yesterday");
+
+        assert!(TokenList::from_solana_slice(json.as_bytes()).is_err());
+    }
+}
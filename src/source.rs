@@ -0,0 +1,418 @@
+//! Fetching a token list from a primary URI with failover to mirrors.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::future::{self, Either};
+use futures::stream::{FuturesUnordered, StreamExt};
+// `std::time::Instant` panics on wasm32
+use instant::Instant;
+use semver::Version;
+
+use crate::fetch::sleep;
+use crate::hash::content_hash;
+use crate::{Error, FetchOptions, Fetcher, ReqwestFetcher, Timestamp, TokenList};
+
+/// A token list published at a primary URI and at mirrors of it, fetched
+/// from whichever source is available.
+///
+/// Sources are tried in order, moving on to the next when one fails, or are
+/// raced with [`racing`](Self::racing), starting the next source whenever the
+/// ones in flight haven't answered within a delay.
+///
+/// The primary source is trusted; the content hash of each version it serves
+/// is remembered, and a mirror serving a version the primary has served with
+/// different content fails with [`Error::MirrorMismatch`].
+pub struct SourceSet {
+    uris: Vec<String>,
+    fetcher: Arc<dyn Fetcher>,
+    stagger: Option<Duration>,
+    verified: Mutex<HashMap<Version, [u8; 32]>>,
+}
+
+/// A list fetched from a [`SourceSet`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct SourcedList {
+    /// The fetched list
+    pub token_list: TokenList,
+
+    /// The URI of the source the list was fetched from
+    pub uri: String,
+
+    /// Whether the list was fetched from a mirror rather than the primary
+    /// source
+    pub from_mirror: bool,
+}
+
+/// The failure of one source of a [`SourceSet`].
+#[derive(Debug)]
+pub struct SourceFailure {
+    /// The URI of the source
+    pub uri: String,
+
+    /// Why fetching from the source failed
+    pub error: Error,
+}
+
+/// The health of each source of a [`SourceSet`], as reported by
+/// [`SourceSet::health_check`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct HealthReport {
+    /// The health of each source, primary first
+    pub sources: Vec<SourceHealth>,
+}
+
+impl HealthReport {
+    /// Returns `true` if every source served a list, with the same content.
+    pub fn is_healthy(&self) -> bool {
+        self.sources.iter().all(|source| source.error.is_none()) && self.in_agreement()
+    }
+
+    /// Returns `true` if every source that served a list served the same
+    /// content.
+    pub fn in_agreement(&self) -> bool {
+        let mut hashes = self.sources.iter().filter_map(|source| source.sha256);
+        match hashes.next() {
+            Some(first) => hashes.all(|hash| hash == first),
+            None => true,
+        }
+    }
+}
+
+/// The health of one source of a [`SourceSet`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct SourceHealth {
+    /// The URI of the source
+    pub uri: String,
+
+    /// The time taken to fetch and parse the list, or to fail
+    pub latency: Duration,
+
+    /// The HTTP status of the response: `200` for a list served over HTTP,
+    /// the status of a failed response, or `None` if no response was received
+    /// or the URI isn't an HTTP URI
+    pub status: Option<u16>,
+
+    /// The version of the list served
+    pub version: Option<Version>,
+
+    /// The timestamp of the list served
+    pub timestamp: Option<Timestamp>,
+
+    /// How long before the check the list served was last updated, by its
+    /// timestamp
+    pub staleness: Option<chrono::Duration>,
+
+    /// The SHA-256 digest of the canonical JSON of the list served
+    pub sha256: Option<[u8; 32]>,
+
+    /// Whether the list served has the same content as the primary
+    /// source's, or `None` if either failed
+    pub agrees_with_primary: Option<bool>,
+
+    /// Why the source failed, if it did
+    pub error: Option<String>,
+}
+
+impl SourceSet {
+    /// Creates a set of sources with `primary` as its only source.
+    pub fn new(primary: &str) -> Self {
+        Self::with_options(primary, FetchOptions::default())
+    }
+
+    /// Creates a set of sources with `primary` as its only source, fetching
+    /// lists using the provided [`FetchOptions`].
+    pub fn with_options(primary: &str, options: FetchOptions) -> Self {
+        Self::with_fetcher(primary, Arc::new(ReqwestFetcher::new(options)))
+    }
+
+    /// Creates a set of sources with `primary` as its only source, fetching
+    /// lists using the provided [`Fetcher`].
+    pub fn with_fetcher(primary: &str, fetcher: Arc<dyn Fetcher>) -> Self {
+        SourceSet {
+            uris: vec![primary.to_owned()],
+            fetcher,
+            stagger: None,
+            verified: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Adds a mirror, tried after the primary source and any mirrors added
+    /// before it.
+    pub fn with_mirror(mut self, uri: &str) -> Self {
+        self.uris.push(uri.to_owned());
+        self
+    }
+
+    /// Races the sources, starting the next one whenever none of those in
+    /// flight has answered within `stagger`, and taking the first list
+    /// fetched.
+    pub fn racing(mut self, stagger: Duration) -> Self {
+        self.stagger = Some(stagger);
+        self
+    }
+
+    /// Returns the URI of the primary source.
+    pub fn primary(&self) -> &str {
+        &self.uris[0]
+    }
+
+    /// Returns the URIs of the mirrors, in the order they're tried.
+    pub fn mirrors(&self) -> &[String] {
+        &self.uris[1..]
+    }
+
+    /// Fetches the list from the first source that serves it.
+    ///
+    /// If every source fails, returns [`Error::SourcesFailed`] with the
+    /// failure of each.
+    pub async fn fetch(&self) -> Result<SourcedList, Error> {
+        let mut pending = FuturesUnordered::new();
+        let mut started = 0;
+        let mut failures = Vec::new();
+        loop {
+            if pending.is_empty() {
+                if started == self.uris.len() {
+                    return Err(Error::SourcesFailed { failures });
+                }
+                pending.push(self.fetch_source(started));
+                started += 1;
+            }
+
+            let outcome = match self.stagger {
+                Some(stagger) if started < self.uris.len() => {
+                    match future::select(pending.next(), Box::pin(sleep(stagger))).await {
+                        Either::Left((outcome, _)) => outcome,
+                        Either::Right(_) => None,
+                    }
+                }
+                _ => pending.next().await,
+            };
+            let (index, result) = match outcome {
+                Some(outcome) => outcome,
+                None => {
+                    pending.push(self.fetch_source(started));
+                    started += 1;
+                    continue;
+                }
+            };
+
+            match result {
+                Ok(token_list) => {
+                    return Ok(SourcedList {
+                        token_list,
+                        uri: self.uris[index].clone(),
+                        from_mirror: index > 0,
+                    })
+                }
+                Err(error) => failures.push(SourceFailure {
+                    uri: self.uris[index].clone(),
+                    error,
+                }),
+            }
+        }
+    }
+
+    /// Fetches the list from every source at once, reporting how long each
+    /// took, what it served, and whether the mirrors agree with the primary
+    /// source.
+    ///
+    /// Lists fetched by a health check aren't verified or remembered.
+    pub async fn health_check(&self) -> HealthReport {
+        let checks = self.uris.iter().map(|uri| self.check(uri));
+        let mut sources = future::join_all(checks).await;
+
+        if let Some(primary) = sources[0].sha256 {
+            for source in &mut sources[1..] {
+                source.agrees_with_primary = source.sha256.map(|hash| hash == primary);
+            }
+            sources[0].agrees_with_primary = Some(true);
+        }
+        HealthReport { sources }
+    }
+
+    async fn check(&self, uri: &str) -> SourceHealth {
+        let start = Instant::now();
+        let result = match TokenList::from_fetcher(&*self.fetcher, uri).await {
+            Ok(token_list) => content_hash(&token_list)
+                .map(|hash| (token_list, hash))
+                .map_err(Error::from),
+            Err(err) => Err(err),
+        };
+        let latency = start.elapsed();
+        let is_http = uri.starts_with("http://") || uri.starts_with("https://");
+
+        let mut health = SourceHealth {
+            uri: uri.to_owned(),
+            latency,
+            status: None,
+            version: None,
+            timestamp: None,
+            staleness: None,
+            sha256: None,
+            agrees_with_primary: None,
+            error: None,
+        };
+        match result {
+            Ok((token_list, hash)) => {
+                health.status = if is_http { Some(200) } else { None };
+                health.staleness = Some(crate::timestamp::elapsed(&token_list.timestamp));
+                health.timestamp = Some(token_list.timestamp);
+                health.version = Some(token_list.version);
+                health.sha256 = Some(hash);
+            }
+            Err(err) => {
+                health.status = err.status();
+                health.error = Some(err.to_string());
+            }
+        }
+        health
+    }
+
+    /// Fetches the list from the source at `index`, verifying it against the
+    /// versions the primary source has served.
+    async fn fetch_source(&self, index: usize) -> (usize, Result<TokenList, Error>) {
+        let uri = &self.uris[index];
+        let result = match TokenList::from_fetcher(&*self.fetcher, uri).await {
+            Ok(token_list) => self.verify(index, token_list),
+            Err(err) => Err(err),
+        };
+        (index, result)
+    }
+
+    fn verify(&self, index: usize, token_list: TokenList) -> Result<TokenList, Error> {
+        let hash = content_hash(&token_list)?;
+        let mut verified = self.verified.lock().unwrap();
+        if index == 0 {
+            verified.insert(token_list.version.clone(), hash);
+        } else if verified
+            .get(&token_list.version)
+            .is_some_and(|expected| *expected != hash)
+        {
+            return Err(Error::MirrorMismatch {
+                uri: self.uris[index].clone(),
+                version: token_list.version,
+            });
+        }
+        Ok(token_list)
+    }
+}
+
+impl fmt::Debug for SourceSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SourceSet")
+            .field("uris", &self.uris)
+            .field("stagger", &self.stagger)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::TOKEN_LIST_JSON;
+    use crate::test_utils::{MockFetcher, MockResponse};
+
+    /// Returns a fetcher serving each body at its URI after a delay in
+    /// milliseconds; other URIs fail.
+    fn fetcher(lists: Vec<(&str, u64, &str)>) -> Arc<MockFetcher> {
+        let fetcher = MockFetcher::new();
+        for (uri, delay, body) in lists {
+            fetcher.respond(
+                uri,
+                MockResponse::body(body).with_latency(Duration::from_millis(delay)),
+            );
+        }
+        Arc::new(fetcher)
+    }
+
+    fn sources(fetcher: Arc<MockFetcher>) -> SourceSet {
+        SourceSet::with_fetcher("memory://primary", fetcher)
+            .with_mirror("memory://first")
+            .with_mirror("memory://second")
+    }
+
+    #[tokio::test]
+    async fn fails_over_in_order() {
+        let sources = sources(fetcher(vec![
+            ("memory://first", 0, "{}"),
+            ("memory://second", 0, TOKEN_LIST_JSON),
+        ]));
+
+        let sourced = sources.fetch().await.unwrap();
+        assert_eq!(sourced.uri, "memory://second");
+        assert!(sourced.from_mirror);
+
+        let sources = SourceSet::with_fetcher("memory://primary", fetcher(Vec::new()))
+            .with_mirror("memory://first");
+        match sources.fetch().await {
+            Err(Error::SourcesFailed { failures }) => {
+                let uris: Vec<_> = failures.iter().map(|failure| &failure.uri[..]).collect();
+                assert_eq!(uris, ["memory://primary", "memory://first"]);
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[tokio::test]
+    async fn races_slow_sources() {
+        let sources = sources(fetcher(vec![
+            ("memory://primary", 1000, TOKEN_LIST_JSON),
+            ("memory://first", 1000, TOKEN_LIST_JSON),
+            ("memory://second", 0, TOKEN_LIST_JSON),
+        ]))
+        .racing(Duration::from_millis(20));
+
+        let start = Instant::now();
+        let sourced = sources.fetch().await.unwrap();
+        assert_eq!(sourced.uri, "memory://second");
+        assert!(start.elapsed() < Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn checks_health() {
+        let newer = TOKEN_LIST_JSON.replace("\"minor\": 1", "\"minor\": 2");
+        let sources = sources(fetcher(vec![
+            ("memory://primary", 0, TOKEN_LIST_JSON),
+            ("memory://first", 20, TOKEN_LIST_JSON),
+            ("memory://second", 0, &newer),
+        ]))
+        .with_mirror("memory://missing");
+
+        let report = sources.health_check().await;
+        let agreement: Vec<_> = report
+            .sources
+            .iter()
+            .map(|source| source.agrees_with_primary)
+            .collect();
+        assert_eq!(agreement, [Some(true), Some(true), Some(false), None]);
+        assert!(!report.in_agreement());
+        assert!(!report.is_healthy());
+
+        let first = &report.sources[1];
+        assert!(first.latency >= Duration::from_millis(20));
+        assert_eq!(first.version, Some(Version::new(0, 1, 0)));
+        assert!(first.staleness.unwrap() > chrono::Duration::days(365));
+        assert_eq!(first.status, None);
+        assert!(report.sources[3].error.is_some());
+    }
+
+    #[tokio::test]
+    async fn rejects_mirrors_that_differ_from_the_primary() {
+        let tampered = TOKEN_LIST_JSON.replace("TELcoins", "Tampered");
+        let fetcher = fetcher(vec![("memory://first", 0, &tampered)]);
+        // the primary is only available for the first fetch
+        fetcher.respond_once("memory://primary", MockResponse::body(TOKEN_LIST_JSON));
+        let sources = sources(fetcher);
+        assert!(!sources.fetch().await.unwrap().from_mirror);
+
+        match sources.fetch().await {
+            Err(Error::SourcesFailed { failures }) => {
+                assert!(matches!(failures[1].error, Error::MirrorMismatch { .. }));
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,495 @@
+//! Storing token lists in SQLite.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use indexmap::IndexMap;
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OptionalExtension};
+use semver::Version;
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::{Error, ExtensionValue, Tag, Token, TokenList};
+
+/// The schema, created when a store is opened.
+///
+/// Tokens are shared between the versions of a list they are unchanged in, so
+/// storing a new version only inserts the tokens it adds or changes. Versions
+/// are stored in full, alongside a key that sorts them by precedence.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS lists (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    version_key TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    logo_uri TEXT,
+    UNIQUE (name, version)
+);
+CREATE INDEX IF NOT EXISTS lists_by_version ON lists (name, version_key);
+CREATE TABLE IF NOT EXISTS keywords (
+    list_id INTEGER NOT NULL REFERENCES lists (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    keyword TEXT NOT NULL,
+    PRIMARY KEY (list_id, position)
+);
+CREATE TABLE IF NOT EXISTS tags (
+    list_id INTEGER NOT NULL REFERENCES lists (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    PRIMARY KEY (list_id, position)
+);
+CREATE TABLE IF NOT EXISTS tokens (
+    id INTEGER PRIMARY KEY,
+    list_name TEXT NOT NULL,
+    chain_id INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    name TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    decimals INTEGER NOT NULL,
+    logo_uri TEXT,
+    content_hash BLOB NOT NULL,
+    UNIQUE (list_name, chain_id, address, content_hash)
+);
+CREATE INDEX IF NOT EXISTS tokens_by_address ON tokens (chain_id, address COLLATE NOCASE);
+CREATE TABLE IF NOT EXISTS token_tags (
+    token_id INTEGER NOT NULL REFERENCES tokens (id),
+    position INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (token_id, position)
+);
+CREATE TABLE IF NOT EXISTS extensions (
+    token_id INTEGER NOT NULL REFERENCES tokens (id),
+    position INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (token_id, position)
+);
+CREATE TABLE IF NOT EXISTS list_tokens (
+    list_id INTEGER NOT NULL REFERENCES lists (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    token_id INTEGER NOT NULL REFERENCES tokens (id),
+    PRIMARY KEY (list_id, position)
+);
+";
+
+/// Token lists stored in a normalized SQLite schema, with a table each for
+/// lists, keywords, tags, tokens, token tags, and extensions, so their
+/// metadata can be queried with SQL.
+///
+/// A list is identified by its name and version; storing a version that is
+/// already stored replaces it. Extension values are stored as JSON.
+///
+/// The connection is shared behind a lock by clones of the store. Queries
+/// block the calling thread, except through the [`Storage`](crate::Storage)
+/// impl, which runs them on tokio's blocking thread pool.
+#[derive(Clone, Debug)]
+pub struct SqliteStore {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStore {
+    /// Opens the database at `path`, creating it and the schema if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Opens a database in memory.
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// Stores lists through `connection`, creating the schema if needed.
+    pub fn from_connection(connection: Connection) -> Result<Self, Error> {
+        connection.execute_batch("PRAGMA foreign_keys = ON;")?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(SqliteStore {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// Returns the underlying connection, e.g. to query tokens with SQL.
+    pub fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.connection.lock().unwrap()
+    }
+
+    /// Stores `token_list`, replacing any list with the same name and version.
+    pub fn store(&self, token_list: &TokenList) -> Result<(), Error> {
+        let version = &token_list.version;
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+
+        let list_id: i64 = transaction.query_row(
+            "INSERT INTO lists (name, version, version_key, timestamp, logo_uri)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (name, version)
+             DO UPDATE SET timestamp = excluded.timestamp, logo_uri = excluded.logo_uri
+             RETURNING id",
+            params![
+                token_list.name,
+                version.to_string(),
+                crate::version::sort_key(version),
+                crate::timestamp::to_rfc3339(&token_list.timestamp),
+                token_list.logo_uri.as_ref().map(Url::as_str),
+            ],
+            |row| row.get(0),
+        )?;
+        for table in &["keywords", "tags", "list_tokens"] {
+            transaction.execute(
+                &format!("DELETE FROM {} WHERE list_id = ?1", table),
+                [list_id],
+            )?;
+        }
+
+        for (position, keyword) in token_list.keywords.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO keywords (list_id, position, keyword) VALUES (?1, ?2, ?3)",
+                params![list_id, position as i64, keyword],
+            )?;
+        }
+        for (position, (id, tag)) in token_list.tags.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO tags (list_id, position, id, name, description)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![list_id, position as i64, id, tag.name, tag.description],
+            )?;
+        }
+
+        for (position, token) in token_list.tokens.iter().enumerate() {
+            let content_hash = Sha256::digest(serde_json::to_vec(&serde_json::to_value(token)?)?);
+            let existing = transaction
+                .query_row(
+                    "SELECT id FROM tokens
+                     WHERE list_name = ?1 AND chain_id = ?2 AND address = ?3
+                         AND content_hash = ?4",
+                    params![
+                        token_list.name,
+                        token.chain_id,
+                        token.address,
+                        &content_hash[..]
+                    ],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let token_id: i64 = match existing {
+                Some(token_id) => token_id,
+                None => insert_token(&transaction, &token_list.name, token, &content_hash)?,
+            };
+            transaction.execute(
+                "INSERT INTO list_tokens (list_id, position, token_id) VALUES (?1, ?2, ?3)",
+                params![list_id, position as i64, token_id],
+            )?;
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Loads the list named `name` at `version`, if it's stored.
+    pub fn load(&self, name: &str, version: &Version) -> Result<Option<TokenList>, Error> {
+        let connection = self.connection();
+        let list_id = connection
+            .query_row(
+                "SELECT id FROM lists WHERE name = ?1 AND version = ?2",
+                params![name, version.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        list_id
+            .map(|list_id| load_list(&connection, list_id))
+            .transpose()
+    }
+
+    /// Loads the latest version of the list named `name`, if any is stored.
+    pub fn load_latest(&self, name: &str) -> Result<Option<TokenList>, Error> {
+        let connection = self.connection();
+        let list_id = connection
+            .query_row(
+                "SELECT id FROM lists WHERE name = ?1
+                 ORDER BY version_key DESC, id DESC LIMIT 1",
+                [name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        list_id
+            .map(|list_id| load_list(&connection, list_id))
+            .transpose()
+    }
+
+    /// Returns the stored versions of the list named `name`, oldest first.
+    pub fn versions(&self, name: &str) -> Result<Vec<Version>, Error> {
+        let connection = self.connection();
+        let mut statement = connection
+            .prepare("SELECT version FROM lists WHERE name = ?1 ORDER BY version_key, id")?;
+        let versions = statement
+            .query_map([name], |row| parse_version(row.get(0)?, 0))?
+            .collect::<Result<_, _>>()?;
+        Ok(versions)
+    }
+}
+
+/// Loads the list with the ID `list_id`.
+fn load_list(connection: &Connection, list_id: i64) -> Result<TokenList, Error> {
+    let (name, version, timestamp, logo_uri) = connection.query_row(
+        "SELECT name, version, timestamp, logo_uri FROM lists WHERE id = ?1",
+        [list_id],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                parse_version(row.get(1)?, 1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        },
+    )?;
+
+    let keywords = connection
+        .prepare("SELECT keyword FROM keywords WHERE list_id = ?1 ORDER BY position")?
+        .query_map([list_id], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    let tags = connection
+        .prepare("SELECT id, name, description FROM tags WHERE list_id = ?1 ORDER BY position")?
+        .query_map([list_id], |row| {
+            Ok((
+                row.get(0)?,
+                Tag {
+                    name: row.get(1)?,
+                    description: row.get(2)?,
+                },
+            ))
+        })?
+        .collect::<Result<_, _>>()?;
+
+    let mut token_tags: HashMap<i64, Vec<String>> = HashMap::new();
+    let mut statement = connection.prepare(
+        "SELECT token_tags.token_id, token_tags.tag FROM list_tokens
+             JOIN token_tags ON token_tags.token_id = list_tokens.token_id
+             WHERE list_tokens.list_id = ?1 ORDER BY token_tags.position",
+    )?;
+    let mut rows = statement.query([list_id])?;
+    while let Some(row) = rows.next()? {
+        token_tags.entry(row.get(0)?).or_default().push(row.get(1)?);
+    }
+
+    type Extensions = IndexMap<String, ExtensionValue>;
+    let mut extensions: HashMap<i64, Extensions> = HashMap::new();
+    let mut statement = connection.prepare(
+        "SELECT extensions.token_id, extensions.key, extensions.value FROM list_tokens
+             JOIN extensions ON extensions.token_id = list_tokens.token_id
+             WHERE list_tokens.list_id = ?1 ORDER BY extensions.position",
+    )?;
+    let mut rows = statement.query([list_id])?;
+    while let Some(row) = rows.next()? {
+        let value: String = row.get(2)?;
+        extensions
+            .entry(row.get(0)?)
+            .or_default()
+            .insert(row.get(1)?, serde_json::from_str(&value)?);
+    }
+
+    let mut statement = connection.prepare(
+        "SELECT tokens.id, tokens.name, tokens.symbol, tokens.address, tokens.chain_id,
+                 tokens.decimals, tokens.logo_uri
+             FROM list_tokens JOIN tokens ON tokens.id = list_tokens.token_id
+             WHERE list_tokens.list_id = ?1 ORDER BY list_tokens.position",
+    )?;
+    let tokens = statement
+        .query_map([list_id], |row| {
+            let id: i64 = row.get(0)?;
+            Ok(Token {
+                name: row.get(1)?,
+                symbol: row.get(2)?,
+                address: row.get(3)?,
+                chain_id: row.get(4)?,
+                decimals: row.get(5)?,
+                logo_uri: parse_uri(row.get(6)?, 6)?,
+                tags: token_tags.remove(&id).unwrap_or_default(),
+                extensions: extensions.remove(&id).unwrap_or_default(),
+                #[cfg(feature = "unknown-fields")]
+                unknown_fields: Default::default(),
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+
+    Ok(TokenList {
+        name,
+        timestamp: crate::timestamp::parse_rfc3339(&timestamp).ok_or_else(|| {
+            rusqlite::Error::FromSqlConversionFailure(2, Type::Text, "invalid timestamp".into())
+        })?,
+        version,
+        logo_uri: parse_uri(logo_uri, 3)?,
+        keywords,
+        tags,
+        tokens,
+        #[cfg(feature = "unknown-fields")]
+        unknown_fields: Default::default(),
+    })
+}
+
+/// Inserts `token` with its tags and extensions, returning its ID.
+fn insert_token(
+    transaction: &rusqlite::Transaction<'_>,
+    list_name: &str,
+    token: &Token,
+    content_hash: &[u8],
+) -> Result<i64, Error> {
+    let token_id = transaction.query_row(
+        "INSERT INTO tokens
+             (list_name, chain_id, address, name, symbol, decimals, logo_uri, content_hash)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         RETURNING id",
+        params![
+            list_name,
+            token.chain_id,
+            token.address,
+            token.name,
+            token.symbol,
+            token.decimals,
+            token.logo_uri.as_ref().map(Url::as_str),
+            content_hash,
+        ],
+        |row| row.get(0),
+    )?;
+    for (position, tag) in token.tags.iter().enumerate() {
+        transaction.execute(
+            "INSERT INTO token_tags (token_id, position, tag) VALUES (?1, ?2, ?3)",
+            params![token_id, position as i64, tag],
+        )?;
+    }
+    for (position, (key, value)) in token.extensions.iter().enumerate() {
+        transaction.execute(
+            "INSERT INTO extensions (token_id, position, key, value) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                token_id,
+                position as i64,
+                key,
+                serde_json::to_string(value)?
+            ],
+        )?;
+    }
+    Ok(token_id)
+}
+
+fn parse_version(version: String, column: usize) -> rusqlite::Result<Version> {
+    Version::parse(&version).map_err(|err| conversion_error(column, err))
+}
+
+fn parse_uri(uri: Option<String>, column: usize) -> rusqlite::Result<Option<Url>> {
+    uri.map(|uri| Url::parse(&uri).map_err(|err| conversion_error(column, err)))
+        .transpose()
+}
+
+fn conversion_error<E>(column: usize, err: E) -> rusqlite::Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    rusqlite::Error::FromSqlConversionFailure(column, Type::Text, Box::new(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    fn count(store: &SqliteStore, table: &str) -> i64 {
+        store
+            .connection()
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn round_trips_lists() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let token_list = fixtures::full();
+
+        store.store(&token_list).unwrap();
+
+        assert_eq!(
+            store
+                .load(&token_list.name, &token_list.version)
+                .unwrap()
+                .unwrap(),
+            token_list
+        );
+        assert!(store
+            .load(&token_list.name, &Version::new(9, 9, 9))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn shares_unchanged_tokens_between_versions() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let first = fixtures::full();
+        store.store(&first).unwrap();
+        let tokens = count(&store, "tokens");
+
+        let mut second = first.clone();
+        second.version = Version::new(1, 0, 0);
+        second.tokens[0].name = "Renamed".to_owned();
+        store.store(&second).unwrap();
+
+        assert_eq!(count(&store, "tokens"), tokens + 1);
+        assert_eq!(
+            store.versions(&first.name).unwrap(),
+            vec![first.version.clone(), second.version.clone()]
+        );
+        assert_eq!(store.load_latest(&first.name).unwrap().unwrap(), second);
+        assert_eq!(
+            store.load(&first.name, &first.version).unwrap().unwrap(),
+            first
+        );
+    }
+
+    #[test]
+    fn keeps_pre_release_versions() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let mut release_candidate = fixtures::full();
+        release_candidate.version = Version::parse("1.0.0-rc.1+build.5").unwrap();
+        let mut release = fixtures::full();
+        release.version = Version::new(1, 0, 0);
+
+        store.store(&release).unwrap();
+        store.store(&release_candidate).unwrap();
+
+        assert_eq!(count(&store, "lists"), 2);
+        assert_eq!(
+            store.versions(&release.name).unwrap(),
+            vec![release_candidate.version.clone(), release.version.clone()]
+        );
+        assert_eq!(
+            store
+                .load(&release.name, &release_candidate.version)
+                .unwrap()
+                .unwrap()
+                .version
+                .to_string(),
+            "1.0.0-rc.1+build.5"
+        );
+        assert_eq!(store.load_latest(&release.name).unwrap().unwrap(), release);
+    }
+
+    #[test]
+    fn replaces_stored_versions() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let mut token_list = fixtures::full();
+        store.store(&token_list).unwrap();
+
+        token_list.tokens.pop();
+        token_list.keywords.push("replaced".to_owned());
+        store.store(&token_list).unwrap();
+
+        assert_eq!(count(&store, "lists"), 1);
+        assert_eq!(
+            store.load_latest(&token_list.name).unwrap().unwrap(),
+            token_list
+        );
+    }
+}
@@ -0,0 +1,347 @@
+//! A backend-agnostic interface for persisting token lists.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::{fs, io};
+
+use async_trait::async_trait;
+use semver::Version;
+
+use crate::{Error, Token, TokenList};
+
+/// Persists versions of token lists, identified by name and version.
+///
+/// Implemented by [`MemoryStorage`] and [`FileStorage`], and by the SQLite,
+/// Postgres, and Redis stores with the `sqlite`, `postgres`, and `redis`
+/// features, so code that keeps lists can take any backend as an
+/// `Arc<dyn Storage>`.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait Storage: Send + Sync {
+    /// Stores `token_list`, replacing any list with the same name and version.
+    async fn put_list(&self, token_list: &TokenList) -> Result<(), Error>;
+
+    /// Returns the list named `name` at `version`, or its latest version if
+    /// `version` is `None`, if it's stored.
+    async fn get_list(
+        &self,
+        name: &str,
+        version: Option<&Version>,
+    ) -> Result<Option<TokenList>, Error>;
+
+    /// Returns the stored versions of the list named `name`, oldest first.
+    async fn list_versions(&self, name: &str) -> Result<Vec<Version>, Error>;
+
+    /// Returns the token at `address` on `chain_id` in the latest version of
+    /// the list named `name`, comparing addresses case-insensitively.
+    ///
+    /// The default implementation loads the whole list.
+    async fn get_token(
+        &self,
+        name: &str,
+        chain_id: u32,
+        address: &str,
+    ) -> Result<Option<Token>, Error> {
+        let token_list = match self.get_list(name, None).await? {
+            Some(token_list) => token_list,
+            None => return Ok(None),
+        };
+        Ok(token_list.tokens.into_iter().find(|token| {
+            token.chain_id == chain_id && token.address.eq_ignore_ascii_case(address)
+        }))
+    }
+}
+
+/// A [`Storage`] holding lists in memory, e.g. for tests.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    lists: Mutex<HashMap<String, BTreeMap<Version, Arc<TokenList>>>>,
+}
+
+impl MemoryStorage {
+    /// Creates an empty storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Storage for MemoryStorage {
+    async fn put_list(&self, token_list: &TokenList) -> Result<(), Error> {
+        self.lists
+            .lock()
+            .unwrap()
+            .entry(token_list.name.clone())
+            .or_default()
+            .insert(token_list.version.clone(), Arc::new(token_list.clone()));
+        Ok(())
+    }
+
+    async fn get_list(
+        &self,
+        name: &str,
+        version: Option<&Version>,
+    ) -> Result<Option<TokenList>, Error> {
+        let lists = self.lists.lock().unwrap();
+        let versions = match lists.get(name) {
+            Some(versions) => versions,
+            None => return Ok(None),
+        };
+        let token_list = match version {
+            Some(version) => versions.get(version),
+            None => versions.values().next_back(),
+        };
+        Ok(token_list.map(|token_list| (**token_list).clone()))
+    }
+
+    async fn list_versions(&self, name: &str) -> Result<Vec<Version>, Error> {
+        let lists = self.lists.lock().unwrap();
+        Ok(lists
+            .get(name)
+            .map(|versions| versions.keys().cloned().collect())
+            .unwrap_or_default())
+    }
+}
+
+/// A [`Storage`] keeping lists as JSON files in a directory, with a
+/// subdirectory per list name and a file per version, such as
+/// `My%20List/1.2.0.json`.
+///
+/// Files are read and written synchronously.
+#[derive(Clone, Debug)]
+pub struct FileStorage {
+    dir: PathBuf,
+}
+
+impl FileStorage {
+    /// Creates a storage keeping lists in `dir`, which is created when the
+    /// first list is stored.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        FileStorage { dir: dir.into() }
+    }
+
+    /// Returns the directory lists are kept in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn list_dir(&self, name: &str) -> PathBuf {
+        self.dir.join(escape(name))
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Storage for FileStorage {
+    async fn put_list(&self, token_list: &TokenList) -> Result<(), Error> {
+        let dir = self.list_dir(&token_list.name);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.json", token_list.version));
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(token_list)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    async fn get_list(
+        &self,
+        name: &str,
+        version: Option<&Version>,
+    ) -> Result<Option<TokenList>, Error> {
+        let version = match version {
+            Some(version) => version.clone(),
+            None => match self.list_versions(name).await?.pop() {
+                Some(version) => version,
+                None => return Ok(None),
+            },
+        };
+        let path = self.list_dir(name).join(format!("{}.json", version));
+        match fs::read(path) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn list_versions(&self, name: &str) -> Result<Vec<Version>, Error> {
+        let entries = match fs::read_dir(self.list_dir(name)) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut versions = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            let version = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| Version::parse(stem).ok());
+            versions.extend(version);
+        }
+        versions.sort();
+        Ok(versions)
+    }
+}
+
+/// Percent-encodes the bytes of `name` that aren't safe in a directory name.
+fn escape(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for (i, byte) in name.bytes().enumerate() {
+        match byte {
+            // a leading dot would make `.`, `..`, and hidden directories
+            b'.' if i > 0 => escaped.push('.'),
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' => escaped.push(byte as char),
+            _ => write!(escaped, "%{:02X}", byte).unwrap(),
+        }
+    }
+    escaped
+}
+
+#[cfg(feature = "sqlite")]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Storage for crate::SqliteStore {
+    async fn put_list(&self, token_list: &TokenList) -> Result<(), Error> {
+        let token_list = token_list.clone();
+        spawn_blocking(self, move |store| store.store(&token_list)).await
+    }
+
+    async fn get_list(
+        &self,
+        name: &str,
+        version: Option<&Version>,
+    ) -> Result<Option<TokenList>, Error> {
+        let name = name.to_owned();
+        let version = version.cloned();
+        spawn_blocking(self, move |store| match version {
+            Some(version) => store.load(&name, &version),
+            None => store.load_latest(&name),
+        })
+        .await
+    }
+
+    async fn list_versions(&self, name: &str) -> Result<Vec<Version>, Error> {
+        let name = name.to_owned();
+        spawn_blocking(self, move |store| store.versions(&name)).await
+    }
+}
+
+/// Runs `f` with a clone of `store` on tokio's blocking thread pool, so
+/// SQLite's blocking calls don't stall the async runtime.
+#[cfg(feature = "sqlite")]
+async fn spawn_blocking<T, F>(store: &crate::SqliteStore, f: F) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce(&crate::SqliteStore) -> Result<T, Error> + Send + 'static,
+{
+    let store = store.clone();
+    tokio::task::spawn_blocking(move || f(&store))
+        .await
+        .map_err(io::Error::other)?
+}
+
+#[cfg(feature = "postgres")]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Storage for crate::PostgresStore {
+    async fn put_list(&self, token_list: &TokenList) -> Result<(), Error> {
+        self.store(token_list).await
+    }
+
+    async fn get_list(
+        &self,
+        name: &str,
+        version: Option<&Version>,
+    ) -> Result<Option<TokenList>, Error> {
+        match version {
+            Some(version) => self.load(name, version).await,
+            None => self.load_latest(name).await,
+        }
+    }
+
+    async fn list_versions(&self, name: &str) -> Result<Vec<Version>, Error> {
+        self.versions(name).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    /// Checks that `storage` keeps versions apart and finds the latest.
+    async fn check(storage: &dyn Storage) {
+        let first = fixtures::full();
+        let mut second = first.clone();
+        second.version = Version::new(1, 0, 0);
+        second.tokens.remove(0);
+
+        assert_eq!(storage.get_list(&first.name, None).await.unwrap(), None);
+        storage.put_list(&second).await.unwrap();
+        storage.put_list(&first).await.unwrap();
+
+        assert_eq!(
+            storage.list_versions(&first.name).await.unwrap(),
+            vec![first.version.clone(), second.version.clone()]
+        );
+        assert_eq!(
+            storage
+                .get_list(&first.name, Some(&first.version))
+                .await
+                .unwrap(),
+            Some(first.clone())
+        );
+        assert_eq!(
+            storage.get_list(&first.name, None).await.unwrap(),
+            Some(second.clone())
+        );
+
+        let token = &second.tokens[0];
+        assert_eq!(
+            storage
+                .get_token(&first.name, token.chain_id, &token.address.to_uppercase())
+                .await
+                .unwrap()
+                .as_ref(),
+            Some(token)
+        );
+        let removed = &first.tokens[0];
+        assert_eq!(
+            storage
+                .get_token(&first.name, removed.chain_id, &removed.address)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn stores_in_memory() {
+        check(&MemoryStorage::new()).await;
+    }
+
+    #[tokio::test]
+    async fn stores_in_files() {
+        let dir = tempfile::tempdir().unwrap();
+        check(&FileStorage::new(dir.path())).await;
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn stores_in_sqlite() {
+        check(&crate::SqliteStore::open_in_memory().unwrap()).await;
+    }
+
+    #[test]
+    fn escapes_list_names() {
+        assert_eq!(escape("My List"), "My%20List");
+        assert_eq!(escape("../x"), "%2E.%2Fx");
+        assert_eq!(escape("."), "%2E");
+    }
+}
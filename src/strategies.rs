@@ -0,0 +1,163 @@
+//! [proptest](https://docs.rs/proptest) strategies generating lists that
+//! conform to the token list schema.
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use token_list::{strategies, TokenList};
+//!
+//! proptest! {
+//!     fn round_trips(token_list in strategies::token_list()) {
+//!         let json = serde_json::to_string(&token_list).unwrap();
+//!         prop_assert_eq!(serde_json::from_str::<TokenList>(&json).unwrap(), token_list);
+//!     }
+//! }
+//! # round_trips();
+//! ```
+
+use indexmap::IndexMap;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::subsequence;
+use semver::Version;
+use url::Url;
+
+use crate::{ExtensionValue, Number, Tag, Timestamp, Token, TokenList};
+
+/// Generates token lists with up to 4 tags and 1 to 8 tokens.
+pub fn token_list() -> impl Strategy<Value = TokenList> {
+    vec(("[A-Za-z0-9_]{1,10}", tag()), 0..=4)
+        .prop_flat_map(|tags| {
+            let tags: IndexMap<String, Tag> = tags.into_iter().collect();
+            let tag_ids: Vec<String> = tags.keys().cloned().collect();
+            (
+                "[A-Za-z0-9_ ]{1,30}",
+                timestamp(),
+                (0..100u64, 0..100u64, 0..100u64),
+                proptest::option::of(logo_uri()),
+                vec("[A-Za-z0-9_ ]{1,20}", 0..=4),
+                Just(tags),
+                vec(tagged_token(tag_ids), 1..=8),
+            )
+        })
+        .prop_map(
+            |(name, timestamp, (major, minor, patch), logo_uri, keywords, tags, tokens)| {
+                let mut token_list = TokenList {
+                    name,
+                    timestamp,
+                    version: Version::new(major, minor, patch),
+                    logo_uri,
+                    keywords,
+                    tags,
+                    tokens,
+                    #[cfg(feature = "unknown-fields")]
+                    unknown_fields: Default::default(),
+                };
+                crate::arbitrary::dedupe(&mut token_list);
+                token_list
+            },
+        )
+}
+
+/// Generates untagged tokens.
+pub fn token() -> impl Strategy<Value = Token> {
+    tagged_token(Vec::new())
+}
+
+/// Generates tags.
+pub fn tag() -> impl Strategy<Value = Tag> {
+    ("[A-Za-z0-9_ ]{1,20}", "[A-Za-z0-9_ ]{1,200}")
+        .prop_map(|(name, description)| Tag { name, description })
+}
+
+/// Generates extension values.
+pub fn extension_value() -> impl Strategy<Value = ExtensionValue> {
+    prop_oneof![
+        "[A-Za-z0-9_ ]{1,42}".prop_map(ExtensionValue::String),
+        number().prop_map(ExtensionValue::Number),
+        any::<bool>().prop_map(ExtensionValue::Boolean),
+        Just(ExtensionValue::Null),
+    ]
+}
+
+/// Generates numbers in their canonical variant, so they read back the same.
+pub fn number() -> impl Strategy<Value = Number> {
+    prop_oneof![
+        any::<i64>().prop_map(Number::Integer),
+        (i64::MAX as u128 + 1..=u64::MAX as u128).prop_map(Number::BigInteger),
+        (proptest::num::f64::NORMAL | proptest::num::f64::ZERO).prop_map(Number::Float),
+    ]
+}
+
+/// Generates tokens tagged with some of `tag_ids`.
+fn tagged_token(tag_ids: Vec<String>) -> impl Strategy<Value = Token> {
+    let tag_count = tag_ids.len();
+    (
+        "[A-Za-z0-9_ ]{1,40}",
+        "[A-Za-z0-9_]{1,20}",
+        "0x[0-9a-fA-F]{40}",
+        1..=u32::MAX,
+        0..=255u8,
+        proptest::option::of(logo_uri()),
+        subsequence(tag_ids, 0..=tag_count),
+        vec(("[A-Za-z0-9_]{1,40}", extension_value()), 0..=4),
+    )
+        .prop_map(
+            |(name, symbol, address, chain_id, decimals, logo_uri, tags, extensions)| Token {
+                name,
+                symbol,
+                address,
+                chain_id,
+                decimals,
+                logo_uri,
+                tags,
+                extensions: extensions.into_iter().collect(),
+                #[cfg(feature = "unknown-fields")]
+                unknown_fields: Default::default(),
+            },
+        )
+}
+
+fn timestamp() -> impl Strategy<Value = Timestamp> {
+    // RFC 3339 offsets have minute precision, and dates up to
+    // 9999-12-30T23:59:59Z keep a four-digit year in any offset
+    (-1439..=1439i32, 0..=253_402_214_399i64).prop_map(|(minutes, seconds)| {
+        crate::timestamp::from_unix(seconds, 0, minutes * 60).expect("timestamp is in range")
+    })
+}
+
+fn logo_uri() -> impl Strategy<Value = Url> {
+    "[A-Za-z0-9_]{1,20}".prop_map(|name| {
+        Url::parse(&format!("https://example.com/{}.png", name)).expect("URI is valid")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn round_trips_through_json(token_list in token_list()) {
+            let json = serde_json::to_string(&token_list).unwrap();
+
+            prop_assert_eq!(serde_json::from_str::<TokenList>(&json).unwrap(), token_list);
+        }
+
+        #[cfg(feature = "validate")]
+        #[test]
+        fn generates_valid_lists(token_list in token_list()) {
+            let report = token_list.validate();
+
+            prop_assert!(!report.has_errors(), "{:?}", report);
+        }
+
+        #[test]
+        fn tags_tokens_with_defined_tags(token_list in token_list()) {
+            for token in &token_list.tokens {
+                for tag in &token.tags {
+                    prop_assert!(token_list.tags.contains_key(tag));
+                }
+            }
+        }
+    }
+}
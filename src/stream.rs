@@ -0,0 +1,453 @@
+//! Streaming deserialization of token lists with many tokens.
+
+use std::io::{BufRead, BufReader, Read};
+use std::vec;
+
+use indexmap::IndexMap;
+use semver::Version;
+use serde::de::Error as _;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{Error, Tag, Timestamp, Token, TokenList};
+
+/// The fields of a [`TokenList`] other than its tokens.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct TokenListHeader {
+    /// The name of the token list
+    pub name: String,
+
+    /// The timestamp of this list version
+    #[serde(with = "crate::timestamp")]
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(schema_with = "crate::timestamp::json_schema")
+    )]
+    pub timestamp: Timestamp,
+
+    /// The version of the list
+    #[serde(with = "crate::version")]
+    #[cfg_attr(feature = "schemars", schemars(with = "crate::version::VersionSchema"))]
+    pub version: Version,
+
+    /// A URI for the logo of the token list
+    #[serde(rename = "logoURI", skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<Url>,
+
+    /// Keywords associated with the contents of the list
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+
+    /// A mapping of tag identifiers to their name and description
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub tags: IndexMap<String, Tag>,
+
+    /// Fields not defined by the token list schema
+    #[cfg(feature = "unknown-fields")]
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl TokenListHeader {
+    /// Combines the header with `tokens` into a [`TokenList`].
+    pub fn with_tokens(self, tokens: Vec<Token>) -> TokenList {
+        TokenList {
+            name: self.name,
+            timestamp: self.timestamp,
+            version: self.version,
+            logo_uri: self.logo_uri,
+            keywords: self.keywords,
+            tags: self.tags,
+            tokens,
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: self.unknown_fields,
+        }
+    }
+}
+
+/// An iterator over the tokens of a JSON token list, parsed one at a time
+/// from a reader.
+///
+/// Created by [`TokenList::stream_tokens`].
+#[derive(Debug)]
+pub struct TokenStream<R> {
+    scanner: Scanner<R>,
+    header: TokenListHeader,
+    /// Header fields seen after the `tokens` array, applied once it ends
+    trailing: serde_json::Map<String, serde_json::Value>,
+    state: State,
+    /// The index of the next token
+    index: usize,
+}
+
+#[derive(Debug)]
+enum State {
+    /// Reading tokens from the array in the reader
+    Streaming {
+        first: bool,
+    },
+    /// Reading tokens that had to be buffered before the header was known
+    Buffered(vec::IntoIter<Vec<u8>>),
+    Done,
+}
+
+impl TokenList {
+    /// Reads the JSON token list in `reader` incrementally, returning its
+    /// header along with an iterator that parses its tokens one at a time.
+    ///
+    /// Only one token is held in memory at a time when the `tokens` array
+    /// follows the list's required fields, as is conventional. Otherwise,
+    /// the raw JSON of the array is buffered until the header has been read.
+    /// Optional header fields that follow the `tokens` array are applied to
+    /// [`TokenStream::header`] once every token has been read.
+    pub fn stream_tokens<R: Read>(reader: R) -> Result<TokenStream<BufReader<R>>, Error> {
+        TokenStream::new(BufReader::new(reader))
+    }
+}
+
+impl<R: BufRead> TokenStream<R> {
+    fn new(reader: R) -> Result<Self, Error> {
+        let mut scanner = Scanner { reader };
+        let mut fields = serde_json::Map::new();
+        let mut buffered = None;
+
+        scanner.expect(b'{')?;
+        let mut first = true;
+        while scanner.next_key(&mut first)? {
+            let key = scanner.key()?;
+
+            if key != "tokens" {
+                fields.insert(key, crate::json::from_slice(&scanner.value()?)?);
+            } else if ["name", "timestamp", "version"]
+                .iter()
+                .all(|field| fields.contains_key(*field))
+            {
+                scanner.expect(b'[')?;
+                return Ok(TokenStream {
+                    scanner,
+                    header: header(fields)?,
+                    trailing: serde_json::Map::new(),
+                    state: State::Streaming { first: true },
+                    index: 0,
+                });
+            } else {
+                buffered = Some(scanner.elements()?);
+            }
+        }
+
+        Ok(TokenStream {
+            scanner,
+            header: header(fields)?,
+            trailing: serde_json::Map::new(),
+            state: State::Buffered(buffered.unwrap_or_default().into_iter()),
+            index: 0,
+        })
+    }
+
+    /// Returns the header of the list.
+    pub fn header(&self) -> &TokenListHeader {
+        &self.header
+    }
+
+    /// Reads every remaining token, returning the complete list.
+    pub fn into_token_list(mut self) -> Result<TokenList, Error> {
+        let tokens = self.by_ref().collect::<Result<_, _>>()?;
+        Ok(self.header.with_tokens(tokens))
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, Error> {
+        let json = match &mut self.state {
+            State::Streaming { first } => {
+                if !self.scanner.next_element(first)? {
+                    self.finish()?;
+                    return Ok(None);
+                }
+                self.scanner.value()?
+            }
+            State::Buffered(tokens) => match tokens.next() {
+                Some(token) => token,
+                None => return Ok(None),
+            },
+            State::Done => return Ok(None),
+        };
+
+        let path = format!("tokens[{}]", self.index);
+        self.index += 1;
+        crate::json::from_slice(&json)
+            .map(Some)
+            .map_err(|err| crate::json::nest(err, &path))
+    }
+
+    /// Reads the fields following the `tokens` array, and applies them to the
+    /// header.
+    fn finish(&mut self) -> Result<(), Error> {
+        self.state = State::Done;
+
+        let mut first = false;
+        while self.scanner.next_key(&mut first)? {
+            let key = self.scanner.key()?;
+            let value = crate::json::from_slice(&self.scanner.value()?)?;
+            self.trailing.insert(key, value);
+        }
+
+        if !self.trailing.is_empty() {
+            let mut fields = match serde_json::to_value(&self.header)? {
+                serde_json::Value::Object(fields) => fields,
+                _ => unreachable!("header serializes to an object"),
+            };
+            fields.append(&mut self.trailing);
+            self.header = header(fields)?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for TokenStream<R> {
+    type Item = Result<Token, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.next_token();
+        if token.is_err() {
+            self.state = State::Done;
+        }
+        token.transpose()
+    }
+}
+
+fn header(fields: serde_json::Map<String, serde_json::Value>) -> Result<TokenListHeader, Error> {
+    crate::json::from_value(serde_json::Value::Object(fields))
+}
+
+/// Splits JSON read from a reader into raw values, without parsing them.
+#[derive(Debug)]
+struct Scanner<R> {
+    reader: R,
+}
+
+impl<R: BufRead> Scanner<R> {
+    fn peek(&mut self) -> Result<Option<u8>, Error> {
+        let buf = self.reader.fill_buf().map_err(serde_json::Error::io)?;
+        Ok(buf.first().copied())
+    }
+
+    fn bump(&mut self) -> Result<u8, Error> {
+        let byte = self
+            .peek()?
+            .ok_or_else(|| syntax("unexpected end of input"))?;
+        self.reader.consume(1);
+        Ok(byte)
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), Error> {
+        while let Some(b' ' | b'\n' | b'\r' | b'\t') = self.peek()? {
+            self.reader.consume(1);
+        }
+        Ok(())
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), Error> {
+        self.skip_whitespace()?;
+        match self.bump()? {
+            byte if byte == expected => Ok(()),
+            byte => Err(syntax(&format!(
+                "expected `{}`, found `{}`",
+                expected as char, byte as char
+            ))),
+        }
+    }
+
+    /// Advances to the next member of an object, returning `false` at its
+    /// end.
+    fn next_key(&mut self, first: &mut bool) -> Result<bool, Error> {
+        self.next_item(first, b'}')
+    }
+
+    /// Advances to the next element of an array, returning `false` at its
+    /// end.
+    fn next_element(&mut self, first: &mut bool) -> Result<bool, Error> {
+        self.next_item(first, b']')
+    }
+
+    fn next_item(&mut self, first: &mut bool, end: u8) -> Result<bool, Error> {
+        self.skip_whitespace()?;
+        if self.peek()? == Some(end) {
+            self.reader.consume(1);
+            return Ok(false);
+        }
+        if !*first {
+            self.expect(b',')?;
+        }
+        *first = false;
+        Ok(true)
+    }
+
+    /// Reads an object key and the following `:`.
+    fn key(&mut self) -> Result<String, Error> {
+        let key = crate::json::from_slice(&self.value()?)?;
+        self.expect(b':')?;
+        Ok(key)
+    }
+
+    /// Reads the raw elements of an array.
+    fn elements(&mut self) -> Result<Vec<Vec<u8>>, Error> {
+        self.expect(b'[')?;
+        let mut elements = Vec::new();
+        let mut first = true;
+        while self.next_element(&mut first)? {
+            elements.push(self.value()?);
+        }
+        Ok(elements)
+    }
+
+    /// Reads the raw bytes of the next value.
+    fn value(&mut self) -> Result<Vec<u8>, Error> {
+        self.skip_whitespace()?;
+        let mut value = Vec::new();
+        let mut depth = 0usize;
+        let mut in_string = false;
+        let mut escaped = false;
+
+        loop {
+            let byte = match self.peek()? {
+                // a scalar ends at the first delimiter following it
+                Some(b',' | b'}' | b']' | b':' | b' ' | b'\n' | b'\r' | b'\t')
+                    if depth == 0 && !in_string && !value.is_empty() =>
+                {
+                    return Ok(value);
+                }
+                Some(_) => self.bump()?,
+                None if depth == 0 && !in_string && !value.is_empty() => return Ok(value),
+                None => return Err(syntax("unexpected end of input")),
+            };
+            value.push(byte);
+
+            if in_string {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+                if !in_string && depth == 0 {
+                    return Ok(value);
+                }
+                continue;
+            }
+
+            match byte {
+                b'"' => in_string = true,
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' => {
+                    depth = depth
+                        .checked_sub(1)
+                        .ok_or_else(|| syntax("unexpected closing bracket"))?;
+                    if depth == 0 {
+                        return Ok(value);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn syntax(message: &str) -> Error {
+    Error::Parse(serde_json::Error::custom(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures::FULL;
+
+    #[test]
+    fn streams_tokens_after_header() {
+        let mut stream = TokenList::stream_tokens(FULL.as_bytes()).unwrap();
+        assert_eq!(stream.header().name, "TELcoins");
+
+        let first = stream.next().unwrap().unwrap();
+        assert_eq!(first.chain_id, 1);
+        assert!(matches!(stream.state, State::Streaming { .. }));
+
+        let second = stream.next().unwrap().unwrap();
+        assert_eq!(second.chain_id, 137);
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn matches_full_parse() {
+        let expected: TokenList = serde_json::from_str(FULL).unwrap();
+
+        let streamed = TokenList::stream_tokens(FULL.as_bytes())
+            .unwrap()
+            .into_token_list()
+            .unwrap();
+
+        assert_eq!(streamed, expected);
+    }
+
+    #[test]
+    fn buffers_tokens_before_header() {
+        let json = r#"{
+            "tokens": [{"name": "Telcoin", "symbol": "TEL", "address": "0x467Bccd9d29f223BcE8043b84E8C8B282827790F", "chainId": 1, "decimals": 2}],
+            "name": "TELcoins",
+            "timestamp": "2021-07-05T20:25:22+00:00",
+            "version": { "major": 0, "minor": 1, "patch": 0 }
+        }"#;
+
+        let mut stream = TokenList::stream_tokens(json.as_bytes()).unwrap();
+
+        assert_eq!(stream.header().name, "TELcoins");
+        assert_eq!(stream.next().unwrap().unwrap().symbol, "TEL");
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn applies_trailing_header_fields() {
+        let json = r#"{
+            "name": "TELcoins",
+            "timestamp": "2021-07-05T20:25:22+00:00",
+            "version": { "major": 0, "minor": 1, "patch": 0 },
+            "tokens": [],
+            "keywords": ["telcoin"]
+        }"#;
+
+        let token_list = TokenList::stream_tokens(json.as_bytes())
+            .unwrap()
+            .into_token_list()
+            .unwrap();
+
+        assert_eq!(token_list.keywords, vec!["telcoin"]);
+        assert!(token_list.tokens.is_empty());
+    }
+
+    #[test]
+    fn reports_invalid_tokens() {
+        let json = r#"{
+            "name": "TELcoins",
+            "timestamp": "2021-07-05T20:25:22+00:00",
+            "version": { "major": 0, "minor": 1, "patch": 0 },
+            "tokens": [{"name": "Telcoin"}, {"name": "Other"}]
+        }"#;
+
+        let mut stream = TokenList::stream_tokens(json.as_bytes()).unwrap();
+
+        match stream.next() {
+            Some(Err(Error::ParseAt { path, .. })) => assert_eq!(path, "tokens[0]"),
+            res => panic!("unexpected result: {:?}", res),
+        }
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn reports_truncated_input() {
+        let truncated = &FULL[..FULL.len() / 2];
+
+        let result =
+            TokenList::stream_tokens(truncated.as_bytes()).and_then(TokenStream::into_token_list);
+
+        assert!(matches!(result, Err(Error::Parse(_))));
+    }
+}
@@ -0,0 +1,163 @@
+//! Helpers shared by the events of the `tracing` feature and the metrics of
+//! the `metrics` feature.
+//!
+//! With the `metrics` feature, the following are recorded through the
+//! [metrics](https://docs.rs/metrics) facade, for whichever recorder the
+//! application installs:
+//!
+//! - `tokenlist_fetch_attempts_total`, a counter of HTTP fetch attempts, with
+//!   an `outcome` label of `success` or `failure`
+//! - `tokenlist_fetch_duration_seconds`, a histogram of the time taken by
+//!   successful fetches, including retries
+//! - `tokenlist_list_bytes`, a histogram of the size of fetched bodies
+//! - `tokenlist_parse_duration_seconds`, a histogram of the time taken to
+//!   parse lists
+//! - `tokenlist_parse_errors_total`, a counter of bodies that failed to parse
+//! - `tokenlist_cache_requests_total`, a counter of cache lookups, with a
+//!   `cache` label of `memory` or `redis` and a `result` label of `hit`,
+//!   `stale`, or `miss`
+//! - `tokenlist_refresh_failures_total`, a counter of failed background
+//!   refreshes, with a `uri` label
+//! - `tokenlist_refreshed_at_seconds`, a gauge of the Unix time of the last
+//!   successful background refresh, with a `uri` label
+//! - `tokenlist_list_timestamp_seconds`, a gauge of the `timestamp` of the
+//!   list a background refresh last fetched, with a `uri` label
+
+// shared by several optional features, not all of which use every helper
+#![allow(dead_code)]
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// Measures how long an operation takes. `Instant` isn't available on
+/// `wasm32-unknown-unknown`, where no duration is recorded.
+pub(crate) struct Stopwatch {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: Instant,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Self {
+        Stopwatch {
+            #[cfg(not(target_arch = "wasm32"))]
+            start: Instant::now(),
+        }
+    }
+
+    /// Returns the microseconds elapsed since the stopwatch was started.
+    pub(crate) fn elapsed_us(&self) -> Option<u64> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return Some(self.start.elapsed().as_micros() as u64);
+
+        #[cfg(target_arch = "wasm32")]
+        None
+    }
+
+    /// Returns the seconds elapsed since the stopwatch was started.
+    pub(crate) fn elapsed_secs(&self) -> Option<f64> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return Some(self.start.elapsed().as_secs_f64());
+
+        #[cfg(target_arch = "wasm32")]
+        None
+    }
+}
+
+/// Records a fetch attempt, successful or not.
+#[cfg(feature = "metrics")]
+pub(crate) fn fetch_attempt(success: bool) {
+    let outcome = if success { "success" } else { "failure" };
+    metrics::counter!("tokenlist_fetch_attempts_total", "outcome" => outcome).increment(1);
+}
+
+/// Records a successful fetch of a `bytes` long body.
+#[cfg(feature = "metrics")]
+pub(crate) fn fetched(stopwatch: &Stopwatch, bytes: Option<usize>) {
+    fetch_attempt(true);
+    if let Some(secs) = stopwatch.elapsed_secs() {
+        metrics::histogram!("tokenlist_fetch_duration_seconds").record(secs);
+    }
+    if let Some(bytes) = bytes {
+        metrics::histogram!("tokenlist_list_bytes").record(bytes as f64);
+    }
+}
+
+/// Records an attempt to parse a list.
+#[cfg(feature = "metrics")]
+pub(crate) fn parsed(stopwatch: &Stopwatch, success: bool) {
+    if let Some(secs) = stopwatch.elapsed_secs() {
+        metrics::histogram!("tokenlist_parse_duration_seconds").record(secs);
+    }
+    if !success {
+        metrics::counter!("tokenlist_parse_errors_total").increment(1);
+    }
+}
+
+/// Records a lookup in `cache` with `result`: `hit`, `stale`, or `miss`.
+#[cfg(feature = "metrics")]
+pub(crate) fn cache_request(cache: &'static str, result: &'static str) {
+    metrics::counter!(
+        "tokenlist_cache_requests_total",
+        "cache" => cache,
+        "result" => result
+    )
+    .increment(1);
+}
+
+/// Records a background refresh of the list at `uri`, with the timestamp of
+/// the list if one was fetched, or its failure.
+#[cfg(feature = "metrics")]
+pub(crate) fn refreshed(uri: &str, result: Result<Option<&crate::TokenList>, ()>) {
+    let uri = uri.to_owned();
+    match result {
+        Ok(token_list) => {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default();
+            metrics::gauge!("tokenlist_refreshed_at_seconds", "uri" => uri.clone())
+                .set(now.as_secs_f64());
+            if let Some(token_list) = token_list {
+                metrics::gauge!("tokenlist_list_timestamp_seconds", "uri" => uri)
+                    .set(crate::timestamp::to_unix(&token_list.timestamp).0 as f64);
+            }
+        }
+        Err(()) => {
+            metrics::counter!("tokenlist_refresh_failures_total", "uri" => uri).increment(1);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "fetcher", feature = "metrics"))]
+mod tests {
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    use metrics_util::MetricKind;
+
+    use crate::TokenList;
+
+    #[test]
+    fn records_parse_metrics() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        metrics::with_local_recorder(&recorder, || {
+            TokenList::from_slice(include_bytes!("../fixtures/full.json")).unwrap();
+            TokenList::from_slice(b"{}").unwrap_err();
+        });
+
+        let snapshot = snapshotter.snapshot().into_vec();
+        let value = |kind, name| {
+            snapshot
+                .iter()
+                .find(|(key, ..)| key.kind() == kind && key.key().name() == name)
+                .map(|(.., value)| value)
+        };
+        match value(MetricKind::Histogram, "tokenlist_parse_duration_seconds") {
+            Some(DebugValue::Histogram(durations)) => assert_eq!(durations.len(), 2),
+            value => panic!("unexpected value: {:?}", value),
+        }
+        assert_eq!(
+            value(MetricKind::Counter, "tokenlist_parse_errors_total"),
+            Some(&DebugValue::Counter(1))
+        );
+    }
+}
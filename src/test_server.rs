@@ -0,0 +1,70 @@
+//! A minimal HTTP server for exercising the fetch path in tests.
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+pub const TOKEN_LIST_JSON: &str = r#"{
+    "name": "TELcoins",
+    "timestamp": "2021-07-05T20:25:22+00:00",
+    "version": { "major": 0, "minor": 1, "patch": 0 }
+}"#;
+
+/// Formats a raw HTTP response.
+pub fn response(status: u16, headers: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {} X\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        headers,
+        body.len(),
+        body
+    )
+}
+
+/// Formats a response with the given status, and a token list body on `200`.
+pub fn status(status: u16) -> String {
+    let body = if status == 200 { TOKEN_LIST_JSON } else { "" };
+    response(status, "", body)
+}
+
+/// Serves each of `responses` to one connection in turn, after waiting for
+/// `delay`, returning the URI of the served list.
+pub fn serve_after(delay: Duration, responses: Vec<String>) -> String {
+    spawn(delay, responses, None)
+}
+
+/// Serves each of `responses` to one connection in turn.
+pub fn serve(responses: Vec<String>) -> String {
+    serve_after(Duration::from_millis(0), responses)
+}
+
+/// Serves each of `responses` to one connection in turn, sending the raw
+/// request received on each connection to the returned channel.
+pub fn serve_recording(responses: Vec<String>) -> (String, Receiver<String>) {
+    let (tx, rx) = mpsc::channel();
+    (spawn(Duration::from_millis(0), responses, Some(tx)), rx)
+}
+
+fn spawn(delay: Duration, responses: Vec<String>, requests: Option<Sender<String>>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+        for response in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 4096];
+            let len = stream.read(&mut buf).unwrap();
+
+            if let Some(requests) = &requests {
+                let _ = requests.send(String::from_utf8_lossy(&buf[..len]).into_owned());
+            }
+
+            thread::sleep(delay);
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+
+    format!("http://{}/list.json", addr)
+}
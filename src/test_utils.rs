@@ -0,0 +1,300 @@
+//! Utilities for testing code that fetches token lists, without network
+//! access.
+//!
+//! [`MockFetcher`] is a programmable [`Fetcher`] that can be passed anywhere
+//! a fetcher is accepted, and [`fixtures`] provides valid and invalid lists to
+//! feed it.
+//!
+//! # Examples
+//!
+//! ```
+//! use token_list::test_utils::{fixtures, MockFetcher, MockResponse};
+//! use token_list::TokenList;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let fetcher = MockFetcher::new();
+//! fetcher.respond("https://example.com/list.json", MockResponse::body(fixtures::MINIMAL));
+//!
+//! let token_list = TokenList::from_fetcher(&fetcher, "https://example.com/list.json")
+//!     .await
+//!     .unwrap();
+//!
+//! assert_eq!(token_list.name, "Minimal");
+//! # }
+//! ```
+
+// The crate's own tests use the fixtures without the `fetcher` feature
+#![cfg_attr(not(feature = "fetcher"), allow(dead_code, unused_imports))]
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "fetcher")]
+use async_trait::async_trait;
+
+use crate::TokenList;
+#[cfg(feature = "fetcher")]
+use crate::{Error, Fetched, Fetcher};
+
+/// A [`Fetcher`] that serves programmed responses from memory.
+///
+/// Responses queued with [`respond_once`](Self::respond_once) are served
+/// first, in order; after that, the response set with
+/// [`respond`](Self::respond) is served for every request. Requests for a URI
+/// with no response programmed fail.
+///
+/// Clones share the same responses and request log.
+#[derive(Clone, Debug, Default)]
+pub struct MockFetcher {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    once: HashMap<String, VecDeque<MockResponse>>,
+    always: HashMap<String, MockResponse>,
+    requests: Vec<String>,
+}
+
+/// A response served by a [`MockFetcher`].
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    result: Result<Vec<u8>, String>,
+    etag: Option<String>,
+    latency: Duration,
+}
+
+impl MockResponse {
+    /// A successful response with the given body.
+    pub fn body<B: Into<Vec<u8>>>(body: B) -> Self {
+        MockResponse {
+            result: Ok(body.into()),
+            etag: None,
+            latency: Duration::from_millis(0),
+        }
+    }
+
+    /// A successful response with `token_list` serialized as JSON.
+    pub fn json(token_list: &TokenList) -> Self {
+        Self::body(serde_json::to_vec(token_list).expect("token lists serialize to JSON"))
+    }
+
+    /// A failed response, reported as an [`Error::Fetch`] with the given
+    /// message.
+    pub fn error<M: Into<String>>(message: M) -> Self {
+        MockResponse {
+            result: Err(message.into()),
+            etag: None,
+            latency: Duration::from_millis(0),
+        }
+    }
+
+    /// Sets the `ETag` of the response; conditional requests with a matching
+    /// `ETag` are answered with [`Fetched::NotModified`].
+    pub fn with_etag<E: Into<String>>(mut self, etag: E) -> Self {
+        self.etag = Some(etag.into());
+        self
+    }
+
+    /// Delays the response by `latency`.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+}
+
+/// The error reported for a failed [`MockResponse`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MockError(pub String);
+
+impl fmt::Display for MockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for MockError {}
+
+impl MockFetcher {
+    /// Creates a fetcher with no responses programmed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves `response` for every request to `uri` once any queued responses
+    /// have been served.
+    pub fn respond(&self, uri: &str, response: MockResponse) -> &Self {
+        let mut state = self.state.lock().unwrap();
+        state.always.insert(uri.to_owned(), response);
+        self
+    }
+
+    /// Queues `response` to be served for a single request to `uri`.
+    pub fn respond_once(&self, uri: &str, response: MockResponse) -> &Self {
+        let mut state = self.state.lock().unwrap();
+        state
+            .once
+            .entry(uri.to_owned())
+            .or_default()
+            .push_back(response);
+        self
+    }
+
+    /// Returns every URI requested so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    fn next_response(&self, uri: &str) -> Option<MockResponse> {
+        let mut state = self.state.lock().unwrap();
+        state.requests.push(uri.to_owned());
+
+        let once = state.once.get_mut(uri).and_then(VecDeque::pop_front);
+        once.or_else(|| state.always.get(uri).cloned())
+    }
+}
+
+#[cfg(feature = "fetcher")]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Fetcher for MockFetcher {
+    async fn fetch(&self, uri: &str) -> Result<Vec<u8>, Error> {
+        match self.fetch_if_modified(uri, None).await? {
+            Fetched::Modified { body, .. } => Ok(body),
+            Fetched::NotModified => unreachable!("unconditional request was not modified"),
+        }
+    }
+
+    async fn fetch_if_modified(&self, uri: &str, etag: Option<&str>) -> Result<Fetched, Error> {
+        let response = self.next_response(uri).ok_or_else(|| {
+            Error::Fetch(Box::new(MockError(format!(
+                "no response programmed for {}",
+                uri
+            ))))
+        })?;
+
+        if response.latency > Duration::from_millis(0) {
+            futures_timer::Delay::new(response.latency).await;
+        }
+
+        match response.result {
+            Err(message) => Err(Error::Fetch(Box::new(MockError(message)))),
+            Ok(_) if etag.is_some() && etag == response.etag.as_deref() => Ok(Fetched::NotModified),
+            Ok(body) => Ok(Fetched::Modified {
+                body,
+                etag: response.etag,
+            }),
+        }
+    }
+}
+
+/// Token lists for use in tests.
+pub mod fixtures {
+    use crate::TokenList;
+
+    /// A valid list with only the required fields and a single token.
+    pub const MINIMAL: &str = include_str!("../fixtures/minimal.json");
+
+    /// A valid list using every field of the schema.
+    pub const FULL: &str = include_str!("../fixtures/full.json");
+
+    /// An invalid list missing its required `name`.
+    pub const MISSING_NAME: &str = include_str!("../fixtures/missing-name.json");
+
+    /// An invalid list containing a token with a negative chain ID and
+    /// non-numeric decimals.
+    pub const INVALID_TOKEN: &str = include_str!("../fixtures/invalid-token.json");
+
+    /// Contents that are not JSON at all.
+    pub const NOT_JSON: &str = "<html><body>502 Bad Gateway</body></html>";
+
+    /// Every valid fixture.
+    pub const VALID: &[&str] = &[MINIMAL, FULL];
+
+    /// Every invalid fixture.
+    pub const INVALID: &[&str] = &[MISSING_NAME, INVALID_TOKEN, NOT_JSON];
+
+    /// Returns the [`MINIMAL`] fixture, parsed.
+    pub fn minimal() -> TokenList {
+        serde_json::from_str(MINIMAL).expect("fixture is valid")
+    }
+
+    /// Returns the [`FULL`] fixture, parsed.
+    pub fn full() -> TokenList {
+        serde_json::from_str(FULL).expect("fixture is valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "fetcher")]
+    use std::time::Instant;
+
+    use super::*;
+
+    #[cfg(feature = "fetcher")]
+    const URI: &str = "https://example.com/list.json";
+
+    #[test]
+    fn fixtures_parse_as_expected() {
+        for fixture in fixtures::VALID {
+            assert!(serde_json::from_str::<TokenList>(fixture).is_ok());
+        }
+        for fixture in fixtures::INVALID {
+            assert!(serde_json::from_str::<TokenList>(fixture).is_err());
+        }
+    }
+
+    #[cfg(feature = "fetcher")]
+    #[tokio::test]
+    async fn serves_queued_responses_before_persistent_one() {
+        let fetcher = MockFetcher::new();
+        fetcher
+            .respond(URI, MockResponse::json(&fixtures::full()))
+            .respond_once(URI, MockResponse::error("connection reset"))
+            .respond_once(URI, MockResponse::body(fixtures::MINIMAL));
+
+        assert!(TokenList::from_fetcher(&fetcher, URI).await.is_err());
+        let minimal = TokenList::from_fetcher(&fetcher, URI).await.unwrap();
+        let full = TokenList::from_fetcher(&fetcher, URI).await.unwrap();
+
+        assert_eq!(minimal, fixtures::minimal());
+        assert_eq!(full, fixtures::full());
+        assert_eq!(fetcher.requests(), vec![URI; 3]);
+    }
+
+    #[cfg(feature = "fetcher")]
+    #[tokio::test]
+    async fn fails_unprogrammed_uris() {
+        let fetcher = MockFetcher::new();
+
+        match fetcher.fetch(URI).await {
+            Err(Error::Fetch(_)) => {}
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[cfg(feature = "fetcher")]
+    #[tokio::test]
+    async fn honours_etags_and_latency() {
+        let fetcher = MockFetcher::new();
+        fetcher.respond(
+            URI,
+            MockResponse::body(fixtures::MINIMAL)
+                .with_etag("\"v1\"")
+                .with_latency(Duration::from_millis(20)),
+        );
+
+        let start = Instant::now();
+        let fetched = fetcher
+            .fetch_if_modified(URI, Some("\"v1\""))
+            .await
+            .unwrap();
+
+        assert_eq!(fetched, Fetched::NotModified);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+}
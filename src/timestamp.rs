@@ -0,0 +1,196 @@
+//! The type of list timestamps, and their (de)serialization.
+//!
+//! Timestamps are chrono's `DateTime<FixedOffset>`, or time's
+//! `OffsetDateTime` with the `time` feature enabled, for projects that have
+//! standardized on the `time` crate. Either way they are serialized as the
+//! same RFC 3339 strings.
+
+// the crate-internal helpers are shared by several optional features, not
+// all of which use every helper
+#![allow(dead_code)]
+
+use std::fmt;
+
+use chrono::{DateTime, FixedOffset, SecondsFormat, TimeZone, Utc};
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+
+/// The timestamp of a list version.
+#[cfg(not(feature = "time"))]
+pub type Timestamp = DateTime<FixedOffset>;
+
+/// The timestamp of a list version.
+#[cfg(feature = "time")]
+pub type Timestamp = time::OffsetDateTime;
+
+/// Returns the current time, in UTC.
+pub fn now() -> Timestamp {
+    from_chrono(Utc::now().into()).expect("the current time is in range")
+}
+
+/// Parses an RFC 3339 timestamp, such as `2021-07-05T20:25:22+00:00`.
+pub fn parse_rfc3339(timestamp: &str) -> Option<Timestamp> {
+    from_chrono(DateTime::parse_from_rfc3339(timestamp).ok()?)
+}
+
+/// Formats `timestamp` as RFC 3339, as it's serialized; e.g.
+/// `2021-07-05T20:25:22Z`.
+///
+/// Fractions of a second are written to the millisecond, microsecond, or
+/// nanosecond, whichever is needed, and UTC is written as `Z`.
+pub fn to_rfc3339(timestamp: &Timestamp) -> String {
+    to_chrono(timestamp).to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Serializes a timestamp as an RFC 3339 string, for use with
+/// `#[serde(with = "token_list::timestamp")]`.
+pub fn serialize<S: Serializer>(timestamp: &Timestamp, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&to_rfc3339(timestamp))
+}
+
+/// Deserializes a timestamp from an RFC 3339 string, for use with
+/// `#[serde(with = "token_list::timestamp")]`.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Timestamp, D::Error> {
+    struct Rfc3339Visitor;
+
+    impl<'de> Visitor<'de> for Rfc3339Visitor {
+        type Value = Timestamp;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("an RFC 3339 timestamp")
+        }
+
+        fn visit_str<E: de::Error>(self, timestamp: &str) -> Result<Timestamp, E> {
+            parse_rfc3339(timestamp)
+                .ok_or_else(|| E::invalid_value(de::Unexpected::Str(timestamp), &self))
+        }
+    }
+
+    deserializer.deserialize_str(Rfc3339Visitor)
+}
+
+/// The JSON schema of a timestamp, a string in the `date-time` format.
+#[cfg(feature = "schemars")]
+pub(crate) fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+    schemars::schema::SchemaObject {
+        instance_type: Some(schemars::schema::InstanceType::String.into()),
+        format: Some("date-time".to_owned()),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// Returns the timestamp `seconds` and `nanos` after the Unix epoch, in the
+/// UTC offset of `offset` seconds, or `None` if it's out of range.
+pub(crate) fn from_unix(seconds: i64, nanos: u32, offset: i32) -> Option<Timestamp> {
+    let timestamp = FixedOffset::east_opt(offset)?
+        .timestamp_opt(seconds, nanos)
+        .single()?;
+    from_chrono(timestamp)
+}
+
+/// Returns the seconds and nanoseconds since the Unix epoch of `timestamp`,
+/// and its UTC offset in seconds; the inverse of [`from_unix`].
+pub(crate) fn to_unix(timestamp: &Timestamp) -> (i64, u32, i32) {
+    let timestamp = to_chrono(timestamp);
+    (
+        timestamp.timestamp(),
+        timestamp.timestamp_subsec_nanos(),
+        timestamp.offset().local_minus_utc(),
+    )
+}
+
+/// Returns `timestamp` truncated to whole seconds.
+pub(crate) fn trunc_seconds(timestamp: &Timestamp) -> Timestamp {
+    let (seconds, _, offset) = to_unix(timestamp);
+    from_unix(seconds, 0, offset).expect("truncating keeps the timestamp in range")
+}
+
+/// Returns the time elapsed since `timestamp`, which is negative if it's in
+/// the future.
+pub(crate) fn elapsed(timestamp: &Timestamp) -> chrono::Duration {
+    Utc::now().signed_duration_since(to_chrono(timestamp))
+}
+
+/// Formats `timestamp` with a chrono format string, in UTC.
+pub(crate) fn format_utc(timestamp: &Timestamp, format: &str) -> String {
+    to_chrono(timestamp)
+        .with_timezone(&Utc)
+        .format(format)
+        .to_string()
+}
+
+/// Converts a timestamp to chrono's representation, which the crate uses
+/// internally.
+#[cfg(not(feature = "time"))]
+pub(crate) fn to_chrono(timestamp: &Timestamp) -> DateTime<FixedOffset> {
+    *timestamp
+}
+
+/// Converts a timestamp to chrono's representation, which the crate uses
+/// internally.
+#[cfg(feature = "time")]
+pub(crate) fn to_chrono(timestamp: &Timestamp) -> DateTime<FixedOffset> {
+    // chrono's range of years is wider than time's
+    let offset = FixedOffset::east_opt(timestamp.offset().whole_seconds())
+        .expect("offsets are less than a day");
+    offset
+        .timestamp_opt(timestamp.unix_timestamp(), timestamp.nanosecond())
+        .single()
+        .expect("timestamp is in range")
+}
+
+/// Converts a timestamp from chrono's representation, returning `None` if
+/// it's out of range.
+#[cfg(not(feature = "time"))]
+pub(crate) fn from_chrono(timestamp: DateTime<FixedOffset>) -> Option<Timestamp> {
+    Some(timestamp)
+}
+
+/// Converts a timestamp from chrono's representation, returning `None` if
+/// it's out of range.
+#[cfg(feature = "time")]
+pub(crate) fn from_chrono(timestamp: DateTime<FixedOffset>) -> Option<Timestamp> {
+    let offset = time::UtcOffset::from_whole_seconds(timestamp.offset().local_minus_utc()).ok()?;
+    let utc = time::OffsetDateTime::from_unix_timestamp(timestamp.timestamp()).ok()?;
+    let utc = utc
+        .replace_nanosecond(timestamp.timestamp_subsec_nanos())
+        .ok()?;
+    utc.checked_to_offset(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_as_rfc3339() {
+        for (json, serialized) in [
+            ("2021-07-05T20:25:22+00:00", "2021-07-05T20:25:22Z"),
+            ("2021-07-05T20:25:22Z", "2021-07-05T20:25:22Z"),
+            (
+                "2021-07-05T20:25:22.5-05:30",
+                "2021-07-05T20:25:22.500-05:30",
+            ),
+            (
+                "2021-07-05T20:25:22.000123+01:00",
+                "2021-07-05T20:25:22.000123+01:00",
+            ),
+        ] {
+            let timestamp = parse_rfc3339(json).unwrap();
+
+            assert_eq!(to_rfc3339(&timestamp), serialized);
+            assert_eq!(
+                serialize(&timestamp, serde_json::value::Serializer).unwrap(),
+                serialized
+            );
+            assert_eq!(deserialize(serde_json::json!(json)).unwrap(), timestamp);
+            assert_eq!(from_chrono(to_chrono(&timestamp)), Some(timestamp));
+            let (seconds, nanos, offset) = to_unix(&timestamp);
+            assert_eq!(from_unix(seconds, nanos, offset), Some(timestamp));
+        }
+
+        assert_eq!(parse_rfc3339("yesterday"), None);
+        assert!(deserialize(serde_json::json!("yesterday")).is_err());
+    }
+}
@@ -0,0 +1,304 @@
+//! An append-only log of the versions of a token list, stored as a diff per
+//! version rather than a snapshot of each.
+//!
+//! A `.tokenlog` file is JSON Lines: a header line identifying the format,
+//! then a record per version in the order they were written, each holding
+//! the version, its timestamp, and a JSON Patch
+//! ([RFC 6902](https://www.rfc-editor.org/rfc/rfc6902)) from the previous
+//! version, or from `null` for the first.
+//!
+//! ```text
+//! {"format":"tokenlog","formatVersion":1}
+//! {"version":{"major":1,"minor":0,"patch":0},"timestamp":"2021-07-05T20:25:22+00:00","patch":[{"op":"replace","path":"","value":{…}}]}
+//! {"version":{"major":1,"minor":1,"patch":0},"timestamp":"2021-08-01T09:00:00+00:00","patch":[{"op":"add","path":"/tokens/3","value":{…}}]}
+//! ```
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use json_patch::Patch;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Error, Timestamp, TokenList};
+
+/// The version of the format written by [`TokenLogWriter`].
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Header {
+    format: String,
+    format_version: u32,
+}
+
+/// A record of one version of a list in a `.tokenlog`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LogRecord {
+    /// The version of the list
+    #[serde(with = "crate::version")]
+    pub version: Version,
+
+    /// The timestamp of the list
+    #[serde(with = "crate::timestamp")]
+    pub timestamp: Timestamp,
+
+    /// The changes from the previous version of the list
+    pub patch: Patch,
+}
+
+/// Writes versions of a list to a `.tokenlog`.
+#[derive(Debug)]
+pub struct TokenLogWriter<W> {
+    writer: W,
+    last: Value,
+}
+
+impl<W: Write> TokenLogWriter<W> {
+    /// Starts a new log in `writer`, writing its header.
+    pub fn new(mut writer: W) -> Result<Self, Error> {
+        let header = Header {
+            format: "tokenlog".to_owned(),
+            format_version: FORMAT_VERSION,
+        };
+        serde_json::to_writer(&mut writer, &header)?;
+        writer.write_all(b"\n")?;
+        Ok(TokenLogWriter {
+            writer,
+            last: Value::Null,
+        })
+    }
+
+    /// Continues a log in `writer`, opened for appending, whose last record
+    /// is of `last`, as replayed with [`TokenList::replay_all`].
+    pub fn resume(writer: W, last: &TokenList) -> Result<Self, Error> {
+        Ok(TokenLogWriter {
+            writer,
+            last: serde_json::to_value(last)?,
+        })
+    }
+
+    /// Appends a record of `token_list`, with the changes since the list
+    /// last written.
+    pub fn append(&mut self, token_list: &TokenList) -> Result<(), Error> {
+        let value = serde_json::to_value(token_list)?;
+        let record = LogRecord {
+            version: token_list.version.clone(),
+            timestamp: token_list.timestamp,
+            patch: json_patch::diff(&self.last, &value),
+        };
+        serde_json::to_writer(&mut self.writer, &record)?;
+        self.writer.write_all(b"\n")?;
+        self.last = value;
+        Ok(())
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        Ok(self.writer.flush()?)
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads the records of a `.tokenlog`, in the order they were written.
+#[derive(Debug)]
+pub struct TokenLogReader<R> {
+    lines: io::Lines<R>,
+    line: usize,
+}
+
+impl<R: BufRead> TokenLogReader<R> {
+    /// Reads the log in `reader`, checking its header.
+    pub fn new(reader: R) -> Result<Self, Error> {
+        let mut lines = reader.lines();
+        let header: Header = match lines.next() {
+            Some(line) => serde_json::from_str(&line?)?,
+            None => return Err(Error::InvalidLog("missing header".to_owned())),
+        };
+        if header.format != "tokenlog" {
+            return Err(Error::InvalidLog(format!(
+                "unknown format `{}`",
+                header.format
+            )));
+        }
+        if header.format_version > FORMAT_VERSION {
+            return Err(Error::InvalidLog(format!(
+                "unsupported format version {}",
+                header.format_version
+            )));
+        }
+        Ok(TokenLogReader { lines, line: 1 })
+    }
+}
+
+impl<R: BufRead> Iterator for TokenLogReader<R> {
+    type Item = Result<LogRecord, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err.into())),
+            };
+            self.line += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(
+                serde_json::from_str(&line)
+                    .map_err(|err| Error::InvalidLog(format!("line {}: {}", self.line, err))),
+            );
+        }
+    }
+}
+
+/// An iterator over the versions of a list in a `.tokenlog`, created by
+/// [`TokenList::replay_all`].
+#[derive(Debug)]
+pub struct Replay<R> {
+    records: TokenLogReader<R>,
+    last: Value,
+}
+
+impl<R: BufRead> Iterator for Replay<R> {
+    type Item = Result<TokenList, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = match self.records.next()? {
+            Ok(record) => record,
+            Err(err) => return Some(Err(err)),
+        };
+        Some(self.apply(&record))
+    }
+}
+
+impl<R> Replay<R> {
+    fn apply(&mut self, record: &LogRecord) -> Result<TokenList, Error> {
+        json_patch::patch(&mut self.last, &record.patch)?;
+        Ok(serde_json::from_value(self.last.clone())?)
+    }
+}
+
+impl TokenList {
+    /// Reconstructs `version` of the list from the `.tokenlog` in `log`, or
+    /// returns `None` if the log has no record of it.
+    ///
+    /// If the version was recorded more than once, the last record of it is
+    /// used.
+    pub fn replay<R: Read>(log: R, version: &Version) -> Result<Option<TokenList>, Error> {
+        let mut replay = Self::replay_all(BufReader::new(log))?;
+        let mut found = None;
+        while let Some(record) = replay.records.next() {
+            let record = record?;
+            let token_list = replay.apply(&record)?;
+            if record.version == *version {
+                found = Some(token_list);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Returns an iterator over every version of the list recorded in the
+    /// `.tokenlog` in `log`, in the order they were written.
+    pub fn replay_all<R: BufRead>(log: R) -> Result<Replay<R>, Error> {
+        Ok(Replay {
+            records: TokenLogReader::new(log)?,
+            last: Value::Null,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    fn versions() -> Vec<TokenList> {
+        let first = fixtures::full();
+        let mut second = first.clone();
+        second.version = Version::new(1, 0, 0);
+        second.tokens.remove(0);
+        let mut third = second.clone();
+        third.version = Version::new(1, 1, 0);
+        third.tokens.push(first.tokens[0].clone());
+        third.keywords.push("restored".to_owned());
+        vec![first, second, third]
+    }
+
+    fn log(token_lists: &[TokenList]) -> Vec<u8> {
+        let mut writer = TokenLogWriter::new(Vec::new()).unwrap();
+        for token_list in token_lists {
+            writer.append(token_list).unwrap();
+        }
+        writer.into_inner()
+    }
+
+    #[test]
+    fn replays_every_version() {
+        let versions = versions();
+        let log = log(&versions);
+
+        let replayed: Vec<_> = TokenList::replay_all(&log[..])
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(replayed, versions);
+
+        let second = TokenList::replay(&log[..], &versions[1].version).unwrap();
+        assert_eq!(second.as_ref(), Some(&versions[1]));
+        assert_eq!(
+            TokenList::replay(&log[..], &Version::new(9, 0, 0)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn resumes_logs() {
+        let versions = versions();
+        let mut log = log(&versions[..2]);
+
+        let last = TokenList::replay_all(&log[..])
+            .unwrap()
+            .last()
+            .unwrap()
+            .unwrap();
+        let mut writer = TokenLogWriter::resume(&mut log, &last).unwrap();
+        writer.append(&versions[2]).unwrap();
+
+        let records: Vec<_> = TokenLogReader::new(&log[..])
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[2].version, versions[2].version);
+        assert_eq!(
+            TokenList::replay(&log[..], &versions[2].version).unwrap(),
+            Some(versions[2].clone())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_logs() {
+        assert!(matches!(
+            TokenLogReader::new(&b""[..]),
+            Err(Error::InvalidLog(_))
+        ));
+        assert!(matches!(
+            TokenLogReader::new(&b"{\"format\":\"other\",\"formatVersion\":1}\n"[..]),
+            Err(Error::InvalidLog(_))
+        ));
+
+        let mut log = log(&versions()[..1]);
+        log.extend_from_slice(b"not json\n");
+        let mut records = TokenLogReader::new(&log[..]).unwrap();
+        assert!(records.next().unwrap().is_ok());
+        match records.next() {
+            Some(Err(Error::InvalidLog(message))) => assert!(message.starts_with("line 3:")),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! TOML serialization of token lists.
+
+use crate::{Error, TokenList};
+
+impl TokenList {
+    /// Parses a [`TokenList`] from a TOML document.
+    pub fn from_toml_str(toml: &str) -> Result<Self, Error> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    /// Serializes the list as a TOML document.
+    pub fn to_toml_string(&self) -> Result<String, Error> {
+        Ok(toml::to_string(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    #[test]
+    fn round_trips_through_toml() {
+        let token_list = fixtures::full();
+
+        let toml = token_list.to_toml_string().unwrap();
+
+        assert!(toml.contains("logoURI = "));
+        assert_eq!(TokenList::from_toml_str(&toml).unwrap(), token_list);
+    }
+
+    #[test]
+    fn parses_hand_written_toml() {
+        let toml = r#"
+name = "TELcoins"
+timestamp = "2021-07-05T20:25:22+00:00"
+
+[version]
+major = 0
+minor = 1
+patch = 0
+
+[[tokens]]
+name = "Telcoin"
+symbol = "TEL"
+address = "0x467Bccd9d29f223BcE8043b84E8C8B282827790F"
+chainId = 1
+decimals = 2
+"#;
+
+        let token_list = TokenList::from_toml_str(toml).unwrap();
+
+        assert_eq!(token_list.tokens[0].symbol, "TEL");
+    }
+
+    #[test]
+    fn reports_invalid_toml() {
+        match TokenList::from_toml_str("name = [") {
+            Err(Error::Toml(_)) => {}
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+}
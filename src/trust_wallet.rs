@@ -0,0 +1,290 @@
+//! Import of tokens from a checkout of Trust Wallet's assets repository.
+//!
+//! The repository keeps each token in its own directory,
+//! `blockchains/<chain>/assets/<address>/`, holding an `info.json` with the
+//! token's metadata and usually a `logo.png`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+use url::Url;
+
+use crate::{Error, Tag, Token, TokenList};
+
+/// Where the repository's files are served from, unless configured otherwise.
+pub const TRUST_WALLET_ASSETS_URI: &str =
+    "https://raw.githubusercontent.com/trustwallet/assets/master";
+
+/// The repository's directories for EVM chains, and their chain IDs.
+const CHAINS: &[(&str, u32)] = &[
+    ("ethereum", 1),
+    ("optimism", 10),
+    ("smartchain", 56),
+    ("xdai", 100),
+    ("polygon", 137),
+    ("fantom", 250),
+    ("base", 8453),
+    ("arbitrum", 42161),
+    ("celo", 42220),
+    ("avalanchec", 43114),
+];
+
+/// Assembles a [`TokenList`] from a checkout of Trust Wallet's assets
+/// repository.
+///
+/// Every active token on a known EVM chain is included, in order of chain ID
+/// and address. Logos are linked where the asset has a `logo.png`, and
+/// Trust Wallet's tags are defined in the list and applied to its tokens.
+///
+/// ```no_run
+/// use token_list::TrustWalletImport;
+///
+/// let token_list = TrustWalletImport::new("assets")
+///     .chain("telcoin", 2017)
+///     .import("Trust Wallet")?;
+/// # Ok::<_, token_list::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct TrustWalletImport {
+    root: PathBuf,
+    chains: HashMap<String, u32>,
+    logo_base_uri: String,
+}
+
+impl TrustWalletImport {
+    /// Creates an import from the repository checked out at `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        TrustWalletImport {
+            root: root.into(),
+            chains: CHAINS
+                .iter()
+                .map(|&(name, chain_id)| (name.to_owned(), chain_id))
+                .collect(),
+            logo_base_uri: TRUST_WALLET_ASSETS_URI.to_owned(),
+        }
+    }
+
+    /// Imports tokens from the repository's `name` directory as tokens on
+    /// `chain_id`.
+    pub fn chain(mut self, name: &str, chain_id: u32) -> Self {
+        self.chains.insert(name.to_owned(), chain_id);
+        self
+    }
+
+    /// Links logos to copies of the repository served from `uri` rather than
+    /// [`TRUST_WALLET_ASSETS_URI`].
+    pub fn logo_base_uri(mut self, uri: &str) -> Self {
+        self.logo_base_uri = uri.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Reads the repository into a list named `name`.
+    pub fn import(&self, name: &str) -> Result<TokenList, Error> {
+        let mut tokens = Vec::new();
+        let mut tags = IndexMap::new();
+
+        for (chain, &chain_id) in &self.chains {
+            let assets = self.root.join("blockchains").join(chain).join("assets");
+            let entries = match fs::read_dir(&assets) {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            for entry in entries {
+                let dir = entry?.path();
+                let info_path = dir.join("info.json");
+                if !info_path.is_file() {
+                    continue;
+                }
+                let info: AssetInfo = crate::json::from_slice(&fs::read(&info_path)?)
+                    .map_err(|err| invalid_asset(&info_path, err))?;
+                if info.status != "active" {
+                    continue;
+                }
+
+                let directory = dir.file_name().and_then(|name| name.to_str());
+                let address = match info.id.as_deref().or(directory) {
+                    Some(address) => address.to_owned(),
+                    None => continue,
+                };
+                let logo_uri = match dir.join("logo.png").is_file() {
+                    true => Some(self.logo_uri(chain, &dir)?),
+                    false => None,
+                };
+                for tag in &info.tags {
+                    tags.entry(tag.clone()).or_insert_with(|| Tag {
+                        name: tag.clone(),
+                        description: format!("Tagged `{}` by Trust Wallet", tag),
+                    });
+                }
+
+                tokens.push(Token {
+                    name: info.name,
+                    symbol: info.symbol,
+                    address,
+                    chain_id,
+                    decimals: info.decimals,
+                    logo_uri,
+                    tags: info.tags,
+                    extensions: Default::default(),
+                    #[cfg(feature = "unknown-fields")]
+                    unknown_fields: Default::default(),
+                });
+            }
+        }
+
+        tokens.sort_by(|a, b| {
+            (a.chain_id, a.address.to_ascii_lowercase())
+                .cmp(&(b.chain_id, b.address.to_ascii_lowercase()))
+        });
+        tags.sort_keys();
+
+        Ok(TokenList {
+            name: name.to_owned(),
+            tags,
+            tokens,
+            ..TokenList::default()
+        })
+    }
+
+    fn logo_uri(&self, chain: &str, dir: &Path) -> Result<Url, Error> {
+        let directory = dir.file_name().unwrap_or_default().to_string_lossy();
+        let uri = format!(
+            "{}/blockchains/{}/assets/{}/logo.png",
+            self.logo_base_uri, chain, directory
+        );
+        Url::parse(&uri).map_err(|err| invalid_asset(dir, err))
+    }
+}
+
+fn invalid_asset(path: &Path, err: impl std::fmt::Display) -> Error {
+    Error::InvalidAsset {
+        path: path.display().to_string(),
+        message: err.to_string(),
+    }
+}
+
+/// The contents of an asset's `info.json`.
+#[derive(Deserialize)]
+struct AssetInfo {
+    name: String,
+    symbol: String,
+    decimals: u8,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default = "active")]
+    status: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+fn active() -> String {
+    "active".to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_asset(root: &Path, chain: &str, address: &str, info: &str, logo: bool) {
+        let dir = root
+            .join("blockchains")
+            .join(chain)
+            .join("assets")
+            .join(address);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("info.json"), info).unwrap();
+        if logo {
+            fs::write(dir.join("logo.png"), b"").unwrap();
+        }
+    }
+
+    #[test]
+    fn imports_active_tokens() {
+        let dir = tempfile::tempdir().unwrap();
+        write_asset(
+            dir.path(),
+            "polygon",
+            "0xdF7837DE1F2Fa4631D716CF2502f8b230F1dcc32",
+            r#"{
+                "name": "Telcoin",
+                "type": "POLYGON",
+                "symbol": "TEL",
+                "decimals": 2,
+                "website": "https://telco.in",
+                "id": "0xdF7837DE1F2Fa4631D716CF2502f8b230F1dcc32",
+                "status": "active",
+                "tags": ["defi"]
+            }"#,
+            true,
+        );
+        write_asset(
+            dir.path(),
+            "ethereum",
+            "0x467Bccd9d29f223BcE8043b84E8C8B282827790F",
+            r#"{"name": "Telcoin", "symbol": "TEL", "decimals": 2}"#,
+            false,
+        );
+        write_asset(
+            dir.path(),
+            "ethereum",
+            "0x0000000000000000000000000000000000000001",
+            r#"{"name": "Scam", "symbol": "SCAM", "decimals": 18, "status": "spam"}"#,
+            false,
+        );
+        write_asset(
+            dir.path(),
+            "bitcoin",
+            "BTC",
+            r#"{"name": "Bitcoin", "symbol": "BTC", "decimals": 8}"#,
+            false,
+        );
+
+        let token_list = TrustWalletImport::new(dir.path())
+            .logo_base_uri("https://assets.example/")
+            .import("Trust Wallet")
+            .unwrap();
+
+        assert_eq!(token_list.name, "Trust Wallet");
+        let tokens: Vec<_> = token_list
+            .tokens
+            .iter()
+            .map(|token| (token.chain_id, token.address.as_str()))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                (1, "0x467Bccd9d29f223BcE8043b84E8C8B282827790F"),
+                (137, "0xdF7837DE1F2Fa4631D716CF2502f8b230F1dcc32"),
+            ]
+        );
+        assert_eq!(token_list.tokens[0].logo_uri, None);
+        assert_eq!(
+            token_list.tokens[1].logo_uri.as_ref().unwrap().as_str(),
+            "https://assets.example/blockchains/polygon/assets/0xdF7837DE1F2Fa4631D716CF2502f8b230F1dcc32/logo.png"
+        );
+        assert_eq!(token_list.tokens[1].tags, ["defi"]);
+        assert!(token_list.tags.contains_key("defi"));
+    }
+
+    #[test]
+    fn reports_invalid_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        write_asset(
+            dir.path(),
+            "ethereum",
+            "0x467Bccd9d29f223BcE8043b84E8C8B282827790F",
+            r#"{"name": "Telcoin", "symbol": "TEL"}"#,
+            false,
+        );
+
+        match TrustWalletImport::new(dir.path()).import("Trust Wallet") {
+            Err(Error::InvalidAsset { path, .. }) => assert!(path.ends_with("info.json")),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,450 @@
+//! Validation of lists against the token list schema, and lints for problems
+//! the schema doesn't catch.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::keccak::checksum_address;
+use crate::{Error, ExtensionValue, TokenList};
+
+/// The most tokens a list may have under the token list schema.
+pub const MAX_TOKENS: usize = 10_000;
+
+/// The problems found in a list by [`TokenList::validate`].
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ValidationReport {
+    /// The problems found, in document order within each check
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    /// Returns `true` if any problem violates the schema.
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    /// Returns the problems that violate the schema.
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    /// Returns the problems that don't violate the schema, but likely
+    /// indicate a mistake.
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Warning)
+    }
+
+    /// Returns `true` if no problem was found.
+    pub fn is_clean(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// Returns the report, or [`Error::Validation`] if any problem violates
+    /// the schema.
+    pub fn into_result(self) -> Result<Self, Error> {
+        match self.has_errors() {
+            true => Err(Error::Validation(self)),
+            false => Ok(self),
+        }
+    }
+}
+
+/// A problem found in a list.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Diagnostic {
+    /// A JSON pointer to the offending value, e.g. `/tokens/3/symbol`
+    pub path: String,
+
+    /// How serious the problem is
+    pub severity: Severity,
+
+    /// What is wrong with the value
+    pub kind: DiagnosticKind,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.severity, self.path, self.kind)
+    }
+}
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Severity {
+    /// The list is valid, but likely has a mistake
+    Warning,
+    /// The list violates the schema
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// The kinds of problem found by validation.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum DiagnosticKind {
+    /// A string or array is shorter than the schema allows
+    TooShort {
+        /// The minimum length
+        min: usize,
+    },
+    /// A string, array, or object is longer than the schema allows
+    TooLong {
+        /// The maximum length
+        max: usize,
+    },
+    /// A string contains characters the schema doesn't allow
+    InvalidCharacters,
+    /// An address is not `0x` followed by 40 hex digits
+    InvalidAddress,
+    /// A chain ID is zero
+    InvalidChainId,
+    /// A keyword appears more than once
+    DuplicateKeyword,
+    /// A token references a tag the list doesn't define
+    UndefinedTag,
+    /// A token has the same chain and address as an earlier token
+    DuplicateToken,
+    /// A token has the same chain and symbol as an earlier token
+    DuplicateSymbol,
+    /// A tag is defined but no token references it
+    UnusedTag,
+    /// An address lacks its EIP-55 checksum
+    UnchecksummedAddress,
+    /// A logo URI is fetched over plain HTTP
+    InsecureLogoUri,
+}
+
+impl DiagnosticKind {
+    /// Returns a short, stable identifier for the kind; e.g. `too-long`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DiagnosticKind::TooShort { .. } => "too-short",
+            DiagnosticKind::TooLong { .. } => "too-long",
+            DiagnosticKind::InvalidCharacters => "invalid-characters",
+            DiagnosticKind::InvalidAddress => "invalid-address",
+            DiagnosticKind::InvalidChainId => "invalid-chain-id",
+            DiagnosticKind::DuplicateKeyword => "duplicate-keyword",
+            DiagnosticKind::UndefinedTag => "undefined-tag",
+            DiagnosticKind::DuplicateToken => "duplicate-token",
+            DiagnosticKind::DuplicateSymbol => "duplicate-symbol",
+            DiagnosticKind::UnusedTag => "unused-tag",
+            DiagnosticKind::UnchecksummedAddress => "unchecksummed-address",
+            DiagnosticKind::InsecureLogoUri => "insecure-logo-uri",
+        }
+    }
+
+    /// Returns the severity of problems of this kind.
+    pub fn severity(&self) -> Severity {
+        match self {
+            DiagnosticKind::DuplicateSymbol
+            | DiagnosticKind::UnusedTag
+            | DiagnosticKind::UnchecksummedAddress
+            | DiagnosticKind::InsecureLogoUri => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagnosticKind::TooShort { min } => write!(f, "shorter than the minimum of {}", min),
+            DiagnosticKind::TooLong { max } => write!(f, "longer than the maximum of {}", max),
+            DiagnosticKind::InvalidCharacters => f.write_str("contains invalid characters"),
+            DiagnosticKind::InvalidAddress => f.write_str("not a hex address"),
+            DiagnosticKind::InvalidChainId => f.write_str("chain ID must be at least 1"),
+            DiagnosticKind::DuplicateKeyword => f.write_str("duplicate keyword"),
+            DiagnosticKind::UndefinedTag => f.write_str("tag is not defined by the list"),
+            DiagnosticKind::DuplicateToken => {
+                f.write_str("token has the same chain and address as an earlier one")
+            }
+            DiagnosticKind::DuplicateSymbol => {
+                f.write_str("token has the same chain and symbol as an earlier one")
+            }
+            DiagnosticKind::UnusedTag => f.write_str("tag is not used by any token"),
+            DiagnosticKind::UnchecksummedAddress => f.write_str("address is not checksummed"),
+            DiagnosticKind::InsecureLogoUri => f.write_str("logo URI uses plain HTTP"),
+        }
+    }
+}
+
+/// Collects diagnostics.
+#[derive(Default)]
+struct Validator {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator {
+    fn report(&mut self, path: String, kind: DiagnosticKind) {
+        self.diagnostics.push(Diagnostic {
+            path,
+            severity: kind.severity(),
+            kind,
+        });
+    }
+
+    /// Checks the length and characters of the string at `path`.
+    fn string(
+        &mut self,
+        path: String,
+        value: &str,
+        min: usize,
+        max: usize,
+        valid: fn(char) -> bool,
+    ) {
+        let len = value.chars().count();
+        if len < min {
+            self.report(path, DiagnosticKind::TooShort { min });
+        } else if len > max {
+            self.report(path, DiagnosticKind::TooLong { max });
+        } else if !value.chars().all(valid) {
+            self.report(path, DiagnosticKind::InvalidCharacters);
+        }
+    }
+
+    /// Checks the number of items in the array or object at `path`.
+    fn count(&mut self, path: String, len: usize, min: usize, max: usize) {
+        if len < min {
+            self.report(path, DiagnosticKind::TooShort { min });
+        } else if len > max {
+            self.report(path, DiagnosticKind::TooLong { max });
+        }
+    }
+}
+
+/// Returns `true` for characters matching `\w` in the schema's patterns.
+fn is_word(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn is_word_or_space(c: char) -> bool {
+    is_word(c) || c == ' '
+}
+
+fn is_description(c: char) -> bool {
+    is_word_or_space(c) || matches!(c, '.' | ',' | ':')
+}
+
+fn is_token_name(c: char) -> bool {
+    is_word_or_space(c)
+        || ".'+-%/:&[]()".contains(c)
+        || matches!(c, 'À'..='Ö' | 'Ø'..='ö' | 'ø'..='ÿ')
+}
+
+fn is_symbol(c: char) -> bool {
+    !c.is_whitespace()
+}
+
+/// Escapes `key` for use as a JSON pointer segment.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+impl TokenList {
+    /// Checks the list against the constraints of the token list schema that
+    /// its types can't express, and lints it for likely mistakes.
+    ///
+    /// Violations of the schema are reported as errors. Duplicate symbols,
+    /// unused tags, addresses without their EIP-55 checksum, and logos served
+    /// over plain HTTP are reported as warnings.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "validate",
+            skip_all,
+            fields(name = %self.name, tokens = self.tokens.len())
+        )
+    )]
+    pub fn validate(&self) -> ValidationReport {
+        let mut v = Validator::default();
+
+        v.string("/name".to_owned(), &self.name, 1, 30, is_word_or_space);
+
+        v.count("/keywords".to_owned(), self.keywords.len(), 0, 20);
+        let mut keywords = HashSet::new();
+        for (i, keyword) in self.keywords.iter().enumerate() {
+            let path = format!("/keywords/{}", i);
+            v.string(path.clone(), keyword, 1, 20, is_word_or_space);
+            if !keywords.insert(keyword) {
+                v.report(path, DiagnosticKind::DuplicateKeyword);
+            }
+        }
+
+        v.count("/tags".to_owned(), self.tags.len(), 0, 20);
+        for (id, tag) in &self.tags {
+            let path = format!("/tags/{}", escape(id));
+            v.string(path.clone(), id, 1, 10, is_word);
+            v.string(format!("{}/name", path), &tag.name, 1, 20, is_word_or_space);
+            v.string(
+                format!("{}/description", path),
+                &tag.description,
+                1,
+                200,
+                is_description,
+            );
+        }
+
+        v.count("/tokens".to_owned(), self.tokens.len(), 1, MAX_TOKENS);
+        let mut addresses = HashSet::new();
+        let mut symbols = HashSet::new();
+        let mut used_tags = HashSet::new();
+        for (i, token) in self.tokens.iter().enumerate() {
+            let path = format!("/tokens/{}", i);
+
+            v.string(format!("{}/name", path), &token.name, 1, 40, is_token_name);
+            v.string(format!("{}/symbol", path), &token.symbol, 1, 20, is_symbol);
+            let address_path = format!("{}/address", path);
+            match checksum_address(&token.address) {
+                None => v.report(address_path, DiagnosticKind::InvalidAddress),
+                Some(checksummed) if checksummed != token.address => {
+                    v.report(address_path, DiagnosticKind::UnchecksummedAddress)
+                }
+                Some(_) => {}
+            }
+            if token.chain_id == 0 {
+                v.report(format!("{}/chainId", path), DiagnosticKind::InvalidChainId);
+            }
+            if token.logo_uri.as_ref().map(|uri| uri.scheme()) == Some("http") {
+                v.report(format!("{}/logoURI", path), DiagnosticKind::InsecureLogoUri);
+            }
+
+            v.count(format!("{}/tags", path), token.tags.len(), 0, 10);
+            for (j, tag) in token.tags.iter().enumerate() {
+                let tag_path = format!("{}/tags/{}", path, j);
+                v.string(tag_path.clone(), tag, 1, 10, is_word);
+                if !self.tags.contains_key(tag) {
+                    v.report(tag_path, DiagnosticKind::UndefinedTag);
+                }
+                used_tags.insert(tag.as_str());
+            }
+
+            v.count(
+                format!("{}/extensions", path),
+                token.extensions.len(),
+                0,
+                10,
+            );
+            for (key, value) in &token.extensions {
+                let key_path = format!("{}/extensions/{}", path, escape(key));
+                v.string(key_path.clone(), key, 1, 40, is_word);
+                if let ExtensionValue::String(value) = value {
+                    if value.chars().count() > 42 {
+                        v.report(key_path, DiagnosticKind::TooLong { max: 42 });
+                    }
+                }
+            }
+
+            if !addresses.insert((token.chain_id, token.address.to_lowercase())) {
+                v.report(path.clone(), DiagnosticKind::DuplicateToken);
+            } else if !symbols.insert((token.chain_id, token.symbol.as_str())) {
+                v.report(path, DiagnosticKind::DuplicateSymbol);
+            }
+        }
+
+        for id in self.tags.keys() {
+            if !used_tags.contains(id.as_str()) {
+                v.report(format!("/tags/{}", escape(id)), DiagnosticKind::UnusedTag);
+            }
+        }
+
+        let report = ValidationReport {
+            diagnostics: v.diagnostics,
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            errors = report.errors().count(),
+            warnings = report.warnings().count(),
+            "validated token list"
+        );
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    fn kinds(report: &ValidationReport) -> Vec<(&str, &DiagnosticKind)> {
+        report
+            .diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.path.as_str(), &diagnostic.kind))
+            .collect()
+    }
+
+    #[test]
+    fn accepts_valid_lists() {
+        let report = fixtures::full().validate();
+
+        assert!(!report.has_errors(), "{:?}", report);
+        assert!(report.into_result().is_ok());
+    }
+
+    #[test]
+    fn reports_schema_violations() {
+        let mut token_list = fixtures::full();
+        token_list.name = "Lots of tokens!".to_owned();
+        token_list.keywords = vec!["defi".to_owned(), "defi".to_owned()];
+        token_list.tokens[0].address = "0x1234".to_owned();
+        token_list.tokens[0].chain_id = 0;
+        token_list.tokens[0].tags = vec!["nope".to_owned()];
+        token_list.tokens[0].symbol = "A B".to_owned();
+
+        let report = token_list.validate();
+
+        let kinds = kinds(&report);
+        for expected in &[
+            ("/name", DiagnosticKind::InvalidCharacters),
+            ("/keywords/1", DiagnosticKind::DuplicateKeyword),
+            ("/tokens/0/symbol", DiagnosticKind::InvalidCharacters),
+            ("/tokens/0/address", DiagnosticKind::InvalidAddress),
+            ("/tokens/0/chainId", DiagnosticKind::InvalidChainId),
+            ("/tokens/0/tags/0", DiagnosticKind::UndefinedTag),
+        ] {
+            assert!(kinds.contains(&(expected.0, &expected.1)), "{:?}", kinds);
+        }
+        assert!(report.errors().all(|d| d.severity == Severity::Error));
+        match report.into_result() {
+            Err(Error::Validation(report)) => assert_eq!(report.errors().count(), 6),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn lints_likely_mistakes() {
+        let mut token_list = fixtures::full();
+        token_list.tokens[0].address = token_list.tokens[0].address.to_lowercase();
+        let duplicate = token_list.tokens[0].clone();
+        token_list.tokens.push(duplicate);
+        let mut same_symbol = token_list.tokens[0].clone();
+        same_symbol.address = "0x0000000000000000000000000000000000000001".to_owned();
+        token_list.tokens.push(same_symbol);
+
+        let report = token_list.validate();
+
+        let last = token_list.tokens.len() - 1;
+        let kinds = kinds(&report);
+        assert!(kinds.contains(&("/tokens/0/address", &DiagnosticKind::UnchecksummedAddress)));
+        let duplicate = format!("/tokens/{}", last - 1);
+        assert!(kinds.contains(&(duplicate.as_str(), &DiagnosticKind::DuplicateToken)));
+        let same_symbol = format!("/tokens/{}", last);
+        assert!(kinds.contains(&(same_symbol.as_str(), &DiagnosticKind::DuplicateSymbol)));
+        assert_eq!(
+            report.diagnostics[0].to_string(),
+            "warning: /tokens/0/address: address is not checksummed"
+        );
+    }
+}
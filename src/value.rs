@@ -0,0 +1,214 @@
+//! Conversions between extension values, `serde_json::Value`, and Rust types.
+
+use std::convert::TryFrom;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{ExtensionValue, Number, Token};
+
+impl From<ExtensionValue> for Value {
+    fn from(value: ExtensionValue) -> Self {
+        match value {
+            ExtensionValue::String(value) => Value::String(value),
+            ExtensionValue::Number(value) => value.into(),
+            ExtensionValue::Boolean(value) => Value::Bool(value),
+            ExtensionValue::Null => Value::Null,
+        }
+    }
+}
+
+/// Converts a number to JSON, or to `null` if it's a float that JSON can't
+/// represent. Integers beyond a `u64` are converted exactly if the
+/// `arbitrary-precision` feature is enabled, and to floats otherwise.
+impl From<Number> for Value {
+    fn from(value: Number) -> Self {
+        match value {
+            Number::Integer(value) => value.into(),
+            Number::BigInteger(value) => {
+                serde_json::to_value(value).unwrap_or_else(|_| (value as f64).into())
+            }
+            Number::Float(value) => value.into(),
+        }
+    }
+}
+
+/// Converts strings, numbers, booleans, and `null`; arrays and objects are
+/// returned as the error.
+impl TryFrom<Value> for ExtensionValue {
+    type Error = Value;
+
+    fn try_from(value: Value) -> Result<Self, Value> {
+        match value {
+            Value::String(value) => Ok(ExtensionValue::String(value)),
+            Value::Bool(value) => Ok(ExtensionValue::Boolean(value)),
+            Value::Null => Ok(ExtensionValue::Null),
+            Value::Number(number) => match Number::deserialize(number.clone()) {
+                Ok(number) => Ok(ExtensionValue::Number(number)),
+                Err(_) => Err(Value::Number(number)),
+            },
+            value => Err(value),
+        }
+    }
+}
+
+/// Converts values from which `$as` returns `Some`; other values are returned
+/// as the error.
+macro_rules! try_from_extension_value {
+    ($($ty:ty => $as:expr,)*) => {
+        $(
+            impl TryFrom<ExtensionValue> for $ty {
+                type Error = ExtensionValue;
+
+                fn try_from(value: ExtensionValue) -> Result<Self, ExtensionValue> {
+                    $as(&value).ok_or(value)
+                }
+            }
+        )*
+    };
+}
+
+try_from_extension_value! {
+    i64 => ExtensionValue::as_i64,
+    u64 => ExtensionValue::as_u64,
+    f64 => |value: &ExtensionValue| value.as_number().map(Number::to_f64),
+    bool => ExtensionValue::as_bool,
+}
+
+/// Converts strings; other values are returned as the error.
+impl TryFrom<ExtensionValue> for String {
+    type Error = ExtensionValue;
+
+    fn try_from(value: ExtensionValue) -> Result<Self, ExtensionValue> {
+        match value {
+            ExtensionValue::String(value) => Ok(value),
+            value => Err(value),
+        }
+    }
+}
+
+impl Token {
+    /// Deserializes the extension `key` as a `T`, or returns `None` if the
+    /// token doesn't have it.
+    ///
+    /// Extensions set to `null` are deserialized from `null`, so reading them
+    /// as an `Option<T>` gives `None`.
+    ///
+    /// ```
+    /// # use token_list::Token;
+    /// let token: Token = serde_json::from_str(r#"{
+    ///     "name": "Telcoin",
+    ///     "symbol": "TEL",
+    ///     "address": "0x467Bccd9d29f223BcE8043b84E8C8B282827790F",
+    ///     "chainId": 1,
+    ///     "decimals": 2,
+    ///     "extensions": { "bridgedChainId": 137 }
+    /// }"#)?;
+    ///
+    /// let chain_id: u32 = token.extension_as("bridgedChainId").unwrap()?;
+    /// assert_eq!(chain_id, 137);
+    /// # Ok::<_, serde_json::Error>(())
+    /// ```
+    pub fn extension_as<T: DeserializeOwned>(
+        &self,
+        key: &str,
+    ) -> Option<Result<T, serde_json::Error>> {
+        let value = self.extensions.get(key)?.clone();
+        Some(serde_json::from_value(value.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn converts_to_json() {
+        let cases = [
+            (ExtensionValue::String("0x0".to_owned()), json!("0x0")),
+            (ExtensionValue::Number(Number::Integer(-1)), json!(-1)),
+            (
+                ExtensionValue::Number(Number::BigInteger(u64::MAX.into())),
+                json!(u64::MAX),
+            ),
+            (ExtensionValue::Number(Number::Float(0.5)), json!(0.5)),
+            (ExtensionValue::Boolean(true), json!(true)),
+            (ExtensionValue::Null, json!(null)),
+        ];
+
+        for (value, json) in cases.iter().cloned() {
+            assert_eq!(Value::from(value.clone()), json);
+            assert_eq!(ExtensionValue::try_from(json), Ok(value));
+        }
+    }
+
+    #[test]
+    fn rejects_other_json() {
+        for json in [json!([1]), json!({ "a": 1 })].iter().cloned() {
+            assert_eq!(ExtensionValue::try_from(json.clone()), Err(json));
+        }
+    }
+
+    #[test]
+    fn converts_to_rust_types() {
+        let integer = ExtensionValue::Number(Number::Integer(-1));
+        let big = ExtensionValue::Number(Number::BigInteger(u64::MAX.into()));
+        let string = ExtensionValue::String("0x0".to_owned());
+
+        assert_eq!(i64::try_from(integer.clone()), Ok(-1));
+        assert_eq!(u64::try_from(integer.clone()), Err(integer.clone()));
+        assert_eq!(u64::try_from(big.clone()), Ok(u64::MAX));
+        assert_eq!(f64::try_from(integer), Ok(-1.0));
+        assert_eq!(f64::try_from(big), Ok(u64::MAX as f64));
+        assert_eq!(bool::try_from(ExtensionValue::Boolean(true)), Ok(true));
+        assert_eq!(String::try_from(string), Ok("0x0".to_owned()));
+        assert_eq!(
+            String::try_from(ExtensionValue::Null),
+            Err(ExtensionValue::Null)
+        );
+        assert_eq!(
+            ExtensionValue::Number(Number::BigInteger(u128::MAX)).as_u64(),
+            None
+        );
+    }
+
+    #[test]
+    fn reads_extensions_as_types() {
+        let mut token: Token = serde_json::from_value(json!({
+            "name": "Telcoin",
+            "symbol": "TEL",
+            "address": "0x467Bccd9d29f223BcE8043b84E8C8B282827790F",
+            "chainId": 1,
+            "decimals": 2,
+            "extensions": {
+                "polygonAddress": "0xdF7837DE1F2Fa4631D716CF2502f8b230F1dcc32",
+                "note": null
+            }
+        }))
+        .unwrap();
+        token
+            .extensions
+            .insert("isMapped".to_owned(), ExtensionValue::Boolean(true));
+
+        assert!(token.extension_as::<bool>("isMapped").unwrap().unwrap());
+        assert_eq!(
+            token
+                .extension_as::<Option<String>>("note")
+                .unwrap()
+                .unwrap(),
+            None
+        );
+        assert!(token
+            .extension_as::<u32>("polygonAddress")
+            .unwrap()
+            .is_err());
+        assert!(token.extension_as::<bool>("missing").is_none());
+        assert_eq!(
+            serde_json::to_value(&token).unwrap()["extensions"]["note"],
+            Value::Null
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! (De)serialization of list versions, which are published either as
+//! `{"major": 1, "minor": 2, "patch": 3}` objects or as `"1.2.3"` strings.
+
+use std::fmt;
+
+use indexmap::IndexMap;
+use semver::Version;
+use serde::de::{self, value::MapAccessDeserializer, Visitor};
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{Tag, Token, TokenList};
+
+/// The form in which the version of a list is serialized.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum VersionFormat {
+    /// An object with `major`, `minor`, and `patch` fields, as required by the
+    /// token list schema
+    #[default]
+    Object,
+    /// A semver string such as `"1.2.3"`
+    String,
+}
+
+/// The schema of list versions, which are accepted in either form.
+#[cfg(any(feature = "schemars", feature = "utoipa"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schemars", schemars(rename = "Version", untagged))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[allow(dead_code)]
+pub(crate) enum VersionSchema {
+    Object { major: u64, minor: u64, patch: u64 },
+    String(String),
+}
+
+impl TokenList {
+    /// Returns a wrapper that serializes the list with its version in the
+    /// given form.
+    ///
+    /// Lists always accept either form when deserialized, and serialize the
+    /// version as an object unless wrapped this way.
+    ///
+    /// ```
+    /// # use token_list::{TokenList, VersionFormat};
+    /// # let token_list: TokenList = serde_json::from_str(r#"{
+    /// #     "name": "TELcoins",
+    /// #     "timestamp": "2021-07-05T20:25:22+00:00",
+    /// #     "version": "0.1.0"
+    /// # }"#)?;
+    /// let json = serde_json::to_value(token_list.with_version_format(VersionFormat::String))?;
+    ///
+    /// assert_eq!(json["version"], "0.1.0");
+    /// # Ok::<_, serde_json::Error>(())
+    /// ```
+    pub fn with_version_format(&self, format: VersionFormat) -> WithVersionFormat<'_> {
+        WithVersionFormat {
+            token_list: self,
+            format,
+        }
+    }
+}
+
+/// A [`TokenList`] that serializes its version in a chosen [`VersionFormat`].
+///
+/// Created by [`TokenList::with_version_format`].
+#[derive(Clone, Copy, Debug)]
+pub struct WithVersionFormat<'a> {
+    token_list: &'a TokenList,
+    format: VersionFormat,
+}
+
+impl Serialize for WithVersionFormat<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        /// Mirrors the fields of [`TokenList`], borrowing them.
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Repr<'a> {
+            name: &'a str,
+            timestamp: String,
+            version: FormattedVersion<'a>,
+            #[serde(rename = "logoURI", skip_serializing_if = "Option::is_none")]
+            logo_uri: Option<&'a Url>,
+            #[serde(skip_serializing_if = "<[_]>::is_empty")]
+            keywords: &'a [String],
+            #[serde(skip_serializing_if = "IndexMap::is_empty")]
+            tags: &'a IndexMap<String, Tag>,
+            #[serde(skip_serializing_if = "<[_]>::is_empty")]
+            tokens: &'a [Token],
+            #[cfg(feature = "unknown-fields")]
+            #[serde(flatten)]
+            unknown_fields: &'a serde_json::Map<String, serde_json::Value>,
+        }
+
+        struct FormattedVersion<'a>(&'a Version, VersionFormat);
+
+        impl Serialize for FormattedVersion<'_> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                match self.1 {
+                    VersionFormat::Object => serialize(self.0, serializer),
+                    VersionFormat::String => serializer.collect_str(self.0),
+                }
+            }
+        }
+
+        let token_list = self.token_list;
+        Repr {
+            name: &token_list.name,
+            timestamp: crate::timestamp::to_rfc3339(&token_list.timestamp),
+            version: FormattedVersion(&token_list.version, self.format),
+            logo_uri: token_list.logo_uri.as_ref(),
+            keywords: &token_list.keywords,
+            tags: &token_list.tags,
+            tokens: &token_list.tokens,
+            #[cfg(feature = "unknown-fields")]
+            unknown_fields: &token_list.unknown_fields,
+        }
+        .serialize(serializer)
+    }
+}
+
+pub fn serialize<S>(value: &Version, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut version = serializer.serialize_struct("Version", 3)?;
+    version.serialize_field("major", &value.major)?;
+    version.serialize_field("minor", &value.minor)?;
+    version.serialize_field("patch", &value.patch)?;
+    version.end()
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Version, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct InternalVersion {
+        major: u64,
+        minor: u64,
+        patch: u64,
+    }
+
+    struct VersionVisitor;
+
+    impl<'de> Visitor<'de> for VersionVisitor {
+        type Value = Version;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a version object or semver string")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Version, E> {
+            Version::parse(value).map_err(E::custom)
+        }
+
+        fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Version, A::Error> {
+            InternalVersion::deserialize(MapAccessDeserializer::new(map))
+                .map(|v| Version::new(v.major, v.minor, v.patch))
+        }
+    }
+
+    deserializer.deserialize_any(VersionVisitor)
+}
+
+/// Returns a key whose byte order is the precedence of `version`, so that
+/// databases can sort versions as text; build metadata, which doesn't affect
+/// precedence, is left out.
+///
+/// Numbers are zero-padded to the width of `u64::MAX`, pre-release
+/// identifiers are separated by a space, which sorts before any character an
+/// identifier may contain, and releases end with `~`, which sorts after any
+/// pre-release.
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) fn sort_key(version: &Version) -> String {
+    let mut key = format!(
+        "{:020}.{:020}.{:020}",
+        version.major, version.minor, version.patch
+    );
+    if version.pre.is_empty() {
+        key.push('~');
+    }
+    for (i, identifier) in version.pre.iter().enumerate() {
+        key.push(if i == 0 { '-' } else { ' ' });
+        // numeric identifiers sort before alphanumeric ones
+        match identifier {
+            semver::Identifier::Numeric(n) => key.push_str(&format!("0{:020}", n)),
+            semver::Identifier::AlphaNumeric(s) => {
+                key.push('1');
+                key.push_str(s);
+            }
+        }
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    #[test]
+    fn accepts_both_forms() {
+        let mut json: serde_json::Value = serde_json::from_str(fixtures::FULL).unwrap();
+        json["version"] = json!("0.1.0");
+
+        let token_list: TokenList = serde_json::from_value(json).unwrap();
+
+        assert_eq!(token_list, fixtures::full());
+    }
+
+    #[test]
+    fn rejects_invalid_strings() {
+        let mut json: serde_json::Value = serde_json::from_str(fixtures::FULL).unwrap();
+        json["version"] = json!("one");
+
+        assert!(serde_json::from_value::<TokenList>(json).is_err());
+    }
+
+    #[test]
+    fn serializes_chosen_form() {
+        let token_list = fixtures::full();
+        let object = serde_json::to_value(&token_list).unwrap();
+
+        let as_object = token_list.with_version_format(VersionFormat::Object);
+        assert_eq!(serde_json::to_value(as_object).unwrap(), object);
+
+        let as_string = token_list.with_version_format(VersionFormat::String);
+        let mut expected = object;
+        expected["version"] = json!("0.1.0");
+        assert_eq!(serde_json::to_value(as_string).unwrap(), expected);
+    }
+
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    #[test]
+    fn sort_keys_follow_precedence() {
+        // from the semver specification, plus a few edge cases
+        let versions = [
+            "0.9.10",
+            "1.0.0-2",
+            "1.0.0-10",
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-alpha-",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+            "1.0.1",
+            "1.10.0",
+            "10.0.0",
+        ];
+
+        for pair in versions.windows(2) {
+            let (a, b) = (
+                Version::parse(pair[0]).unwrap(),
+                Version::parse(pair[1]).unwrap(),
+            );
+            assert!(a < b, "{} < {}", a, b);
+            assert!(sort_key(&a) < sort_key(&b), "{} < {}", a, b);
+        }
+        assert_eq!(
+            sort_key(&Version::parse("1.0.0+build.1").unwrap()),
+            sort_key(&Version::new(1, 0, 0))
+        );
+    }
+}
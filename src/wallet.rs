@@ -0,0 +1,96 @@
+//! Requests asking wallets to track tokens, as specified by EIP-747.
+
+use serde::Serialize;
+
+use crate::Token;
+
+/// The parameters of a `wallet_watchAsset` request, asking a wallet such as
+/// MetaMask to track a token.
+///
+/// ```
+/// # use token_list::Token;
+/// let token: Token = serde_json::from_str(r#"{
+///     "name": "Telcoin",
+///     "symbol": "TEL",
+///     "address": "0x467Bccd9d29f223BcE8043b84E8C8B282827790F",
+///     "chainId": 1,
+///     "decimals": 2
+/// }"#)?;
+///
+/// let params = serde_json::to_value(token.watch_asset_params())?;
+/// assert_eq!(
+///     params,
+///     serde_json::json!({
+///         "type": "ERC20",
+///         "options": {
+///             "address": "0x467Bccd9d29f223BcE8043b84E8C8B282827790F",
+///             "symbol": "TEL",
+///             "decimals": 2
+///         }
+///     })
+/// );
+/// # Ok::<_, serde_json::Error>(())
+/// ```
+#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct WatchAssetParams<'a> {
+    /// The asset's standard, which is always `ERC20`
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+
+    /// The token to track
+    pub options: WatchAssetOptions<'a>,
+}
+
+/// The token of a [`WatchAssetParams`].
+#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct WatchAssetOptions<'a> {
+    /// The address of the token's contract
+    pub address: &'a str,
+
+    /// The token's symbol
+    pub symbol: &'a str,
+
+    /// The number of decimals of the token's amounts
+    pub decimals: u8,
+
+    /// The URI of the token's logo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<&'a str>,
+}
+
+impl Token {
+    /// Returns the parameters of a `wallet_watchAsset` request for the token,
+    /// for "add to wallet" buttons. The request must be sent to a wallet
+    /// connected to the token's chain.
+    pub fn watch_asset_params(&self) -> WatchAssetParams<'_> {
+        WatchAssetParams {
+            kind: "ERC20",
+            options: WatchAssetOptions {
+                address: &self.address,
+                symbol: &self.symbol,
+                decimals: self.decimals,
+                image: self.logo_uri.as_ref().map(|uri| uri.as_str()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::fixtures;
+
+    #[test]
+    fn includes_logo() {
+        let token_list = fixtures::full();
+        let token = &token_list.tokens[0];
+
+        let params = serde_json::to_value(token.watch_asset_params()).unwrap();
+
+        assert_eq!(params["type"], "ERC20");
+        assert_eq!(params["options"]["address"], token.address.as_str());
+        assert_eq!(
+            params["options"]["image"],
+            token.logo_uri.as_ref().unwrap().as_str()
+        );
+    }
+}
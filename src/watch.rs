@@ -0,0 +1,198 @@
+//! Watching a token list for new versions.
+
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::stream::{self, Stream};
+use semver::Version;
+
+use crate::hash::content_hash;
+#[cfg(feature = "storage")]
+use crate::Storage;
+use crate::{FetchOptions, Fetched, Fetcher, ReqwestFetcher, TokenList};
+
+/// A [`Stream`] of new versions of a token list, produced by polling its URI.
+///
+/// The first list fetched is always yielded; after that, a list is only
+/// yielded when its version or content hash differs from the last one. Failed
+/// polls are skipped, and polling continues at the next interval.
+///
+/// **Note**: This must be polled from a running tokio >1.0.0 runtime.
+pub struct TokenListWatcher {
+    inner: Pin<Box<dyn Stream<Item = TokenList> + Send>>,
+}
+
+struct State {
+    fetcher: Arc<dyn Fetcher>,
+    #[cfg(feature = "storage")]
+    storage: Option<Arc<dyn Storage>>,
+    uri: String,
+    interval: Duration,
+    etag: Option<String>,
+    last: Option<(Version, [u8; 32])>,
+    first: bool,
+}
+
+impl TokenListWatcher {
+    /// Polls `uri` every `interval`, yielding each new version of the list.
+    pub fn poll(uri: &str, interval: Duration) -> Self {
+        Self::poll_with_options(uri, interval, FetchOptions::default())
+    }
+
+    /// Polls `uri` every `interval` using the provided [`FetchOptions`],
+    /// yielding each new version of the list.
+    pub fn poll_with_options(uri: &str, interval: Duration, options: FetchOptions) -> Self {
+        Self::poll_with_fetcher(Arc::new(ReqwestFetcher::new(options)), uri, interval)
+    }
+
+    /// Polls `uri` every `interval` using the provided [`Fetcher`], yielding
+    /// each new version of the list.
+    pub fn poll_with_fetcher(fetcher: Arc<dyn Fetcher>, uri: &str, interval: Duration) -> Self {
+        let state = State {
+            fetcher,
+            #[cfg(feature = "storage")]
+            storage: None,
+            uri: uri.to_owned(),
+            interval,
+            etag: None,
+            last: None,
+            first: true,
+        };
+
+        TokenListWatcher {
+            inner: Box::pin(stream::unfold(state, next)),
+        }
+    }
+
+    /// Polls `uri` every `interval` using the provided [`Fetcher`], putting
+    /// each new version of the list in `storage` before yielding it.
+    ///
+    /// A list that can't be stored is skipped like a failed poll, and fetched
+    /// again at the next interval.
+    #[cfg(feature = "storage")]
+    pub fn poll_with_storage(
+        fetcher: Arc<dyn Fetcher>,
+        storage: Arc<dyn Storage>,
+        uri: &str,
+        interval: Duration,
+    ) -> Self {
+        let state = State {
+            fetcher,
+            storage: Some(storage),
+            uri: uri.to_owned(),
+            interval,
+            etag: None,
+            last: None,
+            first: true,
+        };
+
+        TokenListWatcher {
+            inner: Box::pin(stream::unfold(state, next)),
+        }
+    }
+}
+
+async fn next(mut state: State) -> Option<(TokenList, State)> {
+    loop {
+        if !state.first {
+            tokio::time::sleep(state.interval).await;
+        }
+        state.first = false;
+
+        let fetched = state
+            .fetcher
+            .fetch_if_modified(&state.uri, state.etag.as_deref())
+            .await;
+        if let Ok(Fetched::Modified { body, etag }) = fetched {
+            state.etag = etag;
+
+            let token_list = match TokenList::from_slice(&body) {
+                Ok(token_list) => token_list,
+                Err(_) => continue,
+            };
+            let hash = match content_hash(&token_list) {
+                Ok(hash) => hash,
+                Err(_) => continue,
+            };
+            let current = (token_list.version.clone(), hash);
+
+            if state.last.as_ref() != Some(&current) {
+                #[cfg(feature = "storage")]
+                if let Some(storage) = &state.storage {
+                    if storage.put_list(&token_list).await.is_err() {
+                        // fetch the whole list again rather than revalidate it
+                        state.etag = None;
+                        continue;
+                    }
+                }
+                state.last = Some(current);
+                return Some((token_list, state));
+            }
+        }
+    }
+}
+
+impl Stream for TokenListWatcher {
+    type Item = TokenList;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<TokenList>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+impl fmt::Debug for TokenListWatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenListWatcher").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::test_utils::{fixtures, MockFetcher, MockResponse};
+
+    const URI: &str = "https://example.com/list.json";
+
+    #[tokio::test]
+    async fn yields_only_changed_lists() {
+        let mut updated = fixtures::full();
+        updated.version = Version::new(0, 2, 0);
+        let fetcher = Arc::new(MockFetcher::new());
+        fetcher
+            .respond_once(URI, MockResponse::body(fixtures::FULL))
+            .respond_once(URI, MockResponse::body(fixtures::FULL))
+            .respond(URI, MockResponse::json(&updated));
+
+        let mut watcher =
+            TokenListWatcher::poll_with_fetcher(fetcher, URI, Duration::from_millis(10));
+
+        assert_eq!(watcher.next().await.unwrap().version, Version::new(0, 1, 0));
+        assert_eq!(watcher.next().await.unwrap().version, Version::new(0, 2, 0));
+    }
+
+    #[cfg(feature = "storage")]
+    #[tokio::test]
+    async fn puts_new_lists_in_storage() {
+        let fetcher = Arc::new(MockFetcher::new());
+        fetcher.respond(URI, MockResponse::body(fixtures::FULL));
+        let storage = Arc::new(crate::MemoryStorage::new());
+
+        let mut watcher = TokenListWatcher::poll_with_storage(
+            fetcher,
+            storage.clone(),
+            URI,
+            Duration::from_millis(10),
+        );
+        let token_list = watcher.next().await.unwrap();
+
+        assert_eq!(
+            storage.list_versions(&token_list.name).await.unwrap(),
+            [token_list.version]
+        );
+    }
+}
@@ -0,0 +1,61 @@
+//! YAML serialization of token lists.
+
+use crate::{Error, TokenList};
+
+impl TokenList {
+    /// Parses a [`TokenList`] from a YAML document.
+    pub fn from_yaml_str(yaml: &str) -> Result<Self, Error> {
+        Ok(serde_yaml::from_str(yaml)?)
+    }
+
+    /// Serializes the list as a YAML document.
+    pub fn to_yaml_string(&self) -> Result<String, Error> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures;
+
+    #[test]
+    fn round_trips_through_yaml() {
+        let token_list = fixtures::full();
+
+        let yaml = token_list.to_yaml_string().unwrap();
+
+        assert!(yaml.contains("logoURI:"));
+        assert_eq!(TokenList::from_yaml_str(&yaml).unwrap(), token_list);
+    }
+
+    #[test]
+    fn parses_hand_written_yaml() {
+        let yaml = r#"
+name: TELcoins
+timestamp: 2021-07-05T20:25:22+00:00
+version:
+  major: 0
+  minor: 1
+  patch: 0
+tokens:
+  - name: Telcoin
+    symbol: TEL
+    address: "0x467Bccd9d29f223BcE8043b84E8C8B282827790F"
+    chainId: 1
+    decimals: 2
+"#;
+
+        let token_list = TokenList::from_yaml_str(yaml).unwrap();
+
+        assert_eq!(token_list.tokens[0].symbol, "TEL");
+    }
+
+    #[test]
+    fn reports_invalid_yaml() {
+        match TokenList::from_yaml_str("name: [") {
+            Err(Error::Yaml(_)) => {}
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+}